@@ -0,0 +1,39 @@
+use aionic::openai::chat::{AskOutcome, Chat, Function, Message, MessageRole};
+use aionic::openai::OpenAI;
+use serde_json::json;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let weather = Function::new(
+        "get_current_weather",
+        "Get the current weather in a given location",
+        json!({
+            "type": "object",
+            "properties": {
+                "location": { "type": "string", "description": "The city, e.g. Berlin" }
+            },
+            "required": ["location"]
+        }),
+    );
+
+    let mut client = OpenAI::<Chat>::new()
+        .set_stream_responses(false)
+        .set_functions(vec![weather]);
+
+    let outcome = client
+        .ask_with_outcome("What's the weather like in Berlin?", true)
+        .await?;
+
+    if let AskOutcome::FunctionCall(call) = outcome {
+        println!("Model requested {}({})", call.name, call.arguments);
+
+        // Run the actual function here and send its result back to the model
+        let mut result = Message::new(
+            &MessageRole::Function,
+            json!({"temperature": 22, "unit": "celsius"}).to_string(),
+        );
+        result.name = Some(call.name);
+        client.ask(result, true).await?;
+    }
+    Ok(())
+}
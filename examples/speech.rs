@@ -0,0 +1,11 @@
+use aionic::openai::{OpenAI, Speech, Voice};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    OpenAI::<Speech>::new()
+        .set_voice(Voice::Nova)
+        .speak_to_file("Hello from AIonic!", "speech.mp3")
+        .await?;
+    println!("Saved speech to speech.mp3");
+    Ok(())
+}
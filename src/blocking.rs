@@ -0,0 +1,862 @@
+//! A blocking client for codebases without an async runtime.
+//!
+//! `aionic::blocking::OpenAI` offers the requests of `aionic::openai::OpenAI` as plain,
+//! synchronous methods. Every request is driven to completion on a single threaded tokio
+//! runtime that is owned by the client, so no runtime has to be set up by the caller.
+//!
+//! The client is configured through its async counterpart, either by converting a configured
+//! async client with `from_async`, or with `configure`, which takes a closure over the async
+//! client's builder methods.
+//!
+//! The methods of this client must not be called from within an async runtime, as they block
+//! the current thread and panic in that case.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use aionic::blocking::OpenAI;
+//! use aionic::openai::Chat;
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//!     let mut client = OpenAI::<Chat>::new().configure(|client| {
+//!         client
+//!             .set_model("gpt-3.5-turbo")
+//!             .set_stream_responses(false)
+//!     });
+//!     let reply = client.ask("Hello, world!", true)?;
+//!     println!("{}", reply);
+//!     Ok(())
+//! }
+//! ```
+
+#[cfg(feature = "files")]
+use crate::openai::ListParams;
+#[cfg(feature = "files")]
+use crate::openai::{
+    files::{Data as FileData, DeleteResponse, PromptCompletion, Response as FileResponse},
+    FilePurpose, Files,
+};
+#[cfg(feature = "chat")]
+use crate::openai::{AskOutcome, CancellationToken, Chat, ChatOutcome, Message};
+#[cfg(feature = "audio")]
+use crate::openai::{Audio, AudioResponse, AudioVerboseResponse, Speech};
+#[cfg(feature = "embeddings")]
+use crate::openai::{Embedding, EmbeddingResponse, InputType, Usage};
+#[cfg(feature = "fine-tunes")]
+#[allow(deprecated)]
+use crate::openai::{
+    FineTune, FineTuneEvent, FineTuneEventResponse, FineTuneListResponse, FineTuneListSummary,
+    FineTuneResponse,
+};
+#[cfg(feature = "fine-tunes")]
+use crate::openai::{
+    FineTuningJob, FineTuningJobEventList, FineTuningJobList, FineTuningJobResponse,
+};
+#[cfg(feature = "images")]
+use crate::openai::{Image, ImageResponse};
+use crate::openai::{Model, OpenAIConfig, UsageTotals};
+#[cfg(feature = "moderations")]
+use crate::openai::{Moderation, ModerationResponse, ModerationResult};
+#[cfg(any(feature = "images", feature = "audio"))]
+use reqwest::IntoUrl;
+use serde::Serialize;
+use std::error::Error;
+use std::future::Future;
+#[cfg(any(feature = "images", feature = "audio", feature = "files"))]
+use std::path::Path;
+#[cfg(feature = "images")]
+use std::path::PathBuf;
+use std::process::exit;
+use std::sync::Arc;
+#[cfg(feature = "fine-tunes")]
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+/// The blocking counterpart of `aionic::openai::OpenAI`.
+///
+/// Cloning the client is cheap, all clones share the same runtime.
+#[derive(Clone, Debug)]
+pub struct OpenAI<C: OpenAIConfig> {
+    /// The async client the requests are made with.
+    inner: crate::openai::OpenAI<C>,
+
+    /// The runtime the requests of `inner` are driven on.
+    runtime: Arc<Runtime>,
+}
+
+/// Constructs a client without an API key, like `aionic::openai::OpenAI::default`.
+///
+/// # Panics
+///
+/// This panics if the runtime could not be created. Use `from_async` to handle that case.
+impl<C: OpenAIConfig + Serialize + std::fmt::Debug> Default for OpenAI<C> {
+    fn default() -> Self {
+        Self::from_async(crate::openai::OpenAI::default())
+            .unwrap_or_else(|e| panic!("Failed to create the runtime: {e}"))
+    }
+}
+
+impl<C: OpenAIConfig + Serialize + std::fmt::Debug> OpenAI<C> {
+    /// Constructs a client with the API key taken from the `OPENAI_API_KEY` environment variable.
+    ///
+    /// This terminates the process if the variable is not set. Use `try_new` to handle that case.
+    pub fn new() -> Self {
+        Self::try_new().unwrap_or_else(|e| {
+            println!("{e}");
+            exit(1);
+        })
+    }
+
+    /// Constructs a client with the API key taken from the `OPENAI_API_KEY` environment variable.
+    ///
+    /// # Returns
+    ///
+    /// This function returns a `NotFound` error if the environment variable is not set, or an
+    /// error if the runtime could not be created.
+    pub fn try_new() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Self::from_async(crate::openai::OpenAI::try_new()?)
+    }
+
+    /// Constructs a blocking client from an async one, keeping its configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The async client the requests are made with.
+    ///
+    /// # Returns
+    ///
+    /// This function returns an error if the runtime could not be created.
+    pub fn from_async(
+        client: crate::openai::OpenAI<C>,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        Ok(Self {
+            inner: client,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// Configures the client with the builder methods of the async client, e.g. `with_api_key`
+    /// or `set_model`.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - A closure that takes the async client and returns it configured.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the client with the configuration applied.
+    pub fn configure<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(crate::openai::OpenAI<C>) -> crate::openai::OpenAI<C>,
+    {
+        self.inner = f(self.inner);
+        self
+    }
+
+    /// Returns a reference to the async client the requests are made with.
+    pub fn get_ref(&self) -> &crate::openai::OpenAI<C> {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the async client the requests are made with.
+    pub fn get_mut(&mut self) -> &mut crate::openai::OpenAI<C> {
+        &mut self.inner
+    }
+
+    /// Consumes the blocking client and returns the async client.
+    pub fn into_inner(self) -> crate::openai::OpenAI<C> {
+        self.inner
+    }
+
+    /// Drives a future to completion on the runtime of the client.
+    fn block_on<F: Future>(&self, fut: F) -> F::Output {
+        self.runtime.block_on(fut)
+    }
+
+    /// Fetches the IDs of all models available to the API key. See `OpenAI::models`.
+    pub fn models(&self) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.models())
+    }
+
+    /// Returns the token usage accumulated by this client. See `OpenAI::usage_totals`.
+    pub fn usage_totals(&self) -> UsageTotals {
+        self.inner.usage_totals()
+    }
+
+    /// Resets the accumulated token usage of this client. See `OpenAI::reset_usage_totals`.
+    pub fn reset_usage_totals(&self) {
+        self.inner.reset_usage_totals();
+    }
+
+    /// Fetches all available models with their metadata. See `OpenAI::models_detailed`.
+    pub fn models_detailed(&self) -> Result<Vec<Model>, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.models_detailed())
+    }
+
+    /// Fetches all available models owned by `owner`. See `OpenAI::models_owned_by`.
+    pub fn models_owned_by(&self, owner: &str) -> Result<Vec<Model>, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.models_owned_by(owner))
+    }
+
+    /// Checks whether a model is available. See `OpenAI::check_model`.
+    pub fn check_model(&self, model: &str) -> Result<Model, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.check_model(model))
+    }
+}
+
+#[cfg(feature = "chat")]
+impl OpenAI<Chat> {
+    /// Makes a request to the chat model. See `OpenAI::<Chat>::ask`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use aionic::blocking::OpenAI;
+    /// use aionic::openai::Chat;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///     let mut client = OpenAI::<Chat>::new();
+    ///     match client.ask("Hello, world!", true) {
+    ///         Ok(response) => println!("{}", response),
+    ///         Err(e) => println!("Error: {}", e),
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn ask<P: Into<Message> + Send>(
+        &mut self,
+        prompt: P,
+        persist_state: bool,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(self.inner.ask(prompt, persist_state))
+    }
+
+    /// Makes a request to the chat model like `ask`, but passes every content delta of a
+    /// streamed response to `on_token` as it arrives, instead of printing it.
+    ///
+    /// The callback is invoked synchronously on the calling thread. A response that is not
+    /// streamed is passed to it as a single delta.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt` - The message to send, e.g. the user's question.
+    /// * `persist_state` - If true, the answer is pushed to the chat history, otherwise the
+    ///   prompt is removed from it again.
+    /// * `on_token` - The callback invoked for every content delta.
+    ///
+    /// # Returns
+    ///
+    /// The complete answer of the model. If the model requests a function or tool call, an
+    /// error is returned and the chat history is left as it was before the call, like `ask`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use aionic::blocking::OpenAI;
+    /// use aionic::openai::Chat;
+    /// use std::io::Write;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///     let mut client = OpenAI::<Chat>::new().configure(|c| c.set_stream_responses(true));
+    ///     client.ask_streaming("Tell me a story", false, |token| {
+    ///         print!("{}", token);
+    ///         std::io::stdout().flush().unwrap();
+    ///     })?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn ask_streaming<P, F>(
+        &mut self,
+        prompt: P,
+        persist_state: bool,
+        mut on_token: F,
+    ) -> Result<String, Box<dyn Error + Send + Sync>>
+    where
+        P: Into<Message> + Send,
+        F: FnMut(&str) + Send,
+    {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(
+            self.inner
+                ._ask_text(prompt, persist_state, Some(&mut on_token)),
+        )
+    }
+
+    /// Makes a request to the chat model and returns the answer with the metadata of its
+    /// response. See `OpenAI::<Chat>::ask_detailed`.
+    pub fn ask_detailed<P: Into<Message> + Send>(
+        &mut self,
+        prompt: P,
+        persist_state: bool,
+    ) -> Result<ChatOutcome, Box<dyn Error + Send + Sync>> {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(self.inner.ask_detailed(prompt, persist_state))
+    }
+
+    /// Makes a request to the chat model that may result in a function call. See
+    /// `OpenAI::<Chat>::ask_with_outcome`.
+    pub fn ask_with_outcome<P: Into<Message> + Send>(
+        &mut self,
+        prompt: P,
+        persist_state: bool,
+    ) -> Result<AskOutcome, Box<dyn Error + Send + Sync>> {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(self.inner.ask_with_outcome(prompt, persist_state))
+    }
+
+    /// Makes a request to the chat model that stops once `token` is cancelled, e.g. from
+    /// another thread. See `OpenAI::<Chat>::ask_cancellable`.
+    pub fn ask_cancellable<P: Into<Message> + Send>(
+        &mut self,
+        prompt: P,
+        persist_state: bool,
+        token: CancellationToken,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(self.inner.ask_cancellable(prompt, persist_state, token))
+    }
+
+    /// Asks the chat model again for an answer to the last prompt. See
+    /// `OpenAI::<Chat>::regenerate`.
+    pub fn regenerate(&mut self) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(self.inner.regenerate())
+    }
+
+    /// Makes a request to the chat model and runs the registered functions until it answers.
+    /// See `OpenAI::<Chat>::ask_with_functions`.
+    pub fn ask_with_functions<P: Into<Message> + Send>(
+        &mut self,
+        prompt: P,
+        persist_state: bool,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(self.inner.ask_with_functions(prompt, persist_state))
+    }
+
+    /// Makes a request to the chat model and returns every choice. See
+    /// `OpenAI::<Chat>::ask_many`.
+    pub fn ask_many<P: Into<Message> + Send>(
+        &mut self,
+        prompt: P,
+        persist_state: bool,
+    ) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(self.inner.ask_many(prompt, persist_state))
+    }
+}
+
+#[cfg(feature = "images")]
+impl OpenAI<Image> {
+    /// Creates images from a prompt. See `OpenAI::<Image>::create`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use aionic::blocking::OpenAI;
+    /// use aionic::openai::Image;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///     let mut client = OpenAI::<Image>::new();
+    ///     for url in client.create("A cat wearing a hat")? {
+    ///         println!("{}", url);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn create<S: Into<String> + Send>(
+        &mut self,
+        prompt: S,
+    ) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(self.inner.create(prompt))
+    }
+
+    /// Creates images from a prompt and returns the full response. See
+    /// `OpenAI::<Image>::create_response`.
+    pub fn create_response<S: Into<String> + Send>(
+        &mut self,
+        prompt: S,
+    ) -> Result<ImageResponse, Box<dyn Error + Send + Sync>> {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(self.inner.create_response(prompt))
+    }
+
+    /// Creates images from a prompt and saves them in `dir`. See
+    /// `OpenAI::<Image>::create_and_save`.
+    pub fn create_and_save<S: Into<String> + Send, P: AsRef<Path> + Send>(
+        &mut self,
+        prompt: S,
+        dir: P,
+    ) -> Result<Vec<PathBuf>, Box<dyn Error + Send + Sync>> {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(self.inner.create_and_save(prompt, dir))
+    }
+
+    /// Edits an image from a prompt. See `OpenAI::<Image>::edit`.
+    pub fn edit<S: Into<String> + Send>(
+        &mut self,
+        prompt: S,
+        image_file_path: S,
+        mask: Option<S>,
+    ) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(self.inner.edit(prompt, image_file_path, mask))
+    }
+
+    /// Edits an image that is downloaded from a URL. See `OpenAI::<Image>::edit_from_url`.
+    pub fn edit_from_url<S: Into<String> + Send, U: IntoUrl + Send>(
+        &mut self,
+        prompt: S,
+        image_url: U,
+        mask_url: Option<U>,
+    ) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(self.inner.edit_from_url(prompt, image_url, mask_url))
+    }
+
+    /// Creates variations of an image. See `OpenAI::<Image>::variation`.
+    pub fn variation<S: Into<String> + Send>(
+        &mut self,
+        image_file_path: S,
+    ) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(self.inner.variation(image_file_path))
+    }
+}
+
+#[cfg(feature = "embeddings")]
+impl OpenAI<Embedding> {
+    /// Creates an embedding of the input. See `OpenAI::<Embedding>::embed`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use aionic::blocking::OpenAI;
+    /// use aionic::openai::Embedding;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///     let client = OpenAI::<Embedding>::new();
+    ///     let response = client.embed("Hello, world!")?;
+    ///     println!("{:?}", response.data[0].embedding);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn embed<S: Into<InputType> + Send>(
+        &self,
+        prompt: S,
+    ) -> Result<EmbeddingResponse, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.embed(prompt))
+    }
+
+    /// Creates an embedding of the input set in the configuration. See
+    /// `OpenAI::<Embedding>::embed_current`.
+    pub fn embed_current(&self) -> Result<EmbeddingResponse, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.embed_current())
+    }
+
+    /// Embeds many inputs in batches. See `OpenAI::<Embedding>::embed_batch`.
+    pub fn embed_batch(
+        &self,
+        inputs: Vec<String>,
+        chunk_size: usize,
+        concurrency: usize,
+    ) -> Result<Vec<Vec<f64>>, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.embed_batch(inputs, chunk_size, concurrency))
+    }
+
+    /// Returns the token usage of the last call to `embed_batch`.
+    pub fn last_usage(&self) -> Option<Usage> {
+        self.inner.last_usage()
+    }
+}
+
+#[cfg(feature = "audio")]
+impl OpenAI<Audio> {
+    /// Transcribes an audio file. See `OpenAI::<Audio>::transcribe`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use aionic::blocking::OpenAI;
+    /// use aionic::openai::Audio;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///     let mut client = OpenAI::<Audio>::new();
+    ///     let response = client.transcribe("examples/samples/sample-1.mp3")?;
+    ///     println!("{}", response.text);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn transcribe<P: AsRef<Path> + Sync + Send>(
+        &mut self,
+        audio_file: P,
+    ) -> Result<AudioResponse, Box<dyn Error + Send + Sync>> {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(self.inner.transcribe(audio_file))
+    }
+
+    /// Transcribes an audio file that is downloaded from a URL. See
+    /// `OpenAI::<Audio>::transcribe_url`.
+    pub fn transcribe_url<U: IntoUrl + Send>(
+        &mut self,
+        url: U,
+    ) -> Result<AudioResponse, Box<dyn Error + Send + Sync>> {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(self.inner.transcribe_url(url))
+    }
+
+    /// Transcribes an audio file into timestamped segments. See
+    /// `OpenAI::<Audio>::transcribe_verbose`.
+    pub fn transcribe_verbose<P: AsRef<Path> + Sync + Send>(
+        &mut self,
+        audio_file: P,
+    ) -> Result<AudioVerboseResponse, Box<dyn Error + Send + Sync>> {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(self.inner.transcribe_verbose(audio_file))
+    }
+
+    /// Translates an audio file into English. See `OpenAI::<Audio>::translate`.
+    pub fn translate<P: AsRef<Path> + Send + Sync>(
+        &mut self,
+        audio_file: P,
+    ) -> Result<AudioResponse, Box<dyn Error + Send + Sync>> {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(self.inner.translate(audio_file))
+    }
+}
+
+#[cfg(feature = "audio")]
+impl OpenAI<Speech> {
+    /// Turns a text into speech. See `OpenAI::<Speech>::speak`.
+    pub fn speak<S: Into<String> + Send>(
+        &mut self,
+        text: S,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(self.inner.speak(text))
+    }
+
+    /// Turns a text into speech and saves it at `path`. See `OpenAI::<Speech>::speak_to_file`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use aionic::blocking::OpenAI;
+    /// use aionic::openai::Speech;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///     let mut client = OpenAI::<Speech>::new();
+    ///     client.speak_to_file("Hello, world!", "hello.mp3")?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn speak_to_file<S: Into<String> + Send, P: AsRef<Path> + Send + Sync>(
+        &mut self,
+        text: S,
+        path: P,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(self.inner.speak_to_file(text, path))
+    }
+}
+
+#[cfg(feature = "files")]
+impl OpenAI<Files> {
+    /// Lists the uploaded files. See `OpenAI::<Files>::list`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use aionic::blocking::OpenAI;
+    /// use aionic::openai::Files;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///     let mut client = OpenAI::<Files>::new();
+    ///     let file = client.upload("examples/samples/test.jsonl")?;
+    ///     println!("{:?}", file);
+    ///     println!("{:?}", client.list()?);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn list(&self) -> Result<FileResponse, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.list())
+    }
+
+    /// Lists a page of the uploaded files. See `OpenAI::<Files>::list_with_params`.
+    pub fn list_with_params(
+        &self,
+        params: &ListParams,
+    ) -> Result<FileResponse, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.list_with_params(params))
+    }
+
+    /// Lists the uploaded files of all pages. See `OpenAI::<Files>::list_all`.
+    pub fn list_all(&self) -> Result<Vec<FileData>, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.list_all())
+    }
+
+    /// Retrieves the information about a file. See `OpenAI::<Files>::retrieve`.
+    pub fn retrieve<S: Into<String> + std::fmt::Display + Sync + Send>(
+        &self,
+        file_id: S,
+    ) -> Result<FileData, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.retrieve(file_id))
+    }
+
+    /// Retrieves the content of a file. See `OpenAI::<Files>::retrieve_content`.
+    pub fn retrieve_content<S: Into<String> + std::fmt::Display + Send + Sync>(
+        &self,
+        file_id: S,
+    ) -> Result<Vec<PromptCompletion>, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.retrieve_content(file_id))
+    }
+
+    /// Uploads a file. See `OpenAI::<Files>::upload`.
+    pub fn upload<P: AsRef<Path> + Send + Sync>(
+        &mut self,
+        file: P,
+    ) -> Result<FileData, Box<dyn Error + Send + Sync>> {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(self.inner.upload(file))
+    }
+
+    /// Uploads a file for the given purpose. See `OpenAI::<Files>::upload_with_purpose`.
+    pub fn upload_with_purpose<P: AsRef<Path> + Send + Sync>(
+        &mut self,
+        file: P,
+        purpose: FilePurpose,
+    ) -> Result<FileData, Box<dyn Error + Send + Sync>> {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(self.inner.upload_with_purpose(file, purpose))
+    }
+
+    /// Deletes a file. See `OpenAI::<Files>::delete`.
+    pub fn delete<S: Into<String> + std::fmt::Display + Send + Sync>(
+        &self,
+        file_id: S,
+    ) -> Result<DeleteResponse, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.delete(file_id))
+    }
+}
+
+#[cfg(feature = "fine-tunes")]
+#[allow(deprecated)]
+impl OpenAI<FineTune> {
+    /// Creates a fine-tune job. See `OpenAI::<FineTune>::create`.
+    pub fn create<S: Into<String> + Send + Sync>(
+        &mut self,
+        training_file: S,
+    ) -> Result<FineTuneResponse, Box<dyn Error + Send + Sync>> {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(self.inner.create(training_file))
+    }
+
+    /// Lists the fine-tune jobs. See `OpenAI::<FineTune>::list`.
+    pub fn list(&self) -> Result<FineTuneListResponse, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.list())
+    }
+
+    /// Lists a page of the fine-tune jobs. See `OpenAI::<FineTune>::list_with_params`.
+    pub fn list_with_params(
+        &self,
+        params: &ListParams,
+    ) -> Result<FineTuneListResponse, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.list_with_params(params))
+    }
+
+    /// Lists the fine-tune jobs of all pages. See `OpenAI::<FineTune>::list_all`.
+    pub fn list_all(&self) -> Result<Vec<FineTuneListSummary>, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.list_all())
+    }
+
+    /// Retrieves a fine-tune job. See `OpenAI::<FineTune>::retrieve`.
+    pub fn retrieve<S: Into<String> + Send + Sync + std::fmt::Display>(
+        &self,
+        fine_tune_id: S,
+    ) -> Result<FineTuneResponse, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.retrieve(fine_tune_id))
+    }
+
+    /// Cancels a fine-tune job. See `OpenAI::<FineTune>::cancel`.
+    pub fn cancel<S: Into<String> + Send + Sync + std::fmt::Display>(
+        &self,
+        fine_tune_id: S,
+    ) -> Result<FineTuneResponse, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.cancel(fine_tune_id))
+    }
+
+    /// Lists the events of a fine-tune job. See `OpenAI::<FineTune>::list_events`.
+    pub fn list_events<S: Into<String> + Send + Sync + std::fmt::Display>(
+        &self,
+        fine_tune_id: S,
+    ) -> Result<FineTuneEventResponse, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.list_events(fine_tune_id))
+    }
+
+    /// Lists a page of the events of a fine-tune job. See
+    /// `OpenAI::<FineTune>::list_events_with_params`.
+    pub fn list_events_with_params<S: Into<String> + Send + Sync + std::fmt::Display>(
+        &self,
+        fine_tune_id: S,
+        params: &ListParams,
+    ) -> Result<FineTuneEventResponse, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.list_events_with_params(fine_tune_id, params))
+    }
+
+    /// Watches the events of a fine-tune job while it runs. See
+    /// `OpenAI::<FineTune>::stream_events`.
+    ///
+    /// # Returns
+    ///
+    /// An iterator over the events of the job, which blocks while waiting for new ones.
+    pub fn stream_events<S: Into<String>>(
+        &self,
+        fine_tune_id: S,
+    ) -> impl Iterator<Item = Result<FineTuneEvent, Box<dyn Error + Send + Sync>>> + '_ {
+        self.stream_events_with_interval(fine_tune_id, FineTune::DEFAULT_EVENT_POLL_INTERVAL)
+    }
+
+    /// Watches the events of a fine-tune job while it runs, polling every `interval`. See
+    /// `OpenAI::<FineTune>::stream_events_with_interval`.
+    pub fn stream_events_with_interval<S: Into<String>>(
+        &self,
+        fine_tune_id: S,
+        interval: Duration,
+    ) -> impl Iterator<Item = Result<FineTuneEvent, Box<dyn Error + Send + Sync>>> + '_ {
+        use futures_util::StreamExt;
+
+        let mut events = Box::pin(
+            self.inner
+                .stream_events_with_interval(fine_tune_id, interval),
+        );
+        std::iter::from_fn(move || self.block_on(events.next()))
+    }
+
+    /// Deletes a fine-tuned model. See `OpenAI::<FineTune>::delete_model`.
+    pub fn delete_model<S: Into<String> + Send + Sync + std::fmt::Display>(
+        &self,
+        model: S,
+    ) -> Result<DeleteResponse, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.delete_model(model))
+    }
+}
+
+#[cfg(feature = "fine-tunes")]
+impl OpenAI<FineTuningJob> {
+    /// Creates a fine-tuning job. See `OpenAI::<FineTuningJob>::create`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use aionic::blocking::OpenAI;
+    /// use aionic::openai::FineTuningJob;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///     let mut client = OpenAI::<FineTuningJob>::new().configure(|c| c.set_n_epochs(3));
+    ///     let job = client.create("file-abc123")?;
+    ///     println!("{}: {}", job.id, job.status);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn create<S: Into<String> + Send + Sync>(
+        &mut self,
+        training_file: S,
+    ) -> Result<FineTuningJobResponse, Box<dyn Error + Send + Sync>> {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(self.inner.create(training_file))
+    }
+
+    /// Lists the first page of fine-tuning jobs. See `OpenAI::<FineTuningJob>::list`.
+    pub fn list(&self) -> Result<FineTuningJobList, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.list())
+    }
+
+    /// Lists a page of fine-tuning jobs. See `OpenAI::<FineTuningJob>::list_with_params`.
+    pub fn list_with_params(
+        &self,
+        params: &ListParams,
+    ) -> Result<FineTuningJobList, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.list_with_params(params))
+    }
+
+    /// Lists the fine-tuning jobs of all pages. See `OpenAI::<FineTuningJob>::list_all`.
+    pub fn list_all(&self) -> Result<Vec<FineTuningJobResponse>, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.list_all())
+    }
+
+    /// Retrieves a fine-tuning job. See `OpenAI::<FineTuningJob>::retrieve`.
+    pub fn retrieve<S: Into<String> + Send + Sync + std::fmt::Display>(
+        &self,
+        job_id: S,
+    ) -> Result<FineTuningJobResponse, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.retrieve(job_id))
+    }
+
+    /// Cancels a fine-tuning job. See `OpenAI::<FineTuningJob>::cancel`.
+    pub fn cancel<S: Into<String> + Send + Sync + std::fmt::Display>(
+        &self,
+        job_id: S,
+    ) -> Result<FineTuningJobResponse, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.cancel(job_id))
+    }
+
+    /// Lists the first page of events of a fine-tuning job. See
+    /// `OpenAI::<FineTuningJob>::list_events`.
+    pub fn list_events<S: Into<String> + Send + Sync + std::fmt::Display>(
+        &self,
+        job_id: S,
+    ) -> Result<FineTuningJobEventList, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.list_events(job_id))
+    }
+
+    /// Lists a page of events of a fine-tuning job. See
+    /// `OpenAI::<FineTuningJob>::list_events_with_params`.
+    pub fn list_events_with_params<S: Into<String> + Send + Sync + std::fmt::Display>(
+        &self,
+        job_id: S,
+        params: &ListParams,
+    ) -> Result<FineTuningJobEventList, Box<dyn Error + Send + Sync>> {
+        self.block_on(self.inner.list_events_with_params(job_id, params))
+    }
+}
+
+#[cfg(feature = "moderations")]
+impl OpenAI<Moderation> {
+    /// Classifies whether a text violates the content policy. See
+    /// `OpenAI::<Moderation>::moderate`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use aionic::blocking::OpenAI;
+    /// use aionic::openai::Moderation;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///     let client = OpenAI::<Moderation>::new();
+    ///     let response = client.moderate("I want to hug them.")?;
+    ///     println!("{:?}", response.results[0].flagged);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn moderate<S: Into<String> + Send + Sync>(
+        &self,
+        input: S,
+    ) -> Result<ModerationResponse, Box<dyn Error + Send + Sync>> {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(self.inner.moderate(input))
+    }
+
+    /// Classifies many texts in a single request. See `OpenAI::<Moderation>::moderate_many`.
+    pub fn moderate_many(
+        &self,
+        inputs: Vec<String>,
+    ) -> Result<Vec<ModerationResult>, Box<dyn Error + Send + Sync>> {
+        let runtime = Arc::clone(&self.runtime);
+        runtime.block_on(self.inner.moderate_many(inputs))
+    }
+}
@@ -1 +1,19 @@
+#[cfg(all(
+    target_arch = "wasm32",
+    any(
+        feature = "images",
+        feature = "audio",
+        feature = "files",
+        feature = "repl",
+        feature = "blocking"
+    )
+))]
+compile_error!(
+    "the `images`, `audio`, `files`, `fine-tunes`, `repl` and `blocking` features need a file system \
+     or a native runtime and are not supported on wasm32, disable the default features and enable \
+     e.g. `chat`, `embeddings` and `moderations` instead"
+);
+
+#[cfg(feature = "blocking")]
+pub mod blocking;
 pub mod openai;
@@ -6,6 +6,99 @@ pub struct Response {
     pub text: String,
 }
 
+/// The response of a transcription requested with `ResponseFormat::VerboseJson`, which carries
+/// the detected language, the duration and timestamped segments of the audio.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct VerboseResponse {
+    /// The task that was performed, i.e. `transcribe`.
+    #[serde(default)]
+    pub task: Option<String>,
+
+    /// The language of the audio, e.g. `english`.
+    pub language: String,
+
+    /// The duration of the audio in seconds.
+    pub duration: f64,
+
+    /// The full transcription.
+    pub text: String,
+
+    /// The segments of the transcription with their timestamps.
+    #[serde(default)]
+    pub segments: Vec<Segment>,
+
+    /// The words of the transcription with their timestamps. Only returned if the `Word`
+    /// granularity is requested.
+    #[serde(default)]
+    pub words: Vec<Word>,
+}
+
+/// A word of a verbose transcription.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Word {
+    /// The text of the word.
+    pub word: String,
+
+    /// The start time of the word in seconds.
+    pub start: f64,
+
+    /// The end time of the word in seconds.
+    pub end: f64,
+}
+
+/// The level of detail of the timestamps of a verbose transcription.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    Word,
+    Segment,
+}
+
+impl Granularity {
+    /// Returns the name of the granularity as expected by the API.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Word => "word",
+            Self::Segment => "segment",
+        }
+    }
+}
+
+/// A segment of a verbose transcription.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Segment {
+    /// The index of the segment.
+    pub id: u64,
+
+    /// The seek offset of the segment.
+    pub seek: u64,
+
+    /// The start time of the segment in seconds.
+    pub start: f64,
+
+    /// The end time of the segment in seconds.
+    pub end: f64,
+
+    /// The text of the segment.
+    pub text: String,
+
+    /// The token IDs of the text.
+    #[serde(default)]
+    pub tokens: Vec<u64>,
+
+    /// The temperature that was used to generate the segment.
+    pub temperature: f64,
+
+    /// The average log probability of the segment. Below -1 the segment is likely inaccurate.
+    pub avg_logprob: f64,
+
+    /// The compression ratio of the segment. Above 2.4 the segment is likely repetitive.
+    pub compression_ratio: f64,
+
+    /// The probability that the segment contains no speech.
+    pub no_speech_prob: f64,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(untagged)]
 enum FileType {
@@ -131,10 +224,17 @@ pub struct Audio {
     /// The language of the input audio. Supplying the input language in ISO-639-1 format will improve accuracy and latency.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub language: Option<String>,
+
+    /// The timestamp granularities to populate for this transcription. Requires the response
+    /// format `verbose_json`. Not supported by translations.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp_granularities: Option<Vec<Granularity>>,
 }
 
 impl Audio {
     pub const DEFAULT_MODEL: &'static str = "whisper-1";
+    /// The maximum size of an audio file accepted by the API.
+    pub const MAX_FILE_SIZE_BYTES: u64 = 25 * 1024 * 1024;
     pub const ISO_639_1_CODES: &[&'static str] = &[
         "ab", "aa", "af", "ak", "sq", "am", "ar", "an", "hy", "as", "av", "ae", "ay", "az", "bm",
         "ba", "eu", "be", "bn", "bh", "bi", "bs", "br", "bg", "my", "ca", "ch", "ce", "ny", "zh",
@@ -208,3 +308,66 @@ impl Audio {
         Self::ISO_639_1_CODES.contains(&language)
     }
 }
+
+/// The voices available for text-to-speech.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Voice {
+    Alloy,
+    Echo,
+    Fable,
+    Onyx,
+    Nova,
+    Shimmer,
+}
+
+/// The audio formats text-to-speech can produce.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SpeechResponseFormat {
+    Mp3,
+    Opus,
+    Aac,
+    Flac,
+}
+
+/// `OpenAI`s text-to-speech, which turns text into lifelike spoken audio.
+///
+/// For more information check the official [openAI API documentation](https://platform.openai.com/docs/api-reference/audio/createSpeech)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Speech {
+    /// ID of the model to use. One of the available TTS models: tts-1 or tts-1-hd.
+    pub model: String,
+
+    /// The text to generate audio for. The maximum length is 4096 characters.
+    pub input: String,
+
+    /// The voice to use when generating the audio.
+    pub voice: Voice,
+
+    /// The format of the generated audio. Defaults to mp3.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<SpeechResponseFormat>,
+
+    /// The speed of the generated audio. Select a value from 0.25 to 4.0. 1.0 is the default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speed: Option<f64>,
+}
+
+impl Speech {
+    pub const DEFAULT_MODEL: &'static str = "tts-1";
+    pub const DEFAULT_VOICE: Voice = Voice::Alloy;
+    /// The maximum number of characters of the input text.
+    pub const MAX_INPUT_CHARS: usize = 4096;
+    /// The range of valid speeds.
+    pub const SPEED_RANGE: std::ops::RangeInclusive<f64> = 0.25..=4.0;
+
+    /// Returns the default model to be used by this AI system.
+    ///
+    /// # Returns
+    ///
+    /// This function returns a static string slice (`&'static str`) which represents the identifier of the default model used by the AI system.
+    pub fn get_default_model() -> &'static str {
+        Self::DEFAULT_MODEL
+    }
+}
@@ -0,0 +1,274 @@
+/// The static capabilities of an `OpenAI` model, as returned by `model_info`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ModelInfo {
+    /// The name or name prefix of the models these capabilities belong to, e.g. `gpt-4` for
+    /// `gpt-4-0613`.
+    pub name: &'static str,
+
+    /// The maximum number of prompt and completion tokens combined.
+    pub context_window: usize,
+
+    /// The maximum number of tokens the model generates in a single response. It is 0 for
+    /// models that do not generate text, e.g. embedding models.
+    pub max_output_tokens: usize,
+
+    /// Whether the model can call functions.
+    pub supports_functions: bool,
+
+    /// Whether the model accepts images as input.
+    pub supports_vision: bool,
+
+    /// Whether the model is served by the `/v1/chat/completions` endpoint.
+    pub is_chat: bool,
+}
+
+/// The capabilities of the known models by name or name prefix.
+const MODELS: [ModelInfo; 27] = [
+    ModelInfo {
+        name: "gpt-4o",
+        context_window: 128_000,
+        max_output_tokens: 16_384,
+        supports_functions: true,
+        supports_vision: true,
+        is_chat: true,
+    },
+    ModelInfo {
+        name: "gpt-4o-mini",
+        context_window: 128_000,
+        max_output_tokens: 16_384,
+        supports_functions: true,
+        supports_vision: true,
+        is_chat: true,
+    },
+    ModelInfo {
+        name: "gpt-4.1",
+        context_window: 1_047_576,
+        max_output_tokens: 32_768,
+        supports_functions: true,
+        supports_vision: true,
+        is_chat: true,
+    },
+    ModelInfo {
+        name: "gpt-4.1-mini",
+        context_window: 1_047_576,
+        max_output_tokens: 32_768,
+        supports_functions: true,
+        supports_vision: true,
+        is_chat: true,
+    },
+    ModelInfo {
+        name: "gpt-4.1-nano",
+        context_window: 1_047_576,
+        max_output_tokens: 32_768,
+        supports_functions: true,
+        supports_vision: true,
+        is_chat: true,
+    },
+    ModelInfo {
+        name: "gpt-4.5-preview",
+        context_window: 128_000,
+        max_output_tokens: 16_384,
+        supports_functions: true,
+        supports_vision: true,
+        is_chat: true,
+    },
+    ModelInfo {
+        name: "gpt-4-turbo",
+        context_window: 128_000,
+        max_output_tokens: 4_096,
+        supports_functions: true,
+        supports_vision: true,
+        is_chat: true,
+    },
+    ModelInfo {
+        name: "gpt-4-1106",
+        context_window: 128_000,
+        max_output_tokens: 4_096,
+        supports_functions: true,
+        supports_vision: false,
+        is_chat: true,
+    },
+    ModelInfo {
+        name: "gpt-4-0125",
+        context_window: 128_000,
+        max_output_tokens: 4_096,
+        supports_functions: true,
+        supports_vision: false,
+        is_chat: true,
+    },
+    ModelInfo {
+        name: "gpt-4-vision-preview",
+        context_window: 128_000,
+        max_output_tokens: 4_096,
+        supports_functions: false,
+        supports_vision: true,
+        is_chat: true,
+    },
+    ModelInfo {
+        name: "gpt-4-32k",
+        context_window: 32_768,
+        max_output_tokens: 32_768,
+        supports_functions: true,
+        supports_vision: false,
+        is_chat: true,
+    },
+    ModelInfo {
+        name: "gpt-4",
+        context_window: 8_192,
+        max_output_tokens: 8_192,
+        supports_functions: true,
+        supports_vision: false,
+        is_chat: true,
+    },
+    ModelInfo {
+        name: "gpt-3.5-turbo",
+        context_window: 4_096,
+        max_output_tokens: 4_096,
+        supports_functions: true,
+        supports_vision: false,
+        is_chat: true,
+    },
+    ModelInfo {
+        name: "gpt-3.5-turbo-16k",
+        context_window: 16_385,
+        max_output_tokens: 4_096,
+        supports_functions: true,
+        supports_vision: false,
+        is_chat: true,
+    },
+    ModelInfo {
+        name: "gpt-3.5-turbo-1106",
+        context_window: 16_385,
+        max_output_tokens: 4_096,
+        supports_functions: true,
+        supports_vision: false,
+        is_chat: true,
+    },
+    ModelInfo {
+        name: "gpt-3.5-turbo-0125",
+        context_window: 16_385,
+        max_output_tokens: 4_096,
+        supports_functions: true,
+        supports_vision: false,
+        is_chat: true,
+    },
+    ModelInfo {
+        name: "gpt-3.5-turbo-instruct",
+        context_window: 4_096,
+        max_output_tokens: 4_096,
+        supports_functions: false,
+        supports_vision: false,
+        is_chat: false,
+    },
+    ModelInfo {
+        name: "o1",
+        context_window: 200_000,
+        max_output_tokens: 100_000,
+        supports_functions: true,
+        supports_vision: true,
+        is_chat: true,
+    },
+    ModelInfo {
+        name: "o1-mini",
+        context_window: 128_000,
+        max_output_tokens: 65_536,
+        supports_functions: false,
+        supports_vision: false,
+        is_chat: true,
+    },
+    ModelInfo {
+        name: "o1-preview",
+        context_window: 128_000,
+        max_output_tokens: 32_768,
+        supports_functions: false,
+        supports_vision: false,
+        is_chat: true,
+    },
+    ModelInfo {
+        name: "o3",
+        context_window: 200_000,
+        max_output_tokens: 100_000,
+        supports_functions: true,
+        supports_vision: true,
+        is_chat: true,
+    },
+    ModelInfo {
+        name: "o3-mini",
+        context_window: 200_000,
+        max_output_tokens: 100_000,
+        supports_functions: true,
+        supports_vision: false,
+        is_chat: true,
+    },
+    ModelInfo {
+        name: "o4",
+        context_window: 200_000,
+        max_output_tokens: 100_000,
+        supports_functions: true,
+        supports_vision: true,
+        is_chat: true,
+    },
+    ModelInfo {
+        name: "o4-mini",
+        context_window: 200_000,
+        max_output_tokens: 100_000,
+        supports_functions: true,
+        supports_vision: true,
+        is_chat: true,
+    },
+    ModelInfo {
+        name: "text-embedding-ada-002",
+        context_window: 8_191,
+        max_output_tokens: 0,
+        supports_functions: false,
+        supports_vision: false,
+        is_chat: false,
+    },
+    ModelInfo {
+        name: "text-embedding-3-small",
+        context_window: 8_191,
+        max_output_tokens: 0,
+        supports_functions: false,
+        supports_vision: false,
+        is_chat: false,
+    },
+    ModelInfo {
+        name: "text-embedding-3-large",
+        context_window: 8_191,
+        max_output_tokens: 0,
+        supports_functions: false,
+        supports_vision: false,
+        is_chat: false,
+    },
+];
+
+/// Looks up the capabilities of a model.
+///
+/// A model is looked up by the longest entry its name starts with, so that e.g. `gpt-4-0613`
+/// has the capabilities of `gpt-4` and `o4-mini-2025-04-16` those of `o4-mini`.
+///
+/// # Arguments
+///
+/// * `id` - The name of the model, e.g. "gpt-4o".
+///
+/// # Returns
+///
+/// This function returns `None` if the model is not known.
+///
+/// # Example
+///
+/// ```rust
+/// use aionic::openai::capabilities::model_info;
+///
+/// let info = model_info("gpt-4o-2024-08-06").unwrap();
+/// assert_eq!(info.context_window, 128_000);
+/// assert!(info.supports_vision);
+/// assert!(model_info("unknown-model").is_none());
+/// ```
+pub fn model_info(id: &str) -> Option<ModelInfo> {
+    MODELS
+        .iter()
+        .filter(|model| id.starts_with(model.name))
+        .max_by_key(|model| model.name.len())
+        .copied()
+}
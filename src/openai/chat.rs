@@ -1,6 +1,13 @@
-use crate::openai::misc::Usage;
-use serde::{Deserialize, Serialize};
+use crate::openai::capabilities::model_info;
+use crate::openai::misc::{InvalidFunctionArguments, Usage};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
+use std::error::Error;
+use std::future::Future;
+use std::io::{self, Write};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError};
 
 /// Represents the response from a chat model API call to `OpenAI`.
 ///
@@ -25,6 +32,10 @@ pub struct Response {
 
     /// Information on the number of tokens processed in the request.
     pub usage: Option<Usage>,
+
+    /// Fingerprint of the backend configuration the model runs with. Together with `seed`,
+    /// it can be used to detect backend changes that may affect determinism.
+    pub system_fingerprint: Option<String>,
 }
 
 /// Represents a choice made by the model in a chat API call.
@@ -34,12 +45,77 @@ pub struct Choice {
     pub message: Message,
 
     /// Reason for finishing the generation.
-    pub finish_reason: String,
+    pub finish_reason: FinishReason,
 
     /// Index of the choice in the list of choices.
     pub index: u64,
 }
 
+/// The reason why the model stopped generating tokens.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum FinishReason {
+    /// The model hit a natural stop point or a provided stop sequence.
+    Stop,
+
+    /// The maximum number of tokens specified in the request was reached.
+    Length,
+
+    /// The model called a function.
+    FunctionCall,
+
+    /// The model called one or more tools.
+    ToolCalls,
+
+    /// Content was omitted due to a flag from the content filters.
+    ContentFilter,
+
+    /// A finish reason that is not known to this crate, e.g. one added to the API later.
+    Other(String),
+
+    /// No finish reason was given, e.g. for a streamed chunk that is not the last one.
+    #[default]
+    Null,
+}
+
+impl FinishReason {
+    /// Returns the string representation used by the `OpenAI` API, or `None` for `FinishReason::Null`.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::Stop => Some("stop"),
+            Self::Length => Some("length"),
+            Self::FunctionCall => Some("function_call"),
+            Self::ToolCalls => Some("tool_calls"),
+            Self::ContentFilter => Some("content_filter"),
+            Self::Other(reason) => Some(reason),
+            Self::Null => None,
+        }
+    }
+}
+
+impl Serialize for FinishReason {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.as_str() {
+            Some(reason) => serializer.serialize_str(reason),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for FinishReason {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let reason = Option::<String>::deserialize(deserializer)?;
+        Ok(match reason.as_deref() {
+            Some("stop") => Self::Stop,
+            Some("length") => Self::Length,
+            Some("function_call") => Self::FunctionCall,
+            Some("tool_calls") => Self::ToolCalls,
+            Some("content_filter") => Self::ContentFilter,
+            Some(other) => Self::Other(other.to_string()),
+            None => Self::Null,
+        })
+    }
+}
+
 /// Represents the response from a streaming chat model API call to `OpenAI`.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct StreamedReponse {
@@ -57,6 +133,15 @@ pub struct StreamedReponse {
 
     /// Choices made by the chat model during the conversation.
     pub choices: Vec<StreamedChoices>,
+
+    /// Fingerprint of the backend configuration the model runs with.
+    #[serde(default)]
+    pub system_fingerprint: Option<String>,
+
+    /// The token usage of the whole request. It is only sent with the last chunk, which has no
+    /// choices, if `stream_options` include the usage.
+    #[serde(default)]
+    pub usage: Option<Usage>,
 }
 
 /// Represents a choice made by the model in a streaming chat API call.
@@ -68,8 +153,9 @@ pub struct StreamedChoices {
     /// Information about the change made by the model.
     pub delta: Delta,
 
-    /// Reason for finishing the generation.
-    pub finish_reason: Option<String>,
+    /// Reason for finishing the generation. This is `FinishReason::Null` for all but the last chunk.
+    #[serde(default)]
+    pub finish_reason: FinishReason,
 }
 
 /// Represents a change made by the model in a streaming chat API call.
@@ -80,6 +166,43 @@ pub struct Delta {
 
     /// Content of the change made.
     pub content: Option<String>,
+
+    /// A fragment of the function call the model makes, if it chose to call a function.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub function_call: Option<FunctionCallDelta>,
+
+    /// Fragments of the tool calls the model makes, if it chose to call tools.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCallDelta>>,
+}
+
+/// A fragment of a streamed function call. The name is sent with the first fragment, the
+/// arguments are spread over all of them.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct FunctionCallDelta {
+    /// The name of the function to call.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// The next part of the arguments in JSON format.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<String>,
+}
+
+/// A fragment of a streamed tool call. The ID and function name are sent with the first fragment
+/// of a call, the arguments are spread over all of them.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ToolCallDelta {
+    /// The position of the tool call in the list of tool calls.
+    pub index: usize,
+
+    /// The ID of the tool call.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    /// The fragment of the function to call.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub function: Option<FunctionCallDelta>,
 }
 
 /// Enumeration of roles for authors of messages in a chat API call.
@@ -88,7 +211,12 @@ pub enum MessageRole {
     User,
     Assistant,
     System,
+    /// Replaces `System` for newer models. Both are translated to the role the configured
+    /// model expects when the request is sent.
+    Developer,
     Function,
+    /// The result of a tool call, which references the call by its ID.
+    Tool,
 }
 
 impl ToString for MessageRole {
@@ -97,7 +225,9 @@ impl ToString for MessageRole {
             Self::User => "user".to_string(),
             Self::Assistant => "assistant".to_string(),
             Self::System => "system".to_string(),
+            Self::Developer => "developer".to_string(),
             Self::Function => "function".to_string(),
+            Self::Tool => "tool".to_string(),
         }
     }
 }
@@ -107,26 +237,195 @@ impl<T: Into<String>> From<T> for MessageRole {
         match s.into().as_str() {
             "assistant" => Self::Assistant,
             "system" => Self::System,
+            "developer" => Self::Developer,
             "function" => Self::Function,
+            "tool" => Self::Tool,
             _ => Self::User,
         }
     }
 }
 
+/// Deserializes a message role, normalizing `developer` to `system` so that a conversation
+/// reads the same regardless of which model produced it.
+fn deserialize_role<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let role = String::deserialize(deserializer)?;
+    if role == "developer" {
+        Ok(MessageRole::System.to_string())
+    } else {
+        Ok(role)
+    }
+}
+
+/// The content of a message, which is either plain text or, for vision models, a list of
+/// text and image parts.
+///
+/// It is serialized as a plain string or as an array of parts, respectively.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Content {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+impl Content {
+    /// Returns the text if this is a plain text content.
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            Self::Text(text) => Some(text),
+            Self::Parts(_) => None,
+        }
+    }
+
+    /// Checks whether the content holds neither text nor images.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Self::Text(text) => text.is_empty(),
+            Self::Parts(parts) => parts.is_empty(),
+        }
+    }
+}
+
+impl Default for Content {
+    fn default() -> Self {
+        Self::Text(String::new())
+    }
+}
+
+/// Displays the text of the content. Of a multi-part content only the text parts are shown,
+/// separated by newlines.
+impl std::fmt::Display for Content {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Text(text) => write!(f, "{text}"),
+            Self::Parts(parts) => {
+                let texts: Vec<&str> = parts
+                    .iter()
+                    .filter_map(|part| match part {
+                        ContentPart::Text { text } => Some(text.as_str()),
+                        ContentPart::ImageUrl { .. } => None,
+                    })
+                    .collect();
+                write!(f, "{}", texts.join("\n"))
+            }
+        }
+    }
+}
+
+impl From<String> for Content {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl From<&String> for Content {
+    fn from(text: &String) -> Self {
+        Self::Text(text.clone())
+    }
+}
+
+impl From<&str> for Content {
+    fn from(text: &str) -> Self {
+        Self::Text(text.to_string())
+    }
+}
+
+impl From<Vec<ContentPart>> for Content {
+    fn from(parts: Vec<ContentPart>) -> Self {
+        Self::Parts(parts)
+    }
+}
+
+impl PartialEq<str> for Content {
+    fn eq(&self, other: &str) -> bool {
+        self.as_text() == Some(other)
+    }
+}
+
+impl PartialEq<&str> for Content {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_text() == Some(*other)
+    }
+}
+
+/// A single part of a multi-part message content.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(from = "RawContentPart", into = "RawContentPart")]
+pub enum ContentPart {
+    /// A text part.
+    Text { text: String },
+
+    /// An image given by URL, or as base64 encoded `data:` URL.
+    ImageUrl {
+        url: String,
+        detail: Option<ImageDetail>,
+    },
+}
+
+/// The level of detail a vision model looks at an image with.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageDetail {
+    Auto,
+    Low,
+    High,
+}
+
+/// The wire format of a `ContentPart`, i.e. `{"type": "text", "text": ..}` and
+/// `{"type": "image_url", "image_url": {"url": .., "detail": ..}}`.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum RawContentPart {
+    Text { text: String },
+    ImageUrl { image_url: RawImageUrl },
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct RawImageUrl {
+    url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<ImageDetail>,
+}
+
+impl From<RawContentPart> for ContentPart {
+    fn from(part: RawContentPart) -> Self {
+        match part {
+            RawContentPart::Text { text } => Self::Text { text },
+            RawContentPart::ImageUrl { image_url } => Self::ImageUrl {
+                url: image_url.url,
+                detail: image_url.detail,
+            },
+        }
+    }
+}
+
+impl From<ContentPart> for RawContentPart {
+    fn from(part: ContentPart) -> Self {
+        match part {
+            ContentPart::Text { text } => Self::Text { text },
+            ContentPart::ImageUrl { url, detail } => Self::ImageUrl {
+                image_url: RawImageUrl { url, detail },
+            },
+        }
+    }
+}
+
 /// Represents a single Message exchanged with the `OpenAI` API during a conversational model session.
 ///
 /// `Message` struct is used to encapsulate the details of an individual message in the conversation. This includes the role of the author,
 /// the content of the message, the name of the author if the role is 'function', and information about any function that should be called.
 ///
 /// Each message sent or received in a conversational model session with `OpenAI` API will be represented by an instance of this struct.
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct Message {
-    /// The role of the messages author. One of system, user, assistant, or function.
+    /// The role of the messages author. One of system, user, assistant, function, or tool.
+    /// A `developer` role is normalized to `system` when deserialized.
+    #[serde(deserialize_with = "deserialize_role")]
     pub role: String,
 
-    /// The contents of the message. content is required for all messages, and may be null for
-    /// assistant messages with function calls.
-    pub content: String,
+    /// The contents of the message. content is required for all messages, and is `None` (sent
+    /// as null) for assistant messages with function or tool calls.
+    #[serde(default)]
+    pub content: Option<Content>,
 
     /// The name of the author of this message. name is required if role is function, and it should
     /// be the name of the function whose response is in the content. May contain a-z, A-Z, 0-9,
@@ -137,6 +436,14 @@ pub struct Message {
     /// The name and arguments of a function that should be called, as generated by the model.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub function_call: Option<FunctionCall>,
+
+    /// The tools that should be called, as generated by the model.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+
+    /// The ID of the tool call whose result is in the content, for messages with the `tool` role.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
 }
 
 impl Message {
@@ -156,40 +463,227 @@ impl Message {
     ///
     /// let user_message = Message::new(&MessageRole::User, "Hello, assistant!");
     /// ```
-    pub fn new<S: Into<String>>(role: &MessageRole, content: S) -> Self {
+    pub fn new<S: Into<Content>>(role: &MessageRole, content: S) -> Self {
         Self {
             role: role.to_string(),
-            content: content.into(),
+            content: Some(content.into()),
             name: None,
             function_call: None,
+            tool_calls: None,
+            tool_call_id: None,
         }
     }
+
+    /// Constructs a new system `Message` instance. When sent to a model that expects the
+    /// `developer` role instead, the role is translated automatically.
+    ///
+    /// # Arguments
+    ///
+    /// * `content`: The instruction for the AI assistant.
+    pub fn system<S: Into<String>>(content: S) -> Self {
+        Self::new(&MessageRole::System, content.into())
+    }
+
+    /// Constructs a new `Message` with the result of a function call, to send it back to the
+    /// model after it requested the call.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The name of the function that was called.
+    /// * `content`: The result of the function, e.g. as JSON.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use aionic::openai::chat::Message;
+    ///
+    /// let msg = Message::function_response("get_weather", r#"{"temperature": 22}"#);
+    /// assert_eq!(msg.name.as_deref(), Some("get_weather"));
+    /// ```
+    pub fn function_response<N: Into<String>, S: Into<String>>(name: N, content: S) -> Self {
+        let mut msg = Self::new(&MessageRole::Function, content.into());
+        msg.name = Some(name.into());
+        msg
+    }
+
+    /// Constructs a new `Message` with the result of a tool call, to send it back to the model
+    /// after it requested the call.
+    ///
+    /// # Arguments
+    ///
+    /// * `tool_call_id`: The ID of the tool call, as given in `ToolCall::id`.
+    /// * `content`: The result of the tool, e.g. as JSON.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use aionic::openai::chat::Message;
+    ///
+    /// let msg = Message::tool_result("call_abc123", r#"{"temperature": 22}"#);
+    /// assert_eq!(msg.role, "tool");
+    /// assert_eq!(msg.tool_call_id.as_deref(), Some("call_abc123"));
+    /// ```
+    pub fn tool_result<I: Into<String>, S: Into<String>>(tool_call_id: I, content: S) -> Self {
+        let mut msg = Self::new(&MessageRole::Tool, content.into());
+        msg.tool_call_id = Some(tool_call_id.into());
+        msg
+    }
+
+    /// Constructs a new user `Message` that asks about an image, for use with vision models.
+    ///
+    /// # Arguments
+    ///
+    /// * `text`: The question or instruction about the image.
+    /// * `image_url`: The URL of the image, or a base64 encoded `data:` URL.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use aionic::openai::chat::Message;
+    ///
+    /// let msg = Message::with_image("What is in this image?", "https://example.com/cat.png");
+    /// ```
+    pub fn with_image<S: Into<String>, U: Into<String>>(text: S, image_url: U) -> Self {
+        Self::new(
+            &MessageRole::User,
+            vec![
+                ContentPart::Text { text: text.into() },
+                ContentPart::ImageUrl {
+                    url: image_url.into(),
+                    detail: None,
+                },
+            ],
+        )
+    }
+
+    /// Returns a `MessageBuilder` for messages that need more than a role and content, e.g.
+    /// function results.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use aionic::openai::chat::{Message, MessageRole};
+    ///
+    /// let msg = Message::builder()
+    ///     .role(MessageRole::Function)
+    ///     .name("get_weather")
+    ///     .content(r#"{"temperature": 22}"#)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(msg.name.as_deref(), Some("get_weather"));
+    /// ```
+    pub fn builder() -> MessageBuilder {
+        MessageBuilder::default()
+    }
+
+    /// Checks whether the given name is a valid author name, i.e. 1 to 64 characters of a-z,
+    /// A-Z, 0-9 and underscores.
+    pub fn is_valid_name(name: &str) -> bool {
+        (1..=64).contains(&name.len())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    }
+}
+
+/// A builder for a `Message`, created with `Message::builder()`.
+///
+/// The role defaults to `MessageRole::User` and the content to none.
+#[derive(Clone, Debug, Default)]
+pub struct MessageBuilder {
+    role: Option<MessageRole>,
+    content: Option<Content>,
+    name: Option<String>,
+    function_call: Option<FunctionCall>,
+}
+
+impl MessageBuilder {
+    /// Sets the role of the message's author.
+    pub fn role(mut self, role: MessageRole) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    /// Sets the content of the message.
+    pub fn content<S: Into<Content>>(mut self, content: S) -> Self {
+        self.content = Some(content.into());
+        self
+    }
+
+    /// Sets the name of the message's author, e.g. the function whose result is the content.
+    pub fn name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Sets the function call the assistant requested with this message.
+    pub fn function_call(mut self, function_call: FunctionCall) -> Self {
+        self.function_call = Some(function_call);
+        self
+    }
+
+    /// Builds the `Message`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns an `InvalidInput` error if the role is `MessageRole::Function`
+    /// but no name is set, or if the name is not valid according to `Message::is_valid_name`.
+    pub fn build(self) -> Result<Message, Box<dyn std::error::Error + Send + Sync>> {
+        let role = self.role.unwrap_or(MessageRole::User);
+        let invalid = |msg: String| std::io::Error::new(std::io::ErrorKind::InvalidInput, msg);
+        match self.name.as_deref() {
+            None if matches!(role, MessageRole::Function) => {
+                return Err(Box::new(invalid(
+                    "A message with the function role requires the name of the function".into(),
+                )));
+            }
+            Some(name) if !Message::is_valid_name(name) => {
+                return Err(Box::new(invalid(format!(
+                    "Invalid name `{name}`, expected 1 to 64 characters of a-z, A-Z, 0-9 or underscores"
+                ))));
+            }
+            _ => {}
+        }
+        Ok(Message {
+            role: role.to_string(),
+            content: self.content,
+            name: self.name,
+            function_call: self.function_call,
+            tool_calls: None,
+            tool_call_id: None,
+        })
+    }
 }
 
 impl<T: Into<String>> From<T> for Message {
     fn from(s: T) -> Self {
         Self {
             role: MessageRole::User.to_string(),
-            content: s.into(),
+            content: Some(Content::Text(s.into())),
             name: None,
             function_call: None,
+            tool_calls: None,
+            tool_call_id: None,
         }
     }
 }
 
 impl std::fmt::Display for Message {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let role = match self.role.as_str() {
-            "assistant" => MessageRole::Assistant,
-            "system" => MessageRole::System,
-            "function" => MessageRole::Function,
-            _ => MessageRole::User,
-        };
-        write!(f, "{}: {}", role.to_string(), self.content)
+        let role = MessageRole::from(self.role.as_str());
+        if let Some(content) = &self.content {
+            return write!(f, "{}: {}", role.to_string(), content);
+        }
+        let calls: Vec<String> = self
+            .function_call
+            .iter()
+            .chain(self.tool_calls.iter().flatten().map(|call| &call.function))
+            .map(|call| format!("{}({})", call.name, call.arguments))
+            .collect();
+        write!(f, "{}: {}", role.to_string(), calls.join(", "))
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+/// The name and arguments of a function that should be called, as generated by the model.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
 pub struct FunctionCall {
     /// The name of the function to call.
     pub name: String,
@@ -202,11 +696,284 @@ pub struct FunctionCall {
     pub arguments: String,
 }
 
+/// Controls how the model responds to function calls.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FunctionCallBehavior {
+    /// The model does not call a function and responds to the end-user.
+    None,
+
+    /// The model can pick between responding to the end-user or calling a function.
+    Auto,
+
+    /// Forces the model to call the function with the given name.
+    Named(String),
+}
+
+/// Another name of `FunctionCallBehavior`, i.e. whether the model calls no function, picks one
+/// itself or calls the named one.
+pub type FunctionCallMode = FunctionCallBehavior;
+
+impl Serialize for FunctionCallBehavior {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::None => serializer.serialize_str("none"),
+            Self::Auto => serializer.serialize_str("auto"),
+            Self::Named(name) => {
+                use serde::ser::SerializeMap;
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("name", name)?;
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for FunctionCallBehavior {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Named {
+            name: String,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Mode(String),
+            Named(Named),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Mode(mode) if mode == "none" => Ok(Self::None),
+            Repr::Mode(mode) if mode == "auto" => Ok(Self::Auto),
+            Repr::Mode(mode) => Err(serde::de::Error::custom(format!(
+                "Invalid function_call mode: {mode}"
+            ))),
+            Repr::Named(named) => Ok(Self::Named(named.name)),
+        }
+    }
+}
+
+/// A tool the model may call. Currently only functions are supported as tools.
+///
+/// It is serialized as `{"type": "function", "function": {...}}`.
 #[derive(Serialize, Deserialize, Clone, Debug)]
-#[serde(untagged)]
-pub enum Stop {
-    String(String),
-    Array(Vec<String>),
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Tool {
+    /// A function the model may generate JSON inputs for.
+    Function { function: Function },
+}
+
+impl From<Function> for Tool {
+    fn from(function: Function) -> Self {
+        Self::Function { function }
+    }
+}
+
+/// A call of a tool, as generated by the model.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ToolCall {
+    /// The ID of the tool call, which the `tool` message with its result has to reference.
+    pub id: String,
+
+    /// The type of the tool, which is always "function".
+    #[serde(rename = "type", default = "ToolCall::function_type")]
+    pub kind: String,
+
+    /// The name and arguments of the function to call.
+    pub function: FunctionCall,
+}
+
+impl ToolCall {
+    fn function_type() -> String {
+        "function".into()
+    }
+}
+
+impl Default for ToolCall {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            kind: Self::function_type(),
+            function: FunctionCall::default(),
+        }
+    }
+}
+
+/// Controls which tool, if any, the model calls.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ToolChoice {
+    /// The model does not call a tool and responds to the end-user.
+    None,
+
+    /// The model can pick between responding to the end-user or calling tools.
+    Auto,
+
+    /// The model must call one or more tools.
+    Required,
+
+    /// Forces the model to call the function with the given name.
+    Function(String),
+}
+
+impl Serialize for ToolChoice {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::None => serializer.serialize_str("none"),
+            Self::Auto => serializer.serialize_str("auto"),
+            Self::Required => serializer.serialize_str("required"),
+            Self::Function(name) => {
+                use serde::ser::SerializeMap;
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("type", "function")?;
+                map.serialize_entry("function", &HashMap::from([("name", name)]))?;
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ToolChoice {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Named {
+            name: String,
+        }
+
+        #[derive(Deserialize)]
+        struct Forced {
+            function: Named,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Mode(String),
+            Forced(Forced),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Mode(mode) if mode == "none" => Ok(Self::None),
+            Repr::Mode(mode) if mode == "auto" => Ok(Self::Auto),
+            Repr::Mode(mode) if mode == "required" => Ok(Self::Required),
+            Repr::Mode(mode) => Err(serde::de::Error::custom(format!(
+                "Invalid tool_choice mode: {mode}"
+            ))),
+            Repr::Forced(forced) => Ok(Self::Function(forced.function.name)),
+        }
+    }
+}
+
+/// The outcome of a chat request, which is either a text answer or a request by the model
+/// to call one of the provided functions.
+#[derive(Clone, Debug)]
+pub enum AskOutcome {
+    /// The text answer of the model.
+    Text(String),
+
+    /// The function the model wants to call, including the arguments in JSON format.
+    FunctionCall(FunctionCall),
+
+    /// The tools the model wants to call, each with the ID its result has to reference.
+    ToolCalls(Vec<ToolCall>),
+}
+
+/// Represents a function or tool call requested by the model from a method that only accepts a
+/// text answer, e.g. `OpenAI::ask`.
+///
+/// The call is kept, so it can be handled without repeating the request. To get calls as a
+/// regular result instead, use `OpenAI::ask_with_outcome`.
+#[derive(Debug, Clone)]
+pub struct UnhandledCall {
+    /// The function the model wants to call, if any.
+    pub function_call: Option<FunctionCall>,
+
+    /// The tools the model wants to call, which may be empty.
+    pub tool_calls: Vec<ToolCall>,
+}
+
+impl std::fmt::Display for UnhandledCall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.function_call {
+            Some(function_call) => write!(
+                f,
+                "The model requested a call to function `{}`, use `ask_detailed` or `ask_with_outcome` to handle it",
+                function_call.name
+            ),
+            None => write!(
+                f,
+                "The model requested tool calls, use `ask_detailed` or `ask_with_outcome` to handle them"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for UnhandledCall {}
+
+/// A piece of a streamed chat response, as yielded by `OpenAI::ask_stream`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChatChunk {
+    /// The index of the choice the chunk belongs to.
+    pub index: u64,
+
+    /// The role of the author, which is usually only sent with the first chunk.
+    pub role: Option<String>,
+
+    /// The content delta, if the chunk carries one.
+    pub content: Option<String>,
+
+    /// The reason the model stopped generating, which is `FinishReason::Null` for all but the
+    /// last chunk of a choice.
+    pub finish_reason: FinishReason,
+}
+
+/// The text answer of a chat request together with the metadata of its response, as returned
+/// by `OpenAI::ask_detailed`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChatOutcome {
+    /// The text answer of the model.
+    pub text: String,
+
+    /// The reason the model stopped generating, e.g. `FinishReason::Length` if the answer was
+    /// cut off by `max_tokens`.
+    pub finish_reason: FinishReason,
+
+    /// The token usage of the request. For streamed responses it is estimated, unless the
+    /// `stream_options` include the usage.
+    pub usage: Option<Usage>,
+
+    /// The unique ID of the completion, e.g. "chatcmpl-123".
+    pub id: Option<String>,
+
+    /// The model that generated the answer as reported by the API, e.g. "gpt-4-0613".
+    pub model: Option<String>,
+
+    /// The function the model wants to call, if it chose to call one of the functions set with
+    /// `set_functions`. The text is empty then and the finish reason is
+    /// `FinishReason::FunctionCall`.
+    pub function_call: Option<FunctionCall>,
+
+    /// The tools the model wants to call, if it chose to call any of the tools set with
+    /// `set_tools`. The finish reason is `FinishReason::ToolCalls` then.
+    pub tool_calls: Vec<ToolCall>,
+}
+
+/// Deserializes the stop sequences, which the API also accepts as a single string.
+fn deserialize_stop<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Vec<String>>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Stop {
+        Single(String),
+        Many(Vec<String>),
+    }
+
+    Ok(
+        Option::<Stop>::deserialize(deserializer)?.map(|stop| match stop {
+            Stop::Single(sequence) => vec![sequence],
+            Stop::Many(sequences) => sequences,
+        }),
+    )
 }
 
 /// This struct is used for chat completions with `OpenAI`'s models.
@@ -242,7 +1009,22 @@ pub struct Chat {
     /// Specifying a particular function via {"name":\ "my_function"} forces the model to call that function.
     /// "none" is the default when no functions are present. "auto" is the default if functions are present.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub function_call: Option<String>,
+    pub function_call: Option<FunctionCallBehavior>,
+
+    /// A list of tools the model may call, which supersedes `functions` for newer models.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
+
+    /// Controls which tool, if any, the model calls. "none" is the default when no tools are
+    /// present, "auto" if tools are present.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+
+    /// The format the model must output. Setting it to `ChatResponseFormat::JsonObject` enables JSON mode,
+    /// which guarantees that the message the model generates is valid JSON. When using JSON mode, the
+    /// model must also be instructed to produce JSON via a system message.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ChatResponseFormat>,
 
     /// What sampling temperature to use, between 0 and 2. Higher values like 0.8 will make the output more random,
     /// while lower values like 0.2 will make it more focused and deterministic.
@@ -256,23 +1038,38 @@ pub struct Chat {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub top_p: Option<f64>,
 
-    /// How many chat completion choices to generate for each input message.
+    /// How many chat completion choices to generate for each input message, between 1 and 128.
+    /// `ask` only answers with the first choice, use `ask_many` to get all of them.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub n: Option<i64>,
+    pub n: Option<u32>,
 
     /// If set, partial message deltas will be sent, like in ChatGPT.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stream: Option<bool>,
 
-    /// Up to 4 sequences where the API will stop generating further tokens.
+    /// Options of streamed responses. They are only sent if the response is streamed.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub stop: Option<Stop>,
+    pub stream_options: Option<StreamOptions>,
+
+    /// Up to 4 sequences where the API will stop generating further tokens.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_stop"
+    )]
+    pub stop: Option<Vec<String>>,
 
     /// The maximum number of tokens to generate in the chat completion.
     /// The total length of input tokens and generated tokens is limited by the model's context length.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_tokens: Option<u64>,
 
+    /// If specified, the system will make a best effort to sample deterministically, such that repeated
+    /// requests with the same seed and parameters should return the same result. Determinism is not
+    /// guaranteed, and `system_fingerprint` of the response should be used to monitor backend changes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+
     /// Number between -2.0 and 2.0. Positive values penalize new tokens based on whether they appear in the text
     /// so far, increasing the model's likelihood to talk about new topics.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -296,6 +1093,177 @@ pub struct Chat {
     /// A unique identifier representing your end-user, which can help `OpenAI` to monitor and detect abuse.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user: Option<String>,
+
+    /// The optional limit the conversation history is trimmed to before every request.
+    #[serde(skip)]
+    pub history_limit: Option<HistoryStrategy>,
+
+    /// The messages that were dropped from the history by the last trimming.
+    #[serde(skip)]
+    pub trimmed_messages: Vec<Message>,
+
+    /// The models to try, in order, if `model` is not found or overloaded.
+    #[serde(skip)]
+    pub fallback_models: Vec<String>,
+
+    /// The callback that receives the content deltas of streamed responses instead of stdout.
+    #[serde(skip)]
+    pub stream_callback: Option<StreamCallback>,
+
+    /// The writer that receives the streamed answers instead of stdout.
+    #[serde(skip)]
+    pub output: Option<StreamOutput>,
+
+    /// The functions that are called by `ask_with_functions` when the model requests them, by
+    /// name.
+    #[serde(skip)]
+    pub function_handlers: HashMap<String, FunctionHandler>,
+
+    /// The maximum number of function calls `ask_with_functions` makes for a single prompt.
+    #[serde(skip)]
+    pub max_function_calls: Option<usize>,
+}
+
+/// A function that is called with every content delta of a streamed response.
+type DeltaFn = Box<dyn FnMut(&str) + Send>;
+
+/// A callback that receives the content deltas of streamed responses, e.g. to render them in a
+/// TUI. It is shared by all clones of a client.
+#[derive(Clone)]
+pub struct StreamCallback(Arc<Mutex<DeltaFn>>);
+
+impl StreamCallback {
+    /// Wraps a function that is called with every content delta.
+    pub fn new(callback: DeltaFn) -> Self {
+        Self(Arc::new(Mutex::new(callback)))
+    }
+
+    /// Calls the callback with a content delta.
+    pub fn call(&self, delta: &str) {
+        let mut callback = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        callback(delta);
+    }
+}
+
+impl std::fmt::Debug for StreamCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("StreamCallback")
+    }
+}
+
+/// A writer that receives the streamed answers instead of stdout, e.g. a file to keep a live
+/// transcript. It is shared by all clones of a client.
+#[derive(Clone)]
+pub struct StreamOutput(Arc<Mutex<Box<dyn Write + Send>>>);
+
+impl StreamOutput {
+    /// Wraps a writer that receives the streamed answers.
+    pub fn new(writer: Box<dyn Write + Send>) -> Self {
+        Self(Arc::new(Mutex::new(writer)))
+    }
+
+    /// Writes a piece of text and flushes the writer, so every token shows up immediately.
+    pub fn write(&self, text: &str) -> io::Result<()> {
+        let mut writer = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        writer.write_all(text.as_bytes())?;
+        writer.flush()
+    }
+}
+
+impl std::fmt::Debug for StreamOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("StreamOutput")
+    }
+}
+
+/// The pending result of a function registered with `OpenAI::register_function`.
+type FunctionResult =
+    Pin<Box<dyn Future<Output = Result<String, Box<dyn Error + Send + Sync>>> + Send>>;
+
+/// A function that is called with the raw arguments of a function call.
+type HandlerFn = dyn Fn(&str) -> FunctionResult + Send + Sync;
+
+/// A function that is run when the model requests to call it, as registered with
+/// `OpenAI::register_function`. It is shared by all clones of a client.
+#[derive(Clone)]
+pub struct FunctionHandler(Arc<HandlerFn>);
+
+impl FunctionHandler {
+    /// Wraps an async function that takes the parsed arguments of a function call and returns
+    /// the result that is sent back to the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The name of the function, which is reported if its arguments are invalid.
+    /// * `handler`: The function to call.
+    pub fn new<A, F, Fut>(name: impl Into<String>, handler: F) -> Self
+    where
+        A: DeserializeOwned,
+        F: Fn(A) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, Box<dyn Error + Send + Sync>>> + Send + 'static,
+    {
+        let name = name.into();
+        Self(Arc::new(move |arguments: &str| -> FunctionResult {
+            match serde_json::from_str::<A>(arguments) {
+                Ok(args) => Box::pin(handler(args)),
+                Err(e) => {
+                    let err = InvalidFunctionArguments {
+                        function: name.clone(),
+                        arguments: arguments.to_string(),
+                        message: e.to_string(),
+                    };
+                    Box::pin(async move { Err(Box::new(err) as Box<dyn Error + Send + Sync>) })
+                }
+            }
+        }))
+    }
+
+    /// Calls the function with the arguments generated by the model in JSON format.
+    ///
+    /// # Returns
+    ///
+    /// The result of the function, or an `InvalidFunctionArguments` error if the arguments do
+    /// not match the type the function expects.
+    pub async fn call(&self, arguments: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+        (self.0)(arguments).await
+    }
+}
+
+impl std::fmt::Debug for FunctionHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("FunctionHandler")
+    }
+}
+
+/// The options of a streamed chat response.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StreamOptions {
+    /// If true, the usage of the whole request is sent with an additional last chunk.
+    pub include_usage: bool,
+}
+
+/// The format the chat model must output.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChatResponseFormat {
+    /// Regular text output.
+    Text,
+
+    /// JSON mode, which guarantees that the output is a valid JSON object.
+    JsonObject,
+}
+
+/// Determines how much of the conversation history is kept when it is trimmed.
+///
+/// Trimming always drops the oldest messages first, but never drops the leading system
+/// messages (e.g. a primer) or the most recent message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistoryStrategy {
+    /// Keep at most this many messages in addition to the leading system messages.
+    MaxMessages(usize),
+
+    /// Keep as many messages as fit into this many prompt tokens.
+    MaxTokens(usize),
 }
 
 impl Chat {
@@ -303,6 +1271,21 @@ impl Chat {
     const DEFAULT_MAX_TOKENS: u64 = 2048;
     const DEFAULT_STREAM_RESPONSE: bool = true;
     const DEFAULT_MODEL: &str = "gpt-3.5-turbo";
+    /// Model families that expect the `developer` role in place of `system`.
+    const DEVELOPER_ROLE_MODELS: &'static [&'static str] = &["o1", "o3", "o4"];
+    const DEFAULT_CONTEXT_WINDOW: usize = 4_096;
+    /// The smallest and largest bias that can be added to the logit of a token.
+    pub const MIN_LOGIT_BIAS: f32 = -100.0;
+    pub const MAX_LOGIT_BIAS: f32 = 100.0;
+    /// The smallest and largest value of `presence_penalty` and `frequency_penalty`.
+    pub const MIN_PENALTY: f32 = -2.0;
+    pub const MAX_PENALTY: f32 = 2.0;
+    /// The maximum number of stop sequences of a request.
+    pub const MAX_STOP_SEQUENCES: usize = 4;
+    /// The maximum number of choices of a request.
+    pub const MAX_N: u32 = 128;
+    /// The number of function calls `ask_with_functions` makes for a prompt unless set otherwise.
+    pub const DEFAULT_MAX_FUNCTION_CALLS: usize = 8;
     /// Returns the default temperature for this AI system.
     ///
     /// # Returns
@@ -339,6 +1322,53 @@ impl Chat {
     pub fn get_default_model() -> &'static str {
         Self::DEFAULT_MODEL
     }
+
+    /// Returns the context window of the given model, i.e. the maximum number of prompt and
+    /// completion tokens combined.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the context window in tokens according to `model_info`, or a conservative
+    /// 4096 tokens for unknown models.
+    pub fn get_context_window(model: &str) -> usize {
+        model_info(model).map_or(Self::DEFAULT_CONTEXT_WINDOW, |info| info.context_window)
+    }
+
+    /// Checks whether the given bias is within the valid range of a `logit_bias` value.
+    ///
+    /// # Returns
+    ///
+    /// This function returns true if the bias is within `-100.0..=100.0`, otherwise false.
+    pub fn is_valid_logit_bias(bias: f32) -> bool {
+        (Self::MIN_LOGIT_BIAS..=Self::MAX_LOGIT_BIAS).contains(&bias)
+    }
+
+    /// Checks whether the given penalty is within the valid range of `presence_penalty` and
+    /// `frequency_penalty`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns true if the penalty is within `-2.0..=2.0`, otherwise false.
+    pub fn is_valid_penalty(penalty: f32) -> bool {
+        (Self::MIN_PENALTY..=Self::MAX_PENALTY).contains(&penalty)
+    }
+
+    /// Returns the role that system instructions are sent with for the given model.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `MessageRole::Developer` for the o-series models and
+    /// `MessageRole::System` for all other models.
+    pub fn get_system_role(model: &str) -> MessageRole {
+        let is_developer_model = Self::DEVELOPER_ROLE_MODELS
+            .iter()
+            .any(|family| model == *family || model.starts_with(&format!("{family}-")));
+        if is_developer_model {
+            MessageRole::Developer
+        } else {
+            MessageRole::System
+        }
+    }
 }
 
 /// This struct is used to describe a single function the model may generate JSON inputs for.
@@ -355,6 +1385,39 @@ pub struct Function {
     /// reference for documentation about the format.
     ///
     /// To describe a function that accepts no parameters, provide the value {"type": "object", "properties": {}}.
-    // FIXME:
-    pub parameters: String,
+    pub parameters: serde_json::Value,
+}
+
+impl Function {
+    /// Constructs a new `Function` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The name of the function to be called.
+    /// * `description`: A description of what the function does.
+    /// * `parameters`: The parameters the function accepts, described as a JSON Schema object.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use aionic::openai::chat::Function;
+    /// use serde_json::json;
+    ///
+    /// let function = Function::new(
+    ///     "get_weather",
+    ///     "Get the current weather in a given location",
+    ///     json!({
+    ///         "type": "object",
+    ///         "properties": { "location": { "type": "string" } },
+    ///         "required": ["location"]
+    ///     }),
+    /// );
+    /// ```
+    pub fn new<S: Into<String>>(name: S, description: S, parameters: serde_json::Value) -> Self {
+        Self {
+            name: name.into(),
+            description: Some(description.into()),
+            parameters,
+        }
+    }
 }
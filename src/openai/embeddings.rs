@@ -1,5 +1,8 @@
 use crate::openai::misc::Usage;
-use serde::{Deserialize, Serialize};
+use crate::openai::tokenizer;
+use crate::openai::OpenAIConfig;
+use base64::Engine;
+use serde::{Deserialize, Deserializer, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(untagged)]
@@ -7,6 +10,38 @@ pub enum InputType {
     SingleString(String),
     MultipleStrings(Vec<String>),
     MultipleTokens(Vec<u64>),
+    MultipleTokenArrays(Vec<Vec<u64>>),
+    Image(ImageInput),
+    MultipleImages(Vec<ImageInput>),
+}
+
+/// An image to embed with a multimodal embedding model, given either by URL or as base64 encoded data.
+///
+/// It is serialized as `{"image": "<url or data URL>"}`, the format accepted by `OpenAI` compatible
+/// providers of CLIP-style models.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ImageInput {
+    /// The URL of the image, or a `data:` URL holding the base64 encoded image.
+    pub image: String,
+}
+
+impl ImageInput {
+    /// Creates an image input from a publicly accessible URL.
+    pub fn url<S: Into<String>>(url: S) -> Self {
+        Self { image: url.into() }
+    }
+
+    /// Creates an image input from base64 encoded image data.
+    ///
+    /// # Arguments
+    ///
+    /// * `mime_type` - The MIME type of the image, e.g. `image/png`.
+    /// * `data` - The base64 encoded image.
+    pub fn base64(mime_type: &str, data: &str) -> Self {
+        Self {
+            image: format!("data:{mime_type};base64,{data}"),
+        }
+    }
 }
 
 impl InputType {
@@ -22,6 +57,37 @@ impl InputType {
         matches!(self, Self::MultipleTokens(_))
     }
 
+    pub fn is_multiple_token_arrays(&self) -> bool {
+        matches!(self, Self::MultipleTokenArrays(_))
+    }
+
+    pub fn is_image(&self) -> bool {
+        matches!(self, Self::Image(_) | Self::MultipleImages(_))
+    }
+
+    /// Returns the number of inputs, i.e. the number of embeddings the API returns for them.
+    /// A single string, token array or image counts as one input.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::SingleString(_) | Self::MultipleTokens(_) | Self::Image(_) => 1,
+            Self::MultipleStrings(inputs) => inputs.len(),
+            Self::MultipleTokenArrays(inputs) => inputs.len(),
+            Self::MultipleImages(inputs) => inputs.len(),
+        }
+    }
+
+    /// Checks whether there is nothing to embed, which the API rejects with a `400 Bad Request`.
+    ///
+    /// Besides a list without inputs, this is also the case for a single empty string or an
+    /// empty token array.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Self::SingleString(input) => input.is_empty(),
+            Self::MultipleTokens(input) => input.is_empty(),
+            _ => self.len() == 0,
+        }
+    }
+
     pub fn new_single_string(input: String) -> Self {
         Self::SingleString(input)
     }
@@ -59,12 +125,45 @@ impl From<Vec<u64>> for InputType {
     }
 }
 
+impl From<Vec<Vec<u64>>> for InputType {
+    fn from(input: Vec<Vec<u64>>) -> Self {
+        Self::MultipleTokenArrays(input)
+    }
+}
+
+impl From<ImageInput> for InputType {
+    fn from(input: ImageInput) -> Self {
+        Self::Image(input)
+    }
+}
+
+impl From<Vec<ImageInput>> for InputType {
+    fn from(input: Vec<ImageInput>) -> Self {
+        Self::MultipleImages(input)
+    }
+}
+
 impl From<&str> for InputType {
     fn from(input: &str) -> Self {
         Self::SingleString(input.to_string())
     }
 }
 
+/// Controls how `embed` handles a single input string that exceeds `Embedding::MAX_INPUT_TOKENS`.
+///
+/// The input is split into consecutive chunks of at most `Embedding::MAX_INPUT_TOKENS` tokens each,
+/// using the tokenizer of the configured model, and all chunks are embedded in a single request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chunking {
+    /// Return one embedding per chunk. The `index` of each entry is the position of the chunk in the input.
+    Separate,
+
+    /// Return a single embedding that is the mean of all chunk embeddings, weighted by the number of
+    /// tokens in each chunk and re-normalized to unit length. This matches the normalization of the
+    /// embeddings returned by `OpenAI`, so cosine similarity and dot product stay interchangeable.
+    MeanPool,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct Response {
     pub object: String,
@@ -73,13 +172,56 @@ pub struct Response {
     pub usage: Usage,
 }
 
+/// The format the embeddings are returned in.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EncodingFormat {
+    /// A JSON array of floats.
+    Float,
+
+    /// A base64 encoded array of little-endian 32-bit floats, which is a lot smaller than the
+    /// JSON array. It is decoded transparently, so `Data::embedding` is the same either way.
+    Base64,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct Data {
     pub object: String,
+    #[serde(deserialize_with = "deserialize_embedding")]
     pub embedding: Vec<f64>,
     pub index: u64,
 }
 
+/// Deserializes an embedding that is either a JSON array of floats or a base64 encoded array of
+/// little-endian 32-bit floats.
+fn deserialize_embedding<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<f64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawEmbedding {
+        Float(Vec<f64>),
+        Base64(String),
+    }
+
+    match RawEmbedding::deserialize(deserializer)? {
+        RawEmbedding::Float(embedding) => Ok(embedding),
+        RawEmbedding::Base64(encoded) => {
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .map_err(serde::de::Error::custom)?;
+            if bytes.len() % 4 != 0 {
+                return Err(serde::de::Error::custom(format!(
+                    "base64 embedding has {} bytes, which is not a multiple of 4",
+                    bytes.len()
+                )));
+            }
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|b| f64::from(f32::from_le_bytes([b[0], b[1], b[2], b[3]])))
+                .collect())
+        }
+    }
+}
+
 /// `OpenAI`s embeddings that can be used to measure the relatedness of text strings.
 /// Embeddings are commonly used for:
 ///  
@@ -112,11 +254,76 @@ pub struct Embedding {
     /// A unique identifier representing your end-user, which can help `OpenAI` to monitor and detect abuse
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user: Option<String>,
+
+    /// The number of dimensions the resulting embeddings should have. Only supported by the
+    /// `text-embedding-3` models and later.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<u32>,
+
+    /// The format to return the embeddings in. Defaults to `float` if `None`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding_format: Option<EncodingFormat>,
+
+    /// How to handle single string inputs that exceed the model's token limit.
+    /// If `None`, over-length inputs are sent as-is and rejected by the API.
+    #[serde(skip)]
+    pub chunking: Option<Chunking>,
+
+    /// The models to try, in order, if `model` is not found or overloaded.
+    #[serde(skip)]
+    pub fallback_models: Vec<String>,
 }
 
 impl Embedding {
     const DEFAULT_MODEL: &'static str = "text-embedding-ada-002";
 
+    /// Creates the configuration of an embedding client for the given model, e.g. to pass it to
+    /// `OpenAI::with_config`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aionic::openai::{Embedding, OpenAI};
+    ///
+    /// let client = OpenAI::<Embedding>::default()
+    ///     .with_api_key("sk-...")
+    ///     .with_config(Embedding::new("text-embedding-3-small"))
+    ///     .with_input("Hello, world!");
+    /// ```
+    pub fn new<S: Into<String>>(model: S) -> Self {
+        Self {
+            model: model.into(),
+            ..<Self as OpenAIConfig>::default()
+        }
+    }
+
+    /// The maximum number of tokens a single input may have.
+    pub const MAX_INPUT_TOKENS: usize = 8191;
+
+    /// Model families that accept image inputs.
+    const MULTIMODAL_MODELS: &'static [&'static str] = &["clip", "jina-clip", "voyage-multimodal"];
+
+    /// Checks whether the given model accepts image inputs.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `true` for CLIP-style multimodal embedding models.
+    pub fn supports_images(model: &str) -> bool {
+        Self::MULTIMODAL_MODELS
+            .iter()
+            .any(|family| model.starts_with(family))
+    }
+
+    /// Checks whether the given model accepts the `dimensions` parameter.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `true` for the `text-embedding-3` models, `false` for older models
+    /// such as `text-embedding-ada-002`.
+    pub fn supports_dimensions(model: &str) -> bool {
+        model.starts_with("text-embedding-3")
+    }
+
     /// Returns the default model to be used by this AI system.
     ///
     /// # Returns
@@ -126,3 +333,133 @@ impl Embedding {
         Self::DEFAULT_MODEL
     }
 }
+
+/// Tokenizes `input` with the tokenizer of `model` and splits the tokens into consecutive chunks of
+/// at most `max_tokens` tokens.
+///
+/// # Arguments
+///
+/// * `model` - The embedding model whose tokenizer to use.
+/// * `input` - The text to split.
+/// * `max_tokens` - The maximum number of tokens per chunk.
+///
+/// # Returns
+///
+/// A `Result` containing the token chunks, or an error if no tokenizer is known for `model`.
+pub fn chunk_tokens(
+    model: &str,
+    input: &str,
+    max_tokens: usize,
+) -> Result<Vec<Vec<u64>>, Box<dyn std::error::Error + Send + Sync>> {
+    let tokens = tokenizer::with_bpe(model, |bpe| bpe.encode_with_special_tokens(input))
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("No tokenizer available for model {model}"),
+            )
+        })?;
+    Ok(tokens
+        .chunks(max_tokens.max(1))
+        .map(|chunk| chunk.iter().map(|&t| t as u64).collect())
+        .collect())
+}
+
+/// Computes the weighted mean of `embeddings` and normalizes the result to unit length.
+///
+/// # Arguments
+///
+/// * `embeddings` - The embeddings to pool. All must have the same dimension.
+/// * `weights` - The weight of each embedding, e.g. the number of tokens of the chunk it was created from.
+///
+/// # Returns
+///
+/// The pooled embedding, or an empty vector if `embeddings` is empty.
+pub fn mean_pool(embeddings: &[Vec<f64>], weights: &[usize]) -> Vec<f64> {
+    let dim = embeddings.first().map_or(0, Vec::len);
+    let mut pooled = vec![0.0; dim];
+    for (embedding, &weight) in embeddings.iter().zip(weights) {
+        for (acc, value) in pooled.iter_mut().zip(embedding) {
+            *acc += value * weight as f64;
+        }
+    }
+    let norm = pooled.iter().map(|v| v * v).sum::<f64>().sqrt();
+    if norm > 0.0 {
+        pooled.iter_mut().for_each(|v| *v /= norm);
+    }
+    pooled
+}
+
+/// Computes the dot product of two embeddings.
+///
+/// For embeddings normalized to unit length, such as the ones returned by `OpenAI`, this equals
+/// their cosine similarity and is cheaper to compute.
+///
+/// # Panics
+///
+/// This function panics if the embeddings have different dimensions.
+pub fn dot_product(a: &[f64], b: &[f64]) -> f64 {
+    assert_eq!(
+        a.len(),
+        b.len(),
+        "Embeddings have different dimensions: {} and {}",
+        a.len(),
+        b.len()
+    );
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Computes the cosine similarity of two embeddings, i.e. the cosine of the angle between them.
+///
+/// # Returns
+///
+/// A value between -1.0 and 1.0, where 1.0 means the embeddings point in the same direction.
+/// If either embedding has a length of zero, 0.0 is returned.
+///
+/// # Panics
+///
+/// This function panics if the embeddings have different dimensions.
+pub fn cosine_similarity(a: &[f64], b: &[f64]) -> f64 {
+    let dot = dot_product(a, b);
+    let norm = dot_product(a, a).sqrt() * dot_product(b, b).sqrt();
+    if norm > 0.0 {
+        dot / norm
+    } else {
+        0.0
+    }
+}
+
+/// Finds the `k` items of `corpus` that are most similar to `query` by cosine similarity.
+///
+/// # Arguments
+///
+/// * `query` - The embedding to compare against, e.g. the embedding of a search query.
+/// * `corpus` - The items to search, each given by a label and its embedding.
+/// * `k` - The maximum number of items to return.
+///
+/// # Returns
+///
+/// The labels of the most similar items with their similarity, sorted from most to least similar.
+///
+/// # Panics
+///
+/// This function panics if an embedding of `corpus` has a different dimension than `query`.
+///
+/// # Example
+///
+/// ```rust
+/// use aionic::openai::embeddings::top_k;
+///
+/// let corpus = [("cat", vec![1.0, 0.0]), ("dog", vec![0.8, 0.6]), ("car", vec![0.0, 1.0])];
+/// let nearest = top_k(&[1.0, 0.1], &corpus, 2);
+/// assert_eq!(nearest[0].0, "cat");
+/// assert_eq!(nearest[1].0, "dog");
+/// ```
+pub fn top_k<'a>(query: &[f64], corpus: &[(&'a str, Vec<f64>)], k: usize) -> Vec<(&'a str, f64)> {
+    let mut scored: Vec<(&'a str, f64)> = corpus
+        .iter()
+        .map(|(label, embedding)| (*label, cosine_similarity(query, embedding)))
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.truncate(k);
+    scored
+}
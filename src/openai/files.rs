@@ -1,3 +1,4 @@
+use crate::openai::misc::CursorPage;
 use serde::{Deserialize, Serialize};
 
 /// Represents a file in the `OpenAI` Files API.
@@ -20,6 +21,37 @@ pub struct Files {
     pub file_id: Option<String>,
 }
 
+/// The intended purpose of an uploaded file.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FilePurpose {
+    /// Training data of a fine-tuning job, which must be a `.jsonl` file.
+    #[default]
+    #[serde(rename = "fine-tune")]
+    FineTune,
+
+    /// A file to be used by assistants and their tools.
+    Assistants,
+
+    /// The input of a batch request.
+    Batch,
+
+    /// An image to be used as input of a vision model.
+    Vision,
+}
+
+impl FilePurpose {
+    /// Returns the name of the purpose as expected by the API.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::FineTune => "fine-tune",
+            Self::Assistants => "assistants",
+            Self::Batch => "batch",
+            Self::Vision => "vision",
+        }
+    }
+}
+
 /// Represents the response from the `OpenAI` Files API.
 ///
 /// It includes metadata and a list of data objects, each representing a file.
@@ -30,6 +62,26 @@ pub struct Response {
 
     /// The type of the object returned by the API.
     pub object: String,
+
+    /// Whether there are more files after the last one of this page.
+    #[serde(default)]
+    pub has_more: bool,
+}
+
+impl Response {
+    /// Returns the ID of the last file of this page, i.e. the cursor of the next page.
+    pub fn last_id(&self) -> Option<&str> {
+        self.data.last().map(|file| file.id.as_str())
+    }
+}
+
+impl CursorPage for Response {
+    type Item = Data;
+
+    fn into_items(self) -> (Vec<Data>, Option<String>) {
+        let next = self.last_id().filter(|_| self.has_more).map(String::from);
+        (self.data, next)
+    }
 }
 
 /// Represents a file data in the `OpenAI` Files API.
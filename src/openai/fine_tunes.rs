@@ -1,15 +1,47 @@
+use crate::openai::files::PromptCompletion;
+use crate::openai::misc::CursorPage;
+use crate::openai::{pricing, tokenizer};
 use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::File as FsFile;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::time::Duration;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EventResponse {
     pub object: String,
     pub data: Vec<Event>,
+
+    /// Whether there are more events after the last one of this page.
+    #[serde(default)]
+    pub has_more: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ListResponse {
     pub data: Option<Vec<ListSummary>>,
     pub object: String,
+
+    /// Whether there are more fine-tunes after the last one of this page.
+    #[serde(default)]
+    pub has_more: bool,
+}
+
+impl ListResponse {
+    /// Returns the ID of the last fine-tune of this page, i.e. the cursor of the next page.
+    pub fn last_id(&self) -> Option<&str> {
+        self.data.as_ref()?.last().map(|tune| tune.id.as_str())
+    }
+}
+
+impl CursorPage for ListResponse {
+    type Item = ListSummary;
+
+    fn into_items(self) -> (Vec<ListSummary>, Option<String>) {
+        let next = self.last_id().filter(|_| self.has_more).map(String::from);
+        (self.data.unwrap_or_default(), next)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -39,7 +71,7 @@ pub struct Response {
     pub updated_at: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct Event {
     pub object: String,
     pub created_at: u64,
@@ -65,6 +97,10 @@ pub struct File {
     pub purpose: String,
 }
 
+/// The configuration of the legacy `/v1/fine-tunes` API.
+#[deprecated(
+    note = "OpenAI has shut down the `/v1/fine-tunes` API and its base models, use `FineTuningJob` instead"
+)]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FineTune {
     /// Creates a job that fine-tunes a specified model from a given dataset.
@@ -158,7 +194,11 @@ pub struct FineTune {
     pub suffix: Option<String>,
 }
 
+#[allow(deprecated)]
 impl FineTune {
+    /// The interval at which `stream_events` polls for new events by default.
+    pub const DEFAULT_EVENT_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
     pub fn set_model(&mut self, model: String) {
         self.model = Some(model);
     }
@@ -179,3 +219,122 @@ impl FineTune {
         false
     }
 }
+
+/// Represents the result of a dry-run fine-tune estimation as returned by `estimate_fine_tune`.
+#[derive(Debug, Clone)]
+pub struct Estimate {
+    /// Number of training examples found in the JSONL file.
+    pub examples: u64,
+
+    /// Estimated number of tokens in a single pass over the training data.
+    pub tokens: u64,
+
+    /// Number of epochs the estimation was based on.
+    pub epochs: u64,
+
+    /// Estimated training cost in USD, i.e. `tokens * epochs` priced by the model's training rate.
+    pub estimated_cost_usd: f64,
+
+    /// Estimated time the training takes once the job has started. The time a job waits in the
+    /// queue is not included, as it depends on the load of the API.
+    pub estimated_duration: Duration,
+
+    /// Issues found while reading the training data, such as malformed lines or examples
+    /// exceeding the model's token limit.
+    pub warnings: Vec<String>,
+}
+
+/// The maximum number of tokens of a single training example per model.
+const MAX_EXAMPLE_TOKENS: &[(&str, u64)] = &[
+    ("ada", 2048),
+    ("babbage", 2048),
+    ("curie", 2048),
+    ("davinci", 2048),
+    ("babbage-002", 16384),
+    ("davinci-002", 16384),
+    ("gpt-3.5-turbo", 4096),
+];
+
+/// The rough number of tokens trained per second, which the training duration is estimated with.
+const TRAINED_TOKENS_PER_SECOND: u64 = 1000;
+
+/// Estimates the cost of fine-tuning `model` on the JSONL training file at `path` without making
+/// any network calls.
+///
+/// The file is read line by line, every example is checked for the expected `prompt`/`completion`
+/// format and its tokens are counted with the model's tokenizer. Malformed lines and examples
+/// exceeding the model's token limit are reported as `warnings` instead of aborting the
+/// estimation. The cost is priced with `pricing::estimate_training_cost`.
+///
+/// # Arguments
+///
+/// * `path` - The path to the JSONL training file.
+/// * `model` - The base model to fine-tune, e.g. "curie".
+/// * `n_epochs` - The number of epochs to train for.
+///
+/// # Returns
+///
+/// `Result<Estimate, Box<dyn std::error::Error + Send + Sync>>`:
+/// An `Estimate` holding the example count, token count, estimated cost and duration,
+/// or an error if the file cannot be read or the model has no known training price.
+pub fn estimate_fine_tune<P: AsRef<Path>>(
+    path: P,
+    model: &str,
+    n_epochs: u64,
+) -> Result<Estimate, Box<dyn Error + Send + Sync>> {
+    if pricing::estimate_training_cost(model, 0).is_none() {
+        return Err(format!("No training price known for model: {model}").into());
+    }
+    let max_tokens = MAX_EXAMPLE_TOKENS
+        .iter()
+        .find(|(name, _)| *name == model)
+        .map_or(u64::MAX, |(_, max_tokens)| *max_tokens);
+
+    let reader = BufReader::new(FsFile::open(path)?);
+    let mut estimate = Estimate {
+        examples: 0,
+        tokens: 0,
+        epochs: n_epochs,
+        estimated_cost_usd: 0.0,
+        estimated_duration: Duration::ZERO,
+        warnings: vec![],
+    };
+
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line_no = idx + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let example: PromptCompletion = match serde_json::from_str(&line) {
+            Ok(example) => example,
+            Err(e) => {
+                estimate
+                    .warnings
+                    .push(format!("Line {line_no}: not a prompt/completion pair: {e}"));
+                continue;
+            }
+        };
+        if example.prompt.is_empty() || example.completion.is_empty() {
+            estimate
+                .warnings
+                .push(format!("Line {line_no}: empty prompt or completion"));
+        }
+        let tokens = (tokenizer::count_text_tokens(model, &example.prompt)
+            + tokenizer::count_text_tokens(model, &example.completion)) as u64;
+        if tokens > max_tokens {
+            estimate.warnings.push(format!(
+                "Line {line_no}: example has {tokens} tokens, exceeding the limit of {max_tokens}"
+            ));
+        }
+        estimate.examples += 1;
+        estimate.tokens += tokens;
+    }
+
+    let trained_tokens = estimate.tokens * n_epochs;
+    estimate.estimated_cost_usd =
+        pricing::estimate_training_cost(model, trained_tokens).unwrap_or_default();
+    estimate.estimated_duration =
+        Duration::from_secs(trained_tokens.div_ceil(TRAINED_TOKENS_PER_SECOND));
+    Ok(estimate)
+}
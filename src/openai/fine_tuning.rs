@@ -0,0 +1,196 @@
+use crate::openai::misc::CursorPage;
+use serde::{Deserialize, Serialize};
+
+/// The configuration of a job of the `/v1/fine_tuning/jobs` API, which fine-tunes chat models
+/// such as `gpt-3.5-turbo`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FineTuningJob {
+    /// The ID of an uploaded file that contains the training data.
+    ///
+    /// The dataset must be formatted as a JSONL file of chat conversations and be uploaded with
+    /// the purpose "fine-tune".
+    pub training_file: String,
+
+    /// The name of the model to fine-tune, e.g. "gpt-3.5-turbo".
+    pub model: String,
+
+    /// The ID of an uploaded file that contains validation data.
+    ///
+    /// If you provide this file, the data is used to generate validation metrics
+    /// periodically during fine-tuning. Your train and validation data should be
+    /// mutually exclusive.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validation_file: Option<String>,
+
+    /// The hyperparameters used for the fine-tuning job.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hyperparameters: Option<Hyperparameters>,
+
+    /// A string of up to 18 characters that will be added to your fine-tuned model name.
+    ///
+    /// For example, a suffix of "custom-model-name" would produce a model name like
+    /// ft:gpt-3.5-turbo:openai:custom-model-name:7p4lURel.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suffix: Option<String>,
+}
+
+impl FineTuningJob {
+    /// Checks whether a job or legacy fine-tune with the given status has finished, i.e. it will
+    /// not emit any more events.
+    pub fn is_terminal_status(status: &str) -> bool {
+        matches!(status, "succeeded" | "failed" | "cancelled")
+    }
+
+    pub fn get_default_model() -> &'static str {
+        "gpt-3.5-turbo"
+    }
+}
+
+/// The hyperparameters of a fine-tuning job.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct Hyperparameters {
+    /// The number of epochs to train the model for. An epoch refers to one full cycle
+    /// through the training dataset. Chosen by the API if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n_epochs: Option<NEpochs>,
+}
+
+/// The number of epochs of a fine-tuning job, which the API reports as "auto" if it chose it
+/// itself.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum NEpochs {
+    Count(u64),
+    Auto(String),
+}
+
+impl From<u64> for NEpochs {
+    fn from(n_epochs: u64) -> Self {
+        Self::Count(n_epochs)
+    }
+}
+
+/// A fine-tuning job, i.e. a `fine_tuning.job` object.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Job {
+    /// The unique ID of the job, e.g. "ftjob-abc123".
+    pub id: String,
+
+    /// Will always be "fine_tuning.job".
+    pub object: String,
+
+    /// The Unix timestamp of when the job was created.
+    pub created_at: u64,
+
+    /// The Unix timestamp of when the job finished, if it has.
+    pub finished_at: Option<u64>,
+
+    /// The base model that is being fine-tuned.
+    pub model: String,
+
+    /// The name of the fine-tuned model, once the job has succeeded.
+    pub fine_tuned_model: Option<String>,
+
+    /// The organization that owns the job.
+    pub organization_id: String,
+
+    /// The status of the job, i.e. one of "validating_files", "queued", "running",
+    /// "succeeded", "failed" or "cancelled".
+    pub status: String,
+
+    /// The hyperparameters used for the job.
+    pub hyperparameters: Hyperparameters,
+
+    /// The ID of the file used for training.
+    pub training_file: String,
+
+    /// The ID of the file used for validation, if any.
+    pub validation_file: Option<String>,
+
+    /// The IDs of the result files of the job.
+    #[serde(default)]
+    pub result_files: Vec<String>,
+
+    /// The total number of billable tokens processed by the job, once it has succeeded.
+    pub trained_tokens: Option<u64>,
+
+    /// The reason the job failed, if it has.
+    pub error: Option<JobError>,
+}
+
+/// The reason a fine-tuning job failed.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JobError {
+    /// A machine-readable error code.
+    pub code: Option<String>,
+
+    /// A human-readable error message.
+    pub message: Option<String>,
+
+    /// The parameter that was invalid, e.g. "training_file", if any.
+    pub param: Option<String>,
+}
+
+/// A page of fine-tuning jobs.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JobList {
+    pub object: String,
+    pub data: Vec<Job>,
+
+    /// Whether there are more jobs after the last one of this page.
+    #[serde(default)]
+    pub has_more: bool,
+}
+
+impl JobList {
+    /// Returns the ID of the last job of this page, i.e. the cursor of the next page.
+    pub fn last_id(&self) -> Option<&str> {
+        self.data.last().map(|job| job.id.as_str())
+    }
+}
+
+impl CursorPage for JobList {
+    type Item = Job;
+
+    fn into_items(self) -> (Vec<Job>, Option<String>) {
+        let next = self.last_id().filter(|_| self.has_more).map(String::from);
+        (self.data, next)
+    }
+}
+
+/// An event of a fine-tuning job, i.e. a `fine_tuning.job.event` object.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct JobEvent {
+    /// The unique ID of the event.
+    pub id: String,
+
+    /// Will always be "fine_tuning.job.event".
+    pub object: String,
+
+    /// The Unix timestamp of when the event was created.
+    pub created_at: u64,
+
+    /// The log level of the event, e.g. "info" or "warn".
+    pub level: String,
+
+    /// The message of the event.
+    pub message: String,
+}
+
+/// A page of events of a fine-tuning job.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JobEventList {
+    pub object: String,
+    pub data: Vec<JobEvent>,
+
+    /// Whether there are more events after the last one of this page.
+    #[serde(default)]
+    pub has_more: bool,
+}
+
+impl JobEventList {
+    /// Returns the ID of the last event of this page, i.e. the cursor of the next page.
+    pub fn last_id(&self) -> Option<&str> {
+        self.data.last().map(|event| event.id.as_str())
+    }
+}
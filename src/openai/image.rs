@@ -1,4 +1,6 @@
+use base64::Engine;
 use serde::{Deserialize, Serialize};
+use std::error::Error;
 
 /// Enum representing the format in which the response from `OpenAI`'s Image API can be received.
 ///
@@ -69,6 +71,39 @@ pub struct Data {
 
     /// A Base64-encoded JSON string representing the generated image. This field is present when the response format is set to Base64Json.
     pub b64_json: Option<String>,
+
+    /// The prompt that was used to generate the image, if there was any revision to the prompt. Only returned by DALL·E 3.
+    pub revised_prompt: Option<String>,
+}
+
+impl Response {
+    /// Decodes the images of a response requested with `ResponseDataType::Base64Json`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the bytes of each image in the order of `data`, or an error if an
+    /// image was returned as URL or is not valid base64.
+    pub fn decoded(&self) -> Result<Vec<Vec<u8>>, Box<dyn Error + Send + Sync>> {
+        self.data.iter().map(Data::decoded).collect()
+    }
+}
+
+impl Data {
+    /// Decodes the image, if it was returned as base64.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the bytes of the image, or an error if it was returned as URL or is
+    /// not valid base64.
+    pub fn decoded(&self) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let b64_json = self.b64_json.as_deref().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "The image was returned as URL, request it with ResponseDataType::Base64Json to decode it",
+            )
+        })?;
+        Ok(base64::engine::general_purpose::STANDARD.decode(b64_json)?)
+    }
 }
 
 impl ToString for Size {
@@ -86,15 +121,20 @@ impl ToString for Size {
 /// For more information check the official [openAI API documentation](https://platform.openai.com/docs/api-reference/images)
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Image {
+    /// The model to use for image generation, e.g. dall-e-2 or dall-e-3. Defaults to dall-e-2.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
     /// A text description of the desired image(s). The maximum length is 1000 characters.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub prompt: Option<String>,
 
-    /// The number of images to generate. Must be between 1 and 10.
+    /// The number of images to generate. Must be between 1 and 10. For dall-e-3, only n=1 is supported.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub n: Option<u64>,
 
-    /// The size of the generated images. Must be one of 256x256, 512x512, or 1024x1024.
+    /// The size of the generated images. Must be one of 256x256, 512x512, or 1024x1024 for dall-e-2,
+    /// and one of 1024x1024, 1792x1024, or 1024x1792 for dall-e-3.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub size: Option<String>,
 
@@ -102,6 +142,14 @@ pub struct Image {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub response_format: Option<String>,
 
+    /// The quality of the image that will be generated, either standard or hd. Only supported by dall-e-3.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality: Option<String>,
+
+    /// The style of the generated images, either vivid or natural. Only supported by dall-e-3.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub style: Option<String>,
+
     /// A unique identifier representing your end-user, which can help `OpenAI` to monitor and detect abuse
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user: Option<String>,
@@ -125,6 +173,9 @@ impl Image {
     const DEFAULT_N: u64 = 1;
     const DEFAULT_SIZE: &str = "1024x1024";
     const DEFAULT_RESPONSE_FORMAT: &str = "url";
+    const DALL_E_3: &str = "dall-e-3";
+    /// The maximum size of an image or mask accepted by the edit and variation endpoints.
+    pub const MAX_FILE_SIZE_BYTES: u64 = 4 * 1024 * 1024;
 
     /// Returns the default n for the Image API.
     ///
@@ -163,6 +214,39 @@ impl Image {
         valid_sizes.contains(&size)
     }
 
+    /// Checks if the given size is supported by DALL·E 3.
+    ///
+    /// # Returns
+    ///
+    /// This function returns a `bool` value which represents whether the size is valid for DALL·E 3.
+    pub fn is_valid_dall_e_3_size(size: &str) -> bool {
+        let valid_sizes = ["1024x1024", "1792x1024", "1024x1792"];
+        valid_sizes.contains(&size)
+    }
+
+    /// Checks if the given quality is supported.
+    ///
+    /// # Returns
+    ///
+    /// This function returns a `bool` value which represents whether the quality is valid.
+    pub fn is_valid_quality(quality: &str) -> bool {
+        ["standard", "hd"].contains(&quality)
+    }
+
+    /// Checks if the given style is supported.
+    ///
+    /// # Returns
+    ///
+    /// This function returns a `bool` value which represents whether the style is valid.
+    pub fn is_valid_style(style: &str) -> bool {
+        ["vivid", "natural"].contains(&style)
+    }
+
+    /// Checks whether the configured model is DALL·E 3.
+    pub fn is_dall_e_3(&self) -> bool {
+        self.model.as_deref() == Some(Self::DALL_E_3)
+    }
+
     /// Checks if the current Image object is valid in terms of the requested response format
     ///
     /// # Returns
@@ -181,4 +265,13 @@ impl Image {
     pub fn is_valid_n(n: u64) -> bool {
         (1..=10).contains(&n)
     }
+
+    /// Checks if the given bytes represent a PNG file by looking at the file signature.
+    ///
+    /// # Returns
+    ///
+    /// This function returns a `bool` value which represents whether the bytes are a PNG image.
+    pub fn is_png(bytes: &[u8]) -> bool {
+        bytes.starts_with(b"\x89PNG\r\n\x1a\n")
+    }
 }
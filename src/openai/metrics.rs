@@ -0,0 +1,152 @@
+use crate::openai::misc::Usage;
+use crate::openai::rt::Instant;
+use reqwest::StatusCode;
+use std::error::Error;
+use std::time::Duration;
+
+/// Receives the metrics of every request an `OpenAI` client makes, e.g. to export them to
+/// Prometheus.
+///
+/// A sink is registered with `OpenAI::with_metrics_sink` and called once per request after it
+/// completed, whether it succeeded or failed. It is called from within the request, so it should
+/// return quickly, e.g. by only updating counters and histograms.
+///
+/// On wasm32 responses cannot carry the pending metrics until their body is read, so successful
+/// requests are reported as soon as their headers arrived, without usage.
+///
+/// # Example
+///
+/// ```rust
+/// use aionic::openai::metrics::{MetricsSink, RequestMetrics};
+/// use aionic::openai::{Chat, OpenAI};
+///
+/// #[derive(Debug)]
+/// struct Logger;
+///
+/// impl MetricsSink for Logger {
+///     fn on_request_complete(&self, metrics: &RequestMetrics) {
+///         println!(
+///             "{} took {:?} and used {:?} tokens",
+///             metrics.endpoint,
+///             metrics.latency,
+///             metrics.usage.map(|usage| usage.total_tokens)
+///         );
+///     }
+/// }
+///
+/// let client = OpenAI::<Chat>::new().with_metrics_sink(Logger);
+/// ```
+pub trait MetricsSink: Send + Sync + std::fmt::Debug {
+    /// Called after a request completed.
+    fn on_request_complete(&self, metrics: &RequestMetrics);
+}
+
+/// The metrics of a single request.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequestMetrics {
+    /// The path of the endpoint, e.g. `/v1/chat/completions`. It is empty if the request failed
+    /// before its URL was known.
+    pub endpoint: String,
+
+    /// The model of the request, if the endpoint has one.
+    pub model: Option<String>,
+
+    /// The HTTP status of the response, or `None` if no response was received.
+    pub status: Option<u16>,
+
+    /// The time from sending the request until its response was read completely. For streamed
+    /// chat responses this is the total duration of the stream.
+    pub latency: Duration,
+
+    /// The time from sending the request until the first content token of a streamed chat
+    /// response was received. It is `None` for all other requests.
+    pub time_to_first_token: Option<Duration>,
+
+    /// The token usage of the request, if the response reported one. For streamed chat
+    /// responses it is estimated, unless it was requested with `stream_options`.
+    pub usage: Option<Usage>,
+
+    /// The class of the error, if the request failed.
+    pub error: Option<ErrorClass>,
+}
+
+/// The class of the error a request failed with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// The request or reading its response timed out.
+    Timeout,
+
+    /// The request could not be sent or its response could not be read, e.g. because the
+    /// connection failed.
+    Transport,
+
+    /// The API answered with `429 Too Many Requests`.
+    RateLimit,
+
+    /// The API answered with any other 4xx status.
+    Client,
+
+    /// The API answered with a 5xx status.
+    Server,
+
+    /// The response could not be deserialized.
+    Deserialization,
+}
+
+impl ErrorClass {
+    /// Classifies an HTTP status that does not indicate success.
+    pub fn from_status(status: StatusCode) -> Self {
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            Self::RateLimit
+        } else if status.is_server_error() {
+            Self::Server
+        } else {
+            Self::Client
+        }
+    }
+
+    /// Classifies an error that occurred while sending a request or reading its response.
+    pub fn from_error(error: &(dyn Error + 'static)) -> Self {
+        let timed_out = error
+            .downcast_ref::<reqwest::Error>()
+            .is_some_and(reqwest::Error::is_timeout)
+            || error
+                .downcast_ref::<std::io::Error>()
+                .is_some_and(|e| e.kind() == std::io::ErrorKind::TimedOut);
+        if timed_out {
+            Self::Timeout
+        } else {
+            Self::Transport
+        }
+    }
+}
+
+/// The metrics of a successful response whose body was not read yet. It is attached to the
+/// response, so the metrics can be completed with the usage once the body has been read.
+#[derive(Clone, Debug)]
+pub(crate) struct PendingMetrics {
+    pub(crate) endpoint: String,
+    pub(crate) model: Option<String>,
+    pub(crate) status: u16,
+    pub(crate) start: Instant,
+}
+
+impl PendingMetrics {
+    /// Completes the metrics of the response.
+    pub(crate) fn finish(
+        self,
+        usage: Option<Usage>,
+        time_to_first_token: Option<Duration>,
+        error: Option<ErrorClass>,
+    ) -> RequestMetrics {
+        RequestMetrics {
+            endpoint: self.endpoint,
+            model: self.model,
+            status: Some(self.status),
+            latency: self.start.elapsed(),
+            time_to_first_token,
+            usage,
+            error,
+        }
+    }
+}
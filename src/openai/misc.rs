@@ -1,4 +1,6 @@
+use crate::openai::pricing::{Cost, Pricing};
 use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
 // =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
 // COMMON STRUCT DEFINITIONS
 // =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
@@ -15,6 +17,53 @@ pub struct Model {
 
     /// The owner of the fetched model
     pub owned_by: String,
+
+    /// The Unix timestamp of when the model was created.
+    #[serde(default)]
+    pub created: Option<u64>,
+
+    /// The permissions of the model, which older versions of the API report.
+    #[serde(default)]
+    pub permission: Option<Vec<ModelPermission>>,
+}
+
+/// Represents a permission entry of a `Model`, as reported by older versions of the API.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ModelPermission {
+    /// The ID of the permission entry.
+    pub id: String,
+
+    /// Will default to "model_permission".
+    pub object: String,
+
+    /// The Unix timestamp of when the permission was created.
+    pub created: u64,
+
+    #[serde(default)]
+    pub allow_create_engine: bool,
+
+    #[serde(default)]
+    pub allow_sampling: bool,
+
+    #[serde(default)]
+    pub allow_logprobs: bool,
+
+    #[serde(default)]
+    pub allow_search_indices: bool,
+
+    #[serde(default)]
+    pub allow_view: bool,
+
+    #[serde(default)]
+    pub allow_fine_tuning: bool,
+
+    /// The organization the permission applies to, "*" for all of them.
+    pub organization: Option<String>,
+
+    pub group: Option<String>,
+
+    #[serde(default)]
+    pub is_blocking: bool,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -26,7 +75,10 @@ pub struct ModelsResponse {
 /// Represents the usage data from an API call.
 ///
 /// This includes the number of tokens used for the prompt, the completion, and the total tokens.
-#[derive(Deserialize, Debug, Clone)]
+///
+/// Usages can be added together to keep a running total across multiple API calls, where a
+/// missing `completion_tokens` count is treated as zero.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Usage {
     /// Number of tokens used in the prompt.
     pub prompt_tokens: u64,
@@ -39,6 +91,142 @@ pub struct Usage {
     pub total_tokens: u64,
 }
 
+impl Usage {
+    /// Creates an empty `Usage` that can be used as the starting point of a running total.
+    pub fn zero() -> Self {
+        Self {
+            prompt_tokens: 0,
+            completion_tokens: None,
+            total_tokens: 0,
+        }
+    }
+
+    /// Estimates the cost of this usage in US dollars with the default `Pricing` table. Use
+    /// `Pricing::cost` to price fine-tuned or unknown models.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `None` if the model has no known price.
+    pub fn cost_for(&self, model: &str) -> Option<Cost> {
+        Pricing::default().cost(self, model)
+    }
+}
+
+/// The token usage a client has accumulated over all of its requests.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UsageTotals {
+    /// The number of prompt tokens of all requests.
+    pub prompt_tokens: u64,
+
+    /// The number of completion tokens of all requests.
+    pub completion_tokens: u64,
+
+    /// The total number of tokens of all requests.
+    pub total_tokens: u64,
+
+    /// The number of requests that contributed to the totals.
+    pub requests: u64,
+}
+
+impl UsageTotals {
+    /// Adds the usage of a single request to the totals.
+    pub fn add(&mut self, usage: &Usage) {
+        self.prompt_tokens += usage.prompt_tokens;
+        self.completion_tokens += usage.completion_tokens.unwrap_or(0);
+        self.total_tokens += usage.total_tokens;
+        self.requests += 1;
+    }
+}
+
+impl std::ops::Add for Usage {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        let completion_tokens = match (self.completion_tokens, rhs.completion_tokens) {
+            (None, None) => None,
+            (lhs, rhs) => Some(lhs.unwrap_or(0) + rhs.unwrap_or(0)),
+        };
+        Self {
+            prompt_tokens: self.prompt_tokens + rhs.prompt_tokens,
+            completion_tokens,
+            total_tokens: self.total_tokens + rhs.total_tokens,
+        }
+    }
+}
+
+impl std::ops::AddAssign for Usage {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl std::iter::Sum for Usage {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |acc, usage| acc + usage)
+    }
+}
+
+/// The cursor parameters of a request that lists a page of objects, e.g. fine-tuning jobs.
+///
+/// # Example
+///
+/// ```rust
+/// use aionic::openai::ListParams;
+///
+/// let params = ListParams::new().set_limit(10).set_after("ftjob-abc123");
+/// assert_eq!(params.limit, Some(10));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListParams {
+    /// The maximum number of objects to return. The API's default is used if not set.
+    pub limit: Option<u32>,
+
+    /// The ID of the object to start after, i.e. the last ID of the previous page.
+    pub after: Option<String>,
+}
+
+impl ListParams {
+    /// Creates empty `ListParams`, which request the first page with the API's default size.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of objects to return.
+    pub fn set_limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Sets the ID of the object to start after.
+    pub fn set_after<S: Into<String>>(mut self, after: S) -> Self {
+        self.after = Some(after.into());
+        self
+    }
+
+    /// Returns the parameters as query pairs.
+    #[cfg(feature = "files")]
+    pub(crate) fn query(&self) -> Vec<(&'static str, String)> {
+        let mut query = Vec::new();
+        if let Some(limit) = self.limit {
+            query.push(("limit", limit.to_string()));
+        }
+        if let Some(after) = &self.after {
+            query.push(("after", after.clone()));
+        }
+        query
+    }
+}
+
+/// A page of a list endpoint that is paginated with the `after` cursor of `ListParams`.
+#[cfg(feature = "files")]
+pub(crate) trait CursorPage: serde::de::DeserializeOwned {
+    /// The type of the listed objects.
+    type Item;
+
+    /// Returns the objects of this page and the cursor of the next page, if there is one.
+    fn into_items(self) -> (Vec<Self::Item>, Option<String>);
+}
+
 /// Represents an error returned from the `OpenAI`' API.
 ///
 /// This struct is used to deserialize the JSON object that the `OpenAI`' API
@@ -70,3 +258,354 @@ pub struct ErrorDetails {
     /// used to handle specific error types programmatically.
     pub code: Option<String>,
 }
+
+/// Represents a failure to download a remote asset, e.g. an audio file or image given by URL.
+///
+/// This is kept separate from the errors returned by the `OpenAI` API, as the download happens
+/// before any request to the API is made.
+#[derive(Debug, Clone)]
+pub struct DownloadError {
+    /// The URL that could not be downloaded.
+    pub url: String,
+
+    /// A human-readable description of what went wrong.
+    pub reason: String,
+}
+
+impl std::fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Failed to download {}: {}", self.url, self.reason)
+    }
+}
+
+impl std::error::Error for DownloadError {}
+
+/// Represents a response body that could not be deserialized into the expected type.
+///
+/// It carries the body as it was received, so callers can inspect what the API actually sent.
+#[derive(Debug, Clone)]
+pub struct DeserializationError {
+    /// A human-readable description of what failed to deserialize.
+    pub message: String,
+
+    /// The body of the response.
+    pub body: String,
+
+    /// The body of the response as JSON, or `None` if it is not valid JSON at all.
+    pub raw: Option<serde_json::Value>,
+}
+
+impl std::fmt::Display for DeserializationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Failed to deserialize response: {}", self.message)
+    }
+}
+
+impl std::error::Error for DeserializationError {}
+
+/// Represents a streamed response that was aborted because it exceeded the configured
+/// maximum number of bytes.
+///
+/// The text received up to the limit is kept in `partial`, so callers can still make use of it.
+#[derive(Debug, Clone)]
+pub struct ResponseTooLarge {
+    /// The configured maximum number of bytes of a response.
+    pub max_bytes: usize,
+
+    /// The text received before the stream was aborted, truncated to `max_bytes`.
+    pub partial: String,
+}
+
+impl std::fmt::Display for ResponseTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Response exceeded the maximum size of {} bytes",
+            self.max_bytes
+        )
+    }
+}
+
+impl std::error::Error for ResponseTooLarge {}
+
+/// Represents a chat response whose content was omitted by `OpenAI`'s content filters, i.e. its
+/// finish reason is `content_filter`.
+///
+/// The text generated before the content was filtered is kept in `partial`.
+#[derive(Debug, Clone)]
+pub struct ContentFiltered {
+    /// The text received before the content was omitted, which may be empty.
+    pub partial: String,
+}
+
+impl std::fmt::Display for ContentFiltered {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "The response was omitted by the content filter")
+    }
+}
+
+impl std::error::Error for ContentFiltered {}
+
+/// Represents a request that was rejected by the `OpenAI` API.
+///
+/// It keeps everything the API told us about the failure, so callers can react to specific
+/// errors through the `is_*` predicates instead of matching on the error message.
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    /// The HTTP status of the response.
+    pub status: reqwest::StatusCode,
+
+    /// The URL of the endpoint that failed.
+    pub endpoint: String,
+
+    /// A human-readable description of the error. If the body was not an `OpenAIError`, e.g. the
+    /// HTML error page of a proxy, it contains the HTTP status and the start of the body instead.
+    pub message: String,
+
+    /// (Optional) The error details returned by the API, if the body was an `OpenAIError`.
+    pub details: Option<ErrorDetails>,
+
+    /// (Optional) How long to wait before sending the request again, as requested by `Retry-After`.
+    pub retry_after: Option<std::time::Duration>,
+
+    /// (Optional) The `x-request-id` of the failed request.
+    pub request_id: Option<String>,
+}
+
+impl ApiError {
+    /// Returns the error code of the API, e.g. `context_length_exceeded`.
+    pub fn code(&self) -> Option<&str> {
+        self.details.as_ref()?.code.as_deref()
+    }
+
+    /// Checks whether the request was rejected because of a rate limit.
+    pub fn is_rate_limited(&self) -> bool {
+        self.status == reqwest::StatusCode::TOO_MANY_REQUESTS
+    }
+
+    /// Checks whether the request was rejected because the API key is invalid.
+    pub fn is_invalid_api_key(&self) -> bool {
+        self.status == reqwest::StatusCode::UNAUTHORIZED || self.code() == Some("invalid_api_key")
+    }
+
+    /// Checks whether the request was rejected because the prompt exceeds the context window of the model.
+    pub fn is_context_length_exceeded(&self) -> bool {
+        self.code() == Some("context_length_exceeded")
+    }
+
+    /// Checks whether the request was rejected because the model does not exist or is not accessible.
+    pub fn is_model_not_found(&self) -> bool {
+        self.code() == Some("model_not_found")
+    }
+
+    /// Checks whether the request was rejected because the model is currently overloaded.
+    pub fn is_overloaded(&self) -> bool {
+        self.status == reqwest::StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+/// Represents a request that failed with every model of a fallback list.
+///
+/// It is returned if the primary model and all fallback models were either not found or
+/// overloaded, and keeps the error of every attempt in the order the models were tried.
+#[derive(Debug, Clone)]
+pub struct FallbackError {
+    /// The models that were tried, each with the error it failed with.
+    pub attempts: Vec<(String, ApiError)>,
+}
+
+impl FallbackError {
+    /// Returns the models that were tried, in order.
+    pub fn models(&self) -> Vec<&str> {
+        self.attempts
+            .iter()
+            .map(|(model, _)| model.as_str())
+            .collect()
+    }
+}
+
+impl std::fmt::Display for FallbackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "All models failed:")?;
+        for (i, (model, error)) in self.attempts.iter().enumerate() {
+            let separator = if i == 0 { " " } else { "; " };
+            write!(f, "{separator}{model}: {}", error.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for FallbackError {}
+
+/// Represents a request that was cancelled through the `CancellationToken` of the client.
+///
+/// It is returned instead of the result of the request, so it can be told apart from requests
+/// that failed or timed out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "The request was cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Represents a function call whose arguments could not be parsed into the type the registered
+/// function expects, e.g. because the model hallucinated a parameter.
+#[derive(Debug, Clone)]
+pub struct InvalidFunctionArguments {
+    /// The name of the function the model wanted to call.
+    pub function: String,
+
+    /// The arguments as generated by the model.
+    pub arguments: String,
+
+    /// The reason the arguments could not be parsed.
+    pub message: String,
+}
+
+impl std::fmt::Display for InvalidFunctionArguments {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Invalid arguments for function `{}`: {} in {}",
+            self.function, self.message, self.arguments
+        )
+    }
+}
+
+impl std::error::Error for InvalidFunctionArguments {}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(retry_after) = self.retry_after {
+            write!(f, " (retry after {}s)", retry_after.as_secs_f64())?;
+        }
+        if let Some(request_id) = &self.request_id {
+            write!(f, " (request id: {request_id})")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Represents the rate limit state reported by the `OpenAI` API through the `x-ratelimit-*` headers.
+///
+/// Each field is `None` if the corresponding header was missing or could not be parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RateLimitInfo {
+    /// The maximum number of requests that are permitted before exhausting the rate limit.
+    pub limit_requests: Option<u64>,
+
+    /// The maximum number of tokens that are permitted before exhausting the rate limit.
+    pub limit_tokens: Option<u64>,
+
+    /// The remaining number of requests that are permitted before exhausting the rate limit.
+    pub remaining_requests: Option<u64>,
+
+    /// The remaining number of tokens that are permitted before exhausting the rate limit.
+    pub remaining_tokens: Option<u64>,
+
+    /// The time until the request rate limit resets to its initial state.
+    pub reset_requests: Option<std::time::Duration>,
+
+    /// The time until the token rate limit resets to its initial state.
+    pub reset_tokens: Option<std::time::Duration>,
+}
+
+impl RateLimitInfo {
+    /// Extracts the rate limit information from the headers of a response.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `None` if none of the `x-ratelimit-*` headers are present.
+    pub fn from_headers(headers: &reqwest::header::HeaderMap) -> Option<Self> {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+        let number = |name: &str| header(name).and_then(|v| v.trim().parse().ok());
+        let duration = |name: &str| header(name).and_then(parse_reset_duration);
+        let info = Self {
+            limit_requests: number("x-ratelimit-limit-requests"),
+            limit_tokens: number("x-ratelimit-limit-tokens"),
+            remaining_requests: number("x-ratelimit-remaining-requests"),
+            remaining_tokens: number("x-ratelimit-remaining-tokens"),
+            reset_requests: duration("x-ratelimit-reset-requests"),
+            reset_tokens: duration("x-ratelimit-reset-tokens"),
+        };
+        (info != Self::default()).then_some(info)
+    }
+}
+
+/// Parses a reset time as sent by `OpenAI`, e.g. `1s`, `6m0s`, `1h2m3.5s` or `17ms`.
+fn parse_reset_duration(value: &str) -> Option<std::time::Duration> {
+    let mut total = 0.0;
+    let mut rest = value.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let (number, tail) = rest.split_at(split);
+        let number: f64 = number.parse().ok()?;
+        let unit_len = tail
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_len);
+        total += number
+            * match unit {
+                "h" => 3600.0,
+                "m" => 60.0,
+                "s" => 1.0,
+                "ms" => 0.001,
+                _ => return None,
+            };
+        rest = tail;
+    }
+    Some(std::time::Duration::from_secs_f64(total))
+}
+
+/// An API key that is wiped from memory with `zeroize` when it is dropped.
+///
+/// The key is only accessible through `expose`, and it neither implements `Display` nor
+/// `Serialize`, so it cannot end up in logs or serialized configurations by accident. Its
+/// `Debug` output is redacted.
+///
+/// The `Authorization` header built from the key is marked as sensitive, so it is redacted in
+/// the `Debug` output of requests as well. The copy of the header held by the HTTP client while
+/// a request is sent is not wiped.
+#[derive(Clone, Default)]
+pub struct SecretKey(Zeroizing<String>);
+
+impl SecretKey {
+    /// Wraps an API key. A `String` is moved into the `SecretKey` without being copied, so it
+    /// is wiped together with it.
+    pub fn new<S: Into<String>>(key: S) -> Self {
+        Self(Zeroizing::new(key.into()))
+    }
+
+    /// Gives `f` scoped access to the key.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the result of `f`. It should not return the key itself.
+    pub fn expose<R>(&self, f: impl FnOnce(&str) -> R) -> R {
+        f(&self.0)
+    }
+
+    /// Returns whether no key is set.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl std::fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let redacted = if self.is_empty() { "" } else { "sk-****" };
+        f.debug_tuple("SecretKey").field(&redacted).finish()
+    }
+}
@@ -1,42 +1,146 @@
+#[cfg(feature = "audio")]
 pub mod audio;
+pub mod capabilities;
+#[cfg(feature = "chat")]
 pub mod chat;
+#[cfg(feature = "embeddings")]
 pub mod embeddings;
+#[cfg(feature = "files")]
 pub mod files;
+#[cfg(feature = "fine-tunes")]
 pub mod fine_tunes;
+#[cfg(feature = "fine-tunes")]
+pub mod fine_tuning;
+#[cfg(feature = "images")]
 pub mod image;
+pub mod metrics;
 mod misc;
+#[cfg(feature = "moderations")]
 pub mod moderations;
+pub mod pricing;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod recorder;
+#[cfg(feature = "repl")]
+mod repl;
+pub mod retry;
+mod rt;
+#[cfg(any(feature = "chat", feature = "embeddings", feature = "fine-tunes"))]
+pub mod tokenizer;
+pub mod transport;
+
+/// The former name of the `chat` module, kept so that old imports keep compiling.
+#[cfg(feature = "chat")]
+#[deprecated(note = "use `aionic::openai::chat` instead")]
+pub mod chat_completion {
+    pub use super::chat::*;
+}
 
-pub use audio::{Audio, Response as AudioResponse, ResponseFormat as AudioResponseFormat};
+#[cfg(feature = "audio")]
+pub use audio::{
+    Audio, Granularity, Response as AudioResponse, ResponseFormat as AudioResponseFormat, Speech,
+    SpeechResponseFormat, VerboseResponse as AudioVerboseResponse, Voice,
+};
 
-pub use chat::{Chat, Message, MessageRole};
+pub use capabilities::{model_info, ModelInfo};
+#[cfg(feature = "chat")]
+pub use chat::{
+    AskOutcome, Chat, ChatChunk, ChatOutcome, ChatResponseFormat, Content, ContentPart,
+    FinishReason, Function, FunctionCall, FunctionCallBehavior, FunctionCallMode, FunctionHandler,
+    HistoryStrategy, Message, MessageBuilder, MessageRole, StreamCallback, StreamOptions,
+    StreamOutput, Tool, ToolCall, ToolChoice, UnhandledCall,
+};
+#[cfg(feature = "chat")]
 use chat::{Response, StreamedReponse};
-pub use embeddings::{Embedding, InputType, Response as EmbeddingResponse};
-pub use files::Files;
+#[cfg(feature = "embeddings")]
+pub use embeddings::{
+    Chunking, Embedding, EncodingFormat, ImageInput, InputType, Response as EmbeddingResponse,
+};
+#[cfg(feature = "files")]
 use files::{Data as FileData, DeleteResponse, PromptCompletion, Response as FileResponse};
+#[cfg(feature = "files")]
+pub use files::{FilePurpose, Files};
+#[cfg(feature = "fine-tunes")]
+#[allow(deprecated)]
 pub use fine_tunes::{
+    estimate_fine_tune, Estimate as FineTuneEstimate, Event as FineTuneEvent,
     EventResponse as FineTuneEventResponse, FineTune, ListResponse as FineTuneListResponse,
-    Response as FineTuneResponse,
+    ListSummary as FineTuneListSummary, Response as FineTuneResponse,
 };
+#[cfg(feature = "fine-tunes")]
+pub use fine_tuning::{
+    FineTuningJob, Hyperparameters as FineTuningHyperparameters, Job as FineTuningJobResponse,
+    JobEvent as FineTuningJobEvent, JobEventList as FineTuningJobEventList,
+    JobList as FineTuningJobList, NEpochs,
+};
+#[cfg(feature = "images")]
 use image::Size;
+#[cfg(feature = "images")]
 pub use image::{Image, Response as ImageResponse, ResponseDataType};
+use metrics::PendingMetrics;
+pub use metrics::{ErrorClass, MetricsSink, RequestMetrics};
+#[cfg(feature = "files")]
+use misc::CursorPage;
 use misc::ModelsResponse;
-pub use misc::{Model, OpenAIError, Usage};
-pub use moderations::{Moderation, Response as ModerationResponse};
-
+pub use misc::{
+    ApiError, Cancelled, ContentFiltered, DeserializationError, DownloadError, FallbackError,
+    InvalidFunctionArguments, ListParams, Model, ModelPermission, OpenAIError, RateLimitInfo,
+    ResponseTooLarge, SecretKey, Usage, UsageTotals,
+};
+#[cfg(feature = "moderations")]
+pub use moderations::{
+    Category as ModerationCategory, Input as ModerationInput, Moderation,
+    Response as ModerationResponse, Result as ModerationResult,
+};
+pub use pricing::{Cost, ModelPrice, Pricing};
+pub use retry::RetryConfig;
+pub use transport::Transport;
+
+#[cfg(any(feature = "chat", feature = "fine-tunes"))]
+use futures_util::Stream;
+#[cfg(any(
+    feature = "chat",
+    feature = "embeddings",
+    feature = "images",
+    feature = "audio"
+))]
+use futures_util::StreamExt;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, USER_AGENT};
+#[cfg(any(feature = "images", feature = "audio", feature = "files"))]
 use reqwest::multipart::{Form, Part};
-use reqwest::{Body, Client, IntoUrl};
+#[cfg(any(feature = "images", feature = "audio", feature = "files"))]
+use reqwest::Body;
+use reqwest::{Client, IntoUrl, Method, RequestBuilder};
+#[cfg(any(feature = "images", feature = "audio", feature = "files"))]
 use tokio_util::codec::{BytesCodec, FramedRead};
+pub use tokio_util::sync::CancellationToken;
 
-use rustyline::error::ReadlineError;
-use rustyline::DefaultEditor;
+use serde::de::DeserializeOwned;
 use serde::Serialize;
+#[cfg(feature = "chat")]
+use std::collections::HashMap;
+#[cfg(feature = "fine-tunes")]
+use std::collections::{HashSet, VecDeque};
 use std::env;
 use std::error::Error;
+#[cfg(any(feature = "chat", feature = "audio", feature = "files"))]
 use std::fs;
-use std::io::{self, Write};
+use std::future::Future;
+use std::io;
+#[cfg(feature = "chat")]
+use std::io::Write;
+#[cfg(any(
+    feature = "chat",
+    feature = "images",
+    feature = "audio",
+    feature = "files"
+))]
 use std::path::Path;
+#[cfg(feature = "images")]
+use std::path::PathBuf;
 use std::process::exit;
+use std::sync::Arc;
+use std::time::Duration;
+use zeroize::Zeroizing;
 
 // =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
 // = OpenAIConfig TRAIT
@@ -44,53 +148,117 @@ use std::process::exit;
 
 pub trait OpenAIConfig: Send + Sync {
     fn default() -> Self;
+
+    /// Allows an endpoint configuration to adjust the serialized JSON payload right before it
+    /// is sent, e.g. to translate values that depend on other fields. Does nothing by default.
+    fn prepare_payload(&self, _payload: &mut serde_json::Value) {}
+
+    /// The model the requests of this endpoint are made with, if any. It is only used to
+    /// annotate the request spans of the `tracing` feature.
+    fn model(&self) -> Option<&str> {
+        None
+    }
 }
 
+#[cfg(feature = "chat")]
 impl OpenAIConfig for Chat {
+    fn prepare_payload(&self, payload: &mut serde_json::Value) {
+        if payload["stream"] != serde_json::Value::Bool(true) {
+            if let Some(payload) = payload.as_object_mut() {
+                payload.remove("stream_options");
+            }
+        }
+        let role = Self::get_system_role(&self.model).to_string();
+        if let Some(messages) = payload
+            .get_mut("messages")
+            .and_then(serde_json::Value::as_array_mut)
+        {
+            for msg in messages {
+                if matches!(msg["role"].as_str(), Some("system" | "developer")) {
+                    msg["role"] = role.clone().into();
+                }
+            }
+        }
+    }
+
     fn default() -> Self {
         Self {
             model: Self::get_default_model().into(),
             messages: vec![],
             functions: None,
             function_call: None,
+            response_format: None,
             temperature: Some(Self::get_default_temperature()),
             top_p: None,
             n: None,
             stream: Some(Self::get_default_stream()),
+            stream_options: None,
             stop: None,
             max_tokens: Some(Self::get_default_max_tokens()),
+            seed: None,
             presence_penalty: None,
             frequency_penalty: None,
             logit_bias: None,
             user: None,
+            history_limit: None,
+            trimmed_messages: vec![],
+            fallback_models: Vec::new(),
+            stream_callback: None,
+            output: None,
+            function_handlers: HashMap::new(),
+            max_function_calls: None,
+            tools: None,
+            tool_choice: None,
         }
     }
+
+    fn model(&self) -> Option<&str> {
+        Some(&self.model)
+    }
 }
 
+#[cfg(feature = "images")]
 impl OpenAIConfig for Image {
     fn default() -> Self {
         Self {
+            model: None,
             prompt: None,
             n: Some(Self::get_default_n()),
             size: Some(Self::get_default_size().into()),
             response_format: Some(Self::get_default_response_format().into()),
+            quality: None,
+            style: None,
             user: None,
             image: None,
             mask: None,
         }
     }
+
+    fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
 }
 
+#[cfg(feature = "embeddings")]
 impl OpenAIConfig for Embedding {
     fn default() -> Self {
         Self {
             model: Self::get_default_model().into(),
             input: InputType::SingleString(String::new()),
             user: None,
+            dimensions: None,
+            encoding_format: None,
+            chunking: None,
+            fallback_models: Vec::new(),
         }
     }
+
+    fn model(&self) -> Option<&str> {
+        Some(&self.model)
+    }
 }
 
+#[cfg(feature = "audio")]
 impl OpenAIConfig for Audio {
     fn default() -> Self {
         Self {
@@ -100,10 +268,33 @@ impl OpenAIConfig for Audio {
             response_format: Some(AudioResponseFormat::get_default_response_format()),
             temperature: Some(0.0),
             language: None,
+            timestamp_granularities: None,
+        }
+    }
+
+    fn model(&self) -> Option<&str> {
+        Some(&self.model)
+    }
+}
+
+#[cfg(feature = "audio")]
+impl OpenAIConfig for Speech {
+    fn default() -> Self {
+        Self {
+            model: Self::get_default_model().into(),
+            input: String::new(),
+            voice: Self::DEFAULT_VOICE,
+            response_format: None,
+            speed: None,
         }
     }
+
+    fn model(&self) -> Option<&str> {
+        Some(&self.model)
+    }
 }
 
+#[cfg(feature = "files")]
 impl OpenAIConfig for Files {
     fn default() -> Self {
         Self {
@@ -114,14 +305,22 @@ impl OpenAIConfig for Files {
     }
 }
 
+#[cfg(feature = "moderations")]
 impl OpenAIConfig for Moderation {
     fn default() -> Self {
         Self {
-            input: String::new(),
+            input: ModerationInput::Single(String::new()),
+            model: None,
         }
     }
+
+    fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
 }
 
+#[cfg(feature = "fine-tunes")]
+#[allow(deprecated)]
 impl OpenAIConfig for FineTune {
     fn default() -> Self {
         Self {
@@ -139,6 +338,27 @@ impl OpenAIConfig for FineTune {
             suffix: None,
         }
     }
+
+    fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+}
+
+#[cfg(feature = "fine-tunes")]
+impl OpenAIConfig for FineTuningJob {
+    fn default() -> Self {
+        Self {
+            training_file: String::new(),
+            model: Self::get_default_model().into(),
+            validation_file: None,
+            hyperparameters: None,
+            suffix: None,
+        }
+    }
+
+    fn model(&self) -> Option<&str> {
+        Some(&self.model)
+    }
 }
 
 // =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
@@ -154,41 +374,252 @@ pub struct OpenAI<C: OpenAIConfig> {
     /// The HTTP client used to make requests to the `OpenAI` API.
     pub client: Client,
 
-    /// The API key used to authenticate with the `OpenAI` API.
-    pub api_key: String,
+    /// The optional transport that sends the requests instead of `client`.
+    transport: Option<Arc<dyn Transport>>,
+
+    /// The API key used to authenticate with the `OpenAI` API. It is wiped from memory on drop.
+    pub api_key: SecretKey,
+
+    /// The base URL of the API, `https://api.openai.com/v1` by default.
+    pub base_url: String,
+
+    /// The optional organization ID that is sent as `OpenAI-Organization` header.
+    pub organization: Option<String>,
+
+    /// The optional project ID that is sent as `OpenAI-Project` header.
+    pub project: Option<String>,
+
+    /// The `User-Agent` header that is sent with every request.
+    pub user_agent: String,
+
+    /// Additional headers that are sent with every request. An `Authorization` header among
+    /// them is ignored, as it is always derived from the API key.
+    pub headers: HeaderMap,
+
+    /// The optional retry policy for requests that failed with a transient error.
+    pub retry: Option<RetryConfig>,
+
+    /// The optional timeout for a single request. For streamed responses it is applied to
+    /// each chunk instead of the whole request.
+    pub timeout: Option<Duration>,
+
+    /// The optional connect timeout the internal HTTP client is built with.
+    #[cfg(not(target_arch = "wasm32"))]
+    connect_timeout: Option<Duration>,
+
+    /// The optional proxy the internal HTTP client is built with.
+    #[cfg(not(target_arch = "wasm32"))]
+    proxy: Option<reqwest::Proxy>,
+
+    /// The metadata of the last response, e.g. its rate limit information.
+    last_response: LastResponse,
+
+    /// The token usage of all requests. It is shared by all clones of this client.
+    usage_totals: Arc<std::sync::Mutex<UsageTotals>>,
+
+    /// The optional sink that receives the metrics of every request.
+    metrics: Option<Arc<dyn MetricsSink>>,
+
+    /// The optional token that cancels all in-flight requests of this client.
+    cancellation: Option<CancellationToken>,
+
+    /// The token of the current `ask_cancellable` call, which stops its streamed answer.
+    interrupt: Option<CancellationToken>,
 
     /// A boolean flag to disable the live stream of the chat endpoint.
     pub disable_live_stream: bool,
 
+    /// The optional maximum number of bytes a streamed chat response may accumulate.
+    pub max_response_bytes: Option<usize>,
+
+    /// A boolean flag to reject responses that contain fields unknown to this crate.
+    pub strict_deserialization: bool,
+
+    /// A boolean flag to reject invalid parameters instead of correcting them.
+    pub strict_validation: bool,
+
+    /// The model prices used to estimate the cost of API calls.
+    pub pricing: Pricing,
+
+    /// A boolean flag to keep the body of the last response as `serde_json::Value`.
+    pub raw_responses: bool,
+
+    /// A boolean flag to log every request and the status of its response with `tracing`.
+    pub request_logging: bool,
+
     /// An endpoint specific configuration struct that holds all necessary parameters
     /// for the API call.
     pub config: C,
 }
 
-impl<C: OpenAIConfig + Serialize + Sync + Send + std::fmt::Debug> Default for OpenAI<C> {
+/// The metadata of the last response. It is kept behind a lock, so that requests can record it
+/// through a shared reference. A cloned client starts out with a copy of it.
+#[derive(Debug, Default)]
+struct LastResponse(std::sync::Mutex<ResponseMetadata>);
+
+#[derive(Debug, Default, Clone)]
+struct ResponseMetadata {
+    /// The rate limit information of the last response.
+    rate_limit: Option<RateLimitInfo>,
+
+    /// The `x-request-id` of the last response.
+    request_id: Option<String>,
+
+    /// The body of the last deserialized response, if `raw_responses` is enabled.
+    raw: Option<serde_json::Value>,
+
+    /// The token usage of the last request, or of the last `embed_batch` call summed over all
+    /// of its requests.
+    #[cfg(any(feature = "chat", feature = "embeddings"))]
+    usage: Option<Usage>,
+
+    /// The `system_fingerprint` of the last non-streamed chat response.
+    #[cfg(feature = "chat")]
+    system_fingerprint: Option<String>,
+
+    /// The model that served the last chat request.
+    #[cfg(feature = "chat")]
+    model: Option<String>,
+}
+
+impl LastResponse {
+    fn lock(&self) -> std::sync::MutexGuard<'_, ResponseMetadata> {
+        self.0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl Clone for LastResponse {
+    fn clone(&self) -> Self {
+        Self(std::sync::Mutex::new(self.lock().clone()))
+    }
+}
+
+/// The chat history as it was before a request, including the messages dropped by the last
+/// trimming, so that it can be restored if the request fails.
+#[cfg(feature = "chat")]
+struct HistorySnapshot {
+    messages: Vec<Message>,
+    trimmed_messages: Vec<Message>,
+}
+
+/// Reassembles the lines of a server-sent event stream from network chunks, which may end in
+/// the middle of a line or even of a UTF-8 character.
+#[cfg(feature = "chat")]
+#[derive(Debug, Default)]
+struct LineBuffer {
+    partial: Vec<u8>,
+}
+
+#[cfg(feature = "chat")]
+impl LineBuffer {
+    /// Appends a chunk and returns the lines it completed, separated by `\n`. The trailing
+    /// partial line is kept until a later chunk completes it.
+    fn push(&mut self, chunk: &[u8]) -> Option<String> {
+        self.partial.extend_from_slice(chunk);
+        let end = self.partial.iter().rposition(|byte| *byte == b'\n')?;
+        let rest = self.partial.split_off(end + 1);
+        let complete = std::mem::replace(&mut self.partial, rest);
+        Some(String::from_utf8_lossy(&complete).into_owned())
+    }
+
+    /// Returns the remaining partial line once the stream ended.
+    fn finish(&mut self) -> Option<String> {
+        if self.partial.is_empty() {
+            return None;
+        }
+        let rest = std::mem::take(&mut self.partial);
+        Some(String::from_utf8_lossy(&rest).into_owned())
+    }
+}
+
+/// An alias of `OpenAI`, the name the client had in earlier examples.
+pub type OpenAIClient<C> = OpenAI<C>;
+
+/// Constructs a client without an API key.
+///
+/// Unlike `OpenAI::new`, this neither reads the `OPENAI_API_KEY` environment variable nor
+/// terminates the process, so it is safe to use in derived `Default` impls. Every request
+/// made before a key is set with `with_api_key` fails with a `PermissionDenied` error.
+impl<C: OpenAIConfig + Serialize + std::fmt::Debug> Default for OpenAI<C> {
     fn default() -> Self {
-        Self::new()
+        Self {
+            client: Client::new(),
+            transport: None,
+            api_key: SecretKey::default(),
+            base_url: Self::OPENAI_API_BASE_URL.into(),
+            organization: None,
+            project: None,
+            user_agent: Self::DEFAULT_USER_AGENT.into(),
+            headers: HeaderMap::new(),
+            retry: None,
+            timeout: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            connect_timeout: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            proxy: None,
+            last_response: LastResponse::default(),
+            usage_totals: Arc::default(),
+            metrics: None,
+            cancellation: None,
+            interrupt: None,
+            disable_live_stream: false,
+            max_response_bytes: None,
+            strict_deserialization: false,
+            strict_validation: false,
+            pricing: Pricing::default(),
+            raw_responses: false,
+            request_logging: false,
+            config: C::default(),
+        }
     }
 }
 
 impl<C: OpenAIConfig + Serialize + std::fmt::Debug> OpenAI<C> {
+    const OPENAI_API_BASE_URL: &str = "https://api.openai.com/v1";
     const OPENAI_API_MODELS_URL: &str = "https://api.openai.com/v1/models";
+    /// The `User-Agent` header sent by default, e.g. `aionic/0.1.7 (rust)`.
+    pub const DEFAULT_USER_AGENT: &'static str =
+        concat!("aionic/", env!("CARGO_PKG_VERSION"), " (rust)");
+
+    /// Constructs a client with the API key taken from the `OPENAI_API_KEY` environment variable.
+    ///
+    /// This terminates the process if the variable is not set. Use `try_new` to handle that case.
     pub fn new() -> Self {
-        env::var("OPENAI_API_KEY").map_or_else(
-            |_| {
-                println!("OPENAI_API_KEY environment variable not set");
-                exit(1);
-            },
-            |api_key| {
-                let client = Client::new();
-                Self {
-                    client,
-                    api_key,
-                    disable_live_stream: false,
-                    config: C::default(),
-                }
-            },
-        )
+        Self::try_new().unwrap_or_else(|e| {
+            println!("{e}");
+            exit(1);
+        })
+    }
+
+    /// Constructs a client with the API key taken from the `OPENAI_API_KEY` environment variable.
+    ///
+    /// # Returns
+    ///
+    /// This function returns a `NotFound` error if the environment variable is not set.
+    pub fn try_new() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let api_key = env::var("OPENAI_API_KEY").map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "OPENAI_API_KEY environment variable not set",
+            )
+        })?;
+        Ok(Self::default().with_api_key(api_key))
+    }
+
+    /// Sets the API key used to authenticate with the `OpenAI` API.
+    ///
+    /// # Arguments
+    ///
+    /// * `api_key`: The secret API key.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified API key.
+    pub fn with_api_key<S: Into<String>>(mut self, api_key: S) -> Self {
+        self.api_key = SecretKey::new(api_key);
+        self
     }
 
     /// Allows to batch configure the AI assistant with the settings provided in the `Chat` struct.
@@ -213,584 +644,1133 @@ impl<C: OpenAIConfig + Serialize + std::fmt::Debug> OpenAI<C> {
         self
     }
 
-    pub fn is_valid_temperature(&mut self, temperature: f64, limit: f64) -> bool {
+    /// Checks whether a sampling temperature lies between 0.0 and `limit`, which is 2.0 for chat
+    /// models and 1.0 for audio models.
+    ///
+    /// # Arguments
+    ///
+    /// * `temperature` - The temperature to check.
+    /// * `limit` - The maximum temperature of the endpoint.
+    pub fn is_valid_temperature(temperature: f64, limit: f64) -> bool {
         (0.0..=limit).contains(&temperature)
     }
 
-    async fn _make_post_request<S: IntoUrl + Send + Sync>(
-        &mut self,
-        url: S,
-    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
-        let res = self
-            .client
-            .post(url)
-            .header("Content-Type", "application/json")
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .json(&self.config)
-            .send()
-            .await?;
-        Ok(res)
-    }
-
-    async fn _make_delete_request<S: IntoUrl + Send + Sync>(
-        &mut self,
-        url: S,
-    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
-        let res = self
-            .client
-            .delete(url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .send()
-            .await?;
-        Ok(res)
+    /// Rejects an invalid parameter with an `InvalidInput` error in strict validation mode. In
+    /// lenient mode the caller corrects the parameter instead.
+    #[cfg(any(feature = "chat", feature = "images", feature = "audio"))]
+    fn _reject_if_strict(&self, message: String) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self.strict_validation {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                message,
+            )));
+        }
+        Ok(())
     }
 
-    async fn _make_get_request<S: IntoUrl + Send + Sync>(
-        &mut self,
-        url: S,
-    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
-        let res = self
-            .client
-            .get(url)
-            .header("Content-Type", "application/json")
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .send()
-            .await?;
-        Ok(res)
+    /// Sets the base URL all requests are sent to, instead of `https://api.openai.com/v1`.
+    ///
+    /// This allows talking to `OpenAI` compatible APIs, e.g. a proxy, Azure `OpenAI` or a local
+    /// mock server in tests. The endpoint paths, e.g. `/chat/completions`, are appended to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url`: The base URL, e.g. `http://localhost:8080/v1`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified base URL.
+    pub fn with_base_url<S: Into<String>>(mut self, base_url: S) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
     }
 
-    async fn _make_form_request<S: IntoUrl + Send + Sync>(
-        &mut self,
-        url: S,
-        form: Form,
-    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
-        let res = self
-            .client
-            .post(url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .multipart(form)
-            .send()
-            .await?;
-        Ok(res)
+    /// Rebases one of the `OPENAI_API_*_URL` endpoints onto the configured base URL.
+    fn _url(&self, endpoint: &str) -> String {
+        endpoint
+            .strip_prefix(Self::OPENAI_API_BASE_URL)
+            .map_or_else(
+                || endpoint.to_string(),
+                |path| format!("{}{}", self.base_url, path),
+            )
     }
 
-    /// Fetches a list of available models from the `OpenAI` API.
+    /// Replaces the internal HTTP client with a pre-configured one.
     ///
-    /// This method sends a GET request to the `OpenAI` API and returns a vector of identifiers of
-    /// all available models.
+    /// This allows configuring proxies, custom root certificates, connect timeouts or
+    /// connection pool limits via `reqwest::ClientBuilder`. The injected client is used for
+    /// every request, including streamed chat responses and multipart uploads.
+    ///
+    /// # Arguments
+    ///
+    /// * `client`: A `reqwest::Client` that is used for all subsequent requests.
     ///
     /// # Returns
     ///
-    /// A `Result` which is:
-    /// * `Ok` if the request was successful, carrying a `Vec<String>` of model identifiers.
-    /// * `Err` if the request or the parsing failed, carrying the error of type `Box<dyn std::error::Error + Send + Sync>`.
+    /// This function returns the instance of the AI assistant with the specified HTTP client.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Converts this client into a client for another endpoint, e.g. from `OpenAI<Chat>` to
+    /// `OpenAI<Embedding>`, keeping the API key, base URL, headers and all client-level settings.
     ///
-    /// # Errors
+    /// The HTTP client, and with it its connection pool, is moved over rather than rebuilt. As a
+    /// `reqwest::Client` is reference counted, `self.clone().into_endpoint()` gives a second
+    /// client that shares the connection pool with this one. The endpoint configuration starts
+    /// out with its defaults, and the metadata of previous responses is not carried over.
     ///
-    /// This method will return an error if the GET request fails, or if the response from the
-    /// `OpenAI` API cannot be parsed into a `ModelsResponse`.
+    /// # Returns
+    ///
+    /// This function returns a client for the endpoint `D`.
     ///
     /// # Example
     ///
     /// ```rust
-    /// use aionic::openai::{OpenAI, Chat};
-    ///
+    /// use aionic::openai::{Chat, Embedding, Moderation, OpenAI};
     ///
-    /// #[tokio::main]
-    /// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    ///     let mut client = OpenAI::<Chat>::new();
-    ///     match client.models().await {
-    ///         Ok(models) => println!("Models: {:?}", models),
-    ///         Err(e) => println!("Error: {}", e),
-    ///     }
-    ///    Ok(())
-    /// }
+    /// let chat = OpenAI::<Chat>::new();
+    /// let embedding: OpenAI<Embedding> = chat.clone().into_endpoint();
+    /// let moderation = chat.clone().into_endpoint::<Moderation>();
     /// ```
-    ///
-    /// # Note
-    ///
-    /// This method is `async` and needs to be awaited.
-    pub async fn models(
-        &mut self,
-    ) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
-        let resp = self._make_get_request(Self::OPENAI_API_MODELS_URL).await?;
-
-        if !resp.status().is_success() {
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Error: {}", resp.status()),
-            )));
+    pub fn into_endpoint<D: OpenAIConfig + Serialize + std::fmt::Debug>(self) -> OpenAI<D> {
+        OpenAI {
+            client: self.client,
+            transport: self.transport,
+            api_key: self.api_key,
+            base_url: self.base_url,
+            organization: self.organization,
+            project: self.project,
+            user_agent: self.user_agent,
+            headers: self.headers,
+            retry: self.retry,
+            timeout: self.timeout,
+            #[cfg(not(target_arch = "wasm32"))]
+            connect_timeout: self.connect_timeout,
+            #[cfg(not(target_arch = "wasm32"))]
+            proxy: self.proxy,
+            last_response: LastResponse::default(),
+            usage_totals: self.usage_totals,
+            metrics: self.metrics,
+            cancellation: self.cancellation,
+            interrupt: None,
+            disable_live_stream: self.disable_live_stream,
+            max_response_bytes: self.max_response_bytes,
+            strict_deserialization: self.strict_deserialization,
+            strict_validation: self.strict_validation,
+            pricing: self.pricing,
+            raw_responses: self.raw_responses,
+            request_logging: self.request_logging,
+            config: D::default(),
         }
+    }
 
-        let data: ModelsResponse = resp.json().await?;
-        let model_ids: Vec<String> = data.data.into_iter().map(|model| model.id).collect();
-        Ok(model_ids)
+    /// Replaces the way requests are sent with a custom `Transport`.
+    ///
+    /// The requests are still built by this client, with the configured base URL, headers and
+    /// timeouts, but handed to the transport instead of being sent with the HTTP client. This is
+    /// mostly useful to stub the `OpenAI` API in tests.
+    ///
+    /// # Arguments
+    ///
+    /// * `transport`: The `Transport` that sends all subsequent requests.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified transport.
+    pub fn with_transport<T: Transport + 'static>(mut self, transport: T) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
     }
 
-    /// Fetches a specific model by identifier from the `OpenAI` API.
+    /// Sets a timeout for every request, so a hung connection can no longer block forever.
     ///
-    /// This method sends a GET request to the `OpenAI` API for a specific model and returns the `Model`.
+    /// For regular requests the timeout covers the whole request, from connecting until the
+    /// response body has been read. Streamed chat responses can take much longer than any
+    /// reasonable request timeout, so there the timeout is instead applied to waiting for the
+    /// response headers and to waiting for each subsequent chunk. A long generation is
+    /// therefore only aborted if the server stops sending data for longer than `timeout`.
     ///
-    /// # Parameters
+    /// # Arguments
     ///
-    /// * `model`: A `&str` that represents the name of the model to fetch.
+    /// * `timeout`: The maximum duration of a request, or between two streamed chunks.
     ///
     /// # Returns
     ///
-    /// A `Result` which is:
-    /// * `Ok` if the request was successful, carrying the `Model`.
-    /// * `Err` if the request or the parsing failed, carrying the error of type `Box<dyn std::error::Error + Send + Sync>`.
+    /// This function returns the instance of the AI assistant with the specified timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets a timeout for establishing a connection to the `OpenAI` API.
     ///
-    /// # Errors
+    /// Note that this rebuilds the internal HTTP client and therefore replaces a client
+    /// injected with `with_client`. Configure the connect timeout on the injected client
+    /// instead in that case.
     ///
-    /// This method will return an error if the GET request fails, or if the response from the
-    /// `OpenAI` API cannot be parsed into a `Model`.
+    /// # Arguments
     ///
-    /// # Example
+    /// * `timeout`: The maximum duration of the connect phase.
     ///
-    /// ```rust
-    /// use aionic::openai::{OpenAI, Chat};
+    /// # Returns
     ///
-    /// #[tokio::main]
-    /// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    ///     let mut client = OpenAI::<Chat>::new();
-    ///     match client.check_model("gpt-3.5-turbo").await {
-    ///         Ok(model) => println!("Model: {:?}", model),
-    ///         Err(e) => println!("Error: {}", e),
-    ///     }
-    ///     Ok(())
-    /// }
-    /// ```
+    /// This function returns the instance of the AI assistant with the specified connect
+    /// timeout, or an error if the HTTP client cannot be built.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_connect_timeout(
+        mut self,
+        timeout: Duration,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        self.connect_timeout = Some(timeout);
+        self.client = self._build_client()?;
+        Ok(self)
+    }
+
+    /// Sends all requests through an HTTP or HTTPS proxy, including streamed chat responses and
+    /// multipart uploads. The proxy is independent of the base URL, so requests to a custom
+    /// base URL are proxied as well.
     ///
-    /// # Note
+    /// Note that this rebuilds the internal HTTP client and therefore replaces a client
+    /// injected with `with_client`. Configure the proxy on the injected client instead in that
+    /// case. A connect timeout set with `with_connect_timeout` is kept.
     ///
-    /// This method is `async` and needs to be awaited.
-    pub async fn check_model(
-        &mut self,
-        model: &str,
-    ) -> Result<Model, Box<dyn std::error::Error + Send + Sync>> {
-        let resp = self
-            ._make_get_request(format!("{}/{}", Self::OPENAI_API_MODELS_URL, model))
-            .await?;
+    /// # Arguments
+    ///
+    /// * `url`: The URL of the proxy, e.g. `http://proxy.internal:3128`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified proxy, or an
+    /// error if the URL is invalid or the HTTP client cannot be built.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_proxy<U: IntoUrl>(mut self, url: U) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        self.proxy = Some(reqwest::Proxy::all(url)?);
+        self.client = self._build_client()?;
+        Ok(self)
+    }
 
-        if !resp.status().is_success() {
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Error: {}", resp.status()),
-            )));
+    /// Sends all requests through a proxy that requires basic authentication, like `with_proxy`.
+    ///
+    /// # Arguments
+    ///
+    /// * `url`: The URL of the proxy, e.g. `https://proxy.internal:3128`.
+    /// * `username`: The username to authenticate with the proxy.
+    /// * `password`: The password to authenticate with the proxy.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified proxy, or an
+    /// error if the URL is invalid or the HTTP client cannot be built.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_proxy_basic_auth<U: IntoUrl>(
+        mut self,
+        url: U,
+        username: &str,
+        password: &str,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        self.proxy = Some(reqwest::Proxy::all(url)?.basic_auth(username, password));
+        self.client = self._build_client()?;
+        Ok(self)
+    }
+
+    /// Builds an HTTP client with the configured connect timeout and proxy.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn _build_client(&self) -> Result<Client, Box<dyn Error + Send + Sync>> {
+        let mut builder = Client::builder();
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
         }
-        let model: Model = resp.json().await?;
-        Ok(model)
+        if let Some(proxy) = self.proxy.clone() {
+            builder = builder.proxy(proxy);
+        }
+        Ok(builder.build()?)
     }
 
-    /// Creates a file upload part for a multi-part upload operation.
+    /// Enables strict deserialization of API responses, which is disabled by default.
     ///
-    /// This method reads the file at the given path, prepares it for uploading, and
-    /// returns a `Part` that represents this file in the multi-part upload operation.
+    /// By default unknown fields in a response are silently ignored, which keeps the client working
+    /// when `OpenAI` extends its API. In strict mode a response containing any field that is not
+    /// known to this crate is rejected with an `InvalidData` error listing the unexpected fields.
+    /// This is meant to surface API drift early, e.g. in CI, for users who prefer loud failures.
     ///
-    /// # Type Parameters
+    /// Note that this only applies to complete responses, not to the chunks of a streamed chat response.
     ///
-    /// * `P`: The type of the file path. Must implement the `AsRef<Path>` trait.
+    /// # Returns
     ///
-    /// # Parameters
+    /// This function returns the instance of the AI assistant with strict deserialization enabled.
+    pub fn with_strict_deserialization(mut self) -> Self {
+        self.strict_deserialization = true;
+        self
+    }
+
+    /// Enables strict validation of request parameters, which is disabled by default.
     ///
-    /// * `path`: The path of the file to upload. This can be any type that implements `AsRef<Path>`.
+    /// By default some invalid parameters are corrected with a warning before a request is sent,
+    /// e.g. a temperature that is out of range is clamped and an invalid image size is reset to
+    /// the default. In strict mode the request is rejected with an `InvalidInput` error instead.
     ///
     /// # Returns
     ///
-    /// A `Result` which is:
-    /// * `Ok` if the file was read successfully and the `Part` was created, carrying the `Part`.
-    /// * `Err` if there was an error reading the file or creating the `Part`, carrying the error of type `Box<dyn Error + Send + Sync>`.
+    /// This function returns the instance of the AI assistant with strict validation enabled.
+    pub fn with_strict_validation(mut self) -> Self {
+        self.strict_validation = true;
+        self
+    }
+
+    /// Enables or disables capturing the raw JSON body of responses, which is disabled by default.
     ///
-    /// # Errors
+    /// When enabled, the body of every deserialized response is kept as `serde_json::Value` next
+    /// to the typed result, which gives access to fields this crate does not know about (yet).
+    /// Note that this does not apply to the chunks of a streamed chat response.
     ///
-    /// This method will return an error if there was an error reading the file at the given path,
-    /// or if there was an error creating the `Part` (for example, if the MIME type was not recognized).
+    /// # Arguments
     ///
-    /// # Example
+    /// * `enabled` - Whether to capture the raw responses.
     ///
-    /// ```rust
-    /// use aionic::openai::{OpenAI, Chat};
+    /// # Returns
     ///
-    /// #[tokio::main]
-    /// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    ///     let mut client = OpenAI::<Chat>::new();
-    ///     match client.create_file_upload_part("path/to/file.txt").await {
-    ///         Ok(part) => println!("Part created successfully."),
-    ///         Err(e) => println!("Error: {}", e),
-    ///     }
-    ///     Ok(())
-    /// }
-    /// ```
+    /// This function returns the instance of the AI assistant with raw responses enabled or disabled.
+    pub fn with_raw_responses(mut self, enabled: bool) -> Self {
+        self.raw_responses = enabled;
+        if !enabled {
+            self.last_response.lock().raw = None;
+        }
+        self
+    }
+
+    /// Enables or disables logging of requests, which is disabled by default. It requires the
+    /// `tracing` feature, otherwise nothing is logged.
     ///
-    /// # Note
+    /// When enabled, the method, URL and payload of every request are logged at `debug` level
+    /// and the status of its response at `info` level. The API key is masked wherever it
+    /// appears, long payloads are truncated and multipart bodies are left out.
     ///
-    /// This method is `async` and needs to be awaited.
-    pub async fn create_file_upload_part<P: AsRef<Path> + Send>(
-        &mut self,
-        path: P,
-    ) -> Result<Part, Box<dyn Error + Send + Sync>> {
-        let file_name = path.as_ref().to_str().unwrap().to_string();
-        let streamed_body = self._get_streamed_body(path).await?;
-        let part_stream = Part::stream(streamed_body)
-            .file_name(file_name)
-            .mime_str("application/octet-stream")?;
-        Ok(part_stream)
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to log the requests.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with request logging enabled or disabled.
+    pub fn with_request_logging(mut self, enabled: bool) -> Self {
+        self.request_logging = enabled;
+        self
     }
 
-    async fn _get_streamed_body<P: AsRef<Path> + Send>(
-        &mut self,
-        path: P,
-    ) -> Result<Body, Box<dyn Error + Send + Sync>> {
-        if !path.as_ref().exists() {
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Image not found",
-            )));
-        }
-        let file_stream_body = tokio::fs::File::open(path).await?;
-        let stream = FramedRead::new(file_stream_body, BytesCodec::new());
-        let body = Body::wrap_stream(stream);
-        Ok(body)
+    /// Returns the raw JSON body of the last deserialized response.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `None` if raw responses are disabled, no response was received yet,
+    /// or the last response was not valid JSON.
+    pub fn last_raw_response(&self) -> Option<serde_json::Value> {
+        self.last_response.lock().raw.clone()
     }
 
-    /// A helper function to handle potential errors from `OpenAI` API responses.
+    /// Sets the model prices used to estimate the cost of API calls, e.g. to price fine-tuned
+    /// models or to follow price changes. By default `Pricing::default()` is used.
     ///
     /// # Arguments
     ///
-    /// * `res` - A `Response` object from the `OpenAI` API call.
+    /// * `pricing` - The `Pricing` table to use.
     ///
     /// # Returns
     ///
-    /// `Result<Response, Box<dyn std::error::Error + Send + Sync>>`:
-    /// Returns the original `Response` object if the status code indicates success.
-    /// If the status code indicates an error, it will attempt to deserialize the response
-    /// into an `OpenAIError` and returns a `std::io::Error` constructed from the error message.
-    pub async fn handle_api_errors(
-        &mut self,
-        res: reqwest::Response,
-    ) -> Result<reqwest::Response, Box<dyn std::error::Error + Send + Sync>> {
-        if res.status().is_success() {
-            Ok(res)
-        } else {
-            let err_resp: OpenAIError = res.json().await?;
-            Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                err_resp.error.message,
-            )))
-        }
+    /// This function returns the instance of the AI assistant with the specified pricing.
+    pub fn with_pricing(mut self, pricing: Pricing) -> Self {
+        self.pricing = pricing;
+        self
     }
-}
-
-// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
-// = OpenAI CHAT IMPLEMENTATION
-// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
-
-impl OpenAI<Chat> {
-    const OPENAI_API_COMPLETIONS_URL: &str = "https://api.openai.com/v1/chat/completions";
 
-    /// Sets the model of the AI assistant.
+    /// Registers a sink that receives the endpoint, model, status, latency and token usage of
+    /// every request, including failed ones. Streamed chat responses additionally report the
+    /// time to their first token. See `MetricsSink`.
     ///
     /// # Arguments
     ///
-    /// * `model`: A string that specifies the model name to be used by the AI assistant.
+    /// * `sink` - The `MetricsSink` to call after every request.
     ///
     /// # Returns
     ///
-    /// This function returns the instance of the AI assistant with the specified model.
-    pub fn set_model<S: Into<String>>(mut self, model: S) -> Self {
-        self.config.model = model.into();
+    /// This function returns the instance of the AI assistant with the metrics sink registered.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_metrics_sink<M: MetricsSink + 'static>(mut self, sink: M) -> Self {
+        self.metrics = Some(Arc::new(sink));
         self
     }
 
-    /// Sets the maximum number of tokens that the AI model can generate in a single response.
+    /// Sets a token that cancels the in-flight requests of this client, e.g. when the user
+    /// navigates away or the program shuts down.
+    ///
+    /// Once the token is cancelled, requests fail with a `Cancelled` error before they are sent,
+    /// between retry attempts and between the chunks of a streamed response. A cancelled `ask`
+    /// removes its prompt from the chat history again. Clones of the token can be cancelled from
+    /// any task, and a child token allows to cancel only the requests of a single call.
     ///
     /// # Arguments
     ///
-    /// * `max_tokens`: An unsigned 64-bit integer that specifies the maximum number of tokens
-    /// that the AI model can generate in a single response.
+    /// * `token` - The `CancellationToken` to observe.
     ///
     /// # Returns
     ///
-    /// This function returns the instance of the AI assistant with the specified maximum number of tokens.
-    pub fn set_max_tokens(mut self, max_tokens: u64) -> Self {
-        self.config.max_tokens = Some(max_tokens);
+    /// This function returns the instance of the AI assistant with the cancellation token set.
+    pub fn with_cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
         self
     }
 
-    /// Allows to set the chat history in a specific state.
+    /// Sets the organization the requests are attributed to.
     ///
     /// # Arguments
     ///
-    /// * `messages`: A vector of `Message` structs.
+    /// * `organization`: The organization ID that is sent as `OpenAI-Organization` header.
     ///
     /// # Returns
     ///
-    /// This function returns the instance of the AI assistant with the specified messages.
-    pub fn set_messages(mut self, messages: Vec<Message>) -> Self {
-        self.config.messages = messages;
+    /// This function returns the instance of the AI assistant with the specified organization.
+    pub fn with_organization<S: Into<String>>(mut self, organization: S) -> Self {
+        self.organization = Some(organization.into());
         self
     }
 
-    /// Sets the temperature of the AI model's responses.
-    ///
-    /// The temperature setting adjusts the randomness of the AI's responses.
-    /// Higher values produce more random responses, while lower values produce more deterministic responses.
-    /// The allowed range of values is between 0.0 and 2.0, with 0 being the most deterministic and 1 being the most random.
+    /// Sets the project the requests are attributed to. This is required for project-scoped
+    /// API keys and project-level usage tracking.
     ///
     /// # Arguments
     ///
-    /// * `temperature`: A float that specifies the temperature.
+    /// * `project`: The project ID that is sent as `OpenAI-Project` header.
     ///
     /// # Returns
     ///
-    /// This function returns the instance of the AI assistant with the specified temperature.
-    pub fn set_temperature(mut self, temperature: f64) -> Self {
-        self.config.temperature = Some(temperature);
+    /// This function returns the instance of the AI assistant with the specified project.
+    pub fn with_project<S: Into<String>>(mut self, project: S) -> Self {
+        self.project = Some(project.into());
         self
     }
 
-    /// Sets the streaming configuration of the AI assistant.
-    ///
-    /// If streaming is enabled, the AI assistant will fetch and process the AI's responses as they arrive.
-    /// If it's disabled, the assistant will collect all of the AI's responses at once and return them as a single response.
+    /// Sets the `User-Agent` header that is sent with every request instead of
+    /// `DEFAULT_USER_AGENT`.
     ///
     /// # Arguments
     ///
-    /// * `streamed`: A boolean that specifies whether streaming should be enabled.
+    /// * `user_agent`: The user agent, e.g. `format!("{} my-app/1.0", OpenAI::<Chat>::DEFAULT_USER_AGENT)`
+    ///   to identify an application in addition to this crate.
     ///
     /// # Returns
     ///
-    /// This function returns the instance of the AI assistant with the specified streaming setting.
-    pub fn set_stream_responses(mut self, streamed: bool) -> Self {
-        self.config.stream = Some(streamed);
+    /// This function returns the instance of the AI assistant with the specified user agent.
+    pub fn with_user_agent<S: Into<String>>(mut self, user_agent: S) -> Self {
+        self.user_agent = user_agent.into();
         self
     }
 
-    /// Sets a primer message for the AI assistant.
+    /// Adds a header that is sent with every request, e.g. for cost attribution or an API gateway.
     ///
-    /// The primer message is inserted at the beginning of the `messages` vector in the `config` struct.
-    /// This can be used to prime the AI model with a certain context or instruction.
+    /// The value is marked as sensitive, so it does not show up in `Debug` output. Setting the
+    /// same header again replaces its value. An `Authorization` header is ignored, as it is always
+    /// derived from the API key. Use `with_organization` and `with_project` for the `OpenAI`
+    /// specific headers.
     ///
     /// # Arguments
     ///
-    /// * `primer_msg`: A string that specifies the primer message.
+    /// * `name`: The name of the header, e.g. `X-Team`.
+    /// * `value`: The value of the header.
     ///
     /// # Returns
     ///
-    /// This function returns the instance of the AI assistant with the specified primer message.
-    pub fn set_primer<S: Into<String>>(mut self, primer_msg: S) -> Self {
-        let msg = Message::new(&MessageRole::System, primer_msg.into());
-        self.config.messages.insert(0, msg);
-        self
+    /// This function returns the instance of the AI assistant with the header added, or an error
+    /// if the name or value is not a valid header.
+    pub fn with_header<K: AsRef<str>, V: AsRef<str>>(
+        mut self,
+        name: K,
+        value: V,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let name = HeaderName::from_bytes(name.as_ref().as_bytes())?;
+        let mut value = HeaderValue::from_str(value.as_ref())?;
+        value.set_sensitive(true);
+        self.headers.insert(name, value);
+        Ok(self)
     }
 
-    /// Returns the last message in the AI assistant's configuration.
+    /// Adds headers that are sent with every request, like `with_header`. Unlike `with_header`
+    /// the values are taken as they are, so mark secret values as sensitive beforehand.
+    ///
+    /// # Arguments
+    ///
+    /// * `headers`: The headers to add. They replace previously added headers of the same name.
     ///
     /// # Returns
     ///
-    /// This function returns an `Option` that contains a reference to the last `Message`
-    /// in the `config` struct if it exists, or `None` if it doesn't.
-    pub fn get_last_message(&self) -> Option<&Message> {
-        self.config.messages.last()
+    /// This function returns the instance of the AI assistant with the headers added.
+    pub fn with_headers(mut self, headers: HeaderMap) -> Self {
+        for (name, value) in &headers {
+            self.headers.insert(name, value.clone());
+        }
+        self
     }
 
-    /// Clears the messages in the AI assistant's configuration to start from a clean state.
-    /// This is only necessary in very specific cases.
+    /// Enables retrying requests that failed with a rate limit (429) or a transient server
+    /// error (500, 502, 503). Other errors, e.g. 400 or 401, are never retried.
+    ///
+    /// Note that multipart uploads stream the file from disk and can therefore not be replayed,
+    /// so they are always sent exactly once.
+    ///
+    /// # Arguments
+    ///
+    /// * `retry`: A `RetryConfig` that specifies the number of retries and the backoff.
     ///
     /// # Returns
     ///
-    /// This function returns the instance of the AI assistant with no messages in its configuration.
-    pub fn clear_state(mut self) -> Self {
-        self.config.messages.clear();
+    /// This function returns the instance of the AI assistant with the specified retry policy.
+    pub fn with_retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = Some(retry);
         self
     }
 
-    fn _process_delta(
-        &self,
-        line: &str,
-        answer_text: &mut Vec<String>,
-    ) -> Result<(), Box<dyn Error + Send + Sync>> {
-        line.strip_prefix("data: ").map_or(Ok(()), |chunk| {
-            if chunk.starts_with("[DONE]") {
-                return Ok(());
+    /// Builds a request that times out after the configured timeout. On wasm32 requests cannot
+    /// carry a timeout, so `_execute` enforces it instead.
+    fn _build_request<S: IntoUrl>(&self, method: Method, url: S) -> RequestBuilder {
+        let req = self._build_untimed_request(method, url);
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(timeout) = self.timeout {
+            return req.timeout(timeout);
+        }
+        req
+    }
+
+    fn _build_untimed_request<S: IntoUrl>(&self, method: Method, url: S) -> RequestBuilder {
+        let authorization = self.api_key.expose(|key| {
+            // Reserve the exact capacity, so no reallocation leaves a copy of the key behind.
+            let mut value = Zeroizing::new(String::with_capacity("Bearer ".len() + key.len()));
+            value.push_str("Bearer ");
+            value.push_str(key);
+            value
+        });
+        let mut headers = self.headers.clone();
+        headers.remove(AUTHORIZATION);
+        let has_user_agent = headers.contains_key(USER_AGENT);
+        let mut req = self.client.request(method, url).headers(headers);
+        if !has_user_agent {
+            req = req.header(USER_AGENT, self.user_agent.as_str());
+        }
+        req = match HeaderValue::from_str(&authorization) {
+            Ok(mut value) => {
+                value.set_sensitive(true);
+                req.header(AUTHORIZATION, value)
             }
-            let serde_chunk: Result<StreamedReponse, _> = serde_json::from_str(chunk);
-            match serde_chunk {
-                Ok(chunk) => {
-                    for choice in chunk.choices {
-                        if let Some(content) = choice.delta.content {
-                            let sanitized_content =
-                                content.trim().strip_suffix('\n').unwrap_or(&content);
-                            if !self.disable_live_stream {
-                                print!("{}", sanitized_content);
-                                io::stdout().flush()?;
-                            }
-                            answer_text.push(sanitized_content.to_string());
-                        }
+            // Let the request fail with reqwest's error, which does not contain the value.
+            Err(_) => req.header(AUTHORIZATION, authorization.as_str()),
+        };
+        if let Some(organization) = self.organization.as_ref() {
+            req = req.header("OpenAI-Organization", organization);
+        }
+        if let Some(project) = self.project.as_ref() {
+            req = req.header("OpenAI-Project", project);
+        }
+        req
+    }
+
+    /// Returns the JSON payload exactly as it is sent to the `OpenAI` API for the current
+    /// configuration. This is useful for debugging or a dry-run without issuing a request.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the serialized configuration as a `serde_json::Value`.
+    pub fn request_body(&self) -> serde_json::Value {
+        let mut payload = serde_json::to_value(&self.config).unwrap_or_default();
+        self.config.prepare_payload(&mut payload);
+        payload
+    }
+
+    fn _post_request<S: IntoUrl>(&self, url: S) -> RequestBuilder {
+        self._post_request_with_body(url, &self.request_body())
+    }
+
+    fn _post_request_with_body<S: IntoUrl>(
+        &self,
+        url: S,
+        body: &serde_json::Value,
+    ) -> RequestBuilder {
+        // Streamed responses are guarded by a per-chunk timeout instead, see `_within_timeout`
+        let req = if body["stream"] == serde_json::Value::Bool(true) {
+            self._build_untimed_request(Method::POST, url)
+        } else {
+            self._build_request(Method::POST, url)
+        };
+        req.header("Content-Type", "application/json").json(body)
+    }
+
+    #[cfg(any(feature = "chat", feature = "embeddings"))]
+    async fn _within_timeout<T, F>(&self, fut: F) -> Result<T, Box<dyn Error + Send + Sync>>
+    where
+        F: Future<Output = Result<T, Box<dyn Error + Send + Sync>>>,
+    {
+        match self.timeout {
+            Some(timeout) => rt::timeout(timeout, fut).await.ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    format!("No response received within {timeout:?}"),
+                )
+            })?,
+            None => fut.await,
+        }
+    }
+
+    /// Runs `fut` until it completes, or fails with `Cancelled` once the cancellation token of
+    /// the client or of the current `ask_cancellable` call is cancelled.
+    async fn _cancellable<T, F>(&self, fut: F) -> Result<T, Box<dyn Error + Send + Sync>>
+    where
+        F: Future<Output = Result<T, Box<dyn Error + Send + Sync>>>,
+    {
+        if self.cancellation.is_none() && self.interrupt.is_none() {
+            return fut.await;
+        }
+        tokio::select! {
+            biased;
+            () = Self::_until_cancelled(self.cancellation.as_ref()) => Err(Box::new(Cancelled)),
+            () = Self::_until_cancelled(self.interrupt.as_ref()) => Err(Box::new(Cancelled)),
+            res = fut => res,
+        }
+    }
+
+    /// Waits until `token` is cancelled, or forever if there is no token.
+    async fn _until_cancelled(token: Option<&CancellationToken>) {
+        match token {
+            Some(token) => token.cancelled().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Checks whether the token of the current `ask_cancellable` call was cancelled.
+    fn _is_interrupted(&self) -> bool {
+        self.interrupt
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    #[cfg(any(feature = "images", feature = "audio", feature = "files"))]
+    fn _form_request<S: IntoUrl>(&self, url: S, form: Form) -> RequestBuilder {
+        self._build_request(Method::POST, url).multipart(form)
+    }
+
+    /// Sends a request like `_send_traced` and reports its metrics to the `MetricsSink`, if one
+    /// is registered.
+    ///
+    /// Failed requests are reported right away. Successful responses get `PendingMetrics`
+    /// attached, which are reported once their body has been read, e.g. by `_parse_json`.
+    async fn _send(
+        &self,
+        req: RequestBuilder,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        let Some(sink) = self.metrics.as_deref() else {
+            return self._send_traced(req).await;
+        };
+        let start = rt::Instant::now();
+        let model = self.config.model().map(str::to_string);
+        match self._send_traced(req).await {
+            Ok(mut res) => {
+                let pending = PendingMetrics {
+                    endpoint: res.url().path().to_string(),
+                    model,
+                    status: res.status().as_u16(),
+                    start,
+                };
+                if res.status().is_success() {
+                    // Without response extensions, the metrics are reported without usage.
+                    if let Some(pending) = rt::attach(&mut res, pending) {
+                        sink.on_request_complete(&pending.finish(None, None, None));
                     }
-                    Ok(())
+                } else {
+                    let error = ErrorClass::from_status(res.status());
+                    sink.on_request_complete(&pending.finish(None, None, Some(error)));
                 }
-                Err(_) => Err(Box::new(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    "Deserialization Error",
-                ))),
+                Ok(res)
             }
-        })
+            Err(e) => {
+                let endpoint = e
+                    .downcast_ref::<reqwest::Error>()
+                    .and_then(reqwest::Error::url)
+                    .map(|url| url.path().to_string())
+                    .unwrap_or_default();
+                sink.on_request_complete(&RequestMetrics {
+                    endpoint,
+                    model,
+                    status: None,
+                    latency: start.elapsed(),
+                    time_to_first_token: None,
+                    usage: None,
+                    error: Some(ErrorClass::from_error(&*e)),
+                });
+                Err(e)
+            }
+        }
     }
 
-    async fn _ask_openai_streamed(
-        &mut self,
-        res: &mut reqwest::Response,
-        answer_text: &mut Vec<String>,
-    ) -> Result<(), Box<dyn Error + Send + Sync>> {
-        print!("AI: ");
+    /// Reports the metrics of a response whose body has been read to the `MetricsSink`.
+    fn _report_metrics(
+        &self,
+        pending: Option<PendingMetrics>,
+        usage: Option<Usage>,
+        time_to_first_token: Option<std::time::Duration>,
+        error: Option<ErrorClass>,
+    ) {
+        if let (Some(sink), Some(pending)) = (self.metrics.as_deref(), pending) {
+            sink.on_request_complete(&pending.finish(usage, time_to_first_token, error));
+        }
+    }
+
+    /// Sends a request, retrying it according to the `RetryConfig`.
+    ///
+    /// With the `tracing` feature, the request is wrapped in an `openai_request` span that
+    /// records the endpoint, the model, the HTTP status and the latency. The span is attached
+    /// to the response, so `_parse_json` can add the token usage to it later on.
+    async fn _send_traced(
+        &self,
+        req: RequestBuilder,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        #[cfg(feature = "tracing")]
+        {
+            use tracing::field::Empty;
+            use tracing::Instrument;
+
+            let span = tracing::info_span!(
+                "openai_request",
+                endpoint = Empty,
+                model = self.config.model(),
+                status = Empty,
+                latency_ms = Empty,
+                prompt_tokens = Empty,
+                completion_tokens = Empty,
+                total_tokens = Empty,
+            );
+            let start = rt::Instant::now();
+            let mut res = match self._send_with_retries(req).instrument(span.clone()).await {
+                Ok(res) => res,
+                Err(e) => {
+                    tracing::warn!(parent: &span, error = %e, "Request failed");
+                    return Err(e);
+                }
+            };
+            span.record("endpoint", res.url().as_str());
+            span.record("status", res.status().as_u16());
+            span.record("latency_ms", start.elapsed().as_millis() as u64);
+            if self.request_logging {
+                tracing::info!(parent: &span, status = res.status().as_u16(), "Response received");
+            } else {
+                tracing::debug!(parent: &span, "Response received");
+            }
+            rt::attach(&mut res, span);
+            Ok(res)
+        }
+        #[cfg(not(feature = "tracing"))]
+        self._send_with_retries(req).await
+    }
+
+    async fn _send_with_retries(
+        &self,
+        req: RequestBuilder,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        if self.api_key.is_empty() {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "No API key set, use `with_api_key` or the OPENAI_API_KEY environment variable",
+            )));
+        }
+        let Some(retry) = self.retry.as_ref() else {
+            return self._cancellable(self._execute(req)).await;
+        };
+        let mut attempt = 0;
         loop {
-            let chunk = match res.chunk().await {
-                Ok(Some(chunk)) => chunk,
-                Ok(None) => break,
-                Err(e) => return Err(Box::new(e)),
+            let Some(current) = req.try_clone() else {
+                return self._cancellable(self._execute(req)).await;
             };
-            let chunk_str = String::from_utf8_lossy(&chunk);
-            let lines: Vec<&str> = chunk_str.split('\n').collect();
-            for line in lines {
-                self._process_delta(line, answer_text)?;
+            let res = self._cancellable(self._execute(current)).await?;
+            if attempt >= retry.max_retries || !RetryConfig::is_retryable(res.status()) {
+                return Ok(res);
             }
+            let delay = retry.delay_for(attempt, res.headers());
+            #[cfg(feature = "tracing")]
+            retry.trace_attempt(attempt, &res, delay);
+            self._cancellable(async {
+                rt::sleep(delay).await;
+                Ok(())
+            })
+            .await?;
+            attempt += 1;
         }
-        println!();
-        Ok(())
     }
 
-    /// Makes a request to `OpenAI`'s GPT model and retrieves a response based on the provided `prompt`.
+    async fn _execute(
+        &self,
+        req: RequestBuilder,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        let request = req.build()?;
+        #[cfg(feature = "tracing")]
+        if self.request_logging {
+            self._log_request(&request);
+        }
+        let res = match self.transport.as_deref() {
+            Some(transport) => transport.execute(&self.client, request),
+            None => transport::dispatch(&self.client, request),
+        };
+        #[cfg(target_arch = "wasm32")]
+        if let Some(timeout) = self.timeout {
+            return rt::timeout(timeout, res).await.unwrap_or_else(|| {
+                Err(Box::new(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("No response received within {timeout:?}"),
+                )))
+            });
+        }
+        res.await
+    }
+
+    /// The maximum number of characters of a request payload that are logged.
+    #[cfg(feature = "tracing")]
+    const LOG_PAYLOAD_CHARS: usize = 1000;
+
+    /// Logs the method, URL and payload of a request at `debug` level, with the API key masked.
+    #[cfg(feature = "tracing")]
+    fn _log_request(&self, request: &reqwest::Request) {
+        let payload = request
+            .body()
+            .and_then(reqwest::Body::as_bytes)
+            .map(|body| {
+                let body = String::from_utf8_lossy(body);
+                let body = self.api_key.expose(|key| body.replace(key, "sk-***"));
+                let mut payload: String = body.chars().take(Self::LOG_PAYLOAD_CHARS).collect();
+                if payload.len() < body.len() {
+                    payload.push_str("...");
+                }
+                payload
+            });
+        tracing::debug!(
+            method = %request.method(),
+            url = %request.url(),
+            payload = payload.as_deref().unwrap_or("<multipart>"),
+            "Sending request"
+        );
+    }
+
+    /// Remembers the metadata of the last response, e.g. its rate limit headers.
+    fn _record_response(&self, res: &reqwest::Response) {
+        let mut last_response = self.last_response.lock();
+        last_response.rate_limit = RateLimitInfo::from_headers(res.headers());
+        last_response.request_id = Self::_request_id(res);
+    }
+
+    fn _request_id(res: &reqwest::Response) -> Option<String> {
+        res.headers()
+            .get("x-request-id")
+            .and_then(|id| id.to_str().ok())
+            .map(str::to_string)
+    }
+
+    /// Returns the `x-request-id` of the last response, which `OpenAI` support asks for when
+    /// investigating a request.
     ///
-    /// This function accepts a prompt, converts it into a string, and sends a request to the `OpenAI` API.
-    /// Depending on the streaming configuration (`is_streamed`), the function either collects all of the AI's responses
-    /// at once, or fetches and processes them as they arrive.
+    /// # Returns
     ///
-    /// # Arguments
+    /// This function returns `None` if no request was made yet, or if the last response
+    /// did not carry an `x-request-id` header.
+    pub fn last_request_id(&self) -> Option<String> {
+        self.last_response.lock().request_id.clone()
+    }
+
+    /// Returns the rate limit information sent along with the last response.
     ///
-    /// * `prompt`: A value that implements `Into<String>`. This will be converted into a string and sent to the API as the
-    /// prompt for the AI model.
+    /// # Returns
     ///
-    /// * `persist_state`: If true, the function will push the AI's response to the `messages` vector in the `config` struct.
-    /// If false, it will remove the last message from the `messages` vector.
+    /// This function returns `None` if no request was made yet, or if the last response
+    /// did not carry any `x-ratelimit-*` headers.
+    pub fn last_rate_limit(&self) -> Option<RateLimitInfo> {
+        self.last_response.lock().rate_limit
+    }
+
+    /// Returns the token usage this client has accumulated over all successful requests.
+    ///
+    /// All responses that report a usage contribute to it, e.g. non-streamed chat completions
+    /// and embeddings. The usage of streamed chat completions is estimated, unless it is
+    /// requested with `set_stream_usage`. The totals are
+    /// shared by all clones of a client and carried over by `into_endpoint`.
+    pub fn usage_totals(&self) -> UsageTotals {
+        *self.usage_totals.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Resets the accumulated token usage of this client and all of its clones to zero.
+    pub fn reset_usage_totals(&self) {
+        *self.usage_totals.lock().unwrap_or_else(|e| e.into_inner()) = UsageTotals::default();
+    }
+
+    /// Adds the usage of a request to the accumulated totals.
+    fn _add_usage(&self, usage: &Usage) {
+        self.usage_totals
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .add(usage);
+    }
+
+    async fn _make_post_request<S: IntoUrl + Send + Sync>(
+        &self,
+        url: S,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        self._make_post_request_with_body(url, &self.request_body())
+            .await
+    }
+
+    /// Makes a POST request with the given body instead of the serialized configuration, for
+    /// requests whose parameters are passed as arguments rather than set in `config`.
+    async fn _make_post_request_with_body<S: IntoUrl + Send + Sync>(
+        &self,
+        url: S,
+        body: &serde_json::Value,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        let res = self._send(self._post_request_with_body(url, body)).await?;
+        self._record_response(&res);
+        Ok(res)
+    }
+
+    /// Posts `body` to `url`, retrying it with the next of `fallback_models` as long as the
+    /// model of the request is not found or overloaded.
     ///
     /// # Returns
     ///
-    /// * `Ok(String)`: A success value containing the AI's response as a string.
+    /// The successful response and the model that served it. If every model failed, a
+    /// `FallbackError` with the error of each attempt. Without fallback models the error of
+    /// the request is returned as-is, e.g. an `ApiError`.
+    #[cfg(any(feature = "chat", feature = "embeddings"))]
+    async fn _post_with_fallback(
+        &self,
+        url: &str,
+        mut body: serde_json::Value,
+        fallback_models: &[String],
+    ) -> Result<(reqwest::Response, String), Box<dyn Error + Send + Sync>> {
+        let primary = body["model"].as_str().unwrap_or_default().to_string();
+        let mut attempts = Vec::new();
+        for model in std::iter::once(&primary).chain(fallback_models) {
+            body["model"] = model.as_str().into();
+            let res = if body["stream"] == serde_json::Value::Bool(true) {
+                let res = self
+                    ._within_timeout(self._send(self._post_request_with_body(url, &body)))
+                    .await?;
+                self._record_response(&res);
+                res
+            } else {
+                self._make_post_request_with_body(url, &body).await?
+            };
+            let err = match self.handle_api_errors(res).await {
+                Ok(res) => return Ok((res, model.clone())),
+                Err(err) if fallback_models.is_empty() => return Err(err),
+                Err(err) => err,
+            };
+            match err.downcast::<ApiError>() {
+                Ok(api_error) if api_error.is_model_not_found() || api_error.is_overloaded() => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(model = %model, error = %api_error, "Model failed, falling back");
+                    attempts.push((model.clone(), *api_error));
+                }
+                Ok(api_error) => return Err(api_error),
+                Err(err) => return Err(err),
+            }
+        }
+        Err(Box::new(FallbackError { attempts }))
+    }
+
+    #[cfg(feature = "files")]
+    async fn _make_delete_request<S: IntoUrl + Send + Sync>(
+        &self,
+        url: S,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        let res = self._send(self._build_request(Method::DELETE, url)).await?;
+        self._record_response(&res);
+        Ok(res)
+    }
+
+    async fn _make_get_request<S: IntoUrl + Send + Sync>(
+        &self,
+        url: S,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        let req = self
+            ._build_request(Method::GET, url)
+            .header("Content-Type", "application/json");
+        let res = self._send(req).await?;
+        self._record_response(&res);
+        Ok(res)
+    }
+
+    /// Builds the URL of a list request with the given cursor parameters.
+    #[cfg(feature = "files")]
+    fn _list_url(
+        &self,
+        url: &str,
+        params: &ListParams,
+    ) -> Result<reqwest::Url, Box<dyn Error + Send + Sync>> {
+        Ok(reqwest::Url::parse_with_params(url, params.query())?)
+    }
+
+    /// Lists the objects of all pages of a list endpoint, following the cursor from page to page.
+    #[cfg(feature = "files")]
+    async fn _list_all<T: CursorPage>(
+        &self,
+        url: &str,
+    ) -> Result<Vec<T::Item>, Box<dyn Error + Send + Sync>> {
+        let mut params = ListParams::default();
+        let mut items = Vec::new();
+        loop {
+            let res = self
+                ._make_get_request(self._list_url(url, &params)?)
+                .await?;
+            let handled_res = self.handle_api_errors(res).await?;
+            let (page, next) = self._parse_json::<T>(handled_res).await?.into_items();
+            items.extend(page);
+            match next {
+                Some(after) => params = params.set_after(after),
+                None => return Ok(items),
+            }
+        }
+    }
+
+    #[cfg(any(feature = "images", feature = "audio", feature = "files"))]
+    async fn _make_form_request<S: IntoUrl + Send + Sync>(
+        &self,
+        url: S,
+        form: Form,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        let res = self._send(self._form_request(url, form)).await?;
+        self._record_response(&res);
+        Ok(res)
+    }
+
+    /// Fetches a list of available models from the `OpenAI` API.
     ///
-    /// * `Err(Box<dyn std::error::Error + Send + Sync>)`: An error value. This is a dynamic error, meaning it could represent
-    /// various kinds of failures. The function will return an error if any step in the process fails, such as making the HTTP request,
-    /// parsing the JSON response, or if there's an issue with the streaming process.
+    /// This method sends a GET request to the `OpenAI` API and returns a vector of identifiers of
+    /// all available models.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// * `Ok` if the request was successful, carrying a `Vec<String>` of model identifiers.
+    /// * `Err` if the request or the parsing failed, carrying the error of type `Box<dyn std::error::Error + Send + Sync>`.
     ///
     /// # Errors
     ///
-    /// This function will return an error if the HTTP request fails, the JSON response from the API cannot be parsed, or if
-    /// an error occurs during streaming.
+    /// This method will return an error if the GET request fails, or if the response from the
+    /// `OpenAI` API cannot be parsed into a `ModelsResponse`.
     ///
-    /// # Examples
+    /// # Example
     ///
     /// ```rust
-    ///  
-    /// use aionic::openai::chat::Chat;
-    /// use aionic::openai::OpenAI;
+    /// use aionic::openai::{OpenAI, Chat};
+    ///
     ///
     /// #[tokio::main]
     /// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    ///     let prompt = "Hello, world!";
     ///     let mut client = OpenAI::<Chat>::new();
-    ///     let result = client.ask(prompt, true).await;
-    ///     match result {
-    ///         Ok(response) => println!("{}", response),
+    ///     match client.models().await {
+    ///         Ok(models) => println!("Models: {:?}", models),
     ///         Err(e) => println!("Error: {}", e),
     ///     }
-    ///     Ok(())
-    ///  }
+    ///    Ok(())
+    /// }
     /// ```
     ///
     /// # Note
     ///
-    /// This function is `async` and must be awaited when called.
-    pub async fn ask<P: Into<Message> + Send>(
-        &mut self,
-        prompt: P,
-        persist_state: bool,
-    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-        let mut answer_chunks: Vec<String> = Vec::new();
-        let is_streamed = self.config.stream.unwrap_or(false);
-        self.config.messages.push(prompt.into());
-        if let Some(temp) = self.config.temperature {
-            // TODO: Add a log warning
-            if !self.is_valid_temperature(temp, 2.0) {
-                self.config.temperature = Some(2.0);
-            }
-        }
-        let mut r = self
-            ._make_post_request(Self::OPENAI_API_COMPLETIONS_URL)
+    /// This method is `async` and needs to be awaited.
+    pub async fn models(&self) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
+        let models = self.models_detailed().await?;
+        Ok(models.into_iter().map(|model| model.id).collect())
+    }
+
+    /// Fetches all models available in the `OpenAI` API with their full metadata.
+    ///
+    /// Unlike `models`, which only returns the IDs, this keeps the owner, the creation
+    /// timestamp and the permissions of every model, so no `check_model` call per model is
+    /// needed to get them.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// * `Ok` if the request was successful, carrying a `Vec<Model>`.
+    /// * `Err` if the request or the parsing failed, carrying the error of type `Box<dyn std::error::Error + Send + Sync>`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aionic::openai::{OpenAI, Chat};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///     let client = OpenAI::<Chat>::new();
+    ///     if let Ok(models) = client.models_detailed().await {
+    ///         for model in models {
+    ///             println!("{} (owned by {})", model.id, model.owned_by);
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn models_detailed(
+        &self,
+    ) -> Result<Vec<Model>, Box<dyn std::error::Error + Send + Sync>> {
+        let resp = self
+            ._make_get_request(self._url(Self::OPENAI_API_MODELS_URL))
             .await?;
-        if is_streamed {
-            self._ask_openai_streamed(&mut r, &mut answer_chunks)
-                .await?;
-        } else {
-            let r = r.json::<Response>().await?;
-            if let Some(choices) = r.choices {
-                for choice in choices {
-                    if !self.disable_live_stream {
-                        print!("AI: {}\n", choice.message.content);
-                        io::stdout().flush()?;
-                    }
-                    answer_chunks.push(choice.message.content);
-                }
-            }
-        }
+        let resp = self.handle_api_errors(resp).await?;
 
-        let answer_text = answer_chunks.join("");
-        if persist_state {
-            self.config
-                .messages
-                .push(Message::new(&MessageRole::Assistant, &answer_text));
-        } else {
-            self.config.messages.pop();
-        }
-        Ok(answer_text)
+        let data: ModelsResponse = self._parse_json(resp).await?;
+        Ok(data.data)
     }
 
-    /// Starts a chat session with the AI assistant.
+    /// Fetches all models available in the `OpenAI` API that are owned by `owner`,
+    /// e.g. "openai" or the ID of your organization for fine-tuned models.
+    ///
+    /// # Returns
     ///
-    /// This function uses a Readline-style interface for input and output. The user types a message at the `>>> ` prompt,
-    /// and the message is sent to the AI assistant using the `ask` function. The AI's response is then printed to the console.
+    /// A `Result` which is:
+    /// * `Ok` if the request was successful, carrying the matching models.
+    /// * `Err` if the request or the parsing failed, carrying the error of type `Box<dyn std::error::Error + Send + Sync>`.
+    pub async fn models_owned_by(
+        &self,
+        owner: &str,
+    ) -> Result<Vec<Model>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut models = self.models_detailed().await?;
+        models.retain(|model| model.owned_by == owner);
+        Ok(models)
+    }
+
+    /// Fetches a specific model by identifier from the `OpenAI` API.
     ///
-    /// If the user enters CTRL-C, the function prints "CTRL-C" and exits the chat session.
+    /// This method sends a GET request to the `OpenAI` API for a specific model and returns the `Model`.
     ///
-    /// If the user enters CTRL-D, the function prints "CTRL-D" and exits the chat session.
+    /// # Parameters
     ///
-    /// If there's an error during readline, the function prints the error message and exits the chat session.
+    /// * `model`: A `&str` that represents the name of the model to fetch.
     ///
     /// # Returns
     ///
-    /// * `Ok(())`: A success value indicating that the chat session ended normally.
-    ///
-    /// * `Err(Box<dyn std::error::Error + Send + Sync>)`: An error value. This is a dynamic error, meaning it could represent
-    /// various kinds of failures. The function will return an error if any step in the process fails, such as reading a line
-    /// from the console, or if there's an error in the `ask` function.
+    /// A `Result` which is:
+    /// * `Ok` if the request was successful, carrying the `Model`.
+    /// * `Err` if the request or the parsing failed, carrying the error of type `Box<dyn std::error::Error + Send + Sync>`.
     ///
     /// # Errors
     ///
-    /// This function will return an error if the readline fails or if there's an error in the `ask` function.
+    /// This method will return an error if the GET request fails, or if the response from the
+    /// `OpenAI` API cannot be parsed into a `Model`.
     ///
-    /// # Examples
+    /// # Example
     ///
     /// ```rust
-    /// use aionic::openai::chat::Chat;
-    /// use aionic::openai::OpenAI;
+    /// use aionic::openai::{OpenAI, Chat};
     ///
     /// #[tokio::main]
     /// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     ///     let mut client = OpenAI::<Chat>::new();
-    ///     let result = client.chat().await;
-    ///     match result {
-    ///         Ok(()) => println!("Chat session ended."),
-    ///         Err(e) => println!("Error during chat session: {}", e),
+    ///     match client.check_model("gpt-3.5-turbo").await {
+    ///         Ok(model) => println!("Model: {:?}", model),
+    ///         Err(e) => println!("Error: {}", e),
     ///     }
     ///     Ok(())
     /// }
@@ -798,244 +1778,307 @@ impl OpenAI<Chat> {
     ///
     /// # Note
     ///
-    /// This function is `async` and must be awaited when called.
-    pub async fn chat(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let mut rl = DefaultEditor::new()?;
-        let prompt = ">>> ";
-        loop {
-            let readline = rl.readline(prompt);
-            match readline {
-                Ok(line) => {
-                    self.ask(line, true).await?;
-                    println!();
-                }
-                Err(ReadlineError::Interrupted) => {
-                    println!("CTRL-C");
-                    break;
-                }
-                Err(ReadlineError::Eof) => {
-                    println!("CTRL-D");
-                    break;
-                }
-                Err(err) => {
-                    println!("Error: {:?}", err);
-                    break;
-                }
-            }
-        }
-        Ok(())
-    }
-}
-
-// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
-// = OpenAI IMAGE IMPLEMENTATION
-// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
-
-impl OpenAI<Image> {
-    const OPENAI_API_IMAGE_GEN_URL: &str = "https://api.openai.com/v1/images/generations";
-    const OPENAI_API_IMAGE_EDIT_URL: &str = "https://api.openai.com/v1/images/edits";
-    const OPENAI_API_IMAGE_VARIATION_URL: &str = "https://api.openai.com/v1/images/variations";
-
-    /// Allows setting the return format of the response. `ResponseDataType` is an enum with the
-    /// following variants:
-    /// * `Url`: The response will be a vector of URLs to the generated images.
-    /// * `Base64Json`: The response will be a vector of base64 encoded images.
-    pub fn set_response_format(mut self, response_format: &ResponseDataType) -> Self {
-        self.config.response_format = Some(response_format.to_string());
-        self
-    }
-
-    /// Allows setting the number of images to be generated.
-    pub fn set_max_images(mut self, number_of_images: u64) -> Self {
-        self.config.n = Some(number_of_images);
-        self
-    }
-
-    /// Allows setting the dimensions of the generated images.
-    pub fn set_size(mut self, size: &Size) -> Self {
-        self.config.size = Some(size.to_string());
-        self
+    /// This method is `async` and needs to be awaited.
+    pub async fn check_model(
+        &self,
+        model: &str,
+    ) -> Result<Model, Box<dyn std::error::Error + Send + Sync>> {
+        let resp = self
+            ._make_get_request(format!(
+                "{}/{}",
+                self._url(Self::OPENAI_API_MODELS_URL),
+                model
+            ))
+            .await?;
+        let resp = self.handle_api_errors(resp).await?;
+        let model: Model = self._parse_json(resp).await?;
+        Ok(model)
     }
 
-    /// Generates an image based on a textual description.
+    #[cfg(any(feature = "images", feature = "audio", feature = "files"))]
+    /// Creates a file upload part for a multi-part upload operation.
     ///
-    /// This function sets the prompt to the given string and sends a request to the `OpenAI` API to create an image.
-    /// The function then parses the response and returns a vector of image URLs.
+    /// This method reads the file at the given path, prepares it for uploading, and
+    /// returns a `Part` that represents this file in the multi-part upload operation.
     ///
-    /// # Arguments
+    /// # Type Parameters
     ///
-    /// * `prompt`: A string that describes the image to be generated.
+    /// * `P`: The type of the file path. Must implement the `AsRef<Path>` trait.
     ///
-    /// # Returns
+    /// # Parameters
     ///
-    /// This function returns a `Result` with a vector of strings on success, each string being a URL to an image.
-    /// If there's an error, it returns a dynamic error.
-    pub async fn create<S: Into<String> + Send>(
-        &mut self,
-        prompt: S,
-    ) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
-        self.config.prompt = Some(prompt.into());
-        if self.config.image.is_some() {
-            self.config.image = None;
-        }
-        if self.config.mask.is_some() {
-            self.config.mask = None;
-        }
-        let res: reqwest::Response = self
-            ._make_post_request(Self::OPENAI_API_IMAGE_GEN_URL)
-            .await?;
-        let handle_res = self.handle_api_errors(res).await?;
-        let image_response: ImageResponse = handle_res.json().await?;
-
-        Ok(self._parse_response(&image_response))
-    }
-
-    /// Modifies an existing image based on a textual description.
+    /// * `path`: The path of the file to upload. This can be any type that implements `AsRef<Path>`.
     ///
-    /// This function sets the image and optionally the mask, then sets the prompt to the given string and sends a request to the `OpenAI` API to modify the image.
-    /// The function then parses the response and returns a vector of image URLs.
+    /// # Returns
     ///
-    /// # Arguments
+    /// A `Result` which is:
+    /// * `Ok` if the file was read successfully and the `Part` was created, carrying the `Part`.
+    /// * `Err` if there was an error reading the file or creating the `Part`, carrying the error of type `Box<dyn Error + Send + Sync>`.
     ///
-    /// * `prompt`: A string that describes the modifications to be made to the image.
-    /// * `image_file_path`: A string that specifies the path to the image file to be modified.
-    /// * `mask`: An optional string that specifies the path to a mask file. If the mask is not provided, it is set to `None`.
+    /// # Errors
     ///
-    /// # Returns
+    /// This method will return an error if there was an error reading the file at the given path,
+    /// or if there was an error creating the `Part` (for example, if the MIME type was not recognized).
     ///
-    /// This function returns a `Result` with a vector of strings on success, each string being a URL to an image.
-    /// If there's an error, it returns a dynamic error.
-    pub async fn edit<S: Into<String> + Send>(
+    /// # Example
+    ///
+    /// ```rust
+    /// use aionic::openai::{OpenAI, Chat};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///     let mut client = OpenAI::<Chat>::new();
+    ///     match client.create_file_upload_part("path/to/file.txt").await {
+    ///         Ok(part) => println!("Part created successfully."),
+    ///         Err(e) => println!("Error: {}", e),
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Note
+    ///
+    /// This method is `async` and needs to be awaited.
+    pub async fn create_file_upload_part<P: AsRef<Path> + Send>(
         &mut self,
-        prompt: S,
-        image_file_path: S,
-        mask: Option<S>,
-    ) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
-        self.config.image = Some(image_file_path.into());
-        if let Some(mask) = mask {
-            self.config.mask = Some(mask.into());
-        }
-        self.config.prompt = Some(prompt.into());
-
-        if let Some(n) = self.config.n {
-            // TODO: Add a warning here
-            if !image::Image::is_valid_n(n) {
-                self.config.n = Some(image::Image::get_default_n());
-            }
-        }
-
-        if let Some(size) = self.config.size.as_ref() {
-            // TODO: Add a warning here
-            if !image::Image::is_valid_size(size) {
-                self.config.size = Some(image::Image::get_default_size().into());
-            }
-        }
+        path: P,
+    ) -> Result<Part, Box<dyn Error + Send + Sync>> {
+        let file_name = path.as_ref().to_str().unwrap().to_string();
+        let streamed_body = self._get_streamed_body(path).await?;
+        let part_stream = Part::stream(streamed_body)
+            .file_name(file_name)
+            .mime_str("application/octet-stream")?;
+        Ok(part_stream)
+    }
 
-        if let Some(response_format) = self.config.response_format.as_ref() {
-            // TODO: Add a warning here
-            if !image::Image::is_valid_response_format(response_format) {
-                self.config.response_format =
-                    Some(image::Image::get_default_response_format().into());
-            }
+    #[cfg(any(feature = "images", feature = "audio", feature = "files"))]
+    async fn _get_streamed_body<P: AsRef<Path> + Send>(
+        &mut self,
+        path: P,
+    ) -> Result<Body, Box<dyn Error + Send + Sync>> {
+        if !path.as_ref().exists() {
+            return Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "Image not found",
+            )));
         }
-
-        let image_response: ImageResponse = self
-            ._make_file_upload_request(Self::OPENAI_API_IMAGE_EDIT_URL)
-            .await?;
-        Ok(self._parse_response(&image_response))
+        let file_stream_body = tokio::fs::File::open(path).await?;
+        let stream = FramedRead::new(file_stream_body, BytesCodec::new());
+        let body = Body::wrap_stream(stream);
+        Ok(body)
     }
 
-    /// Generates variations of an existing image.
-    ///
-    /// This function sets the image and sends a request to the `OpenAI` API to create variations of the image.
-    /// The function then parses the response and returns a vector of image URLs.
+    #[cfg(any(feature = "images", feature = "audio"))]
+    /// Downloads a remote asset into memory without sending any `OpenAI` credentials.
     ///
-    /// # Arguments
-    ///
-    /// * `image_file_path`: A string that specifies the path to the image file.
+    /// The size is checked against `max_bytes` before and while the body is read, so an
+    /// oversized asset is never buffered completely.
     ///
     /// # Returns
     ///
-    /// This function returns a `Result` with a vector of strings on success, each string being a URL to a new variation of the image.
-    /// If there's an error, it returns a dynamic error.
-    pub async fn variation<S: Into<String> + Send>(
-        &mut self,
-        image_file_path: S,
-    ) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
-        self.config.image = Some(image_file_path.into());
-        if self.config.prompt.is_some() {
-            self.config.prompt = None;
+    /// The file name taken from the last URL path segment together with the downloaded bytes,
+    /// a `DownloadError` if the asset could not be fetched, or an `InvalidInput` error if it is
+    /// larger than `max_bytes`.
+    async fn _download<U: IntoUrl + Send>(
+        &self,
+        url: U,
+        max_bytes: Option<u64>,
+    ) -> Result<(String, Vec<u8>), Box<dyn Error + Send + Sync>> {
+        let url = url.into_url()?;
+        let download_err = |reason: String| DownloadError {
+            url: url.to_string(),
+            reason,
+        };
+        let res = self
+            .client
+            .get(url.clone())
+            .send()
+            .await
+            .map_err(|e| download_err(e.to_string()))?;
+        if !res.status().is_success() {
+            return Err(Box::new(download_err(format!("HTTP {}", res.status()))));
         }
-        if self.config.mask.is_some() {
-            self.config.mask = None;
+        let file_name = res
+            .url()
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .filter(|name| !name.is_empty())
+            .unwrap_or("download")
+            .to_string();
+        let max_bytes = max_bytes.unwrap_or(u64::MAX);
+        let too_large = || {
+            Box::new(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Downloaded file {file_name} exceeds the limit of {max_bytes} bytes"),
+            ))
+        };
+        if res.content_length().is_some_and(|len| len > max_bytes) {
+            return Err(too_large());
         }
-        let image_response: ImageResponse = self
-            ._make_file_upload_request(Self::OPENAI_API_IMAGE_VARIATION_URL)
-            .await?;
-
-        Ok(self._parse_response(&image_response))
+        let mut bytes = Vec::new();
+        let mut stream = res.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| download_err(e.to_string()))?;
+            if (bytes.len() + chunk.len()) as u64 > max_bytes {
+                return Err(too_large());
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        Ok((file_name, bytes))
     }
 
-    fn _parse_response(&mut self, image_response: &ImageResponse) -> Vec<String> {
-        image_response
-            .data
-            .iter()
-            .filter_map(|d| {
-                if self.config.response_format == Some("url".into()) {
-                    d.url.clone()
-                } else {
-                    d.b64_json.clone()
-                }
-            })
-            .collect::<Vec<String>>()
+    #[cfg(any(feature = "images", feature = "audio"))]
+    fn _in_memory_part(
+        file_name: String,
+        bytes: Vec<u8>,
+    ) -> Result<Part, Box<dyn Error + Send + Sync>> {
+        Ok(Part::bytes(bytes)
+            .file_name(file_name)
+            .mime_str("application/octet-stream")?)
     }
 
-    async fn _make_file_upload_request<S: IntoUrl + Send + Sync>(
-        &mut self,
-        url: S,
-    ) -> Result<ImageResponse, Box<dyn Error + Send + Sync>> {
-        let file_name = self.config.image.as_ref().unwrap();
-        let file_part_stream = self.create_file_upload_part(file_name.to_string()).await?;
-        let mut form = Form::new().part("image", file_part_stream);
-
-        if let Some(prompt) = self.config.prompt.as_ref() {
-            form = form.text("prompt", prompt.clone());
+    async fn _parse_json<T: DeserializeOwned>(
+        &self,
+        mut res: reqwest::Response,
+    ) -> Result<T, Box<dyn Error + Send + Sync>> {
+        #[cfg(feature = "tracing")]
+        let span = rt::get::<tracing::Span>(&res);
+        let pending = rt::take::<PendingMetrics>(&mut res);
+        let body = match res.text().await {
+            Ok(body) => body,
+            Err(e) => {
+                let error = ErrorClass::from_error(&e);
+                self._report_metrics(pending, None, None, Some(error));
+                return Err(e.into());
+            }
+        };
+        let usage = Self::_usage_of(&body);
+        #[cfg(feature = "tracing")]
+        if let (Some(span), Some(usage)) = (span, usage) {
+            span.record("prompt_tokens", usage.prompt_tokens);
+            span.record("completion_tokens", usage.completion_tokens);
+            span.record("total_tokens", usage.total_tokens);
         }
-        if let Some(mask_name) = self.config.mask.as_ref() {
-            let mask_part_stream = self.create_file_upload_part(mask_name.to_string()).await?;
-            form = form.part("mask", mask_part_stream);
+        if self.raw_responses {
+            self.last_response.lock().raw = serde_json::from_str(&body).ok();
         }
-
-        if let Some(response_format) = self.config.response_format.as_ref() {
-            form = form.text("response_format", response_format.clone());
+        let parsed = match self._deserialize(&body) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                let error = Some(ErrorClass::Deserialization);
+                self._report_metrics(pending, usage, None, error);
+                return Err(e);
+            }
+        };
+        if let Some(usage) = usage {
+            self._add_usage(&usage);
         }
+        self._report_metrics(pending, usage, None, None);
+        Ok(parsed)
+    }
 
-        if let Some(size) = self.config.size.as_ref() {
-            form = form.text("size", size.clone());
+    /// Returns the token usage of a response body, if it has any.
+    fn _usage_of(body: &str) -> Option<Usage> {
+        #[derive(serde::Deserialize)]
+        struct WithUsage {
+            usage: Option<Usage>,
         }
 
-        if let Some(n) = self.config.n {
-            form = form.text("n", n.to_string());
+        serde_json::from_str::<WithUsage>(body).ok()?.usage
+    }
+
+    fn _deserialize<T: DeserializeOwned>(
+        &self,
+        body: &str,
+    ) -> Result<T, Box<dyn Error + Send + Sync>> {
+        let deserialization_error = |e: serde_json::Error| DeserializationError {
+            message: e.to_string(),
+            body: body.to_string(),
+            raw: serde_json::from_str(body).ok(),
+        };
+        if !self.strict_deserialization {
+            return Ok(serde_json::from_str(body).map_err(deserialization_error)?);
         }
+        let mut unknown_fields = Vec::new();
+        let deserializer = &mut serde_json::Deserializer::from_str(body);
+        let value: T = serde_ignored::deserialize(deserializer, |path| {
+            unknown_fields.push(path.to_string());
+        })
+        .map_err(deserialization_error)?;
+        if !unknown_fields.is_empty() {
+            return Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Unknown fields in response: {}", unknown_fields.join(", ")),
+            )));
+        }
+        Ok(value)
+    }
 
-        if let Some(user) = self.config.user.as_ref() {
-            form = form.text("user", user.clone());
+    /// A helper function to handle potential errors from `OpenAI` API responses.
+    ///
+    /// # Arguments
+    ///
+    /// * `res` - A `Response` object from the `OpenAI` API call.
+    ///
+    /// # Returns
+    ///
+    /// `Result<Response, Box<dyn std::error::Error + Send + Sync>>`:
+    /// Returns the original `Response` object if the status code indicates success.
+    /// If the status code indicates an error, it returns an `ApiError` that carries the HTTP status,
+    /// the failed endpoint and the `ErrorDetails` of the response, if it could be deserialized.
+    pub async fn handle_api_errors(
+        &self,
+        res: reqwest::Response,
+    ) -> Result<reqwest::Response, Box<dyn std::error::Error + Send + Sync>> {
+        if res.status().is_success() {
+            return Ok(res);
         }
+        let status = res.status();
+        let endpoint = res.url().to_string();
+        let retry_after = retry::parse_retry_after(res.headers());
+        let request_id = Self::_request_id(&res);
+        let body = res.text().await?;
+        let details = serde_json::from_str::<OpenAIError>(&body)
+            .ok()
+            .map(|err_resp| err_resp.error);
+        let message = details.as_ref().map_or_else(
+            || Self::_error_message(status, &body),
+            |details| details.message.clone(),
+        );
+        Err(Box::new(ApiError {
+            status,
+            endpoint,
+            message,
+            details,
+            retry_after,
+            request_id,
+        }))
+    }
 
-        let res: reqwest::Response = self._make_form_request(url, form).await?;
-        let handle_res = self.handle_api_errors(res).await?;
-        let image_response: ImageResponse = handle_res.json().await?;
+    /// The maximum number of characters of a non-JSON error body that are kept in the error message.
+    const ERROR_BODY_SNIPPET_CHARS: usize = 200;
 
-        Ok(image_response)
+    /// Describes an error body that is not an `OpenAIError`, e.g. the HTML error page of a proxy,
+    /// by the HTTP status and a truncated copy of the body.
+    fn _error_message(status: reqwest::StatusCode, body: &str) -> String {
+        let body = body.trim();
+        let snippet: String = body.chars().take(Self::ERROR_BODY_SNIPPET_CHARS).collect();
+        let ellipsis = if snippet.len() < body.len() {
+            "..."
+        } else {
+            ""
+        };
+        format!("HTTP {status}: {snippet}{ellipsis}")
     }
 }
 
 // =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
-// = OpenAI EMBEDDINGS IMPLEMENTATION
+// = OpenAI CHAT IMPLEMENTATION
 // =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
 
-impl OpenAI<Embedding> {
-    const OPENAI_API_EMBEDDINGS_URL: &str = "https://api.openai.com/v1/embeddings";
+#[cfg(feature = "chat")]
+impl OpenAI<Chat> {
+    const OPENAI_API_COMPLETIONS_URL: &str = "https://api.openai.com/v1/chat/completions";
 
     /// Sets the model of the AI assistant.
     ///
@@ -1051,674 +2094,7811 @@ impl OpenAI<Embedding> {
         self
     }
 
-    /// Sends a POST request to the `OpenAI` API to get embeddings for the given prompt.
+    /// Sets the maximum number of tokens that the AI model can generate in a single response.
     ///
-    /// This method accepts a prompt of type `S` which can be converted into `InputType`
-    /// (an enum that encapsulates the different types of possible inputs). The method converts
-    /// the provided prompt into `InputType` and assigns it to the `input` field of the `config`
-    /// instance variable. It then sends a POST request to the `OpenAI` API and attempts to parse
-    /// the response as `EmbeddingResponse`.
+    /// # Arguments
     ///
-    /// # Type Parameters
+    /// * `max_tokens`: An unsigned 64-bit integer that specifies the maximum number of tokens
+    /// that the AI model can generate in a single response.
     ///
-    /// * `S`: The type of the prompt. Must implement the `Into<InputType>` trait.
+    /// # Returns
     ///
-    /// # Parameters
+    /// This function returns the instance of the AI assistant with the specified maximum number of tokens.
+    pub fn set_max_tokens(mut self, max_tokens: u64) -> Self {
+        self.config.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Allows to set the chat history in a specific state.
     ///
-    /// * `prompt`: The prompt for which to get embeddings. Can be a `String`, a `Vec<String>`,
-    /// a `Vec<u64>`, or a `&str` that is converted into an `InputType`.
+    /// # Arguments
+    ///
+    /// * `messages`: A vector of `Message` structs.
     ///
     /// # Returns
     ///
-    /// A `Result` which is:
-    /// * `Ok` if the request was successful, carrying the `EmbeddingResponse` which contains the embeddings.
-    /// * `Err` if the request or the parsing failed, carrying the error of type `Box<dyn std::error::Error + Send + Sync>`.
+    /// This function returns the instance of the AI assistant with the specified messages.
+    pub fn set_messages(mut self, messages: Vec<Message>) -> Self {
+        self.config.messages = messages;
+        self
+    }
+
+    /// Sets the temperature of the AI model's responses.
     ///
-    /// # Errors
+    /// The temperature setting adjusts the randomness of the AI's responses.
+    /// Higher values produce more random responses, while lower values produce more deterministic responses.
+    /// The allowed range of values is between 0.0 and 2.0, with 0 being the most deterministic and 1 being the most random.
     ///
-    /// This method will return an error if the POST request fails, or if the response from the
-    /// `OpenAI` API cannot be parsed into an `EmbeddingResponse`.
+    /// # Arguments
     ///
-    /// # Example
+    /// * `temperature`: A float that specifies the temperature.
     ///
-    /// ```rust
-    /// use aionic::openai::embeddings::Embedding;
-    /// use aionic::openai::OpenAI;
+    /// # Returns
     ///
-    /// #[tokio::main]
-    /// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    ///     let mut client = OpenAI::<Embedding>::new();
-    ///     let prompt = "Hello, world!";
-    ///     match client.embed(prompt).await {
-    ///         Ok(response) => println!("Embeddings: {:?}", response),
-    ///         Err(e) => println!("Error: {}", e),
-    ///     }
-    ///     Ok(())
-    /// }
-    /// ```
-    ///
-    /// # Note
-    ///
-    /// This method is `async` and needs to be awaited.
-    pub async fn embed<S: Into<InputType> + Send>(
-        &mut self,
-        prompt: S,
-    ) -> Result<EmbeddingResponse, Box<dyn std::error::Error + Send + Sync>> {
-        self.config.input = prompt.into();
-        let res: reqwest::Response = self
-            ._make_post_request(Self::OPENAI_API_EMBEDDINGS_URL)
-            .await?;
-        let handled_res = self.handle_api_errors(res).await?;
-        let embedding: EmbeddingResponse = handled_res.json().await?;
-        Ok(embedding)
+    /// This function returns the instance of the AI assistant with the specified temperature.
+    pub fn set_temperature(mut self, temperature: f64) -> Self {
+        self.config.temperature = Some(temperature);
+        self
     }
-}
-
-// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
-// = OpenAI AUDIO IMPLEMENTATION
-// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
-
-impl OpenAI<Audio> {
-    const OPENAI_API_TRANSCRIPTION_URL: &str = "https://api.openai.com/v1/audio/transcriptions";
-    const OPENAI_API_TRANSLATION_URL: &str = "https://api.openai.com/v1/audio/translations";
 
-    /// Sets the model of the AI assistant.
+    /// Sets the streaming configuration of the AI assistant.
+    ///
+    /// If streaming is enabled, the AI assistant will fetch and process the AI's responses as they arrive.
+    /// If it's disabled, the assistant will collect all of the AI's responses at once and return them as a single response.
     ///
     /// # Arguments
     ///
-    /// * `model`: A string that specifies the model name to be used by the AI assistant.
+    /// * `streamed`: A boolean that specifies whether streaming should be enabled.
     ///
     /// # Returns
     ///
-    /// This function returns the instance of the AI assistant with the specified model.
-    pub fn set_model<S: Into<String>>(mut self, model: S) -> Self {
-        self.config.model = model.into();
+    /// This function returns the instance of the AI assistant with the specified streaming setting.
+    pub fn set_stream_responses(mut self, streamed: bool) -> Self {
+        self.config.stream = Some(streamed);
         self
     }
 
-    /// Sets the optional prompt to giode the model's style of response.
+    /// Requests the token usage of streamed responses, which is sent with an additional last
+    /// chunk. Without it, the usage of streamed responses is estimated.
     ///
     /// # Arguments
     ///
-    /// * `prompt`: An optional string that specifies the prompt to guide the model's style of response.
+    /// * `include_usage`: A boolean that specifies whether the usage should be requested.
     ///
     /// # Returns
     ///
-    /// This function returns the instance of the AI assistant with the specified prompt
-    pub fn set_prompt<S: Into<String>>(mut self, prompt: S) -> Self {
-        self.config.prompt = Some(prompt.into());
+    /// This function returns the instance of the AI assistant with the specified stream options.
+    pub fn set_stream_usage(mut self, include_usage: bool) -> Self {
+        self.config.stream_options = Some(StreamOptions { include_usage });
         self
     }
 
-    /// Sets the required audio file to be transcribed or translated.
+    /// Sets a primer message for the AI assistant.
+    ///
+    /// The primer message is inserted at the beginning of the `messages` vector in the `config` struct.
+    /// This can be used to prime the AI model with a certain context or instruction.
     ///
     /// # Arguments
     ///
-    /// * `file`: A string that specifies the path to the audio file to be transcribed or translated.
-    /// The path must be a valid path to a file.
+    /// * `primer_msg`: A string that specifies the primer message.
     ///
     /// # Returns
     ///
-    /// This function returns the instance of the AI assistant with the specified audio file.
-    fn _set_file<P: AsRef<Path> + Send + Sync>(
-        &mut self,
-        file: P,
-    ) -> Result<&mut Self, Box<dyn std::error::Error + Send + Sync>> {
-        let path = file.as_ref();
-        if fs::metadata(path)?.is_file() {
-            let path_str = path.to_str().ok_or("Path is not valid UTF-8")?;
-            self.config.file = path_str.to_string();
-            if self._is_valid_mime_time().is_err() {
-                return Err(Box::new(std::io::Error::new(
-                    std::io::ErrorKind::InvalidInput,
-                    format!(
-                        "Invalid audio file type. Supported types are {:?}",
-                        Audio::get_supported_file_types()
-                    ),
-                )));
-            }
-            Ok(self)
-        } else {
-            Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                format!("Path is not a file: {}", path.display()),
-            )))
-        }
+    /// This function returns the instance of the AI assistant with the specified primer message.
+    pub fn set_primer<S: Into<String>>(mut self, primer_msg: S) -> Self {
+        let msg = Message::new(&MessageRole::System, primer_msg.into());
+        self.config.messages.insert(0, msg);
+        self
     }
 
-    /// Sets the optional audio file format to be returned
+    /// Sets the functions the model may generate JSON inputs for.
     ///
     /// # Arguments
     ///
-    /// * `format`: An optional enum type that specifies the audio file format to be returned.
-    /// The default is `AudioResponseFormat::Json`..
+    /// * `functions`: A vector of `Function` structs describing the callable functions.
     ///
     /// # Returns
     ///
-    /// This function returns the instance of the AI assistant with the specified audio file format.
-    pub fn set_response_format(&mut self, format: AudioResponseFormat) -> &mut Self {
-        self.config.response_format = Some(format);
+    /// This function returns the instance of the AI assistant with the specified functions.
+    pub fn set_functions(mut self, functions: Vec<Function>) -> Self {
+        self.config.functions = Some(functions);
         self
     }
 
-    fn _is_valid_mime_time(&mut self) -> Result<bool, String> {
-        Audio::is_file_type_supported(&self.config.file)
-    }
-
-    fn _is_valid_model(&mut self) -> bool {
-        self.config.model == "whisper-1"
-    }
-
-    fn _sanity_checks(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        if let Some(temp) = self.config.temperature {
-            if !self.is_valid_temperature(temp, 1.0) {
-                // TODO: Log warning
-                self.config.temperature = Some(1.0);
-            }
-        }
-
-        if !self._is_valid_model() {
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                format!(
-                    "Invalid model. Supported models are {:?}",
-                    Audio::get_supported_models()
-                ),
-            )));
-        }
-
-        if let Some(lang) = &self.config.language {
-            if !Audio::is_valid_language(lang) {
-                return Err(Box::new(std::io::Error::new(
-                    std::io::ErrorKind::InvalidInput,
-                    format!(
-                        "Invalid language code. Supported language codes are {:?}",
-                        Audio::ISO_639_1_CODES
-                    ),
-                )));
-            }
-        }
-        Ok(())
+    /// Controls whether and which function the model calls.
+    ///
+    /// # Arguments
+    ///
+    /// * `function_call`: A `FunctionCallBehavior` that is either `None`, `Auto` or `Named`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified function call behavior.
+    pub fn set_function_call(mut self, function_call: FunctionCallBehavior) -> Self {
+        self.config.function_call = Some(function_call);
+        self
     }
 
-    async fn _form_builder(&mut self) -> Result<Form, Box<dyn std::error::Error + Send + Sync>> {
-        let file_part_stream = self
-            .create_file_upload_part(self.config.file.clone())
-            .await?;
-        let mut form = Form::new().part("file", file_part_stream);
-        form = form.text("model", self.config.model.clone());
-
-        if let Some(prompt) = self.config.prompt.as_ref() {
-            form = form.text("prompt", prompt.clone());
-        }
-
-        if let Some(response_format) = self.config.response_format.as_ref() {
-            form = form.text("response_format", response_format.to_string());
-        }
-
-        if let Some(temp) = self.config.temperature {
-            form = form.text("temperature", temp.to_string());
-        }
-        Ok(form)
+    /// Sets the tools the model may call, which supersede the functions set with `set_functions`.
+    /// Newer models only support tools.
+    ///
+    /// # Arguments
+    ///
+    /// * `tools`: The tools, e.g. `Function`s, which are converted into `Tool`s.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified tools.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aionic::openai::{Chat, Function, OpenAI, ToolChoice};
+    /// use serde_json::json;
+    ///
+    /// let weather = Function::new(
+    ///     "get_weather",
+    ///     "Get the current weather in a given city",
+    ///     json!({"type": "object", "properties": {"city": {"type": "string"}}}),
+    /// );
+    /// let client = OpenAI::<Chat>::new()
+    ///     .set_tools(vec![weather])
+    ///     .set_tool_choice(ToolChoice::Required);
+    /// ```
+    pub fn set_tools<T: Into<Tool>>(mut self, tools: Vec<T>) -> Self {
+        self.config.tools = Some(tools.into_iter().map(Into::into).collect());
+        self
     }
 
-    /// Transcribe an audio file.
+    /// Controls whether and which tool the model calls.
     ///
     /// # Arguments
     ///
-    /// * `audio_file` - The path to the audio file to transcribe.
+    /// * `tool_choice`: A `ToolChoice` that is either `None`, `Auto`, `Required` or `Function`.
     ///
     /// # Returns
     ///
-    /// `Result<AudioResponse, Box<dyn std::error::Error + Send + Sync>>`:
-    /// An `AudioResponse` object representing the transcription of the audio file,
-    /// or an error if the request fails.
-    pub async fn transcribe<P: AsRef<Path> + Sync + Send>(
-        &mut self,
-        audio_file: P,
-    ) -> Result<AudioResponse, Box<dyn std::error::Error + Send + Sync>> {
-        self._set_file(audio_file)?;
-        self._sanity_checks()?;
-        let mut form = self._form_builder().await?;
-
-        if let Some(lang) = self.config.language.clone() {
-            form = form.text("language", lang);
-        }
-
-        let res: reqwest::Response = self
-            ._make_form_request(Self::OPENAI_API_TRANSCRIPTION_URL, form)
-            .await?;
-
-        let handled_res = self.handle_api_errors(res).await?;
-        let transcription: AudioResponse = handled_res.json().await?;
-        Ok(transcription)
+    /// This function returns the instance of the AI assistant with the specified tool choice.
+    pub fn set_tool_choice(mut self, tool_choice: ToolChoice) -> Self {
+        self.config.tool_choice = Some(tool_choice);
+        self
     }
 
-    /// Translate an audio file. Currently only supports translating
-    /// to English.
+    /// Registers a function the model may call, together with the async function that runs it
+    /// for `ask_with_functions`. The function is added to the functions sent to the model,
+    /// replacing one with the same name.
+    ///
+    /// The arguments generated by the model are parsed into the argument type of `handler`. If
+    /// they do not match it, `ask_with_functions` fails with an `InvalidFunctionArguments` error
+    /// that includes the raw arguments.
     ///
     /// # Arguments
     ///
-    /// * `audio_file` - The path to the audio file to translate.
+    /// * `function`: The name, description and JSON Schema of the parameters of the function.
+    /// * `handler`: The function to run with the parsed arguments. Its result is sent back to
+    ///   the model.
     ///
     /// # Returns
     ///
-    /// `Result<AudioResponse, Box<dyn std::error::Error + Send + Sync>>`:
-    /// An `AudioResponse` object representing the translation of the audio file,
-    /// or an error if the request fails.
-    pub async fn translate<P: AsRef<Path> + Send + Sync>(
-        &mut self,
-        audio_file: P,
-    ) -> Result<AudioResponse, Box<dyn std::error::Error + Send + Sync>> {
-        self._set_file(audio_file)?;
-        self._sanity_checks()?;
-        if self.config.language.is_some() {
-            self.config.language = None;
-        }
-        let form = self._form_builder().await?;
-        let res: reqwest::Response = self
-            ._make_form_request(Self::OPENAI_API_TRANSLATION_URL, form)
-            .await?;
-        let handled_res = self.handle_api_errors(res).await?;
-        let translation: AudioResponse = handled_res.json().await?;
-        Ok(translation)
+    /// This function returns the instance of the AI assistant with the function registered.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aionic::openai::{Chat, Function, OpenAI};
+    /// use serde::Deserialize;
+    /// use serde_json::json;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Location {
+    ///     city: String,
+    /// }
+    ///
+    /// let weather = Function::new(
+    ///     "get_weather",
+    ///     "Get the current weather in a given city",
+    ///     json!({"type": "object", "properties": {"city": {"type": "string"}}}),
+    /// );
+    /// let client = OpenAI::<Chat>::new().register_function(weather, |location: Location| async move {
+    ///     Ok(format!("It is sunny in {}", location.city))
+    /// });
+    /// ```
+    pub fn register_function<A, F, Fut>(mut self, function: Function, handler: F) -> Self
+    where
+        A: serde::de::DeserializeOwned,
+        F: Fn(A) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, Box<dyn Error + Send + Sync>>> + Send + 'static,
+    {
+        let handler = FunctionHandler::new(function.name.clone(), handler);
+        self.config
+            .function_handlers
+            .insert(function.name.clone(), handler);
+        let functions = self.config.functions.get_or_insert_with(Vec::new);
+        functions.retain(|f| f.name != function.name);
+        functions.push(function);
+        self
     }
-}
 
-// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
-// = OpenAI FILES IMPLEMENTATION
-// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
-
-impl OpenAI<Files> {
-    const OPENAI_API_LIST_FILES_URL: &str = "https://api.openai.com/v1/files";
-
-    /// List all files that have been uploaded.
+    /// Sets the maximum number of function calls `ask_with_functions` makes for a single prompt,
+    /// which is `Chat::DEFAULT_MAX_FUNCTION_CALLS` by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_calls`: The maximum number of function calls.
     ///
     /// # Returns
     ///
-    /// `Result<FileResponse, Box<dyn std::error::Error + Send + Sync>>`:
-    /// A `FileResponse` object representing all uploaded files,
-    /// or an error if the request fails.
-    pub async fn list(&mut self) -> Result<FileResponse, Box<dyn std::error::Error + Send + Sync>> {
-        let res: reqwest::Response = self
-            ._make_get_request(Self::OPENAI_API_LIST_FILES_URL)
-            .await?;
-        let handled_res = self.handle_api_errors(res).await?;
-        let files: FileResponse = handled_res.json().await?;
-        Ok(files)
+    /// This function returns the instance of the AI assistant with the specified limit.
+    pub fn set_max_function_calls(mut self, max_calls: usize) -> Self {
+        self.config.max_function_calls = Some(max_calls);
+        self
     }
 
-    /// Retrieve the details of a specific file.
+    /// Sets the format the model must output, e.g. `ChatResponseFormat::JsonObject` to enable JSON mode.
+    ///
+    /// When JSON mode is enabled, a system message (e.g. the primer) must instruct the model to
+    /// produce JSON, otherwise `ask` returns an error without sending the request.
     ///
     /// # Arguments
     ///
-    /// * `file_id` - A string that holds the unique id of the file.
+    /// * `format`: A `ChatResponseFormat` that specifies the output format.
     ///
     /// # Returns
     ///
-    /// `Result<FileData, Box<dyn std::error::Error + Send + Sync>>`:
-    /// A `FileData` object representing the file's details,
-    /// or an error if the request fails.
-    pub async fn retrieve<S: Into<String> + std::fmt::Display + Sync + Send>(
-        &mut self,
-        file_id: S,
-    ) -> Result<FileData, Box<dyn std::error::Error + Send + Sync>> {
-        let res: reqwest::Response = self
-            ._make_get_request(format!("{}/{}", Self::OPENAI_API_LIST_FILES_URL, file_id))
-            .await?;
-
-        let handled_res = self.handle_api_errors(res).await?;
-        let file: FileData = handled_res.json().await?;
-        Ok(file)
+    /// This function returns the instance of the AI assistant with the specified response format.
+    pub fn set_response_format(mut self, format: ChatResponseFormat) -> Self {
+        self.config.response_format = Some(format);
+        self
     }
 
-    /// Retrieve the content of a specific file.
+    /// Sets the presence penalty, which penalizes tokens that already appear in the text so far
+    /// and thus makes the model more likely to talk about new topics.
+    ///
+    /// Penalties outside of `-2.0..=2.0` are rejected when the request is made.
     ///
     /// # Arguments
     ///
-    /// * `file_id` - A string that holds the unique id of the file.
+    /// * `presence_penalty`: A float between -2.0 and 2.0.
     ///
     /// # Returns
     ///
-    /// `Result<FileData, Box<dyn std::error::Error + Send + Sync>>`:
-    /// A `FileData` object representing the file's content,
-    /// or an error if the request fails.
-    pub async fn retrieve_content<S: Into<String> + std::fmt::Display + Send + Sync>(
-        &mut self,
-        file_id: S,
-    ) -> Result<Vec<PromptCompletion>, Box<dyn std::error::Error + Send + Sync>> {
-        let res = self
-            ._make_get_request(format!(
-                "{}/{}/content",
-                Self::OPENAI_API_LIST_FILES_URL,
-                file_id
-            ))
-            .await?;
-
-        let handled_res = self.handle_api_errors(res).await?;
-        let files: Vec<PromptCompletion> = handled_res
-            .text()
-            .await?
-            .lines()
-            .map(serde_json::from_str)
-            .collect::<Result<Vec<PromptCompletion>, _>>()?;
-        Ok(files)
+    /// This function returns the instance of the AI assistant with the specified presence penalty.
+    pub fn set_presence_penalty(mut self, presence_penalty: f32) -> Self {
+        self.config.presence_penalty = Some(presence_penalty);
+        self
     }
 
-    /// Upload a file to the `OpenAI` API.
+    /// Sets the frequency penalty, which penalizes tokens based on how often they already appear
+    /// in the text so far and thus makes the model less likely to repeat itself verbatim.
+    ///
+    /// Penalties outside of `-2.0..=2.0` are rejected when the request is made.
     ///
     /// # Arguments
     ///
-    /// * `file` - The path to the file to upload.
-    /// * `purpose` - The purpose of the upload (e.g., 'answers', 'questions').
+    /// * `frequency_penalty`: A float between -2.0 and 2.0.
     ///
     /// # Returns
     ///
-    /// `Result<FileData, Box<dyn std::error::Error + Send + Sync>>`:
-    /// A `FileData` object representing the uploaded file's details,
-    /// or an error if the request fails.
-    pub async fn upload<P: AsRef<Path> + Send + Sync>(
-        &mut self,
-        file: P,
-    ) -> Result<FileData, Box<dyn std::error::Error + Send + Sync>> {
-        let path = file.as_ref();
-        if fs::metadata(path)?.is_file() {
-            let path_str = path.to_str().ok_or("Path is not valid UTF-8")?;
-            if !std::path::Path::new(path_str)
-                .extension()
-                .map_or(false, |ext| ext.eq_ignore_ascii_case("jsonl"))
-            {
-                return Err(Box::new(std::io::Error::new(
-                    std::io::ErrorKind::InvalidInput,
-                    format!("File must be a .jsonl file: {}", path.display()),
-                )));
-            }
-            self.config.file = Some(path_str.to_string());
-        } else {
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                format!("Path is not a file: {}", path.display()),
-            )));
-        }
-
-        let file_part_stream = self.create_file_upload_part(file).await?;
-        let mut form = Form::new().part("file", file_part_stream);
-        form = form.text("purpose", "fine-tune");
-        let res: reqwest::Response = self
-            ._make_form_request(Self::OPENAI_API_LIST_FILES_URL, form)
-            .await?;
+    /// This function returns the instance of the AI assistant with the specified frequency penalty.
+    pub fn set_frequency_penalty(mut self, frequency_penalty: f32) -> Self {
+        self.config.frequency_penalty = Some(frequency_penalty);
+        self
+    }
 
-        let handled_res = self.handle_api_errors(res).await?;
-        let file_data: FileData = handled_res.json().await?;
-        Ok(file_data)
+    /// Sets how many choices the model generates for each prompt.
+    ///
+    /// `ask` and `ask_stream` only answer with the first choice and persist it to the chat
+    /// history, so the other choices are only returned by `ask_many`. Values outside of
+    /// `1..=128` are rejected when the request is made.
+    ///
+    /// # Arguments
+    ///
+    /// * `n`: The number of choices, between 1 and 128.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified number of choices.
+    pub fn set_n(mut self, n: u32) -> Self {
+        self.config.n = Some(n);
+        self
     }
 
-    /// Delete a specific file.
+    /// Sets the probability mass of the tokens the model samples from, e.g. 0.1 to only consider
+    /// the tokens of the top 10% probability mass. It is recommended to set either this or the
+    /// temperature, but not both.
+    ///
+    /// Values outside of `0.0..=1.0` are rejected when the request is made.
     ///
     /// # Arguments
     ///
-    /// * `file_id` - A string that holds the unique id of the file.
+    /// * `top_p`: A float between 0.0 and 1.0.
     ///
     /// # Returns
     ///
-    /// `Result<DeleteResponse, Box<dyn std::error::Error + Send + Sync>>`:
-    /// A `DeleteResponse` object representing the response from the delete request,
-    /// or an error if the request fails.
-    pub async fn delete<S: Into<String> + std::fmt::Display + Send + Sync>(
-        &mut self,
-        file_id: S,
-    ) -> Result<DeleteResponse, Box<dyn std::error::Error + Send + Sync>> {
-        let res: reqwest::Response = self
-            ._make_delete_request(format!("{}/{}", Self::OPENAI_API_LIST_FILES_URL, file_id))
-            .await?;
+    /// This function returns the instance of the AI assistant with the specified `top_p`.
+    pub fn set_top_p(mut self, top_p: f64) -> Self {
+        self.config.top_p = Some(top_p);
+        self
+    }
 
-        let handled_res = self.handle_api_errors(res).await?;
-        let del_resp: DeleteResponse = handled_res.json().await?;
-        Ok(del_resp)
+    /// Sets the sequences at which the model stops generating further tokens.
+    ///
+    /// More than four sequences are rejected when the request is made.
+    ///
+    /// # Arguments
+    ///
+    /// * `stop`: Up to four stop sequences.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified stop sequences.
+    pub fn set_stop<S: Into<String>>(mut self, stop: Vec<S>) -> Self {
+        self.config.stop = Some(stop.into_iter().map(Into::into).collect());
+        self
     }
-}
 
-// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
-// = OpenAI FINE-TUNE IMPLEMENTATION
-// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
+    /// Sets the seed for sampling, so that repeated requests with the same seed and parameters
+    /// return the same result on a best effort basis.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed`: The seed to sample with.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified seed.
+    pub fn set_seed(mut self, seed: u64) -> Self {
+        self.config.seed = Some(seed);
+        self
+    }
 
-impl OpenAI<FineTune> {
-    const OPENAI_API_FINE_TUNE_URL: &str = "https://api.openai.com/v1/fine-tunes";
+    /// Sets the bias that is added to the logits of the given tokens before sampling, replacing
+    /// any biases set before.
+    ///
+    /// Biases outside of `-100.0..=100.0` are clamped to that range when the request is made,
+    /// or rejected if strict validation is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `logit_bias`: A map of token IDs of the model's tokenizer to their bias.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified logit bias.
+    pub fn set_logit_bias(mut self, logit_bias: HashMap<u64, f32>) -> Self {
+        self.config.logit_bias = None;
+        for (token_id, bias) in logit_bias {
+            self = self.bias_token(token_id, bias);
+        }
+        self
+    }
 
-    /// Create a fine-tune from an uploaded `training_file`.
+    /// Adds a bias to the logit of a single token before sampling. Values between -1 and 1
+    /// decrease or increase the likelihood of the token, while -100 or 100 should ban it or
+    /// make it the only choice.
     ///
     /// # Arguments
     ///
-    /// * `training_file` - A string that holds the unique id of the file.
+    /// * `token_id`: The ID of the token in the model's tokenizer.
+    /// * `bias`: The bias to add. When the request is made, a bias outside of `-100.0..=100.0`
+    ///   is clamped to that range, or rejected if strict validation is enabled.
     ///
     /// # Returns
     ///
-    /// `Result<FineTuneResponse, Box<dyn std::error::Error + Send + Sync>>`:
-    /// A `FineTuneResponse` object representing the result of the fine-tune request,
-    /// or an error if the request fails.
-    pub async fn create<S: Into<String> + Send + Sync>(
-        &mut self,
-        training_file: S,
-    ) -> Result<FineTuneResponse, Box<dyn std::error::Error + Send + Sync>> {
-        self.config.training_file = training_file.into();
-        let res: reqwest::Response = self
-            ._make_post_request(Self::OPENAI_API_FINE_TUNE_URL)
-            .await?;
+    /// This function returns the instance of the AI assistant with the token biased.
+    pub fn bias_token(mut self, token_id: u64, bias: f32) -> Self {
+        self.config
+            .logit_bias
+            .get_or_insert_with(HashMap::new)
+            .insert(token_id.to_string(), bias);
+        self
+    }
 
-        let handled_res = self.handle_api_errors(res).await?;
-        let fine_tune_resp: FineTuneResponse = handled_res.json().await?;
-        Ok(fine_tune_resp)
+    /// Prevents the model from generating the given token by biasing it with -100.
+    ///
+    /// # Arguments
+    ///
+    /// * `token_id`: The ID of the token in the model's tokenizer.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the token banned.
+    pub fn ban_token(self, token_id: u64) -> Self {
+        self.bias_token(token_id, Chat::MIN_LOGIT_BIAS)
     }
 
-    /// List all fine-tunes.
+    /// Returns the `system_fingerprint` of the last non-streamed response, which changes
+    /// whenever the backend configuration changes in a way that may affect determinism.
     ///
     /// # Returns
     ///
-    /// `Result<FineTuneListResponse, Box<dyn std::error::Error + Send + Sync>>`:
-    /// A `FineTuneResponse` object representing the result of the list fine-tunes request,
-    /// or an error if the request fails.
-    pub async fn list(
-        &mut self,
-    ) -> Result<FineTuneListResponse, Box<dyn std::error::Error + Send + Sync>> {
-        let res: reqwest::Response = self
-            ._make_get_request(Self::OPENAI_API_FINE_TUNE_URL)
-            .await?;
+    /// This function returns `None` if no response was received yet or it carried no fingerprint.
+    pub fn last_system_fingerprint(&self) -> Option<String> {
+        self.last_response.lock().system_fingerprint.clone()
+    }
 
-        let handled_res = self.handle_api_errors(res).await?;
-        let res: FineTuneListResponse = handled_res.json().await?;
-        Ok(res)
+    /// Sets the models to fall back to, in order, if the configured model is not found or
+    /// overloaded. The request is then sent again with the next model, and `last_model`
+    /// reports which model served it. If all models fail, a `FallbackError` is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `models` - The fallback models, e.g. `["gpt-4o-mini", "gpt-3.5-turbo"]`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the fallback models set.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aionic::openai::{Chat, OpenAI};
+    ///
+    /// let client = OpenAI::<Chat>::new()
+    ///     .set_model("gpt-4")
+    ///     .with_fallback_models(vec!["gpt-4o-mini", "gpt-3.5-turbo"]);
+    /// ```
+    pub fn with_fallback_models<S: Into<String>>(mut self, models: Vec<S>) -> Self {
+        self.config.fallback_models = models.into_iter().map(Into::into).collect();
+        self
     }
 
-    /// Get a specific fine-tune by its id
+    /// Sets a callback that receives every content delta of a streamed response instead of
+    /// printing it to stdout, e.g. to render the answer with colors or in a TUI. A response that
+    /// is not streamed is passed to it as a single delta. It is called even if the live stream
+    /// is disabled. It replaces a callback set before with `with_stream_callback` or `on_token`.
     ///
     /// # Arguments
     ///
-    /// * `fine_tune_id` - A string that holds the unique id of the file.
+    /// * `callback` - The function to call with every content delta.
     ///
     /// # Returns
     ///
-    /// `Result<FineTuneResponse, Box<dyn std::error::Error + Send + Sync>>`:
-    /// A `FineTuneResponse` object representing the result of the get fine-tune request,
-    /// or an error if the request fails.
-    pub async fn retrieve<S: Into<String> + Send + Sync + std::fmt::Display>(
-        &mut self,
-        fine_tune_id: S,
-    ) -> Result<FineTuneResponse, Box<dyn std::error::Error + Send + Sync>> {
-        let res: reqwest::Response = self
-            ._make_get_request(format!(
-                "{}/{}",
-                Self::OPENAI_API_FINE_TUNE_URL,
-                fine_tune_id
-            ))
-            .await?;
+    /// This function returns the instance of the AI assistant with the stream callback set.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aionic::openai::{Chat, OpenAI};
+    ///
+    /// let client = OpenAI::<Chat>::new()
+    ///     .with_stream_callback(Box::new(|delta: &str| print!("\x1b[32m{delta}\x1b[0m")));
+    /// ```
+    pub fn with_stream_callback(mut self, callback: Box<dyn FnMut(&str) + Send>) -> Self {
+        self.config.stream_callback = Some(StreamCallback::new(callback));
+        self
+    }
 
-        let handled_res = self.handle_api_errors(res).await?;
-        let res: FineTuneResponse = handled_res.json().await?;
-        Ok(res)
+    /// Sets a callback that receives the answer token by token instead of printing it to stdout.
+    ///
+    /// This is a shorthand for `with_stream_callback(Box::new(callback))` and sets the same
+    /// callback, so whichever of the two is called last wins. Without a callback the answer is
+    /// printed to stdout, or to the writer set with `set_output`.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - The function to call with every content delta.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the stream callback set.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aionic::openai::{Chat, OpenAI};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let received = Arc::new(Mutex::new(0));
+    /// let counter = Arc::clone(&received);
+    /// let client = OpenAI::<Chat>::new().on_token(move |_| *counter.lock().unwrap() += 1);
+    /// ```
+    pub fn on_token<F: FnMut(&str) + Send + 'static>(self, callback: F) -> Self {
+        self.with_stream_callback(Box::new(callback))
     }
 
-    /// Immediately cancel a fine-tune job.
+    /// Sets the writer that receives the answers of the AI assistant instead of stdout, e.g. a
+    /// file to keep a live transcript. Streamed answers are written token by token and the
+    /// writer is flushed after every token, just like stdout.
+    ///
+    /// This enables the live stream again if it was disabled with `disable_stdout`. A stream
+    /// callback set with `with_stream_callback` or `on_token` takes precedence over the writer.
     ///
     /// # Arguments
     ///
-    /// * `fine_tune_id` - A string that holds the unique id of the file.
+    /// * `writer` - The writer to write the answers to.
     ///
     /// # Returns
     ///
-    /// `Result<FineTuneResponse, Box<dyn std::error::Error + Send + Sync>>`:
-    /// A `FineTuneResponse` object representing the result of the cancel fine-tune request,
-    /// or an error if the request fails.
-    pub async fn cancel<S: Into<String> + Send + Sync + std::fmt::Display>(
-        &mut self,
-        fine_tune_id: S,
-    ) -> Result<FineTuneResponse, Box<dyn std::error::Error + Send + Sync>> {
-        let url = format!("{}/{}/cancel", Self::OPENAI_API_FINE_TUNE_URL, fine_tune_id);
-        let res = self
-            .client
-            .post(url)
-            .header("Content-Type", "application/json")
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .send()
-            .await?;
+    /// This function returns the instance of the AI assistant with the output set.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aionic::openai::{Chat, OpenAI};
+    ///
+    /// let transcript = std::fs::File::create("transcript.txt").unwrap();
+    /// let client = OpenAI::<Chat>::new().set_output(transcript);
+    /// # std::fs::remove_file("transcript.txt").unwrap();
+    /// ```
+    pub fn set_output<W: Write + Send + 'static>(mut self, writer: W) -> Self {
+        self.config.output = Some(StreamOutput::new(Box::new(writer)));
+        self.disable_live_stream = false;
+        self
+    }
 
-        let handled_res = self.handle_api_errors(res).await?;
-        let res: FineTuneResponse = handled_res.json().await?;
-        Ok(res)
+    /// Returns the model that served the last request, which differs from the configured model
+    /// if a fallback model was used.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `None` if no request was made yet.
+    pub fn last_model(&self) -> Option<String> {
+        self.last_response.lock().model.clone()
     }
 
-    /// Get fine-grained status updates for a fine-tune job.
+    /// Returns the token usage of the last request made with `ask` or `ask_with_outcome`.
+    ///
+    /// Streamed responses only carry a usage if it is requested with `set_stream_usage`.
+    /// Otherwise it is estimated by tokenizing the sent messages and the received answer with
+    /// the tokenizer of the configured model.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `None` if no request was made yet.
+    pub fn last_usage(&self) -> Option<Usage> {
+        self.last_response.lock().usage
+    }
+
+    /// Estimates the cost of the last request made with `ask` or `ask_with_outcome` from its
+    /// `last_usage` and the configured `Pricing`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the cost in US dollars, or `None` if no request was made yet or the
+    /// model has no known price.
+    pub fn last_cost(&self) -> Option<f64> {
+        self.pricing
+            .estimate_cost(&self.last_usage()?, &self.config.model)
+    }
+
+    /// Sets a hard limit on the number of bytes a streamed response may accumulate.
+    ///
+    /// Once the limit is hit, the stream is aborted and a `ResponseTooLarge` error carrying
+    /// the text received so far is returned. This bounds the memory used by very long
+    /// generations or a misbehaving upstream.
     ///
     /// # Arguments
     ///
-    /// * `fine_tune_id` - A string that holds the unique id of the file.
+    /// * `max_bytes`: The maximum number of bytes of a single streamed response.
     ///
     /// # Returns
     ///
-    /// `Result<FineTuneEventResponse, Box<dyn std::error::Error + Send + Sync>>`:
-    /// A `FineTuneEventResponse` object representing the result of the list fine-tunes request,
-    /// or an error if the request fails.
-    pub async fn list_events<S: Into<String> + Send + Sync + std::fmt::Display>(
-        &mut self,
-        fine_tune_id: S,
-    ) -> Result<FineTuneEventResponse, Box<dyn std::error::Error + Send + Sync>> {
-        let url = format!("{}/{}/events", Self::OPENAI_API_FINE_TUNE_URL, fine_tune_id);
-        let res = self._make_get_request(url).await?;
+    /// This function returns the instance of the AI assistant with the specified response limit.
+    pub fn set_max_response_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_response_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Returns the last message in the AI assistant's configuration.
+    ///
+    /// # Returns
+    ///
+    /// This function returns an `Option` that contains a reference to the last `Message`
+    /// in the `config` struct if it exists, or `None` if it doesn't.
+    pub fn get_last_message(&self) -> Option<&Message> {
+        self.config.messages.last()
+    }
+
+    /// Returns the chat history, i.e. all messages of the conversation in order.
+    pub fn messages(&self) -> &[Message] {
+        &self.config.messages
+    }
+
+    /// Returns the chat history for editing, e.g. to change a prior message before asking the
+    /// model again.
+    pub fn messages_mut(&mut self) -> &mut Vec<Message> {
+        &mut self.config.messages
+    }
+
+    /// Returns an iterator over the messages of the chat history, oldest first.
+    pub fn iter(&self) -> std::slice::Iter<'_, Message> {
+        self.config.messages.iter()
+    }
+
+    /// Removes the last message from the chat history.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the removed `Message`, or `None` if the history is empty.
+    pub fn pop_last(&mut self) -> Option<Message> {
+        self.config.messages.pop()
+    }
+
+    /// Removes a message from the chat history, shifting all later messages to the front.
+    ///
+    /// # Arguments
+    ///
+    /// * `index`: The position of the message in the history, starting at 0.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the removed `Message`, or `None` if `index` is out of bounds.
+    pub fn remove_message(&mut self, index: usize) -> Option<Message> {
+        (index < self.config.messages.len()).then(|| self.config.messages.remove(index))
+    }
+
+    /// Estimates the number of prompt tokens the current conversation takes up.
+    ///
+    /// The messages are tokenized with the BPE encoder of the configured model. The result is an
+    /// estimate, as the exact message framing of the API is not documented and may change.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the number of tokens of `config.messages`.
+    pub fn count_tokens(&self) -> usize {
+        tokenizer::count_message_tokens(&self.config.model, &self.config.messages)
+    }
+
+    /// Checks whether the current conversation plus the requested completion fit into the
+    /// context window of the configured model.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `true` if the prompt tokens plus `max_tokens` do not exceed
+    /// `Chat::get_context_window` of the configured model.
+    pub fn fits_context_window(&self) -> bool {
+        let max_tokens = self
+            .config
+            .max_tokens
+            .map_or(0, |t| usize::try_from(t).unwrap_or(usize::MAX));
+        self.count_tokens().saturating_add(max_tokens)
+            <= Chat::get_context_window(&self.config.model)
+    }
+
+    /// Enables trimming of the conversation history before every request.
+    ///
+    /// # Arguments
+    ///
+    /// * `strategy`: A `HistoryStrategy` that limits the number of messages or prompt tokens.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified history limit.
+    pub fn with_history_limit(mut self, strategy: HistoryStrategy) -> Self {
+        self.config.history_limit = Some(strategy);
+        self
+    }
+
+    /// Drops the oldest messages from the conversation until it satisfies the configured
+    /// `HistoryStrategy`. Leading system messages and the most recent message are always kept.
+    ///
+    /// This is done automatically by `ask`, which stores the dropped messages in
+    /// `config.trimmed_messages`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the dropped messages in their original order, which is empty if no
+    /// history limit is set or the conversation is within the limit.
+    pub fn trim_history(&mut self) -> Vec<Message> {
+        let Some(strategy) = self.config.history_limit else {
+            return vec![];
+        };
+        let messages = &self.config.messages;
+        let first = messages
+            .iter()
+            .take_while(|msg| msg.role == MessageRole::System.to_string())
+            .count();
+        let last = messages.len().saturating_sub(1).max(first);
+        let mut drop = 0;
+        match strategy {
+            HistoryStrategy::MaxMessages(max) => {
+                drop = (messages.len() - first).saturating_sub(max.max(1));
+            }
+            HistoryStrategy::MaxTokens(max) => {
+                let counts = tokenizer::count_tokens_per_message(&self.config.model, messages);
+                let mut tokens = tokenizer::TOKENS_PER_REPLY + counts.iter().sum::<usize>();
+                while first + drop < last && tokens > max {
+                    tokens -= counts[first + drop];
+                    drop += 1;
+                }
+            }
+        }
+        self.config
+            .messages
+            .drain(first..(first + drop).min(last))
+            .collect()
+    }
+
+    /// Clears the messages in the AI assistant's configuration to start from a clean state.
+    /// This is only necessary in very specific cases.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with no messages in its configuration.
+    pub fn clear_state(mut self) -> Self {
+        self.config.messages.clear();
+        self
+    }
+
+    /// Returns a copy of the current conversation, e.g. to store it alongside other state.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the messages in `config.messages`.
+    pub fn export_history(&self) -> Vec<Message> {
+        self.config.messages.clone()
+    }
+
+    /// Replaces the current conversation with the given messages, e.g. ones previously returned
+    /// by `export_history`.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages`: The messages to resume the conversation with.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the imported messages.
+    pub fn import_history(mut self, messages: Vec<Message>) -> Self {
+        self.config.messages = messages;
+        self
+    }
+
+    /// Saves the current conversation as a JSON array of messages, so it can be resumed later
+    /// with `load_history`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: The file to write the conversation to. An existing file is overwritten.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the messages cannot be serialized or the file cannot
+    /// be written.
+    pub fn save_history<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let json = serde_json::to_string_pretty(&self.config.messages)?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Loads a conversation saved with `save_history`, replacing the current messages.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: The file to read the conversation from.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the file cannot be read or does not contain a JSON
+    /// array of messages. The current messages are left untouched in that case.
+    pub fn load_history<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let json = fs::read_to_string(path)?;
+        self.config.messages = serde_json::from_str(&json)?;
+        Ok(())
+    }
+
+    fn _json_mode_sanity_check(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self.config.response_format != Some(ChatResponseFormat::JsonObject) {
+            return Ok(());
+        }
+        let mentions_json = self.config.messages.iter().any(|msg| {
+            msg.role == MessageRole::System.to_string()
+                && msg
+                    .content
+                    .as_ref()
+                    .is_some_and(|content| content.to_string().to_lowercase().contains("json"))
+        });
+        if mentions_json {
+            Ok(())
+        } else {
+            Err(Box::new(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "JSON mode requires a system message that instructs the model to produce JSON",
+            )))
+        }
+    }
+
+    /// Estimates the usage of a streamed request from the sent messages and the answer.
+    fn _estimate_usage(&self, answer_text: &str) -> Usage {
+        let prompt_tokens = self.count_tokens() as u64;
+        let completion_tokens =
+            tokenizer::count_text_tokens(&self.config.model, answer_text) as u64;
+        Usage {
+            prompt_tokens,
+            completion_tokens: Some(completion_tokens),
+            total_tokens: prompt_tokens + completion_tokens,
+        }
+    }
+
+    fn _logit_bias_sanity_check(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let Some(logit_bias) = self.config.logit_bias.as_ref() else {
+            return Ok(());
+        };
+        let mut out_of_range = Vec::new();
+        for (token_id, bias) in logit_bias {
+            if token_id.parse::<u64>().is_err() {
+                return Err(Box::new(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid logit bias key `{token_id}`, expected a token ID"),
+                )));
+            }
+            if !Chat::is_valid_logit_bias(*bias) {
+                out_of_range.push((token_id.clone(), *bias));
+            }
+        }
+        for (token_id, bias) in out_of_range {
+            self._reject_if_strict(format!(
+                "Logit bias {bias} of token {token_id} is out of range, expected a value between {} and {}",
+                Chat::MIN_LOGIT_BIAS,
+                Chat::MAX_LOGIT_BIAS
+            ))?;
+            let clamped = bias.clamp(Chat::MIN_LOGIT_BIAS, Chat::MAX_LOGIT_BIAS);
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                token_id,
+                bias,
+                "Logit bias out of range, clamping it to {clamped}"
+            );
+            if let Some(logit_bias) = self.config.logit_bias.as_mut() {
+                logit_bias.insert(token_id, clamped);
+            }
+        }
+        Ok(())
+    }
+
+    fn _sampling_sanity_check(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let penalties = [
+            ("presence_penalty", self.config.presence_penalty),
+            ("frequency_penalty", self.config.frequency_penalty),
+        ];
+        for (name, penalty) in penalties {
+            if let Some(penalty) = penalty.filter(|penalty| !Chat::is_valid_penalty(*penalty)) {
+                return Err(Box::new(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "The {name} {penalty} is out of range, expected a value between {} and {}",
+                        Chat::MIN_PENALTY,
+                        Chat::MAX_PENALTY
+                    ),
+                )));
+            }
+        }
+        if let Some(top_p) = self
+            .config
+            .top_p
+            .filter(|top_p| !(0.0..=1.0).contains(top_p))
+        {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("The top_p {top_p} is out of range, expected a value between 0 and 1"),
+            )));
+        }
+        if let Some(n) = self.config.n.filter(|n| !(1..=Chat::MAX_N).contains(n)) {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "The number of choices {n} is out of range, expected a value between 1 and {}",
+                    Chat::MAX_N
+                ),
+            )));
+        }
+        let stop_sequences = self.config.stop.as_ref().map_or(0, Vec::len);
+        if stop_sequences > Chat::MAX_STOP_SEQUENCES {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Got {stop_sequences} stop sequences, but at most {} are allowed",
+                    Chat::MAX_STOP_SEQUENCES
+                ),
+            )));
+        }
+        Ok(())
+    }
+
+    fn _process_delta(
+        &self,
+        line: &str,
+        answer: &mut ChatOutcome,
+        on_token: &mut Option<&mut (dyn FnMut(&str) + Send)>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match Self::_parse_delta(line)? {
+            Some(chunk) => self._accumulate_delta(&chunk, answer, on_token),
+            None => Ok(()),
+        }
+    }
+
+    /// Parses a line of a streamed response.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `None` for lines that carry no chunk, e.g. empty lines or `[DONE]`.
+    fn _parse_delta(line: &str) -> Result<Option<StreamedReponse>, Box<dyn Error + Send + Sync>> {
+        let Some(chunk) = line.strip_prefix("data: ") else {
+            return Ok(None);
+        };
+        if chunk.starts_with("[DONE]") {
+            return Ok(None);
+        }
+        match serde_json::from_str(chunk) {
+            Ok(delta) => Ok(Some(delta)),
+            Err(e) => Err(Box::new(DeserializationError {
+                message: format!("{e} in stream line `{line}`"),
+                body: chunk.to_string(),
+                raw: serde_json::from_str(chunk).ok(),
+            })),
+        }
+    }
+
+    /// Adds the first choice of a streamed chunk to the answer, passing its content to
+    /// `on_token` or printing it. The fragments of function and tool calls are accumulated until
+    /// the finish reason `function_call` or `tool_calls` is received.
+    fn _accumulate_delta(
+        &self,
+        chunk: &StreamedReponse,
+        answer: &mut ChatOutcome,
+        on_token: &mut Option<&mut (dyn FnMut(&str) + Send)>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        answer.id.get_or_insert_with(|| chunk.id.clone());
+        answer.model.get_or_insert_with(|| chunk.model.clone());
+        if chunk.usage.is_some() {
+            answer.usage = chunk.usage;
+        }
+        for choice in chunk.choices.iter().filter(|choice| choice.index == 0) {
+            if let Some(fragment) = choice.delta.function_call.as_ref() {
+                if answer.finish_reason != FinishReason::FunctionCall {
+                    let call = answer.function_call.get_or_insert_with(|| FunctionCall {
+                        name: String::new(),
+                        arguments: String::new(),
+                    });
+                    call.name
+                        .push_str(fragment.name.as_deref().unwrap_or_default());
+                    call.arguments
+                        .push_str(fragment.arguments.as_deref().unwrap_or_default());
+                }
+            }
+            if answer.finish_reason != FinishReason::ToolCalls {
+                for fragment in choice.delta.tool_calls.iter().flatten() {
+                    if answer.tool_calls.len() <= fragment.index {
+                        answer
+                            .tool_calls
+                            .resize_with(fragment.index + 1, ToolCall::default);
+                    }
+                    let call = &mut answer.tool_calls[fragment.index];
+                    call.id.push_str(fragment.id.as_deref().unwrap_or_default());
+                    if let Some(function) = fragment.function.as_ref() {
+                        call.function
+                            .name
+                            .push_str(function.name.as_deref().unwrap_or_default());
+                        call.function
+                            .arguments
+                            .push_str(function.arguments.as_deref().unwrap_or_default());
+                    }
+                }
+            }
+            if choice.finish_reason != FinishReason::Null {
+                answer.finish_reason = choice.finish_reason.clone();
+            }
+            if let Some(content) = &choice.delta.content {
+                // Deltas are kept verbatim, their whitespace is part of the answer
+                self._emit_token(on_token, content)?;
+                self._append_bounded(&mut answer.text, content)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Passes a content delta to `on_token`, or writes it to the output by default unless the
+    /// live stream is disabled.
+    fn _emit_token(
+        &self,
+        on_token: &mut Option<&mut (dyn FnMut(&str) + Send)>,
+        token: &str,
+    ) -> io::Result<()> {
+        match on_token {
+            Some(on_token) => on_token(token),
+            None => self._write_output(token)?,
+        }
+        Ok(())
+    }
+
+    /// Writes text to the configured output, or to stdout if none is set. Nothing is written if
+    /// the live stream is disabled.
+    fn _write_output(&self, text: &str) -> io::Result<()> {
+        if self.disable_live_stream {
+            return Ok(());
+        }
+        match &self.config.output {
+            Some(output) => output.write(text),
+            None => {
+                let mut stdout = io::stdout().lock();
+                stdout.write_all(text.as_bytes())?;
+                stdout.flush()
+            }
+        }
+    }
+
+    fn _append_bounded(
+        &self,
+        answer_text: &mut String,
+        content: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let Some(max_bytes) = self.max_response_bytes else {
+            answer_text.push_str(content);
+            return Ok(());
+        };
+        if answer_text.len() + content.len() <= max_bytes {
+            answer_text.push_str(content);
+            return Ok(());
+        }
+        let mut cut = max_bytes - answer_text.len();
+        while !content.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        answer_text.push_str(&content[..cut]);
+        Err(Box::new(ResponseTooLarge {
+            max_bytes,
+            partial: std::mem::take(answer_text),
+        }))
+    }
+
+    /// Estimates the capacity needed for an answer from `max_tokens` with roughly four bytes per
+    /// token, bounded by `max_response_bytes`.
+    fn _answer_capacity(&self) -> usize {
+        let estimate = self.config.max_tokens.map_or(0, |tokens| {
+            usize::try_from(tokens)
+                .unwrap_or(usize::MAX)
+                .saturating_mul(4)
+        });
+        self.max_response_bytes
+            .map_or(estimate, |max_bytes| estimate.min(max_bytes))
+    }
+
+    async fn _ask_openai_streamed(
+        &mut self,
+        mut res: reqwest::Response,
+        answer: &mut ChatOutcome,
+        mut on_token: Option<&mut (dyn FnMut(&str) + Send)>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let print = on_token.is_none();
+        if print {
+            self._write_output("AI: ")?;
+        }
+        let pending = rt::take::<PendingMetrics>(&mut res);
+        let mut time_to_first_token = None;
+        let mut chunks = Box::pin(res.bytes_stream());
+        let mut buffer = LineBuffer::default();
+        let streamed: Result<(), Box<dyn Error + Send + Sync>> = async {
+            loop {
+                let next_chunk = async { Ok(chunks.next().await.transpose()?) };
+                let chunk = self._cancellable(self._within_timeout(next_chunk)).await?;
+                let ended = chunk.is_none();
+                let lines = match chunk {
+                    Some(chunk) => buffer.push(&chunk),
+                    None => buffer.finish(),
+                };
+                for line in lines.as_deref().unwrap_or_default().split('\n') {
+                    self._process_delta(line, answer, &mut on_token)?;
+                }
+                if time_to_first_token.is_none() && !answer.text.is_empty() {
+                    time_to_first_token = pending.as_ref().map(|p| p.start.elapsed());
+                }
+                if ended {
+                    return Ok(());
+                }
+            }
+        }
+        .await;
+        let streamed = match streamed {
+            Err(e) if e.is::<Cancelled>() && self._is_interrupted() => Ok(()),
+            streamed => streamed,
+        };
+        if pending.is_some() {
+            match &streamed {
+                Ok(()) => {
+                    let usage = answer
+                        .usage
+                        .or_else(|| Some(self._estimate_usage(&answer.text)));
+                    self._report_metrics(pending, usage, time_to_first_token, None);
+                }
+                Err(e) => {
+                    let error = Some(ErrorClass::from_error(&**e));
+                    self._report_metrics(pending, None, time_to_first_token, error);
+                }
+            }
+        }
+        streamed?;
+        if print {
+            self._write_output("\n")?;
+        }
+        Ok(())
+    }
+
+    /// Makes a request to `OpenAI`'s GPT model and retrieves a response based on the provided `prompt`.
+    ///
+    /// This function accepts a prompt, converts it into a string, and sends a request to the `OpenAI` API.
+    /// Depending on the streaming configuration (`is_streamed`), the function either collects all of the AI's responses
+    /// at once, or fetches and processes them as they arrive.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt`: A value that implements `Into<String>`. This will be converted into a string and sent to the API as the
+    /// prompt for the AI model.
+    ///
+    /// * `persist_state`: If true, the function will push the AI's response to the `messages` vector in the `config` struct.
+    /// If false, it will remove the last message from the `messages` vector.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)`: A success value containing the AI's response as a string.
+    ///
+    /// * `Err(Box<dyn std::error::Error + Send + Sync>)`: An error value. This is a dynamic error, meaning it could represent
+    /// various kinds of failures. The function will return an error if any step in the process fails, such as making the HTTP request,
+    /// parsing the JSON response, or if there's an issue with the streaming process.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the HTTP request fails, the JSON response from the API cannot be parsed, or if
+    /// an error occurs during streaming. A response without any choices is an error as well, and a
+    /// response whose content was omitted by the content filter returns a `ContentFiltered` error.
+    /// If the model requests a function or tool call, an `UnhandledCall` error carrying the call
+    /// is returned. Use `ask_with_outcome` to get such calls as a regular result instead.
+    /// If the request fails, or the model requests a function or tool call, the chat history is
+    /// left as it was before the call, including messages dropped by a history limit.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    ///  
+    /// use aionic::openai::chat::Chat;
+    /// use aionic::openai::OpenAI;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///     let prompt = "Hello, world!";
+    ///     let mut client = OpenAI::<Chat>::new();
+    ///     let result = client.ask(prompt, true).await;
+    ///     match result {
+    ///         Ok(response) => println!("{}", response),
+    ///         Err(e) => println!("Error: {}", e),
+    ///     }
+    ///     Ok(())
+    ///  }
+    /// ```
+    ///
+    /// # Note
+    ///
+    /// This function is `async` and must be awaited when called.
+    pub async fn ask<P: Into<Message> + Send>(
+        &mut self,
+        prompt: P,
+        persist_state: bool,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        self._ask_text(prompt, persist_state, None).await
+    }
+
+    /// Makes a request to `OpenAI`'s GPT model like `ask`, but stops reading the streamed answer
+    /// once `token` is cancelled, e.g. when the user presses CTRL-C.
+    ///
+    /// When the token is cancelled while the answer is streamed, the stream is closed, the
+    /// current output line is ended and the text received so far is returned. With
+    /// `persist_state` the partial answer is added to the chat history like a complete one, so
+    /// the conversation can be continued from it, otherwise the prompt is removed again. When
+    /// the token is cancelled before the answer started, e.g. while waiting for the response or
+    /// for a non-streamed response, a `Cancelled` error is returned and the chat history is
+    /// left as it was before the call.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt`: A value that implements `Into<Message>`, e.g. the user's question.
+    ///
+    /// * `persist_state`: If true, the AI's (partial) response is pushed to the chat history,
+    ///   otherwise the prompt is removed from it again.
+    ///
+    /// * `token`: The `CancellationToken` that interrupts the answer.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)`: The AI's response, or the part of it received before the interruption.
+    ///
+    /// * `Err(Box<dyn std::error::Error + Send + Sync>)`: An error value if the request fails, or
+    ///   a `Cancelled` error if it was interrupted before the answer started.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use aionic::openai::{CancellationToken, Chat, OpenAI};
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///     let mut client = OpenAI::<Chat>::new();
+    ///     let token = CancellationToken::new();
+    ///     let interrupt = token.clone();
+    ///     tokio::spawn(async move {
+    ///         tokio::time::sleep(Duration::from_secs(5)).await;
+    ///         interrupt.cancel();
+    ///     });
+    ///     match client.ask_cancellable("Write a long story", true, token).await {
+    ///         Ok(story) => println!("{}", story),
+    ///         Err(e) => println!("Error: {}", e),
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn ask_cancellable<P: Into<Message> + Send>(
+        &mut self,
+        prompt: P,
+        persist_state: bool,
+        token: CancellationToken,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let previous = self.interrupt.replace(token);
+        let answer = self.ask(prompt, persist_state).await;
+        self.interrupt = previous;
+        answer
+    }
+
+    /// Makes a request to `OpenAI`'s GPT model like `ask`, but returns the answer together with
+    /// the metadata of the response.
+    ///
+    /// For streamed responses, the ID, model and finish reason are taken from the streamed
+    /// chunks. The usage is estimated, unless it is requested with `set_stream_usage`.
+    ///
+    /// If the model chooses to call one of the functions set with `set_functions`, the call is
+    /// returned in `function_call` and the finish reason is `FinishReason::FunctionCall`. Its
+    /// result can be sent back with `Message::function_response` to continue the conversation.
+    /// Calls of the tools set with `set_tools` are returned in `tool_calls` likewise, and their
+    /// results are sent back with `Message::tool_result`.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt`: A value that implements `Into<Message>`, e.g. the user's question.
+    ///
+    /// * `persist_state`: If true, the AI's response is pushed to the chat history, otherwise the
+    ///   prompt is removed from it again.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ChatOutcome)`: The AI's response or function call with its finish reason, usage, ID
+    ///   and model.
+    ///
+    /// * `Err(Box<dyn std::error::Error + Send + Sync>)`: An error value if the request or parsing
+    ///   fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use aionic::openai::chat::{Chat, FinishReason};
+    /// use aionic::openai::OpenAI;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///     let mut client = OpenAI::<Chat>::new().set_max_tokens(16);
+    ///     let outcome = client.ask_detailed("Tell me a story", false).await?;
+    ///     if outcome.finish_reason == FinishReason::Length {
+    ///         println!("The answer was cut off: {}", outcome.text);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn ask_detailed<P: Into<Message> + Send>(
+        &mut self,
+        prompt: P,
+        persist_state: bool,
+    ) -> Result<ChatOutcome, Box<dyn std::error::Error + Send + Sync>> {
+        self._ask_detailed(prompt, persist_state, None).await
+    }
+
+    /// Makes a request to `OpenAI`'s GPT model like `ask`, but also handles the case where the model
+    /// decides to call one of the functions set with `set_functions` or the tools set with `set_tools`.
+    ///
+    /// If the model requests a function call, the `FunctionCall` is returned instead of a text. The caller
+    /// is expected to run the function and send its result back as a message with `MessageRole::Function`.
+    /// Tool calls are returned as `AskOutcome::ToolCalls` likewise, and each result is sent back with
+    /// `Message::tool_result`. With `persist_state`, the assistant's function or tool calls are kept in the
+    /// chat history, so the conversation can continue with their results.
+    ///
+    /// In streamed responses, the fragments of the function and tool calls are assembled until the
+    /// model finishes the calls.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt`: A value that implements `Into<Message>`, e.g. the user's question or a function result.
+    ///
+    /// * `persist_state`: If true, the AI's response or function call is pushed to the chat history,
+    ///   otherwise the prompt is removed from it again.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(AskOutcome)`: Either the AI's text response, or the function call or tool calls
+    ///   requested by the model.
+    ///
+    /// * `Err(Box<dyn std::error::Error + Send + Sync>)`: An error value if the request or parsing fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use aionic::openai::chat::{AskOutcome, Chat, Function};
+    /// use aionic::openai::OpenAI;
+    /// use serde_json::json;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///     let weather = Function::new(
+    ///         "get_weather",
+    ///         "Get the current weather in a given location",
+    ///         json!({"type": "object", "properties": {"location": {"type": "string"}}}),
+    ///     );
+    ///     let mut client = OpenAI::<Chat>::new()
+    ///         .set_stream_responses(false)
+    ///         .set_functions(vec![weather]);
+    ///     match client.ask_with_outcome("How is the weather in Berlin?", true).await? {
+    ///         AskOutcome::Text(text) => println!("{}", text),
+    ///         AskOutcome::FunctionCall(call) => println!("{}({})", call.name, call.arguments),
+    ///         AskOutcome::ToolCalls(calls) => {
+    ///             for call in calls {
+    ///                 println!("{}: {}({})", call.id, call.function.name, call.function.arguments);
+    ///             }
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Note
+    ///
+    /// This function is `async` and must be awaited when called.
+    pub async fn ask_with_outcome<P: Into<Message> + Send>(
+        &mut self,
+        prompt: P,
+        persist_state: bool,
+    ) -> Result<AskOutcome, Box<dyn std::error::Error + Send + Sync>> {
+        self._ask_with_outcome(prompt, persist_state, None).await
+    }
+
+    /// Makes a request like `ask_with_outcome`. The content deltas of a streamed response are
+    /// passed to `on_token` if given, or else to the stream callback if one is set, instead of
+    /// being printed.
+    pub(crate) async fn _ask_with_outcome<P: Into<Message> + Send>(
+        &mut self,
+        prompt: P,
+        persist_state: bool,
+        on_token: Option<&mut (dyn FnMut(&str) + Send)>,
+    ) -> Result<AskOutcome, Box<dyn std::error::Error + Send + Sync>> {
+        let answer = self._ask_detailed(prompt, persist_state, on_token).await?;
+        if !answer.tool_calls.is_empty() {
+            return Ok(AskOutcome::ToolCalls(answer.tool_calls));
+        }
+        Ok(answer
+            .function_call
+            .map_or(AskOutcome::Text(answer.text), AskOutcome::FunctionCall))
+    }
+
+    /// Makes a request like `_ask_with_outcome`, but only accepts a text answer. A function or
+    /// tool call is returned as an `UnhandledCall` error, and the chat history is restored to the
+    /// state before the call.
+    pub(crate) async fn _ask_text<P: Into<Message> + Send>(
+        &mut self,
+        prompt: P,
+        persist_state: bool,
+        on_token: Option<&mut (dyn FnMut(&str) + Send)>,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let snapshot = self._snapshot_history();
+        let outcome = self
+            ._ask_with_outcome(prompt, persist_state, on_token)
+            .await?;
+        if !matches!(outcome, AskOutcome::Text(_)) {
+            self._restore_history(snapshot);
+        }
+        match outcome {
+            AskOutcome::Text(text) => Ok(text),
+            AskOutcome::ToolCalls(tool_calls) => Err(Box::new(UnhandledCall {
+                function_call: None,
+                tool_calls,
+            })),
+            AskOutcome::FunctionCall(function_call) => Err(Box::new(UnhandledCall {
+                function_call: Some(function_call),
+                tool_calls: Vec::new(),
+            })),
+        }
+    }
+
+    /// Makes a request like `_ask_with_outcome`, but returns the answer with the metadata of its
+    /// response.
+    async fn _ask_detailed<P: Into<Message> + Send>(
+        &mut self,
+        prompt: P,
+        persist_state: bool,
+        on_token: Option<&mut (dyn FnMut(&str) + Send)>,
+    ) -> Result<ChatOutcome, Box<dyn std::error::Error + Send + Sync>> {
+        let snapshot = self._snapshot_history();
+        let mut stream_callback = self
+            .config
+            .stream_callback
+            .clone()
+            .map(|callback| move |delta: &str| callback.call(delta));
+        let outcome = match (on_token, stream_callback.as_mut()) {
+            (None, Some(callback)) => {
+                self._ask_uncancelled(prompt, persist_state, Some(callback))
+                    .await
+            }
+            (on_token, _) => self._ask_uncancelled(prompt, persist_state, on_token).await,
+        };
+        self._restore_history_on_error(snapshot, &outcome);
+        outcome
+    }
+
+    async fn _ask_uncancelled<P: Into<Message> + Send>(
+        &mut self,
+        prompt: P,
+        persist_state: bool,
+        mut on_token: Option<&mut (dyn FnMut(&str) + Send)>,
+    ) -> Result<ChatOutcome, Box<dyn std::error::Error + Send + Sync>> {
+        let mut answer = ChatOutcome {
+            text: String::with_capacity(self._answer_capacity()),
+            ..ChatOutcome::default()
+        };
+        let is_streamed = self.config.stream.unwrap_or(false);
+        self._prepare_ask(prompt)?;
+        let url = self._url(Self::OPENAI_API_COMPLETIONS_URL);
+        let (r, model) = self
+            ._post_with_fallback(&url, self.request_body(), &self.config.fallback_models)
+            .await?;
+        self.last_response.lock().model = Some(model);
+        if is_streamed {
+            self._ask_openai_streamed(r, &mut answer, on_token).await?;
+            let usage = answer
+                .usage
+                .unwrap_or_else(|| self._estimate_usage(&answer.text));
+            self._add_usage(&usage);
+            answer.usage = Some(usage);
+        } else {
+            let r = self._parse_json::<Response>(r).await?;
+            self.last_response.lock().system_fingerprint = r.system_fingerprint;
+            answer.usage = r.usage;
+            answer.id = r.id;
+            answer.model = r.model;
+            let first_choice = r
+                .choices
+                .and_then(|choices| choices.into_iter().min_by_key(|choice| choice.index));
+            let Some(choice) = first_choice else {
+                self.last_response.lock().usage = answer.usage;
+                return Err(Box::new(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "The response contains no choices",
+                )));
+            };
+            answer.finish_reason = choice.finish_reason;
+            answer.tool_calls = choice.message.tool_calls.unwrap_or_default();
+            if let Some(call) = choice.message.function_call {
+                answer.function_call = Some(call);
+            } else if answer.finish_reason == FinishReason::ContentFilter {
+                answer.text = choice.message.content.unwrap_or_default().to_string();
+            } else if answer.tool_calls.is_empty()
+                || choice
+                    .message
+                    .content
+                    .as_ref()
+                    .is_some_and(|c| !c.is_empty())
+            {
+                let content = choice.message.content.unwrap_or_default().to_string();
+                if let Some(on_token) = on_token.as_mut() {
+                    on_token(&content);
+                } else {
+                    self._write_output(&format!("AI: {content}\n"))?;
+                }
+                answer.text.push_str(&content);
+            }
+        }
+        self.last_response.lock().usage = answer.usage;
+        if answer.finish_reason == FinishReason::ContentFilter {
+            return Err(Box::new(ContentFiltered {
+                partial: answer.text,
+            }));
+        }
+
+        if !persist_state {
+            self.config.messages.pop();
+        } else if let Some(call) = answer.function_call.as_ref() {
+            let mut msg = Message::new(&MessageRole::Assistant, "");
+            msg.content = None;
+            msg.function_call = Some(call.clone());
+            self.config.messages.push(msg);
+        } else {
+            let mut msg = Message::new(&MessageRole::Assistant, &answer.text);
+            if !answer.tool_calls.is_empty() {
+                msg.tool_calls = Some(answer.tool_calls.clone());
+                if answer.text.is_empty() {
+                    msg.content = None;
+                }
+            }
+            self.config.messages.push(msg);
+        }
+        Ok(answer)
+    }
+
+    /// Asks `OpenAI`'s GPT model again for an answer to the last prompt, e.g. for a "regenerate
+    /// response" button.
+    ///
+    /// The last assistant message is removed from the chat history and the conversation is sent
+    /// again, so the new answer replaces the old one. If the history does not end with an
+    /// assistant message, the last message is simply asked for an answer, unless it is a system
+    /// message. If the request fails, the chat history is left as it was.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)`: The new answer of the AI.
+    ///
+    /// * `Err(Box<dyn std::error::Error + Send + Sync>)`: An `InvalidInput` error if there is no
+    ///   prompt in the chat history, or an error value if the request or parsing fails.
+    pub async fn regenerate(&mut self) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let snapshot = self._snapshot_history();
+        let assistant = MessageRole::Assistant.to_string();
+        let system = MessageRole::System.to_string();
+        if self
+            .get_last_message()
+            .is_some_and(|msg| msg.role == assistant)
+        {
+            self.config.messages.pop();
+        }
+        let prompt = match self.config.messages.pop() {
+            Some(msg) if msg.role != assistant && msg.role != system => msg,
+            _ => {
+                self._restore_history(snapshot);
+                return Err(Box::new(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "There is no prompt to regenerate an answer for",
+                )));
+            }
+        };
+        let answer = self.ask(prompt, true).await;
+        self._restore_history_on_error(snapshot, &answer);
+        answer
+    }
+
+    /// Makes a request to `OpenAI`'s GPT model like `ask`, and runs the functions registered with
+    /// `register_function` until the model answers with a text.
+    ///
+    /// Whenever the model requests a function call, the registered function is run with the
+    /// parsed arguments, and its result is sent back to the model with
+    /// `Message::function_response`. The function calls and their results are part of the chat
+    /// history while the conversation goes on.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt`: A value that implements `Into<Message>`, e.g. the user's question.
+    ///
+    /// * `persist_state`: If true, the function calls, their results and the AI's answer are kept
+    ///   in the chat history, otherwise the history is restored once the answer is received. The
+    ///   history is always restored if an error occurs.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)`: The AI's text answer after all function calls.
+    ///
+    /// * `Err(Box<dyn std::error::Error + Send + Sync>)`: An error value if a request fails, the
+    ///   model calls a function that is not registered, a function fails or gets invalid
+    ///   arguments, or the model did not answer within the maximum number of function calls.
+    pub async fn ask_with_functions<P: Into<Message> + Send>(
+        &mut self,
+        prompt: P,
+        persist_state: bool,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let snapshot = self._snapshot_history();
+        let answer = self._ask_with_functions(prompt.into()).await;
+        if !persist_state || answer.is_err() {
+            self._restore_history(snapshot);
+        }
+        answer
+    }
+
+    async fn _ask_with_functions(
+        &mut self,
+        mut message: Message,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let max_calls = self
+            .config
+            .max_function_calls
+            .unwrap_or(Chat::DEFAULT_MAX_FUNCTION_CALLS);
+        let mut calls = 0;
+        loop {
+            let outcome = self.ask_detailed(message, true).await?;
+            let Some(call) = outcome.function_call else {
+                return Ok(outcome.text);
+            };
+            if calls == max_calls {
+                return Err(Box::new(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("The model did not answer within {max_calls} function calls"),
+                )));
+            }
+            let handler = self
+                .config
+                .function_handlers
+                .get(&call.name)
+                .cloned()
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "The model requested a call to the unregistered function `{}`",
+                            call.name
+                        ),
+                    )
+                })?;
+            let result = handler.call(&call.arguments).await?;
+            message = Message::function_response(call.name, result);
+            calls += 1;
+        }
+    }
+
+    /// Makes a request to `OpenAI`'s GPT model like `ask`, but returns every completion choice
+    /// instead of only the first one. The number of choices is set with `set_n`.
+    ///
+    /// The request is always made without streaming, even if streamed responses are enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt`: A value that implements `Into<Message>`, e.g. the user's question.
+    ///
+    /// * `persist_state`: If true, only the first choice is pushed to the chat history as the AI's
+    ///   response, otherwise the prompt is removed from it again.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<String>)`: The text of every choice, ordered by the choice's index.
+    ///
+    /// * `Err(Box<dyn std::error::Error + Send + Sync>)`: An error value if the request or parsing fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use aionic::openai::chat::Chat;
+    /// use aionic::openai::OpenAI;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///     let mut client = OpenAI::<Chat>::new().set_n(3);
+    ///     for (i, answer) in client.ask_many("Name a color", false).await?.iter().enumerate() {
+    ///         println!("{}: {}", i, answer);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn ask_many<P: Into<Message> + Send>(
+        &mut self,
+        prompt: P,
+        persist_state: bool,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
+        let snapshot = self._snapshot_history();
+        let answers = self._ask_many(prompt, persist_state).await;
+        self._restore_history_on_error(snapshot, &answers);
+        answers
+    }
+
+    async fn _ask_many<P: Into<Message> + Send>(
+        &mut self,
+        prompt: P,
+        persist_state: bool,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
+        self._prepare_ask(prompt)?;
+        let stream = self.config.stream.replace(false);
+        let url = self._url(Self::OPENAI_API_COMPLETIONS_URL);
+        let res = self
+            ._post_with_fallback(&url, self.request_body(), &self.config.fallback_models)
+            .await;
+        self.config.stream = stream;
+        let (res, model) = res?;
+        self.last_response.lock().model = Some(model);
+        let r = self._parse_json::<Response>(res).await?;
+        {
+            let mut last_response = self.last_response.lock();
+            last_response.system_fingerprint = r.system_fingerprint;
+            last_response.usage = r.usage;
+        }
+        let mut choices = r.choices.unwrap_or_default();
+        if choices.is_empty() {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "The response contains no choices",
+            )));
+        }
+        choices.sort_by_key(|choice| choice.index);
+        let answers: Vec<String> = choices
+            .iter()
+            .map(|choice| {
+                choice
+                    .message
+                    .content
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        if !persist_state {
+            self.config.messages.pop();
+        } else if let Some(first) = answers.first() {
+            self.config
+                .messages
+                .push(Message::new(&MessageRole::Assistant, first));
+        }
+        Ok(answers)
+    }
+
+    /// Makes a streamed request to `OpenAI`'s GPT model and returns the chunks of the response
+    /// as they arrive, e.g. to forward them to a web client. Nothing is printed to stdout.
+    ///
+    /// The request is always streamed, even if streamed responses are disabled. The stream ends
+    /// after the `[DONE]` message and yields an error if the response cannot be read or parsed.
+    /// Once the stream is drained, the chat history is updated like in `ask`. If it is dropped
+    /// or fails before, the prompt is kept in the chat history without an answer. If the request
+    /// fails before the stream starts or is cancelled, the chat history is left as it was.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt`: A value that implements `Into<Message>`, e.g. the user's question.
+    ///
+    /// * `persist_state`: If true, the complete answer is pushed to the chat history once the
+    ///   stream is drained, otherwise the prompt is removed from it again.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(impl Stream)`: The stream of `ChatChunk`s of the response.
+    ///
+    /// * `Err(Box<dyn std::error::Error + Send + Sync>)`: An error value if the request fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use aionic::openai::chat::Chat;
+    /// use aionic::openai::OpenAI;
+    /// use futures_util::StreamExt;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///     let mut client = OpenAI::<Chat>::new();
+    ///     let mut stream = Box::pin(client.ask_stream("Tell me a story", true).await?);
+    ///     while let Some(chunk) = stream.next().await {
+    ///         if let Some(content) = chunk?.content {
+    ///             print!("{}", content);
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn ask_stream<P: Into<Message> + Send>(
+        &mut self,
+        prompt: P,
+        persist_state: bool,
+    ) -> Result<
+        impl Stream<Item = Result<ChatChunk, Box<dyn std::error::Error + Send + Sync>>> + '_,
+        Box<dyn std::error::Error + Send + Sync>,
+    > {
+        struct State<'a, S> {
+            client: &'a mut OpenAI<Chat>,
+            chunks: S,
+            lines: LineBuffer,
+            pending: std::collections::VecDeque<ChatChunk>,
+            answer: ChatOutcome,
+            metrics: Option<PendingMetrics>,
+            time_to_first_token: Option<Duration>,
+            snapshot: Option<HistorySnapshot>,
+            persist_state: bool,
+            error: Option<Box<dyn Error + Send + Sync>>,
+            finished: bool,
+        }
+
+        let snapshot = self._snapshot_history();
+        self._prepare_ask(prompt)?;
+        let stream = self.config.stream.replace(true);
+        let body = self.request_body();
+        self.config.stream = stream;
+        let url = self._url(Self::OPENAI_API_COMPLETIONS_URL);
+        let sent = self
+            ._post_with_fallback(&url, body, &self.config.fallback_models)
+            .await;
+        let (mut res, model) = match sent {
+            Ok(sent) => sent,
+            Err(e) => {
+                self._restore_history(snapshot);
+                return Err(e);
+            }
+        };
+        self.last_response.lock().model = Some(model);
+
+        let state = State {
+            metrics: rt::take::<PendingMetrics>(&mut res),
+            chunks: Box::pin(res.bytes_stream()),
+            answer: ChatOutcome {
+                text: String::with_capacity(self._answer_capacity()),
+                ..ChatOutcome::default()
+            },
+            client: self,
+            lines: LineBuffer::default(),
+            pending: std::collections::VecDeque::new(),
+            time_to_first_token: None,
+            snapshot: Some(snapshot),
+            persist_state,
+            error: None,
+            finished: false,
+        };
+        Ok(futures_util::stream::unfold(
+            state,
+            |mut state| async move {
+                loop {
+                    if let Some(chunk) = state.pending.pop_front() {
+                        return Some((Ok(chunk), state));
+                    }
+                    if let Some(error) = state.error.take() {
+                        return Some((Err(error), state));
+                    }
+                    if state.finished {
+                        return None;
+                    }
+                    let chunks = &mut state.chunks;
+                    let next_chunk = async { Ok(chunks.next().await.transpose()?) };
+                    let read = state
+                        .client
+                        ._cancellable(state.client._within_timeout(next_chunk))
+                        .await;
+                    let parsed = read.and_then(|bytes| {
+                        let ended = bytes.is_none();
+                        let lines = match bytes {
+                            Some(bytes) => state.lines.push(&bytes),
+                            None => state.lines.finish(),
+                        };
+                        let mut silent = |_: &str| {};
+                        let mut on_token: Option<&mut (dyn FnMut(&str) + Send)> = Some(&mut silent);
+                        for line in lines.as_deref().unwrap_or_default().split('\n') {
+                            if line.trim() == "data: [DONE]" {
+                                return Ok(true);
+                            }
+                            let Some(chunk) = Self::_parse_delta(line)? else {
+                                continue;
+                            };
+                            state.client._accumulate_delta(
+                                &chunk,
+                                &mut state.answer,
+                                &mut on_token,
+                            )?;
+                            state
+                                .pending
+                                .extend(chunk.choices.into_iter().map(|choice| ChatChunk {
+                                    index: choice.index,
+                                    role: choice.delta.role,
+                                    content: choice.delta.content,
+                                    finish_reason: choice.finish_reason,
+                                }));
+                        }
+                        Ok(ended)
+                    });
+                    if state.time_to_first_token.is_none() && !state.answer.text.is_empty() {
+                        state.time_to_first_token =
+                            state.metrics.as_ref().map(|p| p.start.elapsed());
+                    }
+                    match parsed {
+                        Ok(false) => {}
+                        Ok(true) => {
+                            state.finished = true;
+                            let usage = state.answer.usage.unwrap_or_else(|| {
+                                state.client._estimate_usage(&state.answer.text)
+                            });
+                            state.client._add_usage(&usage);
+                            state.client.last_response.lock().usage = Some(usage);
+                            let metrics = state.metrics.take();
+                            state.client._report_metrics(
+                                metrics,
+                                Some(usage),
+                                state.time_to_first_token,
+                                None,
+                            );
+                            if state.persist_state {
+                                let mut answer =
+                                    Message::new(&MessageRole::Assistant, &state.answer.text);
+                                answer.function_call = state.answer.function_call.clone();
+                                if !state.answer.tool_calls.is_empty() {
+                                    answer.tool_calls = Some(state.answer.tool_calls.clone());
+                                }
+                                let has_calls =
+                                    answer.function_call.is_some() || answer.tool_calls.is_some();
+                                if has_calls && state.answer.text.is_empty() {
+                                    answer.content = None;
+                                }
+                                state.client.config.messages.push(answer);
+                            } else {
+                                state.client.config.messages.pop();
+                            }
+                        }
+                        Err(error) => {
+                            state.finished = true;
+                            let metrics = state.metrics.take();
+                            let class = Some(ErrorClass::from_error(&*error));
+                            state.client._report_metrics(
+                                metrics,
+                                None,
+                                state.time_to_first_token,
+                                class,
+                            );
+                            if let Some(snapshot) =
+                                state.snapshot.take().filter(|_| error.is::<Cancelled>())
+                            {
+                                state.client._restore_history(snapshot);
+                            }
+                            state.error = Some(error);
+                        }
+                    }
+                }
+            },
+        ))
+    }
+
+    /// Takes a snapshot of the chat history before a request changes it.
+    fn _snapshot_history(&self) -> HistorySnapshot {
+        HistorySnapshot {
+            messages: self.config.messages.clone(),
+            trimmed_messages: self.config.trimmed_messages.clone(),
+        }
+    }
+
+    /// Restores the chat history from a snapshot, including the messages that were trimmed
+    /// since it was taken.
+    fn _restore_history(&mut self, snapshot: HistorySnapshot) {
+        self.config.messages = snapshot.messages;
+        self.config.trimmed_messages = snapshot.trimmed_messages;
+    }
+
+    /// Restores the chat history from a snapshot if the request failed or was cancelled, so
+    /// neither the prompt of a failed request is kept nor older messages are trimmed for it.
+    fn _restore_history_on_error<T>(
+        &mut self,
+        snapshot: HistorySnapshot,
+        result: &Result<T, Box<dyn std::error::Error + Send + Sync>>,
+    ) {
+        if result.is_err() {
+            self._restore_history(snapshot);
+        }
+    }
+
+    /// Validates the configuration and adds the prompt to the chat history before a request.
+    fn _prepare_ask<P: Into<Message>>(
+        &mut self,
+        prompt: P,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self._json_mode_sanity_check()?;
+        self._logit_bias_sanity_check()?;
+        self._sampling_sanity_check()?;
+        if let Some(temp) = self.config.temperature {
+            if !Self::is_valid_temperature(temp, 2.0) {
+                self._reject_if_strict(format!(
+                    "Temperature {temp} is out of range, expected a value between 0.0 and 2.0"
+                ))?;
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    temperature = temp,
+                    "Temperature out of range, clamping it to 2.0"
+                );
+                self.config.temperature = Some(2.0);
+            }
+        }
+        self.config.messages.push(prompt.into());
+        self.config.trimmed_messages = self.trim_history();
+        Ok(())
+    }
+}
+
+#[cfg(feature = "chat")]
+impl AsRef<[Message]> for OpenAI<Chat> {
+    fn as_ref(&self) -> &[Message] {
+        self.messages()
+    }
+}
+
+// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
+// = OpenAI IMAGE IMPLEMENTATION
+// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
+
+#[cfg(feature = "images")]
+impl OpenAI<Image> {
+    const OPENAI_API_IMAGE_GEN_URL: &str = "https://api.openai.com/v1/images/generations";
+    const OPENAI_API_IMAGE_EDIT_URL: &str = "https://api.openai.com/v1/images/edits";
+    const OPENAI_API_IMAGE_VARIATION_URL: &str = "https://api.openai.com/v1/images/variations";
+
+    /// Allows setting the return format of the response. `ResponseDataType` is an enum with the
+    /// following variants:
+    /// * `Url`: The response will be a vector of URLs to the generated images.
+    /// * `Base64Json`: The response will be a vector of base64 encoded images.
+    pub fn set_response_format(mut self, response_format: &ResponseDataType) -> Self {
+        self.config.response_format = Some(response_format.to_string());
+        self
+    }
+
+    /// Allows setting the number of images to be generated.
+    pub fn set_max_images(mut self, number_of_images: u64) -> Self {
+        self.config.n = Some(number_of_images);
+        self
+    }
+
+    /// Allows setting the dimensions of the generated images.
+    pub fn set_size(mut self, size: &Size) -> Self {
+        self.config.size = Some(size.to_string());
+        self
+    }
+
+    /// Allows setting the model used for image generation, e.g. `dall-e-3`.
+    pub fn set_model<S: Into<String>>(mut self, model: S) -> Self {
+        self.config.model = Some(model.into());
+        self
+    }
+
+    /// Allows setting the quality of the generated images, either `standard` or `hd`. Only supported by DALL·E 3.
+    pub fn set_quality<S: Into<String>>(mut self, quality: S) -> Self {
+        self.config.quality = Some(quality.into());
+        self
+    }
+
+    /// Allows setting the style of the generated images, either `vivid` or `natural`. Only supported by DALL·E 3.
+    pub fn set_style<S: Into<String>>(mut self, style: S) -> Self {
+        self.config.style = Some(style.into());
+        self
+    }
+
+    fn _generation_sanity_checks(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let invalid = |msg: String| -> Result<(), Box<dyn Error + Send + Sync>> {
+            Err(Box::new(io::Error::new(io::ErrorKind::InvalidInput, msg)))
+        };
+        if !self.config.is_dall_e_3() {
+            if self.config.quality.is_some() || self.config.style.is_some() {
+                return invalid("Quality and style are only supported by dall-e-3".into());
+            }
+            return Ok(());
+        }
+        if let Some(n) = self.config.n.filter(|n| *n != 1) {
+            return invalid(format!("dall-e-3 only supports n = 1, got n = {n}"));
+        }
+        if let Some(size) = self
+            .config
+            .size
+            .as_deref()
+            .filter(|size| !Image::is_valid_dall_e_3_size(size))
+        {
+            return invalid(format!(
+                "dall-e-3 only supports the sizes 1024x1024, 1792x1024 and 1024x1792, got {size}"
+            ));
+        }
+        if let Some(quality) = self
+            .config
+            .quality
+            .as_deref()
+            .filter(|quality| !Image::is_valid_quality(quality))
+        {
+            return invalid(format!(
+                "Invalid quality: {quality}, must be standard or hd"
+            ));
+        }
+        if let Some(style) = self
+            .config
+            .style
+            .as_deref()
+            .filter(|style| !Image::is_valid_style(style))
+        {
+            return invalid(format!("Invalid style: {style}, must be vivid or natural"));
+        }
+        Ok(())
+    }
+
+    /// Generates an image based on a textual description.
+    ///
+    /// This function sets the prompt to the given string and sends a request to the `OpenAI` API to create an image.
+    /// The function then parses the response and returns a vector of image URLs.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt`: A string that describes the image to be generated.
+    ///
+    /// # Returns
+    ///
+    /// This function returns a `Result` with a vector of strings on success, each string being a URL to an image.
+    /// If there's an error, it returns a dynamic error.
+    pub async fn create<S: Into<String> + Send>(
+        &mut self,
+        prompt: S,
+    ) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+        let image_response = self.create_response(prompt).await?;
+        Ok(self._parse_response(&image_response))
+    }
+
+    /// Generates an image based on a textual description, like `create`, but returns the full response.
+    ///
+    /// This gives access to additional data, such as the `revised_prompt` DALL·E 3 returns.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt`: A string that describes the image to be generated.
+    ///
+    /// # Returns
+    ///
+    /// This function returns a `Result` with the `ImageResponse` on success.
+    /// If the configured model, `n`, size, quality and style are an invalid combination, it returns an error.
+    pub async fn create_response<S: Into<String> + Send>(
+        &mut self,
+        prompt: S,
+    ) -> Result<ImageResponse, Box<dyn Error + Send + Sync>> {
+        self.config.prompt = Some(prompt.into());
+        if self.config.image.is_some() {
+            self.config.image = None;
+        }
+        if self.config.mask.is_some() {
+            self.config.mask = None;
+        }
+        self._generation_sanity_checks()?;
+        let res: reqwest::Response = self
+            ._make_post_request(self._url(Self::OPENAI_API_IMAGE_GEN_URL))
+            .await?;
+        let handle_res = self.handle_api_errors(res).await?;
+        self._parse_json(handle_res).await
+    }
+
+    /// Generates images based on a textual description, like `create`, and saves them to disk.
+    ///
+    /// Images returned as URL are downloaded with the client's HTTP client, images returned as
+    /// base64 are decoded directly. They are written as `image_0.png`, `image_1.png`, etc.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt`: A string that describes the image to be generated.
+    /// * `dir`: The directory to save the images in. It is created if it does not exist, and
+    ///   existing images of the same name are overwritten.
+    ///
+    /// # Returns
+    ///
+    /// This function returns a `Result` with the paths of the saved images on success.
+    /// If a download fails, a `DownloadError` is returned, otherwise any other error is a dynamic error.
+    pub async fn create_and_save<S: Into<String> + Send, P: AsRef<Path> + Send>(
+        &mut self,
+        prompt: S,
+        dir: P,
+    ) -> Result<Vec<PathBuf>, Box<dyn Error + Send + Sync>> {
+        let image_response = self.create_response(prompt).await?;
+        tokio::fs::create_dir_all(dir.as_ref()).await?;
+        let mut paths = Vec::with_capacity(image_response.data.len());
+        for (i, data) in image_response.data.iter().enumerate() {
+            let bytes = match (&data.b64_json, &data.url) {
+                (Some(_), _) => data.decoded()?,
+                (None, Some(url)) => self._download(url.as_str(), None).await?.1,
+                (None, None) => {
+                    return Err(Box::new(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Image {i} has neither a URL nor base64 data"),
+                    )))
+                }
+            };
+            let path = dir.as_ref().join(format!("image_{i}.png"));
+            tokio::fs::write(&path, bytes).await?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    /// Modifies an existing image based on a textual description.
+    ///
+    /// This function sets the image and optionally the mask, then sets the prompt to the given string and sends a request to the `OpenAI` API to modify the image.
+    /// The function then parses the response and returns a vector of image URLs.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt`: A string that describes the modifications to be made to the image.
+    /// * `image_file_path`: A string that specifies the path to the image file to be modified.
+    /// * `mask`: An optional string that specifies the path to a mask file. If the mask is not provided, it is set to `None`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns a `Result` with a vector of strings on success, each string being a URL to an image.
+    /// If there's an error, it returns a dynamic error.
+    pub async fn edit<S: Into<String> + Send>(
+        &mut self,
+        prompt: S,
+        image_file_path: S,
+        mask: Option<S>,
+    ) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+        self.config.image = Some(image_file_path.into());
+        if let Some(mask) = mask {
+            self.config.mask = Some(mask.into());
+        }
+        self.config.prompt = Some(prompt.into());
+        self._edit_sanity_checks()?;
+
+        let image_part = self
+            .create_file_upload_part(self.config.image.clone().unwrap_or_default())
+            .await?;
+        let mask_part = match self.config.mask.clone() {
+            Some(mask_name) => Some(self.create_file_upload_part(mask_name).await?),
+            None => None,
+        };
+        let image_response: ImageResponse = self
+            ._make_file_upload_request(
+                self._url(Self::OPENAI_API_IMAGE_EDIT_URL),
+                image_part,
+                mask_part,
+            )
+            .await?;
+        Ok(self._parse_response(&image_response))
+    }
+
+    /// Modifies a remote image based on a textual description.
+    ///
+    /// This function downloads the image and optionally the mask from the given URLs without sending
+    /// any `OpenAI` credentials, validates that they are PNG files within the size limit, and proceeds
+    /// exactly like `edit`.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt`: A string that describes the modifications to be made to the image.
+    /// * `image_url`: The URL of the image to be modified.
+    /// * `mask_url`: An optional URL of a mask image.
+    ///
+    /// # Returns
+    ///
+    /// This function returns a `Result` with a vector of strings on success, each string being a URL to an image.
+    /// If the download fails, a `DownloadError` is returned, otherwise any other error is a dynamic error.
+    pub async fn edit_from_url<S: Into<String> + Send, U: IntoUrl + Send>(
+        &mut self,
+        prompt: S,
+        image_url: U,
+        mask_url: Option<U>,
+    ) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+        let (image_name, image_bytes) = self
+            ._download(image_url, Some(Image::MAX_FILE_SIZE_BYTES))
+            .await?;
+        Self::_validate_remote_image(&image_name, &image_bytes)?;
+        self.config.image = Some(image_name.clone());
+        let image_part = Self::_in_memory_part(image_name, image_bytes)?;
+
+        let mask_part = match mask_url {
+            Some(mask_url) => {
+                let (mask_name, mask_bytes) = self
+                    ._download(mask_url, Some(Image::MAX_FILE_SIZE_BYTES))
+                    .await?;
+                Self::_validate_remote_image(&mask_name, &mask_bytes)?;
+                self.config.mask = Some(mask_name.clone());
+                Some(Self::_in_memory_part(mask_name, mask_bytes)?)
+            }
+            None => {
+                self.config.mask = None;
+                None
+            }
+        };
+        self.config.prompt = Some(prompt.into());
+        self._edit_sanity_checks()?;
+
+        let image_response: ImageResponse = self
+            ._make_file_upload_request(
+                self._url(Self::OPENAI_API_IMAGE_EDIT_URL),
+                image_part,
+                mask_part,
+            )
+            .await?;
+        Ok(self._parse_response(&image_response))
+    }
+
+    fn _validate_remote_image(
+        name: &str,
+        bytes: &[u8],
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if !Image::is_png(bytes) {
+            return Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Downloaded image is not a PNG file: {name}"),
+            )));
+        }
+        Ok(())
+    }
+
+    fn _edit_sanity_checks(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if let Some(n) = self.config.n {
+            if !image::Image::is_valid_n(n) {
+                self._reject_if_strict(format!("Invalid number of images {n}"))?;
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    n,
+                    default = image::Image::get_default_n(),
+                    "Invalid number of images, resetting it to the default"
+                );
+                self.config.n = Some(image::Image::get_default_n());
+            }
+        }
+
+        if let Some(size) = self.config.size.as_ref() {
+            if !image::Image::is_valid_size(size) {
+                self._reject_if_strict(format!("Invalid image size `{size}`"))?;
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    size = %size,
+                    default = image::Image::get_default_size(),
+                    "Invalid image size, resetting it to the default"
+                );
+                self.config.size = Some(image::Image::get_default_size().into());
+            }
+        }
+
+        if let Some(response_format) = self.config.response_format.as_ref() {
+            if !image::Image::is_valid_response_format(response_format) {
+                self._reject_if_strict(format!(
+                    "Invalid image response format `{response_format}`"
+                ))?;
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    response_format = %response_format,
+                    default = image::Image::get_default_response_format(),
+                    "Invalid response format, resetting it to the default"
+                );
+                self.config.response_format =
+                    Some(image::Image::get_default_response_format().into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Generates variations of an existing image.
+    ///
+    /// This function sets the image and sends a request to the `OpenAI` API to create variations of the image.
+    /// The function then parses the response and returns a vector of image URLs.
+    ///
+    /// # Arguments
+    ///
+    /// * `image_file_path`: A string that specifies the path to the image file.
+    ///
+    /// # Returns
+    ///
+    /// This function returns a `Result` with a vector of strings on success, each string being a URL to a new variation of the image.
+    /// If there's an error, it returns a dynamic error.
+    pub async fn variation<S: Into<String> + Send>(
+        &mut self,
+        image_file_path: S,
+    ) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+        self.config.image = Some(image_file_path.into());
+        if self.config.prompt.is_some() {
+            self.config.prompt = None;
+        }
+        if self.config.mask.is_some() {
+            self.config.mask = None;
+        }
+        let image_part = self
+            .create_file_upload_part(self.config.image.clone().unwrap_or_default())
+            .await?;
+        let image_response: ImageResponse = self
+            ._make_file_upload_request(
+                self._url(Self::OPENAI_API_IMAGE_VARIATION_URL),
+                image_part,
+                None,
+            )
+            .await?;
+
+        Ok(self._parse_response(&image_response))
+    }
+
+    fn _parse_response(&mut self, image_response: &ImageResponse) -> Vec<String> {
+        image_response
+            .data
+            .iter()
+            .filter_map(|d| {
+                if self.config.response_format == Some("url".into()) {
+                    d.url.clone()
+                } else {
+                    d.b64_json.clone()
+                }
+            })
+            .collect::<Vec<String>>()
+    }
+
+    async fn _make_file_upload_request<S: IntoUrl + Send + Sync>(
+        &mut self,
+        url: S,
+        image_part: Part,
+        mask_part: Option<Part>,
+    ) -> Result<ImageResponse, Box<dyn Error + Send + Sync>> {
+        let mut form = Form::new().part("image", image_part);
+
+        if let Some(prompt) = self.config.prompt.as_ref() {
+            form = form.text("prompt", prompt.clone());
+        }
+        if let Some(mask_part) = mask_part {
+            form = form.part("mask", mask_part);
+        }
+
+        if let Some(response_format) = self.config.response_format.as_ref() {
+            form = form.text("response_format", response_format.clone());
+        }
+
+        if let Some(size) = self.config.size.as_ref() {
+            form = form.text("size", size.clone());
+        }
+
+        if let Some(n) = self.config.n {
+            form = form.text("n", n.to_string());
+        }
+
+        if let Some(user) = self.config.user.as_ref() {
+            form = form.text("user", user.clone());
+        }
+
+        let res: reqwest::Response = self._make_form_request(url, form).await?;
+        let handle_res = self.handle_api_errors(res).await?;
+        let image_response: ImageResponse = self._parse_json(handle_res).await?;
+
+        Ok(image_response)
+    }
+}
+
+// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
+// = OpenAI EMBEDDINGS IMPLEMENTATION
+// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
+
+#[cfg(feature = "embeddings")]
+impl OpenAI<Embedding> {
+    const OPENAI_API_EMBEDDINGS_URL: &str = "https://api.openai.com/v1/embeddings";
+
+    /// Sets the model of the AI assistant.
+    ///
+    /// # Arguments
+    ///
+    /// * `model`: A string that specifies the model name to be used by the AI assistant.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified model.
+    pub fn set_model<S: Into<String>>(mut self, model: S) -> Self {
+        self.config.model = model.into();
+        self
+    }
+
+    /// Sends a POST request to the `OpenAI` API to get embeddings for the given prompt.
+    ///
+    /// This method accepts a prompt of type `S` which can be converted into `InputType`
+    /// (an enum that encapsulates the different types of possible inputs). The method converts
+    /// the provided prompt into `InputType` and sends it as the input of a POST request to the
+    /// `OpenAI` API, without modifying the client. It then attempts to parse the response as
+    /// `EmbeddingResponse`. As it only takes `&self`, a client in an `Arc` can embed from many
+    /// tasks concurrently, sharing the connection pool.
+    ///
+    /// # Type Parameters
+    ///
+    /// * `S`: The type of the prompt. Must implement the `Into<InputType>` trait.
+    ///
+    /// # Parameters
+    ///
+    /// * `prompt`: The prompt for which to get embeddings. Can be a `String`, a `Vec<String>`,
+    /// a `Vec<u64>`, or a `&str` that is converted into an `InputType`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// * `Ok` if the request was successful, carrying the `EmbeddingResponse` which contains the embeddings.
+    /// * `Err` if the request or the parsing failed, carrying the error of type `Box<dyn std::error::Error + Send + Sync>`.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if the POST request fails, or if the response from the
+    /// `OpenAI` API cannot be parsed into an `EmbeddingResponse`. An empty input is rejected with
+    /// an `InvalidInput` error before it is sent.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aionic::openai::embeddings::Embedding;
+    /// use aionic::openai::OpenAI;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///     let client = OpenAI::<Embedding>::new();
+    ///     let prompt = "Hello, world!";
+    ///     match client.embed(prompt).await {
+    ///         Ok(response) => println!("Embeddings: {:?}", response),
+    ///         Err(e) => println!("Error: {}", e),
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Note
+    ///
+    /// This method is `async` and needs to be awaited.
+    pub async fn embed<S: Into<InputType> + Send>(
+        &self,
+        prompt: S,
+    ) -> Result<EmbeddingResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let url = self._url(Self::OPENAI_API_EMBEDDINGS_URL);
+        self._embed(&url, prompt.into()).await
+    }
+
+    /// Sets the input that `embed_current` embeds, so a client can be prepared with its model
+    /// and input in one go.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The input to embed, e.g. a `&str` or a `Vec<String>`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified input.
+    pub fn with_input<S: Into<InputType>>(mut self, input: S) -> Self {
+        self.config.input = input.into();
+        self
+    }
+
+    /// Sends a request like `embed`, but embeds the input set with `with_input` or in the
+    /// configuration.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `EmbeddingResponse`, or an `InvalidInput` error if no input
+    /// was set, see `InputType::is_empty`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aionic::openai::{Embedding, OpenAI};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///     let client = OpenAI::<Embedding>::new().with_input("Hello, world!");
+    ///     match client.embed_current().await {
+    ///         Ok(response) => println!("Embeddings: {:?}", response),
+    ///         Err(e) => println!("Error: {}", e),
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn embed_current(
+        &self,
+    ) -> Result<EmbeddingResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let url = self._url(Self::OPENAI_API_EMBEDDINGS_URL);
+        self._embed(&url, self.config.input.clone()).await
+    }
+
+    /// Embeds a large number of inputs by splitting them into batches of `chunk_size` inputs,
+    /// of which up to `concurrency` are requested at the same time.
+    ///
+    /// The embeddings are returned in the order of `inputs`, regardless of the order the
+    /// requests complete in. The usage of all requests is summed up and available via
+    /// `last_usage` afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - The texts to embed.
+    /// * `chunk_size` - The maximum number of inputs per request, e.g. 2048 for `OpenAI`.
+    /// * `concurrency` - The maximum number of requests in flight at once. Use 1 to send the
+    ///   requests one after the other.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing one embedding per input, or the first error of any request.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aionic::openai::{Embedding, OpenAI};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///     let client = OpenAI::<Embedding>::new();
+    ///     let documents = vec!["first document".to_string(), "second document".to_string()];
+    ///     if let Ok(embeddings) = client.embed_batch(documents, 1000, 4).await {
+    ///         println!("{} embeddings, {:?}", embeddings.len(), client.last_usage());
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn embed_batch(
+        &self,
+        inputs: Vec<String>,
+        chunk_size: usize,
+        concurrency: usize,
+    ) -> Result<Vec<Vec<f64>>, Box<dyn std::error::Error + Send + Sync>> {
+        let url = self._url(Self::OPENAI_API_EMBEDDINGS_URL);
+        self._embed_batch(&url, inputs, chunk_size, concurrency)
+            .await
+    }
+
+    /// Returns the token usage of the last call to `embed_batch`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `None` if `embed_batch` was not called yet.
+    pub fn last_usage(&self) -> Option<Usage> {
+        self.last_response.lock().usage
+    }
+
+    async fn _embed_batch(
+        &self,
+        url: &str,
+        inputs: Vec<String>,
+        chunk_size: usize,
+        concurrency: usize,
+    ) -> Result<Vec<Vec<f64>>, Box<dyn std::error::Error + Send + Sync>> {
+        let chunk_size = chunk_size.max(1);
+        let total = inputs.len();
+        let batches: Vec<(usize, Vec<String>)> = inputs
+            .chunks(chunk_size)
+            .enumerate()
+            .map(|(i, batch)| (i * chunk_size, batch.to_vec()))
+            .collect();
+
+        let mut responses = futures_util::stream::iter(batches)
+            .map(|(offset, batch)| async move {
+                let len = batch.len();
+                let res = self._embed(url, InputType::MultipleStrings(batch)).await?;
+                Ok::<_, Box<dyn std::error::Error + Send + Sync>>((offset, len, res))
+            })
+            .buffer_unordered(concurrency.max(1));
+
+        let mut embeddings: Vec<Option<Vec<f64>>> = vec![None; total];
+        let mut usage = Usage::zero();
+        while let Some(response) = responses.next().await {
+            let (offset, len, res) = response?;
+            usage += res.usage;
+            for data in res.data {
+                let index = usize::try_from(data.index).unwrap_or(usize::MAX);
+                if index >= len {
+                    return Err(Box::new(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "Embedding index {index} is out of range for a batch of {len} inputs"
+                        ),
+                    )));
+                }
+                embeddings[offset + index] = Some(data.embedding);
+            }
+        }
+        drop(responses);
+        self.last_response.lock().usage = Some(usage);
+
+        embeddings
+            .into_iter()
+            .enumerate()
+            .map(|(i, embedding)| {
+                embedding.ok_or_else(|| {
+                    Box::new(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("No embedding was returned for input {i}"),
+                    )) as Box<dyn std::error::Error + Send + Sync>
+                })
+            })
+            .collect()
+    }
+
+    /// Sets the models to fall back to, in order, if the configured model is not found or
+    /// overloaded. The model that served a request is reported in `EmbeddingResponse::model`.
+    ///
+    /// # Arguments
+    ///
+    /// * `models` - The fallback models, e.g. `["text-embedding-ada-002"]`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the fallback models set.
+    pub fn with_fallback_models<S: Into<String>>(mut self, models: Vec<S>) -> Self {
+        self.config.fallback_models = models.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Enables automatic chunking of single string inputs that exceed `Embedding::MAX_INPUT_TOKENS`.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunking` - Whether `embed` returns the embedding of every chunk or their mean-pooled average.
+    ///   See `Chunking` for the pooling semantics.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with chunking enabled.
+    pub fn set_chunking(mut self, chunking: Chunking) -> Self {
+        self.config.chunking = Some(chunking);
+        self
+    }
+
+    /// Sets the number of dimensions of the resulting embeddings, which shortens them without
+    /// losing their concept-representing properties. Only the `text-embedding-3` models support
+    /// this, `embed` fails for other models.
+    ///
+    /// # Arguments
+    ///
+    /// * `dimensions` - The number of dimensions, e.g. 256 for `text-embedding-3-small`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified dimensions.
+    pub fn set_dimensions(mut self, dimensions: u32) -> Self {
+        self.config.dimensions = Some(dimensions);
+        self
+    }
+
+    /// Sets the format the embeddings are transferred in. Base64 encoded embeddings are decoded
+    /// transparently, so this only affects the size of the response.
+    ///
+    /// # Arguments
+    ///
+    /// * `encoding_format` - The encoding format, i.e. `EncodingFormat::Float` or `EncodingFormat::Base64`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified encoding format.
+    pub fn set_encoding_format(mut self, encoding_format: EncodingFormat) -> Self {
+        self.config.encoding_format = Some(encoding_format);
+        self
+    }
+
+    async fn _embed(
+        &self,
+        url: &str,
+        input: InputType,
+    ) -> Result<EmbeddingResponse, Box<dyn std::error::Error + Send + Sync>> {
+        if input.is_empty() {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "The input to embed is empty",
+            )));
+        }
+        if input.is_image() && !Embedding::supports_images(&self.config.model) {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Model {} does not support image inputs", self.config.model),
+            )));
+        }
+        if self.config.dimensions.is_some() && !Embedding::supports_dimensions(&self.config.model) {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Model {} does not support the dimensions parameter",
+                    self.config.model
+                ),
+            )));
+        }
+        let chunked = match (&self.config.chunking, &input) {
+            (Some(chunking), InputType::SingleString(input)) => {
+                let chunks = embeddings::chunk_tokens(
+                    &self.config.model,
+                    input,
+                    Embedding::MAX_INPUT_TOKENS,
+                )?;
+                (chunks.len() > 1).then_some((*chunking, chunks))
+            }
+            _ => None,
+        };
+        let mut body = self.request_body();
+        let fallback_models = &self.config.fallback_models;
+        let Some((chunking, chunks)) = chunked else {
+            body["input"] = serde_json::to_value(input)?;
+            let (res, _) = self._post_with_fallback(url, body, fallback_models).await?;
+            return self._parse_json(res).await;
+        };
+
+        let weights: Vec<usize> = chunks.iter().map(Vec::len).collect();
+        body["input"] = serde_json::to_value(InputType::from(chunks))?;
+        let (res, _) = self._post_with_fallback(url, body, fallback_models).await?;
+        let mut embedding: EmbeddingResponse = self._parse_json(res).await?;
+        embedding.data.sort_by_key(|d| d.index);
+        if chunking == Chunking::MeanPool {
+            let vectors: Vec<Vec<f64>> = embedding.data.drain(..).map(|d| d.embedding).collect();
+            embedding.data.push(embeddings::Data {
+                object: "embedding".into(),
+                embedding: embeddings::mean_pool(&vectors, &weights),
+                index: 0,
+            });
+        }
+        Ok(embedding)
+    }
+}
+
+// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
+// = OpenAI AUDIO IMPLEMENTATION
+// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
+
+#[cfg(feature = "audio")]
+impl OpenAI<Audio> {
+    const OPENAI_API_TRANSCRIPTION_URL: &str = "https://api.openai.com/v1/audio/transcriptions";
+    const OPENAI_API_TRANSLATION_URL: &str = "https://api.openai.com/v1/audio/translations";
+
+    /// Sets the model of the AI assistant.
+    ///
+    /// # Arguments
+    ///
+    /// * `model`: A string that specifies the model name to be used by the AI assistant.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified model.
+    pub fn set_model<S: Into<String>>(mut self, model: S) -> Self {
+        self.config.model = model.into();
+        self
+    }
+
+    /// Sets the optional prompt to giode the model's style of response.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt`: An optional string that specifies the prompt to guide the model's style of response.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified prompt
+    pub fn set_prompt<S: Into<String>>(mut self, prompt: S) -> Self {
+        self.config.prompt = Some(prompt.into());
+        self
+    }
+
+    /// Sets the timestamp granularities of a transcription, i.e. whether words and/or segments
+    /// get timestamps. This requires the response format `AudioResponseFormat::VerboseJson`,
+    /// e.g. by using `transcribe_verbose`.
+    ///
+    /// # Arguments
+    ///
+    /// * `granularities`: The granularities to populate.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified timestamp granularities.
+    pub fn set_timestamp_granularities(mut self, granularities: Vec<Granularity>) -> Self {
+        self.config.timestamp_granularities = Some(granularities);
+        self
+    }
+
+    /// Sets the required audio file to be transcribed or translated.
+    ///
+    /// # Arguments
+    ///
+    /// * `file`: A string that specifies the path to the audio file to be transcribed or translated.
+    /// The path must be a valid path to a file.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified audio file.
+    fn _set_file<P: AsRef<Path> + Send + Sync>(
+        &mut self,
+        file: P,
+    ) -> Result<&mut Self, Box<dyn std::error::Error + Send + Sync>> {
+        let path = file.as_ref();
+        let metadata = fs::metadata(path)?;
+        if metadata.is_file() {
+            let path_str = path.to_str().ok_or("Path is not valid UTF-8")?;
+            self.config.file = path_str.to_string();
+            if self._is_valid_mime_time().is_err() {
+                return Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!(
+                        "Invalid audio file type. Supported types are {:?}",
+                        Audio::get_supported_file_types()
+                    ),
+                )));
+            }
+            if metadata.len() > Audio::MAX_FILE_SIZE_BYTES {
+                return Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!(
+                        "Audio file {} has {} bytes and exceeds the limit of {} bytes, split or compress it first",
+                        path.display(),
+                        metadata.len(),
+                        Audio::MAX_FILE_SIZE_BYTES
+                    ),
+                )));
+            }
+            Ok(self)
+        } else {
+            Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Path is not a file: {}", path.display()),
+            )))
+        }
+    }
+
+    /// Sets the optional audio file format to be returned
+    ///
+    /// # Arguments
+    ///
+    /// * `format`: An optional enum type that specifies the audio file format to be returned.
+    /// The default is `AudioResponseFormat::Json`..
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified audio file format.
+    pub fn set_response_format(&mut self, format: AudioResponseFormat) -> &mut Self {
+        self.config.response_format = Some(format);
+        self
+    }
+
+    fn _is_valid_mime_time(&self) -> Result<bool, String> {
+        Audio::is_file_type_supported(&self.config.file)
+    }
+
+    fn _is_valid_model(&self) -> bool {
+        self.config.model == "whisper-1"
+    }
+
+    fn _sanity_checks(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(temp) = self.config.temperature {
+            if !Self::is_valid_temperature(temp, 1.0) {
+                self._reject_if_strict(format!(
+                    "Temperature {temp} is out of range, expected a value between 0.0 and 1.0"
+                ))?;
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    temperature = temp,
+                    "Temperature out of range, clamping it to 1.0"
+                );
+                self.config.temperature = Some(1.0);
+            }
+        }
+
+        if !self._is_valid_model() {
+            return Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Invalid model. Supported models are {:?}",
+                    Audio::get_supported_models()
+                ),
+            )));
+        }
+
+        if let Some(lang) = &self.config.language {
+            if !Audio::is_valid_language(lang) {
+                return Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!(
+                        "Invalid language code. Supported language codes are {:?}",
+                        Audio::ISO_639_1_CODES
+                    ),
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates an audio file downloaded from `url` in the same way `_set_file` validates
+    /// a local file and returns it as an in-memory upload part.
+    async fn _set_remote_file<U: IntoUrl + Send>(
+        &mut self,
+        url: U,
+    ) -> Result<Part, Box<dyn std::error::Error + Send + Sync>> {
+        let (file_name, bytes) = self
+            ._download(url, Some(Audio::MAX_FILE_SIZE_BYTES))
+            .await?;
+        self.config.file = file_name.clone();
+        if self._is_valid_mime_time().is_err() {
+            return Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Invalid audio file type. Supported types are {:?}",
+                    Audio::get_supported_file_types()
+                ),
+            )));
+        }
+        Self::_in_memory_part(file_name, bytes)
+    }
+
+    fn _form_builder(&mut self, file_part: Part) -> Form {
+        let mut form = Form::new().part("file", file_part);
+        form = form.text("model", self.config.model.clone());
+
+        if let Some(prompt) = self.config.prompt.as_ref() {
+            form = form.text("prompt", prompt.clone());
+        }
+
+        if let Some(response_format) = self.config.response_format.as_ref() {
+            form = form.text("response_format", response_format.to_string());
+        }
+
+        if let Some(temp) = self.config.temperature {
+            form = form.text("temperature", temp.to_string());
+        }
+        form
+    }
+
+    /// Transcribe an audio file.
+    ///
+    /// # Arguments
+    ///
+    /// * `audio_file` - The path to the audio file to transcribe.
+    ///
+    /// # Returns
+    ///
+    /// `Result<AudioResponse, Box<dyn std::error::Error + Send + Sync>>`:
+    /// An `AudioResponse` object representing the transcription of the audio file,
+    /// or an error if the request fails.
+    pub async fn transcribe<P: AsRef<Path> + Sync + Send>(
+        &mut self,
+        audio_file: P,
+    ) -> Result<AudioResponse, Box<dyn std::error::Error + Send + Sync>> {
+        self._set_file(audio_file)?;
+        self._sanity_checks()?;
+        let file_part = self
+            .create_file_upload_part(self.config.file.clone())
+            .await?;
+        self._transcribe(file_part).await
+    }
+
+    /// Transcribe a remote audio file.
+    ///
+    /// The file is downloaded without sending any `OpenAI` credentials, validated like a local
+    /// file and then transcribed.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the audio file to transcribe.
+    ///
+    /// # Returns
+    ///
+    /// `Result<AudioResponse, Box<dyn std::error::Error + Send + Sync>>`:
+    /// An `AudioResponse` object representing the transcription of the audio file,
+    /// a `DownloadError` if the file could not be downloaded, or an error if the request fails.
+    pub async fn transcribe_url<U: IntoUrl + Send>(
+        &mut self,
+        url: U,
+    ) -> Result<AudioResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let file_part = self._set_remote_file(url).await?;
+        self._sanity_checks()?;
+        self._transcribe(file_part).await
+    }
+
+    /// Transcribe an audio file with timestamps, e.g. to create subtitles.
+    ///
+    /// The transcription is requested as `AudioResponseFormat::VerboseJson`, regardless of the
+    /// configured response format, which is left untouched.
+    ///
+    /// # Arguments
+    ///
+    /// * `audio_file` - The path to the audio file to transcribe.
+    ///
+    /// # Returns
+    ///
+    /// `Result<AudioVerboseResponse, Box<dyn std::error::Error + Send + Sync>>`:
+    /// An `AudioVerboseResponse` object with the language, duration and timestamped segments of
+    /// the audio file, or an error if the request fails.
+    pub async fn transcribe_verbose<P: AsRef<Path> + Sync + Send>(
+        &mut self,
+        audio_file: P,
+    ) -> Result<AudioVerboseResponse, Box<dyn std::error::Error + Send + Sync>> {
+        self._set_file(audio_file)?;
+        self._sanity_checks()?;
+        let file_part = self
+            .create_file_upload_part(self.config.file.clone())
+            .await?;
+        let format = self
+            .config
+            .response_format
+            .replace(AudioResponseFormat::VerboseJson);
+        let transcription = self._transcribe(file_part).await;
+        self.config.response_format = format;
+        transcription
+    }
+
+    async fn _transcribe<T: DeserializeOwned>(
+        &mut self,
+        file_part: Part,
+    ) -> Result<T, Box<dyn std::error::Error + Send + Sync>> {
+        let mut form = self._form_builder(file_part);
+
+        if let Some(lang) = self.config.language.clone() {
+            form = form.text("language", lang);
+        }
+
+        if let Some(granularities) = self.config.timestamp_granularities.as_ref() {
+            if !matches!(
+                self.config.response_format,
+                Some(AudioResponseFormat::VerboseJson)
+            ) {
+                return Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "Timestamp granularities require the response format verbose_json, use transcribe_verbose",
+                )));
+            }
+            for granularity in granularities {
+                form = form.text("timestamp_granularities[]", granularity.as_str());
+            }
+        }
+
+        let res: reqwest::Response = self
+            ._make_form_request(self._url(Self::OPENAI_API_TRANSCRIPTION_URL), form)
+            .await?;
+
+        let handled_res = self.handle_api_errors(res).await?;
+        self._parse_json(handled_res).await
+    }
+
+    /// Translate an audio file. Currently only supports translating
+    /// to English.
+    ///
+    /// # Arguments
+    ///
+    /// * `audio_file` - The path to the audio file to translate.
+    ///
+    /// # Returns
+    ///
+    /// `Result<AudioResponse, Box<dyn std::error::Error + Send + Sync>>`:
+    /// An `AudioResponse` object representing the translation of the audio file,
+    /// or an error if the request fails.
+    pub async fn translate<P: AsRef<Path> + Send + Sync>(
+        &mut self,
+        audio_file: P,
+    ) -> Result<AudioResponse, Box<dyn std::error::Error + Send + Sync>> {
+        self._set_file(audio_file)?;
+        self._sanity_checks()?;
+        if self.config.language.is_some() {
+            self._reject_if_strict(
+                "Translations are always into English, a language must not be set".into(),
+            )?;
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                language = ?self.config.language,
+                "Translations are always into English, ignoring the language"
+            );
+            self.config.language = None;
+        }
+        let file_part = self
+            .create_file_upload_part(self.config.file.clone())
+            .await?;
+        let form = self._form_builder(file_part);
+        let res: reqwest::Response = self
+            ._make_form_request(self._url(Self::OPENAI_API_TRANSLATION_URL), form)
+            .await?;
+        let handled_res = self.handle_api_errors(res).await?;
+        let translation: AudioResponse = self._parse_json(handled_res).await?;
+        Ok(translation)
+    }
+}
+
+#[cfg(feature = "audio")]
+impl OpenAI<Speech> {
+    const OPENAI_API_SPEECH_URL: &str = "https://api.openai.com/v1/audio/speech";
+
+    /// Sets the model of the AI assistant.
+    ///
+    /// # Arguments
+    ///
+    /// * `model`: A string that specifies the model name to be used by the AI assistant.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified model.
+    pub fn set_model<S: Into<String>>(mut self, model: S) -> Self {
+        self.config.model = model.into();
+        self
+    }
+
+    /// Sets the voice the audio is generated with.
+    ///
+    /// # Arguments
+    ///
+    /// * `voice`: A `Voice` that specifies the voice to use.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified voice.
+    pub fn set_voice(mut self, voice: Voice) -> Self {
+        self.config.voice = voice;
+        self
+    }
+
+    /// Sets the format of the generated audio.
+    ///
+    /// # Arguments
+    ///
+    /// * `format`: A `SpeechResponseFormat` that specifies the audio format.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified audio format.
+    pub fn set_response_format(mut self, format: SpeechResponseFormat) -> Self {
+        self.config.response_format = Some(format);
+        self
+    }
+
+    /// Sets the speed of the generated audio.
+    ///
+    /// # Arguments
+    ///
+    /// * `speed`: The speed, between 0.25 and 4.0.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified speed.
+    pub fn set_speed(mut self, speed: f64) -> Self {
+        self.config.speed = Some(speed);
+        self
+    }
+
+    fn _sanity_checks(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if self.config.input.chars().count() > Speech::MAX_INPUT_CHARS {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Input exceeds the maximum length of {} characters",
+                    Speech::MAX_INPUT_CHARS
+                ),
+            )));
+        }
+        if let Some(speed) = self.config.speed {
+            if !Speech::SPEED_RANGE.contains(&speed) {
+                return Err(Box::new(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid speed: {speed}, must be between 0.25 and 4.0"),
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Generates spoken audio from the given text.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to generate audio for, with at most 4096 characters.
+    ///
+    /// # Returns
+    ///
+    /// `Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>>`:
+    /// The raw audio in the configured format, or an error if the request fails.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aionic::openai::{OpenAI, Speech, Voice};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///     let mut client = OpenAI::<Speech>::new().set_voice(Voice::Nova);
+    ///     let audio = client.speak("Hello, world!").await?;
+    ///     println!("Received {} bytes of audio", audio.len());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn speak<S: Into<String> + Send>(
+        &mut self,
+        text: S,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        self.config.input = text.into();
+        self._sanity_checks()?;
+        let res: reqwest::Response = self
+            ._make_post_request(self._url(Self::OPENAI_API_SPEECH_URL))
+            .await?;
+        let mut handled_res = self.handle_api_errors(res).await?;
+        let pending = rt::take::<PendingMetrics>(&mut handled_res);
+        let audio = handled_res.bytes().await?.to_vec();
+        self._report_metrics(pending, None, None, None);
+        Ok(audio)
+    }
+
+    /// Generates spoken audio from the given text and writes it to a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to generate audio for, with at most 4096 characters.
+    /// * `path` - The path of the file to write the audio to. It is created or truncated.
+    ///
+    /// # Returns
+    ///
+    /// `Result<(), Box<dyn std::error::Error + Send + Sync>>`:
+    /// An error if the request fails or the file cannot be written.
+    pub async fn speak_to_file<S: Into<String> + Send, P: AsRef<Path> + Send + Sync>(
+        &mut self,
+        text: S,
+        path: P,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let audio = self.speak(text).await?;
+        tokio::fs::write(path, audio).await?;
+        Ok(())
+    }
+}
+
+// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
+// = OpenAI FILES IMPLEMENTATION
+// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
+
+#[cfg(feature = "files")]
+impl OpenAI<Files> {
+    const OPENAI_API_LIST_FILES_URL: &str = "https://api.openai.com/v1/files";
+
+    /// List the first page of files that have been uploaded.
+    ///
+    /// # Returns
+    ///
+    /// `Result<FileResponse, Box<dyn std::error::Error + Send + Sync>>`:
+    /// A `FileResponse` object representing the uploaded files,
+    /// or an error if the request fails.
+    pub async fn list(&self) -> Result<FileResponse, Box<dyn std::error::Error + Send + Sync>> {
+        self.list_with_params(&ListParams::default()).await
+    }
+
+    /// List a page of files that have been uploaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The cursor parameters, i.e. the page size and the ID of the last file of
+    ///   the previous page.
+    ///
+    /// # Returns
+    ///
+    /// `Result<FileResponse, Box<dyn std::error::Error + Send + Sync>>`:
+    /// A `FileResponse` object holding the files and whether there are more,
+    /// or an error if the request fails.
+    pub async fn list_with_params(
+        &self,
+        params: &ListParams,
+    ) -> Result<FileResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let url = self._list_url(&self._url(Self::OPENAI_API_LIST_FILES_URL), params)?;
+        let res: reqwest::Response = self._make_get_request(url).await?;
+        let handled_res = self.handle_api_errors(res).await?;
+        let files: FileResponse = self._parse_json(handled_res).await?;
+        Ok(files)
+    }
+
+    /// List all files that have been uploaded, following the cursor from page to page.
+    ///
+    /// # Returns
+    ///
+    /// `Result<Vec<FileData>, Box<dyn std::error::Error + Send + Sync>>`:
+    /// The files of all pages, or an error if a request fails.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aionic::openai::{Files, OpenAI};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///     let client = OpenAI::<Files>::new();
+    ///     for file in client.list_all().await? {
+    ///         println!("{}: {}", file.id, file.filename);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn list_all(
+        &self,
+    ) -> Result<Vec<FileData>, Box<dyn std::error::Error + Send + Sync>> {
+        self._list_all::<FileResponse>(&self._url(Self::OPENAI_API_LIST_FILES_URL))
+            .await
+    }
+
+    /// Retrieve the details of a specific file.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_id` - A string that holds the unique id of the file.
+    ///
+    /// # Returns
+    ///
+    /// `Result<FileData, Box<dyn std::error::Error + Send + Sync>>`:
+    /// A `FileData` object representing the file's details,
+    /// or an error if the request fails.
+    pub async fn retrieve<S: Into<String> + std::fmt::Display + Sync + Send>(
+        &self,
+        file_id: S,
+    ) -> Result<FileData, Box<dyn std::error::Error + Send + Sync>> {
+        let res: reqwest::Response = self
+            ._make_get_request(format!(
+                "{}/{}",
+                self._url(Self::OPENAI_API_LIST_FILES_URL),
+                file_id
+            ))
+            .await?;
+
+        let handled_res = self.handle_api_errors(res).await?;
+        let file: FileData = self._parse_json(handled_res).await?;
+        Ok(file)
+    }
+
+    /// Retrieve the content of a specific file.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_id` - A string that holds the unique id of the file.
+    ///
+    /// # Returns
+    ///
+    /// `Result<FileData, Box<dyn std::error::Error + Send + Sync>>`:
+    /// A `FileData` object representing the file's content,
+    /// or an error if the request fails.
+    pub async fn retrieve_content<S: Into<String> + std::fmt::Display + Send + Sync>(
+        &self,
+        file_id: S,
+    ) -> Result<Vec<PromptCompletion>, Box<dyn std::error::Error + Send + Sync>> {
+        let res = self
+            ._make_get_request(format!(
+                "{}/{}/content",
+                self._url(Self::OPENAI_API_LIST_FILES_URL),
+                file_id
+            ))
+            .await?;
+
+        let mut handled_res = self.handle_api_errors(res).await?;
+        let pending = rt::take::<PendingMetrics>(&mut handled_res);
+        let content = handled_res.text().await?;
+        self._report_metrics(pending, None, None, None);
+        let files: Vec<PromptCompletion> = content
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<Vec<PromptCompletion>, _>>()?;
+        Ok(files)
+    }
+
+    /// Upload a `.jsonl` file with fine-tuning training data to the `OpenAI` API.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The path to the file to upload.
+    ///
+    /// # Returns
+    ///
+    /// `Result<FileData, Box<dyn std::error::Error + Send + Sync>>`:
+    /// A `FileData` object representing the uploaded file's details,
+    /// or an error if the request fails.
+    pub async fn upload<P: AsRef<Path> + Send + Sync>(
+        &mut self,
+        file: P,
+    ) -> Result<FileData, Box<dyn std::error::Error + Send + Sync>> {
+        self.upload_with_purpose(file, FilePurpose::FineTune).await
+    }
+
+    /// Upload a file for the given purpose to the `OpenAI` API.
+    ///
+    /// Files for fine-tuning must be `.jsonl` files, other purposes accept any file type.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The path to the file to upload.
+    /// * `purpose` - The intended purpose of the file, e.g. `FilePurpose::Assistants`.
+    ///
+    /// # Returns
+    ///
+    /// `Result<FileData, Box<dyn std::error::Error + Send + Sync>>`:
+    /// A `FileData` object representing the uploaded file's details,
+    /// or an error if the request fails.
+    pub async fn upload_with_purpose<P: AsRef<Path> + Send + Sync>(
+        &mut self,
+        file: P,
+        purpose: FilePurpose,
+    ) -> Result<FileData, Box<dyn std::error::Error + Send + Sync>> {
+        let path = file.as_ref();
+        if fs::metadata(path)?.is_file() {
+            let path_str = path.to_str().ok_or("Path is not valid UTF-8")?;
+            if purpose == FilePurpose::FineTune
+                && !path
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("jsonl"))
+            {
+                return Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("File must be a .jsonl file: {}", path.display()),
+                )));
+            }
+            self.config.file = Some(path_str.to_string());
+            self.config.purpose = Some(purpose.as_str().to_string());
+        } else {
+            return Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Path is not a file: {}", path.display()),
+            )));
+        }
+
+        let file_part_stream = self.create_file_upload_part(file).await?;
+        let mut form = Form::new().part("file", file_part_stream);
+        form = form.text("purpose", purpose.as_str());
+        let res: reqwest::Response = self
+            ._make_form_request(self._url(Self::OPENAI_API_LIST_FILES_URL), form)
+            .await?;
+
+        let handled_res = self.handle_api_errors(res).await?;
+        let file_data: FileData = self._parse_json(handled_res).await?;
+        Ok(file_data)
+    }
+
+    /// Delete a specific file.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_id` - A string that holds the unique id of the file.
+    ///
+    /// # Returns
+    ///
+    /// `Result<DeleteResponse, Box<dyn std::error::Error + Send + Sync>>`:
+    /// A `DeleteResponse` object representing the response from the delete request,
+    /// or an error if the request fails.
+    pub async fn delete<S: Into<String> + std::fmt::Display + Send + Sync>(
+        &self,
+        file_id: S,
+    ) -> Result<DeleteResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let res: reqwest::Response = self
+            ._make_delete_request(format!(
+                "{}/{}",
+                self._url(Self::OPENAI_API_LIST_FILES_URL),
+                file_id
+            ))
+            .await?;
+
+        let handled_res = self.handle_api_errors(res).await?;
+        let del_resp: DeleteResponse = self._parse_json(handled_res).await?;
+        Ok(del_resp)
+    }
+}
+
+// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
+// = OpenAI FINE-TUNE IMPLEMENTATION
+// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
+
+#[cfg(feature = "fine-tunes")]
+#[allow(deprecated)]
+impl OpenAI<FineTune> {
+    const OPENAI_API_FINE_TUNE_URL: &str = "https://api.openai.com/v1/fine-tunes";
+
+    /// Create a fine-tune from an uploaded `training_file`.
+    ///
+    /// # Arguments
+    ///
+    /// * `training_file` - A string that holds the unique id of the file.
+    ///
+    /// # Returns
+    ///
+    /// `Result<FineTuneResponse, Box<dyn std::error::Error + Send + Sync>>`:
+    /// A `FineTuneResponse` object representing the result of the fine-tune request,
+    /// or an error if the request fails.
+    pub async fn create<S: Into<String> + Send + Sync>(
+        &mut self,
+        training_file: S,
+    ) -> Result<FineTuneResponse, Box<dyn std::error::Error + Send + Sync>> {
+        self.config.training_file = training_file.into();
+        let res: reqwest::Response = self
+            ._make_post_request(self._url(Self::OPENAI_API_FINE_TUNE_URL))
+            .await?;
+
+        let handled_res = self.handle_api_errors(res).await?;
+        let fine_tune_resp: FineTuneResponse = self._parse_json(handled_res).await?;
+        Ok(fine_tune_resp)
+    }
+
+    /// List the first page of fine-tunes.
+    ///
+    /// # Returns
+    ///
+    /// `Result<FineTuneListResponse, Box<dyn std::error::Error + Send + Sync>>`:
+    /// A `FineTuneResponse` object representing the result of the list fine-tunes request,
+    /// or an error if the request fails.
+    pub async fn list(
+        &self,
+    ) -> Result<FineTuneListResponse, Box<dyn std::error::Error + Send + Sync>> {
+        self.list_with_params(&ListParams::default()).await
+    }
+
+    /// List a page of fine-tunes.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The cursor parameters, i.e. the page size and the ID of the last fine-tune
+    ///   of the previous page.
+    ///
+    /// # Returns
+    ///
+    /// `Result<FineTuneListResponse, Box<dyn std::error::Error + Send + Sync>>`:
+    /// A `FineTuneListResponse` object holding the fine-tunes and whether there are more,
+    /// or an error if the request fails.
+    pub async fn list_with_params(
+        &self,
+        params: &ListParams,
+    ) -> Result<FineTuneListResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let url = self._list_url(&self._url(Self::OPENAI_API_FINE_TUNE_URL), params)?;
+        let res: reqwest::Response = self._make_get_request(url).await?;
+
+        let handled_res = self.handle_api_errors(res).await?;
+        let res: FineTuneListResponse = self._parse_json(handled_res).await?;
+        Ok(res)
+    }
+
+    /// List all fine-tunes, following the cursor from page to page.
+    ///
+    /// # Returns
+    ///
+    /// `Result<Vec<FineTuneListSummary>, Box<dyn std::error::Error + Send + Sync>>`:
+    /// The fine-tunes of all pages, or an error if a request fails.
+    pub async fn list_all(
+        &self,
+    ) -> Result<Vec<FineTuneListSummary>, Box<dyn std::error::Error + Send + Sync>> {
+        self._list_all::<FineTuneListResponse>(&self._url(Self::OPENAI_API_FINE_TUNE_URL))
+            .await
+    }
+
+    /// Get a specific fine-tune by its id
+    ///
+    /// # Arguments
+    ///
+    /// * `fine_tune_id` - A string that holds the unique id of the file.
+    ///
+    /// # Returns
+    ///
+    /// `Result<FineTuneResponse, Box<dyn std::error::Error + Send + Sync>>`:
+    /// A `FineTuneResponse` object representing the result of the get fine-tune request,
+    /// or an error if the request fails.
+    pub async fn retrieve<S: Into<String> + Send + Sync + std::fmt::Display>(
+        &self,
+        fine_tune_id: S,
+    ) -> Result<FineTuneResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let res: reqwest::Response = self
+            ._make_get_request(format!(
+                "{}/{}",
+                self._url(Self::OPENAI_API_FINE_TUNE_URL),
+                fine_tune_id
+            ))
+            .await?;
+
+        let handled_res = self.handle_api_errors(res).await?;
+        let res: FineTuneResponse = self._parse_json(handled_res).await?;
+        Ok(res)
+    }
+
+    /// Immediately cancel a fine-tune job.
+    ///
+    /// # Arguments
+    ///
+    /// * `fine_tune_id` - A string that holds the unique id of the file.
+    ///
+    /// # Returns
+    ///
+    /// `Result<FineTuneResponse, Box<dyn std::error::Error + Send + Sync>>`:
+    /// A `FineTuneResponse` object representing the result of the cancel fine-tune request,
+    /// or an error if the request fails.
+    pub async fn cancel<S: Into<String> + Send + Sync + std::fmt::Display>(
+        &self,
+        fine_tune_id: S,
+    ) -> Result<FineTuneResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!(
+            "{}/{}/cancel",
+            self._url(Self::OPENAI_API_FINE_TUNE_URL),
+            fine_tune_id
+        );
+        let req = self
+            ._build_request(Method::POST, url)
+            .header("Content-Type", "application/json");
+        let res = self._send(req).await?;
+        self._record_response(&res);
+
+        let handled_res = self.handle_api_errors(res).await?;
+        let res: FineTuneResponse = self._parse_json(handled_res).await?;
+        Ok(res)
+    }
+
+    /// Get fine-grained status updates for a fine-tune job.
+    ///
+    /// # Arguments
+    ///
+    /// * `fine_tune_id` - A string that holds the unique id of the file.
+    ///
+    /// # Returns
+    ///
+    /// `Result<FineTuneEventResponse, Box<dyn std::error::Error + Send + Sync>>`:
+    /// A `FineTuneEventResponse` object representing the result of the list fine-tunes request,
+    /// or an error if the request fails.
+    pub async fn list_events<S: Into<String> + Send + Sync + std::fmt::Display>(
+        &self,
+        fine_tune_id: S,
+    ) -> Result<FineTuneEventResponse, Box<dyn std::error::Error + Send + Sync>> {
+        self.list_events_with_params(fine_tune_id, &ListParams::default())
+            .await
+    }
+
+    /// Get a page of fine-grained status updates for a fine-tune job.
+    ///
+    /// # Arguments
+    ///
+    /// * `fine_tune_id` - A string that holds the unique id of the file.
+    /// * `params` - The cursor parameters, i.e. the page size and where to start.
+    ///
+    /// # Returns
+    ///
+    /// `Result<FineTuneEventResponse, Box<dyn std::error::Error + Send + Sync>>`:
+    /// A `FineTuneEventResponse` object holding the events and whether there are more,
+    /// or an error if the request fails.
+    pub async fn list_events_with_params<S: Into<String> + Send + Sync + std::fmt::Display>(
+        &self,
+        fine_tune_id: S,
+        params: &ListParams,
+    ) -> Result<FineTuneEventResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!(
+            "{}/{}/events",
+            self._url(Self::OPENAI_API_FINE_TUNE_URL),
+            fine_tune_id
+        );
+        let url = self._list_url(&url, params)?;
+        let res = self._make_get_request(url).await?;
+
+        let handled_res = self.handle_api_errors(res).await?;
+        let res: FineTuneEventResponse = self._parse_json(handled_res).await?;
+        Ok(res)
+    }
+
+    /// Watch the events of a fine-tune job while it runs, polling every
+    /// `FineTune::DEFAULT_EVENT_POLL_INTERVAL`.
+    ///
+    /// See `stream_events_with_interval` for details.
+    ///
+    /// # Arguments
+    ///
+    /// * `fine_tune_id` - A string that holds the unique id of the fine-tune job.
+    ///
+    /// # Returns
+    ///
+    /// A stream of the events of the job, or of the error a poll failed with.
+    pub fn stream_events<S: Into<String>>(
+        &self,
+        fine_tune_id: S,
+    ) -> impl Stream<Item = Result<FineTuneEvent, Box<dyn std::error::Error + Send + Sync>>> + '_
+    {
+        self.stream_events_with_interval(fine_tune_id, FineTune::DEFAULT_EVENT_POLL_INTERVAL)
+    }
+
+    /// Watch the events of a fine-tune job while it runs.
+    ///
+    /// The events are polled every `interval` and only events that were not seen before are
+    /// yielded, in the order they were created. The stream ends once the job has reached a
+    /// terminal status, i.e. `succeeded`, `failed` or `cancelled`, and all of its events have
+    /// been yielded. As every poll retrieves the status before it lists the events, the events
+    /// created right before the job finished are not missed. The stream also ends after
+    /// yielding an error, if a poll fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `fine_tune_id` - A string that holds the unique id of the fine-tune job.
+    /// * `interval` - The time to wait between two polls.
+    ///
+    /// # Returns
+    ///
+    /// A stream of the events of the job, or of the error a poll failed with.
+    pub fn stream_events_with_interval<S: Into<String>>(
+        &self,
+        fine_tune_id: S,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<FineTuneEvent, Box<dyn std::error::Error + Send + Sync>>> + '_
+    {
+        struct State {
+            fine_tune_id: String,
+            pending: VecDeque<FineTuneEvent>,
+            seen: HashSet<(u64, String)>,
+            polled: bool,
+            finished: bool,
+        }
+
+        let state = State {
+            fine_tune_id: fine_tune_id.into(),
+            pending: VecDeque::new(),
+            seen: HashSet::new(),
+            polled: false,
+            finished: false,
+        };
+        futures_util::stream::unfold(state, move |mut state| async move {
+            loop {
+                if let Some(event) = state.pending.pop_front() {
+                    return Some((Ok(event), state));
+                }
+                if state.finished {
+                    return None;
+                }
+                let wait = state.polled;
+                state.polled = true;
+                let poll = async {
+                    if wait {
+                        self._cancellable(async {
+                            rt::sleep(interval).await;
+                            Ok(())
+                        })
+                        .await?;
+                    }
+                    let job = self.retrieve(state.fine_tune_id.as_str()).await?;
+                    let events = self.list_events(state.fine_tune_id.as_str()).await?;
+                    Ok::<_, Box<dyn std::error::Error + Send + Sync>>((events, job.status))
+                };
+                match poll.await {
+                    Ok((mut events, status)) => {
+                        events.data.sort_by_key(|event| event.created_at);
+                        for event in events.data {
+                            if state.seen.insert((event.created_at, event.message.clone())) {
+                                state.pending.push_back(event);
+                            }
+                        }
+                        state.finished = FineTuningJob::is_terminal_status(&status);
+                    }
+                    Err(e) => {
+                        state.finished = true;
+                        return Some((Err(e), state));
+                    }
+                }
+            }
+        })
+    }
+
+    /// Delete a fine-tuned model. You must have the Owner role in your organization.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - The model to delete
+    ///
+    /// # Returns
+    ///
+    /// `Result<DeleteResponse, Box<dyn std::error::Error + Send + Sync>>`:
+    /// A `DeleteResponse` object representing the status of the delete request,
+    /// or an error if the request fails.
+    pub async fn delete_model<S: Into<String> + Send + Sync + std::fmt::Display>(
+        &self,
+        model: S,
+    ) -> Result<DeleteResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!("{}/{}", self._url(Self::OPENAI_API_MODELS_URL), model);
+        let res = self._make_delete_request(url).await?;
+
+        let handled_res = self.handle_api_errors(res).await?;
+        let res: DeleteResponse = self._parse_json(handled_res).await?;
+        Ok(res)
+    }
+}
+
+// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
+// = OpenAI FINE-TUNING JOBS IMPLEMENTATION
+// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
+
+#[cfg(feature = "fine-tunes")]
+impl OpenAI<FineTuningJob> {
+    const OPENAI_API_FINE_TUNING_JOBS_URL: &str = "https://api.openai.com/v1/fine_tuning/jobs";
+
+    /// Sets the model to fine-tune.
+    ///
+    /// # Arguments
+    ///
+    /// * `model`: A string that specifies the name of the base model, e.g. "gpt-3.5-turbo".
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified model.
+    pub fn set_model<S: Into<String>>(mut self, model: S) -> Self {
+        self.config.model = model.into();
+        self
+    }
+
+    /// Sets the uploaded file that contains validation data.
+    ///
+    /// # Arguments
+    ///
+    /// * `validation_file`: A string that holds the unique id of the file.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified validation file.
+    pub fn set_validation_file<S: Into<String>>(mut self, validation_file: S) -> Self {
+        self.config.validation_file = Some(validation_file.into());
+        self
+    }
+
+    /// Sets the number of epochs to train for, instead of letting the API choose it.
+    ///
+    /// # Arguments
+    ///
+    /// * `n_epochs`: The number of epochs.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified number of epochs.
+    pub fn set_n_epochs(mut self, n_epochs: u64) -> Self {
+        self.config
+            .hyperparameters
+            .get_or_insert_with(Default::default)
+            .n_epochs = Some(n_epochs.into());
+        self
+    }
+
+    /// Sets the suffix that is added to the name of the fine-tuned model.
+    ///
+    /// # Arguments
+    ///
+    /// * `suffix`: A string of up to 18 characters.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified suffix.
+    pub fn set_suffix<S: Into<String>>(mut self, suffix: S) -> Self {
+        self.config.suffix = Some(suffix.into());
+        self
+    }
+
+    /// Create a fine-tuning job from an uploaded `training_file`.
+    ///
+    /// # Arguments
+    ///
+    /// * `training_file` - A string that holds the unique id of the file.
+    ///
+    /// # Returns
+    ///
+    /// `Result<FineTuningJobResponse, Box<dyn std::error::Error + Send + Sync>>`:
+    /// A `FineTuningJobResponse` object representing the created job,
+    /// or an error if the request fails.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aionic::openai::{FineTuningJob, OpenAI};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///     let mut client = OpenAI::<FineTuningJob>::new()
+    ///         .set_model("gpt-3.5-turbo")
+    ///         .set_n_epochs(3);
+    ///     let job = client.create("file-abc123").await?;
+    ///     println!("{}: {}", job.id, job.status);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn create<S: Into<String> + Send + Sync>(
+        &mut self,
+        training_file: S,
+    ) -> Result<FineTuningJobResponse, Box<dyn std::error::Error + Send + Sync>> {
+        self.config.training_file = training_file.into();
+        let res: reqwest::Response = self
+            ._make_post_request(self._url(Self::OPENAI_API_FINE_TUNING_JOBS_URL))
+            .await?;
+
+        let handled_res = self.handle_api_errors(res).await?;
+        let job: FineTuningJobResponse = self._parse_json(handled_res).await?;
+        Ok(job)
+    }
+
+    /// List the first page of fine-tuning jobs.
+    ///
+    /// # Returns
+    ///
+    /// `Result<FineTuningJobList, Box<dyn std::error::Error + Send + Sync>>`:
+    /// A `FineTuningJobList` object holding the jobs, or an error if the request fails.
+    pub async fn list(
+        &self,
+    ) -> Result<FineTuningJobList, Box<dyn std::error::Error + Send + Sync>> {
+        self.list_with_params(&ListParams::default()).await
+    }
+
+    /// List a page of fine-tuning jobs.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The cursor parameters, i.e. the page size and the ID of the last job of
+    ///   the previous page.
+    ///
+    /// # Returns
+    ///
+    /// `Result<FineTuningJobList, Box<dyn std::error::Error + Send + Sync>>`:
+    /// A `FineTuningJobList` object holding the jobs and whether there are more,
+    /// or an error if the request fails.
+    pub async fn list_with_params(
+        &self,
+        params: &ListParams,
+    ) -> Result<FineTuningJobList, Box<dyn std::error::Error + Send + Sync>> {
+        let url = self._list_url(&self._url(Self::OPENAI_API_FINE_TUNING_JOBS_URL), params)?;
+        let res = self._make_get_request(url).await?;
+
+        let handled_res = self.handle_api_errors(res).await?;
+        let res: FineTuningJobList = self._parse_json(handled_res).await?;
+        Ok(res)
+    }
+
+    /// List all fine-tuning jobs, following the cursor from page to page.
+    ///
+    /// # Returns
+    ///
+    /// `Result<Vec<FineTuningJobResponse>, Box<dyn std::error::Error + Send + Sync>>`:
+    /// The jobs of all pages, or an error if a request fails.
+    pub async fn list_all(
+        &self,
+    ) -> Result<Vec<FineTuningJobResponse>, Box<dyn std::error::Error + Send + Sync>> {
+        self._list_all::<FineTuningJobList>(&self._url(Self::OPENAI_API_FINE_TUNING_JOBS_URL))
+            .await
+    }
+
+    /// Get a specific fine-tuning job by its id.
+    ///
+    /// # Arguments
+    ///
+    /// * `job_id` - A string that holds the unique id of the job.
+    ///
+    /// # Returns
+    ///
+    /// `Result<FineTuningJobResponse, Box<dyn std::error::Error + Send + Sync>>`:
+    /// A `FineTuningJobResponse` object representing the job, or an error if the request fails.
+    pub async fn retrieve<S: Into<String> + Send + Sync + std::fmt::Display>(
+        &self,
+        job_id: S,
+    ) -> Result<FineTuningJobResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!(
+            "{}/{}",
+            self._url(Self::OPENAI_API_FINE_TUNING_JOBS_URL),
+            job_id
+        );
+        let res = self._make_get_request(url).await?;
+
+        let handled_res = self.handle_api_errors(res).await?;
+        let res: FineTuningJobResponse = self._parse_json(handled_res).await?;
+        Ok(res)
+    }
+
+    /// Immediately cancel a fine-tuning job.
+    ///
+    /// # Arguments
+    ///
+    /// * `job_id` - A string that holds the unique id of the job.
+    ///
+    /// # Returns
+    ///
+    /// `Result<FineTuningJobResponse, Box<dyn std::error::Error + Send + Sync>>`:
+    /// A `FineTuningJobResponse` object representing the cancelled job,
+    /// or an error if the request fails.
+    pub async fn cancel<S: Into<String> + Send + Sync + std::fmt::Display>(
+        &self,
+        job_id: S,
+    ) -> Result<FineTuningJobResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!(
+            "{}/{}/cancel",
+            self._url(Self::OPENAI_API_FINE_TUNING_JOBS_URL),
+            job_id
+        );
+        let req = self
+            ._build_request(Method::POST, url)
+            .header("Content-Type", "application/json");
+        let res = self._send(req).await?;
+        self._record_response(&res);
+
+        let handled_res = self.handle_api_errors(res).await?;
+        let res: FineTuningJobResponse = self._parse_json(handled_res).await?;
+        Ok(res)
+    }
+
+    /// List the first page of status updates of a fine-tuning job.
+    ///
+    /// # Arguments
+    ///
+    /// * `job_id` - A string that holds the unique id of the job.
+    ///
+    /// # Returns
+    ///
+    /// `Result<FineTuningJobEventList, Box<dyn std::error::Error + Send + Sync>>`:
+    /// A `FineTuningJobEventList` object holding the events, or an error if the request fails.
+    pub async fn list_events<S: Into<String> + Send + Sync + std::fmt::Display>(
+        &self,
+        job_id: S,
+    ) -> Result<FineTuningJobEventList, Box<dyn std::error::Error + Send + Sync>> {
+        self.list_events_with_params(job_id, &ListParams::default())
+            .await
+    }
+
+    /// List a page of status updates of a fine-tuning job.
+    ///
+    /// # Arguments
+    ///
+    /// * `job_id` - A string that holds the unique id of the job.
+    /// * `params` - The cursor parameters, i.e. the page size and the ID of the last event of
+    ///   the previous page.
+    ///
+    /// # Returns
+    ///
+    /// `Result<FineTuningJobEventList, Box<dyn std::error::Error + Send + Sync>>`:
+    /// A `FineTuningJobEventList` object holding the events and whether there are more,
+    /// or an error if the request fails.
+    pub async fn list_events_with_params<S: Into<String> + Send + Sync + std::fmt::Display>(
+        &self,
+        job_id: S,
+        params: &ListParams,
+    ) -> Result<FineTuningJobEventList, Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!(
+            "{}/{}/events",
+            self._url(Self::OPENAI_API_FINE_TUNING_JOBS_URL),
+            job_id
+        );
+        let url = self._list_url(&url, params)?;
+        let res = self._make_get_request(url).await?;
+
+        let handled_res = self.handle_api_errors(res).await?;
+        let res: FineTuningJobEventList = self._parse_json(handled_res).await?;
+        Ok(res)
+    }
+}
+
+// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
+// = OpenAI MODERATIONS IMPLEMENTATION
+// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
+
+#[cfg(feature = "moderations")]
+impl OpenAI<Moderation> {
+    const OPENAI_API_MODERATIONS_URL: &str = "https://api.openai.com/v1/moderations";
+
+    /// Sets the moderation model, e.g. `Moderation::STABLE_MODEL`.
+    ///
+    /// # Arguments
+    ///
+    /// * `model`: A string that specifies the model name to be used.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the instance of the AI assistant with the specified model.
+    pub fn set_model<S: Into<String>>(mut self, model: S) -> Self {
+        self.config.model = Some(model.into());
+        self
+    }
+
+    /// Create moderation for a classification if text violates `OpenAI`'s Content Policy
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The text input to classify
+    ///
+    /// # Returns
+    ///
+    /// `Result<, Box<dyn std::error::Error + Send + Sync>>`:
+    /// A `ModerationResponse` object representing the result of the moderation request,
+    /// or an error if the request fails.
+    pub async fn moderate<S: Into<String> + Send + Sync>(
+        &self,
+        input: S,
+    ) -> Result<ModerationResponse, Box<dyn std::error::Error + Send + Sync>> {
+        self._moderate(ModerationInput::Single(input.into())).await
+    }
+
+    /// Classifies many texts in a single request.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - The texts to classify.
+    ///
+    /// # Returns
+    ///
+    /// `Result<Vec<ModerationResult>, Box<dyn std::error::Error + Send + Sync>>`:
+    /// One `ModerationResult` per input, in the order of `inputs`, or an error if the request
+    /// fails or the response does not hold a result for every input.
+    pub async fn moderate_many(
+        &self,
+        inputs: Vec<String>,
+    ) -> Result<Vec<ModerationResult>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(self._moderate(ModerationInput::Many(inputs)).await?.results)
+    }
+
+    async fn _moderate(
+        &self,
+        input: ModerationInput,
+    ) -> Result<ModerationResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let expected = input.len();
+        let mut body = self.request_body();
+        body["input"] = serde_json::to_value(input)?;
+        let res: reqwest::Response = self
+            ._make_post_request_with_body(self._url(Self::OPENAI_API_MODERATIONS_URL), &body)
+            .await?;
+
+        let handled_res = self.handle_api_errors(res).await?;
+        let mod_resp: ModerationResponse = self._parse_json(handled_res).await?;
+        if mod_resp.results.len() != expected {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Expected {} moderation results, but the response holds {}",
+                    expected,
+                    mod_resp.results.len()
+                ),
+            )));
+        }
+        Ok(mod_resp)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    #[cfg(feature = "images")]
+    use base64::Engine;
+    #[cfg(feature = "images")]
+    use std::fs;
+
+    #[cfg(any(
+        feature = "chat",
+        feature = "images",
+        feature = "audio",
+        feature = "files",
+        feature = "moderations",
+        feature = "embeddings"
+    ))]
+    async fn mock_endpoint(
+        http_method: &str,
+        endpoint: &str,
+        response: wiremock::ResponseTemplate,
+    ) -> wiremock::MockServer {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer};
+
+        let server = MockServer::start().await;
+        Mock::given(method(http_method))
+            .and(path(endpoint))
+            .respond_with(response)
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[cfg(any(feature = "chat", feature = "files"))]
+    fn api_error(message: &str) -> wiremock::ResponseTemplate {
+        wiremock::ResponseTemplate::new(404).set_body_json(serde_json::json!({"error": {
+            "message": message,
+            "type": "invalid_request_error",
+            "param": null,
+            "code": null
+        }}))
+    }
+
+    #[cfg(any(
+        feature = "chat",
+        feature = "images",
+        feature = "audio",
+        feature = "files",
+        feature = "moderations",
+        feature = "embeddings"
+    ))]
+    fn offline_client<C: OpenAIConfig + Serialize + Sync + Send + std::fmt::Debug>() -> OpenAI<C> {
+        OpenAI::default().with_api_key("sk-test").disable_stdout()
+    }
+
+    #[cfg(feature = "images")]
+    fn image_response(n: usize) -> serde_json::Value {
+        let data: Vec<serde_json::Value> = (0..n)
+            .map(|i| serde_json::json!({"url": format!("https://example.com/{i}.png")}))
+            .collect();
+        serde_json::json!({"created": 0, "data": data})
+    }
+
+    #[cfg(feature = "files")]
+    fn file_data(id: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": id,
+            "object": "file",
+            "bytes": 140,
+            "created_at": 0,
+            "filename": "test.jsonl",
+            "purpose": "fine-tune"
+        })
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_project_header_on_chat_request() {
+        let client = offline_client::<Chat>()
+            .with_organization("org-test")
+            .with_project("proj_test");
+        let req = client
+            ._post_request(OpenAI::<Chat>::OPENAI_API_COMPLETIONS_URL)
+            .build()
+            .unwrap();
+        assert_eq!(req.headers()["OpenAI-Project"], "proj_test");
+        assert_eq!(req.headers()["OpenAI-Organization"], "org-test");
+    }
+
+    #[cfg(feature = "images")]
+    #[test]
+    fn test_project_header_on_image_upload() {
+        let client = offline_client::<Image>().with_project("proj_test");
+        let form = Form::new().text("prompt", "A red circle");
+        let req = client
+            ._form_request(OpenAI::<Image>::OPENAI_API_IMAGE_EDIT_URL, form)
+            .build()
+            .unwrap();
+        assert_eq!(req.headers()["OpenAI-Project"], "proj_test");
+        assert!(!req.headers().contains_key("OpenAI-Organization"));
+    }
+
+    #[cfg(all(feature = "chat", feature = "images"))]
+    #[test]
+    fn test_custom_headers() {
+        let client = offline_client::<Image>()
+            .with_header("X-Team", "search")
+            .unwrap()
+            .with_header("Authorization", "Bearer sk-other")
+            .unwrap()
+            .with_headers(HeaderMap::from_iter([(
+                HeaderName::from_static("x-api-gateway-key"),
+                HeaderValue::from_static("gateway-secret"),
+            )]));
+        assert!(!format!("{client:?}").contains("search"));
+        let form = Form::new().text("prompt", "A red circle");
+        let requests = [
+            client
+                ._form_request(OpenAI::<Image>::OPENAI_API_IMAGE_EDIT_URL, form)
+                .build()
+                .unwrap(),
+            client
+                ._post_request(OpenAI::<Image>::OPENAI_API_IMAGE_GEN_URL)
+                .build()
+                .unwrap(),
+            client
+                ._build_request(Method::GET, "https://api.openai.com/v1/models")
+                .build()
+                .unwrap(),
+        ];
+        for req in requests {
+            assert_eq!(req.headers()["X-Team"], "search");
+            assert_eq!(req.headers()["X-Api-Gateway-Key"], "gateway-secret");
+            let authorization: Vec<_> = req.headers().get_all(AUTHORIZATION).iter().collect();
+            assert_eq!(authorization, ["Bearer sk-test"]);
+        }
+        assert!(offline_client::<Chat>().with_header("X Team", "a").is_err());
+        assert!(offline_client::<Chat>()
+            .with_header("X-Team", "a\nb")
+            .is_err());
+    }
+
+    #[cfg(all(feature = "chat", feature = "images"))]
+    #[test]
+    fn test_user_agent() {
+        let client = offline_client::<Image>();
+        let form = Form::new().text("prompt", "A red circle");
+        let req = client
+            ._form_request(OpenAI::<Image>::OPENAI_API_IMAGE_EDIT_URL, form)
+            .build()
+            .unwrap();
+        assert_eq!(
+            req.headers()[USER_AGENT],
+            format!("aionic/{} (rust)", env!("CARGO_PKG_VERSION"))
+        );
+
+        let user_agent = format!("{} my-app/1.0", OpenAI::<Chat>::DEFAULT_USER_AGENT);
+        let client = offline_client::<Chat>().with_user_agent(user_agent.as_str());
+        let req = client
+            ._post_request(OpenAI::<Chat>::OPENAI_API_COMPLETIONS_URL)
+            .build()
+            .unwrap();
+        assert_eq!(req.headers()[USER_AGENT], user_agent.as_str());
+        assert_eq!(req.headers().get_all(USER_AGENT).iter().count(), 1);
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_system_role_normalization() {
+        let mut client = offline_client::<Chat>()
+            .set_primer("You are a helpful assistant")
+            .set_messages(vec![
+                Message::system("Answer briefly"),
+                Message::new(&MessageRole::Developer, "Answer in English"),
+                Message::new(&MessageRole::User, "Hello"),
+            ]);
+        let roles = |body: serde_json::Value| -> Vec<String> {
+            body["messages"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|m| m["role"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        client.config.model = "o1-mini".into();
+        assert_eq!(
+            roles(client.request_body()),
+            ["developer", "developer", "user"]
+        );
+
+        client.config.model = "gpt-4o".into();
+        assert_eq!(roles(client.request_body()), ["system", "system", "user"]);
+
+        let msg: Message = serde_json::from_str(r#"{"role":"developer","content":"hi"}"#).unwrap();
+        assert_eq!(msg.role, "system");
+    }
+
+    #[cfg(feature = "audio")]
+    #[tokio::test]
+    async fn test_transcribe_url_download_error() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let mut client = offline_client::<Audio>();
+        let err = client
+            .transcribe_url(format!("{}/sample.mp3", server.uri()))
+            .await
+            .unwrap_err();
+        assert!(err.downcast_ref::<DownloadError>().is_some());
+        let req = &server.received_requests().await.unwrap()[0];
+        assert!(!req.headers.contains_key("Authorization"));
+    }
+
+    #[cfg(feature = "images")]
+    #[tokio::test]
+    async fn test_edit_from_url_too_large() {
+        let oversized = vec![0; Image::MAX_FILE_SIZE_BYTES as usize + 1];
+        let server = mock_endpoint(
+            "GET",
+            "/image.png",
+            wiremock::ResponseTemplate::new(200).set_body_raw(oversized, "image/png"),
+        )
+        .await;
+
+        let mut client = offline_client::<Image>();
+        let err = client
+            .edit_from_url("A cat", format!("{}/image.png", server.uri()), None)
+            .await
+            .unwrap_err();
+        let err = err.downcast_ref::<io::Error>().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("exceeds the limit"));
+    }
+
+    #[test]
+    fn test_usage_accumulation() {
+        let chat = Usage {
+            prompt_tokens: 10,
+            completion_tokens: Some(5),
+            total_tokens: 15,
+        };
+        let embedding = Usage {
+            prompt_tokens: 8,
+            completion_tokens: None,
+            total_tokens: 8,
+        };
+
+        let mut total = Usage::zero();
+        total += chat;
+        total += embedding;
+        assert_eq!(total.prompt_tokens, 18);
+        assert_eq!(total.completion_tokens, Some(5));
+        assert_eq!(total.total_tokens, 23);
+        assert_eq!(total, vec![chat, embedding].into_iter().sum());
+        assert_eq!((embedding + embedding).completion_tokens, None);
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_request_timeout() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+            .mount(&server)
+            .await;
+
+        let mut client = offline_client::<Chat>().with_timeout(Duration::from_millis(50));
+        assert!(client._make_get_request(server.uri()).await.is_err());
+
+        client.timeout = None;
+        assert!(client._make_get_request(server.uri()).await.is_ok());
+    }
+
+    #[cfg(feature = "chat")]
+    fn delta_line(content: &str) -> String {
+        format!(
+            "data: {}",
+            serde_json::json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion.chunk",
+                "created": 0,
+                "model": "gpt-3.5-turbo",
+                "choices": [{"index": 0, "delta": {"content": content}, "finish_reason": null}]
+            })
+        )
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_streamed_answer_reserves_once() {
+        let client = offline_client::<Chat>().set_max_tokens(50_000 / 4);
+        let line = delta_line("a");
+        let mut answer = ChatOutcome {
+            text: String::with_capacity(client._answer_capacity()),
+            ..ChatOutcome::default()
+        };
+        let buffer = answer.text.as_ptr();
+        for _ in 0..50_000 {
+            client
+                ._process_delta(&line, &mut answer, &mut None)
+                .unwrap();
+        }
+        assert_eq!(answer.text.len(), 50_000);
+        // The accumulator was never reallocated while streaming 50k chunks
+        assert_eq!(answer.text.as_ptr(), buffer);
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_streamed_lines_split_across_chunks() {
+        let client = offline_client::<Chat>();
+        let body = format!(
+            "{}\n\n{}\n\ndata: [DONE]",
+            delta_line("Hello "),
+            delta_line("Wörld")
+        );
+        let body = body.as_bytes();
+        for offset in 0..=body.len() {
+            let mut buffer = LineBuffer::default();
+            let mut answer = ChatOutcome::default();
+            let (first, second) = body.split_at(offset);
+            let lines = [buffer.push(first), buffer.push(second), buffer.finish()];
+            for line in lines.iter().flatten().flat_map(|lines| lines.split('\n')) {
+                client
+                    ._process_delta(line, &mut answer, &mut Some(&mut |_: &str| {}))
+                    .unwrap();
+            }
+            assert_eq!(answer.text, "Hello Wörld", "split at byte {offset}");
+        }
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_empty_and_filtered_choices() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let filtered = serde_json::json!({
+            "id": "chatcmpl-2",
+            "model": "gpt-3.5-turbo",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": ""},
+                "finish_reason": "content_filter"
+            }]
+        });
+        let response: Response = serde_json::from_value(filtered.clone()).unwrap();
+        let choices = response.choices.unwrap();
+        assert_eq!(choices[0].finish_reason, FinishReason::ContentFilter);
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "chatcmpl-1",
+                "model": "gpt-3.5-turbo",
+                "choices": []
+            })))
+            .mount(&server)
+            .await;
+        let mut client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_stream_responses(false);
+        let err = client.ask("Say nothing", false).await.unwrap_err();
+        assert!(err.to_string().contains("no choices"));
+        let err = client.ask("Say nothing", true).await.unwrap_err();
+        assert!(err.to_string().contains("no choices"));
+        let err = client.ask_many("Say nothing", true).await.unwrap_err();
+        assert!(err.to_string().contains("no choices"));
+        assert!(client.config.messages.is_empty());
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(filtered))
+            .mount(&server)
+            .await;
+        let mut client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_stream_responses(false);
+        let err = client.ask("Say something bad", true).await.unwrap_err();
+        let filtered = err.downcast_ref::<ContentFiltered>().unwrap();
+        assert!(filtered.partial.is_empty());
+        assert!(client.config.messages.is_empty());
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_streamed_whitespace_is_kept() {
+        let completion =
+            "Steps:\n\n1. Install\n2. Run\n\n```rust\nfn main() {\n    println!(\"hi\");\n}\n```\n";
+        let deltas = [
+            "Steps",
+            ":\n\n",
+            "1",
+            ". Install",
+            "\n",
+            "2. ",
+            "Run\n\n",
+            "```rust\n",
+            "fn main() {",
+            "\n    ",
+            "println!(\"hi\");",
+            "\n}\n",
+            "```",
+            "\n",
+        ];
+        assert_eq!(deltas.concat(), completion);
+
+        let client = offline_client::<Chat>();
+        let mut answer = ChatOutcome::default();
+        let mut tokens = String::new();
+        for delta in deltas {
+            client
+                ._process_delta(
+                    &delta_line(delta),
+                    &mut answer,
+                    &mut Some(&mut |token: &str| tokens.push_str(token)),
+                )
+                .unwrap();
+        }
+        assert_eq!(answer.text, completion);
+        assert_eq!(tokens, completion);
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_streamed_answer_size_cap() {
+        let client = offline_client::<Chat>().set_max_response_bytes(10);
+        let mut answer = ChatOutcome::default();
+        client
+            ._process_delta(&delta_line("Hello"), &mut answer, &mut None)
+            .unwrap();
+        let err = client
+            ._process_delta(&delta_line("Wörld!"), &mut answer, &mut None)
+            .unwrap_err();
+        let err = err.downcast_ref::<ResponseTooLarge>().unwrap();
+        assert_eq!(err.max_bytes, 10);
+        assert_eq!(err.partial, "HelloWörl");
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_strict_deserialization() {
+        let body = r#"{"id": "gpt-4", "object": "model", "owned_by": "openai", "tier": "new"}"#;
+        let client = offline_client::<Chat>();
+        assert!(client._deserialize::<Model>(body).is_ok());
+
+        let client = client.with_strict_deserialization();
+        let err = client._deserialize::<Model>(body).unwrap_err();
+        assert_eq!(err.to_string(), "Unknown fields in response: tier");
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_function_call_behavior_serialization() {
+        let mut chat = Chat::default();
+        chat.functions = Some(vec![Function::new(
+            "get_weather",
+            "Get the current weather",
+            serde_json::json!({"type": "object", "properties": {}}),
+        )]);
+        chat.function_call = Some(FunctionCallBehavior::Named("get_weather".into()));
+        let body = serde_json::to_value(&chat).unwrap();
+        assert_eq!(
+            body["function_call"],
+            serde_json::json!({"name": "get_weather"})
+        );
+        assert_eq!(body["functions"][0]["parameters"]["type"], "object");
+
+        let msg: Message = serde_json::from_str(
+            r#"{"role": "assistant", "content": null,
+                "function_call": {"name": "get_weather", "arguments": "{}"}}"#,
+        )
+        .unwrap();
+        assert!(msg.content.is_none());
+        assert_eq!(msg.function_call.unwrap().name, "get_weather");
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_history_access() {
+        let mut client = offline_client::<Chat>();
+        client.config.messages = vec![
+            Message::system("Be brief."),
+            Message::new(&MessageRole::User, "Hi"),
+            Message::new(&MessageRole::Assistant, "Hello"),
+            Message::new(&MessageRole::User, "Bye"),
+        ];
+        assert_eq!(client.messages().len(), 4);
+        let history: &[Message] = client.as_ref();
+        assert_eq!(history[1].content.as_ref().unwrap().to_string(), "Hi");
+        let roles: Vec<&str> = client.iter().map(|msg| msg.role.as_str()).collect();
+        assert_eq!(roles, ["system", "user", "assistant", "user"]);
+
+        client.messages_mut()[1] = Message::new(&MessageRole::User, "Hey");
+        assert_eq!(
+            client.messages()[1].content.as_ref().unwrap().to_string(),
+            "Hey"
+        );
+        assert_eq!(
+            client
+                .pop_last()
+                .unwrap()
+                .content
+                .as_ref()
+                .unwrap()
+                .to_string(),
+            "Bye"
+        );
+        assert_eq!(
+            client
+                .remove_message(0)
+                .unwrap()
+                .content
+                .as_ref()
+                .unwrap()
+                .to_string(),
+            "Be brief."
+        );
+        assert!(client.remove_message(2).is_none());
+        assert_eq!(client.messages().len(), 2);
+        assert_eq!(client.get_last_message().unwrap().role, "assistant");
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_is_valid_temperature() {
+        assert!(OpenAI::<Chat>::is_valid_temperature(0.0, 2.0));
+        assert!(OpenAI::<Chat>::is_valid_temperature(2.0, 2.0));
+        assert!(!OpenAI::<Chat>::is_valid_temperature(2.1, 2.0));
+        assert!(!OpenAI::<Chat>::is_valid_temperature(-0.1, 2.0));
+
+        let client = offline_client::<Chat>().set_temperature(1.5);
+        let temperature = client.config.temperature.as_ref().unwrap();
+        assert!(!OpenAI::<Chat>::is_valid_temperature(*temperature, 1.0));
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_strict_validation() {
+        let server = mock_endpoint(
+            "POST",
+            "/chat/completions",
+            wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hi"}}]
+            })),
+        )
+        .await;
+        let mut client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_stream_responses(false)
+            .set_temperature(3.5);
+        client.ask("Hello", false).await.unwrap();
+        assert_eq!(client.config.temperature, Some(2.0));
+
+        let mut client = client.with_strict_validation().set_temperature(3.5);
+        let err = client.ask("Hello", false).await.unwrap_err();
+        assert!(err.to_string().contains("Temperature 3.5 is out of range"));
+        assert_eq!(client.config.temperature, Some(3.5));
+
+        let mut client = client.set_temperature(1.0).bias_token(50256, 150.0);
+        let err = client.ask("Hello", false).await.unwrap_err();
+        assert!(err.to_string().contains("Logit bias 150 of token 50256"));
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_function_calling() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({
+                "messages": [{}, {}, {"role": "function", "name": "get_weather"}]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "It is sunny."},
+                    "finish_reason": "stop"
+                }]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{
+                    "index": 0,
+                    "message": {
+                        "role": "assistant",
+                        "content": null,
+                        "function_call": {"name": "get_weather", "arguments": "{\"city\": \"Berlin\"}"}
+                    },
+                    "finish_reason": "function_call"
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let weather = Function::new(
+            "get_weather",
+            "Get the current weather",
+            serde_json::json!({"type": "object", "properties": {}}),
+        );
+        let mut client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_stream_responses(false)
+            .set_functions(vec![weather])
+            .set_function_call(FunctionCallBehavior::Auto);
+        let err = client.ask("How is the weather?", false).await.unwrap_err();
+        assert!(err.to_string().contains("get_weather"));
+        let err = client.ask("How is the weather?", true).await.unwrap_err();
+        assert!(err.to_string().contains("ask_with_outcome"));
+        let call = err.downcast_ref::<UnhandledCall>().unwrap();
+        assert_eq!(call.function_call.as_ref().unwrap().name, "get_weather");
+        assert!(client.config.messages.is_empty());
+
+        let outcome = client
+            .ask_detailed("How is the weather?", true)
+            .await
+            .unwrap();
+        assert!(outcome.text.is_empty());
+        assert_eq!(outcome.finish_reason, FinishReason::FunctionCall);
+        let call = outcome.function_call.unwrap();
+        assert_eq!(call.name, "get_weather");
+        assert_eq!(call.arguments, r#"{"city": "Berlin"}"#);
+        assert_eq!(client.config.messages[1].function_call, Some(call));
+        assert!(client.config.messages[1].content.is_none());
+
+        let result = Message::function_response("get_weather", r#"{"sky": "sunny"}"#);
+        let outcome = client.ask_detailed(result, true).await.unwrap();
+        assert_eq!(outcome.text, "It is sunny.");
+        assert!(outcome.function_call.is_none());
+        assert_eq!(client.config.messages.len(), 4);
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_regenerate() {
+        let server = mock_endpoint(
+            "POST",
+            "/chat/completions",
+            wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hi again"}}]
+            })),
+        )
+        .await;
+        let mut client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_stream_responses(false);
+        let err = client.regenerate().await.unwrap_err();
+        assert!(err.to_string().contains("no prompt"));
+        client.config.messages = vec![Message::new(&MessageRole::System, "Be brief")];
+        let err = client.regenerate().await.unwrap_err();
+        assert!(err.to_string().contains("no prompt"));
+        assert_eq!(client.config.messages.len(), 1);
+
+        client.config.messages = vec![
+            Message::new(&MessageRole::User, "Hello"),
+            Message::new(&MessageRole::Assistant, "Hi"),
+        ];
+        assert_eq!(client.regenerate().await.unwrap(), "Hi again");
+        let history: Vec<String> = client
+            .iter()
+            .map(|msg| msg.content.as_ref().unwrap().to_string())
+            .collect();
+        assert_eq!(history, ["Hello", "Hi again"]);
+
+        client.pop_last();
+        assert_eq!(client.regenerate().await.unwrap(), "Hi again");
+        assert_eq!(client.messages().len(), 2);
+
+        let mut client = client.with_base_url("http://127.0.0.1:1");
+        assert!(client.regenerate().await.is_err());
+        assert_eq!(client.messages().len(), 2);
+        assert_eq!(client.get_last_message().unwrap().role, "assistant");
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_ask_with_functions() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[derive(serde::Deserialize)]
+        struct Location {
+            city: String,
+        }
+
+        let function_call = |arguments: &str| {
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{
+                    "index": 0,
+                    "message": {
+                        "role": "assistant",
+                        "content": null,
+                        "function_call": {"name": "get_weather", "arguments": arguments}
+                    },
+                    "finish_reason": "function_call"
+                }]
+            }))
+        };
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({
+                "messages": [{}, {}, {"role": "function", "content": "Sunny in Berlin"}]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"index": 0, "message": {"role": "assistant", "content": "It is sunny."}}]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({
+                "messages": [{"content": "Weather in Berlin?"}]
+            })))
+            .respond_with(function_call(r#"{"city": "Berlin"}"#))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(function_call(r#"{"town": "Paris"}"#))
+            .mount(&server)
+            .await;
+
+        let weather = Function::new(
+            "get_weather",
+            "Get the current weather in a given city",
+            serde_json::json!({"type": "object", "properties": {"city": {"type": "string"}}}),
+        );
+        let mut client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_stream_responses(false)
+            .register_function(weather, |location: Location| async move {
+                Ok(format!("Sunny in {}", location.city))
+            });
+        assert_eq!(client.config.functions.as_ref().unwrap().len(), 1);
+        let answer = client
+            .ask_with_functions("Weather in Berlin?", false)
+            .await
+            .unwrap();
+        assert_eq!(answer, "It is sunny.");
+        assert!(client.messages().is_empty());
+
+        let err = client
+            .ask_with_functions("Weather in Paris?", false)
+            .await
+            .unwrap_err();
+        let err = err.downcast_ref::<InvalidFunctionArguments>().unwrap();
+        assert_eq!(err.function, "get_weather");
+        assert_eq!(err.arguments, r#"{"town": "Paris"}"#);
+        assert!(err.message.contains("city"));
+        assert!(client.messages().is_empty());
+
+        let mut client = client.set_max_function_calls(0);
+        let err = client
+            .ask_with_functions("Weather in Berlin?", true)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("within 0 function calls"));
+        assert!(client.messages().is_empty());
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_streamed_function_call() {
+        let chunk = |delta: serde_json::Value, finish_reason: serde_json::Value| {
+            let chunk = serde_json::json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion.chunk",
+                "created": 0,
+                "model": "gpt-3.5-turbo",
+                "choices": [{"index": 0, "delta": delta, "finish_reason": finish_reason}]
+            });
+            format!("data: {chunk}\n\n")
+        };
+        let body = [
+            chunk(
+                serde_json::json!({"role": "assistant", "content": null,
+                    "function_call": {"name": "get_weather", "arguments": ""}}),
+                serde_json::Value::Null,
+            ),
+            chunk(
+                serde_json::json!({"function_call": {"arguments": "{\"city\":"}}),
+                serde_json::Value::Null,
+            ),
+            chunk(
+                serde_json::json!({"function_call": {"arguments": " \"Berlin\"}"}}),
+                serde_json::Value::Null,
+            ),
+            chunk(serde_json::json!({}), serde_json::json!("function_call")),
+            chunk(
+                serde_json::json!({"function_call": {"arguments": "ignored"}}),
+                serde_json::Value::Null,
+            ),
+            "data: [DONE]\n\n".to_string(),
+        ]
+        .concat();
+        let server = mock_endpoint(
+            "POST",
+            "/chat/completions",
+            wiremock::ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"),
+        )
+        .await;
+
+        let mut client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_stream_responses(true);
+        let outcome = client
+            .ask_detailed("How is the weather?", true)
+            .await
+            .unwrap();
+        assert!(outcome.text.is_empty());
+        assert_eq!(outcome.finish_reason, FinishReason::FunctionCall);
+        let call = outcome.function_call.unwrap();
+        assert_eq!(call.name, "get_weather");
+        assert_eq!(call.arguments, r#"{"city": "Berlin"}"#);
+        assert_eq!(client.config.messages[1].function_call, Some(call));
+        assert!(client.config.messages[1].content.is_none());
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_tool_serialization() {
+        let weather = Function::new(
+            "get_weather",
+            "Get the current weather",
+            serde_json::json!({"type": "object", "properties": {}}),
+        );
+        let client = offline_client::<Chat>()
+            .set_tools(vec![weather])
+            .set_tool_choice(ToolChoice::Function("get_weather".into()));
+        let body = client.request_body();
+        assert_eq!(body["tools"][0]["type"], "function");
+        assert_eq!(body["tools"][0]["function"]["name"], "get_weather");
+        assert_eq!(
+            body["tool_choice"],
+            serde_json::json!({"type": "function", "function": {"name": "get_weather"}})
+        );
+
+        let choices = [
+            (ToolChoice::None, serde_json::json!("none")),
+            (ToolChoice::Auto, serde_json::json!("auto")),
+            (ToolChoice::Required, serde_json::json!("required")),
+        ];
+        for (choice, json) in choices {
+            assert_eq!(serde_json::to_value(&choice).unwrap(), json);
+            assert_eq!(serde_json::from_value::<ToolChoice>(json).unwrap(), choice);
+        }
+
+        let msg = serde_json::to_value(Message::tool_result("call_1", "22 degrees")).unwrap();
+        assert_eq!(
+            msg,
+            serde_json::json!({"role": "tool", "content": "22 degrees", "tool_call_id": "call_1"})
+        );
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_null_content() {
+        let msg: Message = serde_json::from_value(serde_json::json!({
+            "role": "assistant",
+            "content": null,
+            "function_call": {"name": "get_weather", "arguments": "{}"}
+        }))
+        .unwrap();
+        assert!(msg.content.is_none());
+        assert_eq!(msg.to_string(), "assistant: get_weather({})");
+        assert_eq!(
+            serde_json::to_value(&msg).unwrap(),
+            serde_json::json!({
+                "role": "assistant",
+                "content": null,
+                "function_call": {"name": "get_weather", "arguments": "{}"}
+            })
+        );
+
+        let mut client = offline_client::<Chat>();
+        client.config.messages = vec![
+            Message::new(&MessageRole::User, "How is the weather?"),
+            msg,
+            Message::new(&MessageRole::Assistant, ""),
+        ];
+        let body = client.request_body();
+        assert_eq!(body["messages"][1]["content"], serde_json::Value::Null);
+        assert_eq!(body["messages"][2]["content"], "");
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_message_role_round_trip() {
+        for role in [
+            MessageRole::User,
+            MessageRole::Assistant,
+            MessageRole::System,
+            MessageRole::Developer,
+            MessageRole::Function,
+            MessageRole::Tool,
+        ] {
+            let name = role.to_string();
+            assert_eq!(MessageRole::from(name.as_str()).to_string(), name);
+        }
+        let result = Message::tool_result("call_1", "{}");
+        assert_eq!(result.to_string(), "tool: {}");
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_tool_calls() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({
+                "messages": [{}, {}, {"role": "tool", "tool_call_id": "call_1"}]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "It is sunny."},
+                    "finish_reason": "stop"
+                }]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{
+                    "index": 0,
+                    "message": {
+                        "role": "assistant",
+                        "content": null,
+                        "tool_calls": [{
+                            "id": "call_1",
+                            "type": "function",
+                            "function": {"name": "get_weather", "arguments": "{}"}
+                        }]
+                    },
+                    "finish_reason": "tool_calls"
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let mut client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_stream_responses(false);
+        let err = client.ask("How is the weather?", false).await.unwrap_err();
+        assert!(err.to_string().contains("tool calls"));
+        let call = err.downcast_ref::<UnhandledCall>().unwrap();
+        assert_eq!(call.tool_calls[0].id, "call_1");
+
+        let outcome = client
+            .ask_detailed("How is the weather?", true)
+            .await
+            .unwrap();
+        assert_eq!(outcome.finish_reason, FinishReason::ToolCalls);
+        assert_eq!(outcome.tool_calls.len(), 1);
+        assert_eq!(outcome.tool_calls[0].id, "call_1");
+        assert_eq!(outcome.tool_calls[0].function.name, "get_weather");
+        assert_eq!(
+            client.messages()[1].tool_calls.as_deref(),
+            Some(outcome.tool_calls.as_slice())
+        );
+        assert!(client.messages()[1].content.is_none());
+
+        let result = Message::tool_result("call_1", "sunny");
+        let outcome = client.ask_with_outcome(result, true).await.unwrap();
+        assert!(matches!(outcome, AskOutcome::Text(text) if text == "It is sunny."));
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_streamed_tool_calls() {
+        let chunk = |tool_calls: serde_json::Value, finish_reason: serde_json::Value| {
+            let chunk = serde_json::json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion.chunk",
+                "created": 0,
+                "model": "gpt-4o",
+                "choices": [{
+                    "index": 0,
+                    "delta": {"tool_calls": tool_calls},
+                    "finish_reason": finish_reason
+                }]
+            });
+            format!("data: {chunk}")
+        };
+        let lines = [
+            chunk(
+                serde_json::json!([{"index": 0, "id": "call_1", "type": "function",
+                    "function": {"name": "get_weather", "arguments": "{\"city\":"}}]),
+                serde_json::Value::Null,
+            ),
+            chunk(
+                serde_json::json!([{"index": 1, "id": "call_2", "type": "function",
+                    "function": {"name": "get_time", "arguments": "{}"}}]),
+                serde_json::Value::Null,
+            ),
+            chunk(
+                serde_json::json!([{"index": 0, "function": {"arguments": "\"Berlin\"}"}}]),
+                serde_json::Value::Null,
+            ),
+            chunk(serde_json::Value::Null, serde_json::json!("tool_calls")),
+        ];
+
+        let client = offline_client::<Chat>();
+        let mut answer = ChatOutcome::default();
+        for line in &lines {
+            let chunk = OpenAI::<Chat>::_parse_delta(line).unwrap().unwrap();
+            client
+                ._accumulate_delta(&chunk, &mut answer, &mut None)
+                .unwrap();
+        }
+        assert_eq!(answer.finish_reason, FinishReason::ToolCalls);
+        assert_eq!(answer.tool_calls.len(), 2);
+        assert_eq!(answer.tool_calls[0].id, "call_1");
+        assert_eq!(
+            answer.tool_calls[0].function.arguments,
+            r#"{"city":"Berlin"}"#
+        );
+        assert_eq!(answer.tool_calls[1].function.name, "get_time");
+        assert_eq!(answer.tool_calls[1].kind, "function");
+
+        let err = OpenAI::<Chat>::_parse_delta(r#"data: {"choices": 1}"#).unwrap_err();
+        assert!(err.to_string().contains(r#"data: {"choices": 1}"#));
+        let err = err.downcast::<DeserializationError>().unwrap();
+        assert!(err.message.contains("invalid type"));
+        assert_eq!(err.body, r#"{"choices": 1}"#);
+        assert_eq!(err.raw, Some(serde_json::json!({"choices": 1})));
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_function_call_mode_round_trip() {
+        let modes = [
+            (FunctionCallMode::None, serde_json::json!("none")),
+            (FunctionCallMode::Auto, serde_json::json!("auto")),
+            (
+                FunctionCallMode::Named("get_weather".into()),
+                serde_json::json!({"name": "get_weather"}),
+            ),
+        ];
+        for (mode, json) in modes {
+            assert_eq!(serde_json::to_value(&mode).unwrap(), json);
+            assert_eq!(
+                serde_json::from_value::<FunctionCallMode>(json).unwrap(),
+                mode
+            );
+        }
+
+        let client = offline_client::<Chat>()
+            .set_function_call(FunctionCallMode::Named("get_weather".into()));
+        assert_eq!(
+            client.request_body()["function_call"],
+            serde_json::json!({"name": "get_weather"})
+        );
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_count_tokens() {
+        let mut client = offline_client::<Chat>().set_model("gpt-4");
+        assert_eq!(client.count_tokens(), 3);
+
+        client.config.messages = vec![
+            Message::system("You are a helpful assistant."),
+            Message::new(&MessageRole::User, "Hello world"),
+        ];
+        // 3 reply + 2 * 3 framing + "system" + 6 content + "user" + 2 content
+        assert_eq!(client.count_tokens(), 19);
+        assert!(client.fits_context_window());
+
+        let mut client = client.set_max_tokens(8_192);
+        assert!(!client.fits_context_window());
+        client.config.model = "gpt-4-32k".into();
+        assert!(client.fits_context_window());
+        assert_eq!(Chat::get_context_window("unknown-model"), 4_096);
+    }
+
+    #[test]
+    fn test_model_info() {
+        let info = model_info("gpt-4-0613").unwrap();
+        assert_eq!(info, model_info("gpt-4").unwrap());
+        assert_eq!(info.context_window, 8_192);
+        assert!(info.supports_functions && !info.supports_vision && info.is_chat);
+
+        let info = model_info("gpt-4o-mini-2024-07-18").unwrap();
+        assert_eq!(info.max_output_tokens, 16_384);
+        assert!(info.supports_vision);
+        assert_eq!(model_info("o1-mini").unwrap().context_window, 128_000);
+        assert!(!model_info("gpt-3.5-turbo-instruct").unwrap().is_chat);
+        assert_eq!(
+            model_info("text-embedding-3-small")
+                .unwrap()
+                .max_output_tokens,
+            0
+        );
+        assert!(model_info("gpt-5o").is_none());
+        assert!(model_info("unknown-model").is_none());
+
+        // Ids that were resolved by prefix before the capabilities table existed.
+        for (id, context_window) in [
+            ("gpt-4o-2024-05-13", 128_000),
+            ("gpt-4-turbo-2024-04-09", 128_000),
+            ("gpt-4-1106-preview", 128_000),
+            ("gpt-4-0125-preview", 128_000),
+            ("gpt-4-32k-0613", 32_768),
+            ("gpt-4-0314", 8_192),
+            ("gpt-3.5-turbo-16k-0613", 16_385),
+            ("gpt-3.5-turbo-1106", 16_385),
+            ("gpt-3.5-turbo-0125", 16_385),
+            ("gpt-3.5-turbo-0613", 4_096),
+            ("o1-2024-12-17", 200_000),
+            ("o3-mini-2025-01-31", 200_000),
+            ("o4", 200_000),
+            ("o4-mini-2025-04-16", 200_000),
+        ] {
+            assert_eq!(
+                model_info(id).unwrap().context_window,
+                context_window,
+                "{id}"
+            );
+        }
+        assert_eq!(model_info("gpt-4.1-2025-04-14").unwrap().name, "gpt-4.1");
+        assert_eq!(model_info("gpt-4.1-mini").unwrap().name, "gpt-4.1-mini");
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_trim_history_preserves_system_message() {
+        let mut client = offline_client::<Chat>()
+            .set_primer("You are a helpful assistant.")
+            .with_history_limit(HistoryStrategy::MaxMessages(2));
+        for i in 0..5 {
+            client
+                .config
+                .messages
+                .push(Message::new(&MessageRole::User, format!("message {i}")));
+        }
+        let dropped = client.trim_history();
+        assert_eq!(
+            dropped
+                .iter()
+                .map(|m| m.content.as_ref().unwrap().as_text().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["message 0", "message 1", "message 2"]
+        );
+        assert_eq!(client.config.messages.len(), 3);
+        assert_eq!(client.config.messages[0].role, "system");
+        assert_eq!(
+            client.config.messages[2].content.as_ref().unwrap(),
+            "message 4"
+        );
+        assert!(client.trim_history().is_empty());
+
+        // The system message and the latest message are kept, even if they alone exceed the limit.
+        let mut client = client.with_history_limit(HistoryStrategy::MaxTokens(1));
+        let dropped = client.trim_history();
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(client.config.messages.len(), 2);
+        assert_eq!(client.config.messages[0].role, "system");
+        assert_eq!(
+            client.config.messages[1].content.as_ref().unwrap(),
+            "message 4"
+        );
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_trim_history_max_tokens() {
+        let mut client = offline_client::<Chat>()
+            .set_model("gpt-4")
+            .with_history_limit(HistoryStrategy::MaxTokens(30));
+        for i in 0..5 {
+            client
+                .config
+                .messages
+                .push(Message::new(&MessageRole::User, format!("message {i}")));
+        }
+        let dropped = client.trim_history();
+        assert!(!dropped.is_empty());
+        assert!(client.count_tokens() <= 30);
+        assert_eq!(client.config.messages.len() + dropped.len(), 5);
+        assert_eq!(dropped[0].content.as_ref().unwrap(), "message 0");
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_trimmed_history_is_restored_on_error() {
+        let server = mock_endpoint(
+            "POST",
+            "/chat/completions",
+            wiremock::ResponseTemplate::new(500).set_body_json(serde_json::json!({
+                "error": {"message": "Internal error", "type": "server_error"}
+            })),
+        )
+        .await;
+        let mut client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_stream_responses(false)
+            .with_history_limit(HistoryStrategy::MaxMessages(2));
+        for i in 0..3 {
+            client
+                .config
+                .messages
+                .push(Message::new(&MessageRole::User, format!("message {i}")));
+        }
+        let history = client.export_history();
+
+        assert!(client.ask("Hello", true).await.is_err());
+        assert_eq!(client.export_history(), history);
+        assert!(client.config.trimmed_messages.is_empty());
+        assert!(client.ask_many("Hello", true).await.is_err());
+        assert_eq!(client.export_history(), history);
+        assert!(client.ask_stream("Hello", true).await.is_err());
+        assert_eq!(client.export_history(), history);
+        assert!(client.config.trimmed_messages.is_empty());
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_json_mode_requires_json_system_message() {
+        let mut client = offline_client::<Chat>()
+            .set_primer("You are a helpful assistant.")
+            .set_response_format(ChatResponseFormat::JsonObject);
+        let err = client.ask("List three colors", true).await.unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("JSON mode requires a system message"));
+        assert_eq!(client.config.messages.len(), 1);
+
+        let client = offline_client::<Chat>()
+            .set_primer("Reply in JSON.")
+            .set_response_format(ChatResponseFormat::JsonObject);
+        assert!(client._json_mode_sanity_check().is_ok());
+        assert_eq!(
+            client.request_body()["response_format"],
+            serde_json::json!({"type": "json_object"})
+        );
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_seeded_requests() {
+        use wiremock::matchers::{body_partial_json, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({"seed": 42})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "gpt-4",
+                "system_fingerprint": "fp_44709d6fcb",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "4"},
+                    "finish_reason": "stop"
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let mut client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_stream_responses(false)
+            .set_seed(42);
+        let first = client.ask("What is 2 + 2?", false).await.unwrap();
+        let first_fingerprint = client.last_system_fingerprint();
+        let second = client.ask("What is 2 + 2?", false).await.unwrap();
+        assert_eq!(first, second);
+        assert_eq!(first_fingerprint.as_deref(), Some("fp_44709d6fcb"));
+        assert_eq!(client.last_system_fingerprint(), first_fingerprint);
+
+        let unseeded = offline_client::<Chat>();
+        assert!(unseeded.request_body().get("seed").is_none());
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_last_usage() {
+        let server = mock_endpoint(
+            "POST",
+            "/chat/completions",
+            wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hi"}}],
+                "usage": {"prompt_tokens": 9, "completion_tokens": 1, "total_tokens": 10}
+            })),
+        )
+        .await;
+        let mut client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_stream_responses(false);
+        assert!(client.last_usage().is_none());
+        client.ask("Hello", false).await.unwrap();
+        let usage = client.last_usage().unwrap();
+        assert_eq!(usage.total_tokens, 10);
+        assert_eq!(usage.completion_tokens, Some(1));
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_fallback_models() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({"model": "gpt-4"})))
+            .respond_with(
+                ResponseTemplate::new(404).set_body_json(serde_json::json!({"error": {
+                    "message": "The model `gpt-4` does not exist",
+                    "type": "invalid_request_error",
+                    "param": null,
+                    "code": "model_not_found"
+                }})),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(
+                serde_json::json!({"model": "gpt-4o-mini"}),
+            ))
+            .respond_with(ResponseTemplate::new(503).set_body_string("overloaded"))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(
+                serde_json::json!({"model": "gpt-3.5-turbo"}),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hi"}}]
+            })))
+            .mount(&server)
+            .await;
+
+        let mut client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_model("gpt-4")
+            .set_stream_responses(false)
+            .with_fallback_models(vec!["gpt-4o-mini", "gpt-3.5-turbo"]);
+        assert_eq!(client.ask("Hello", false).await.unwrap(), "Hi");
+        assert_eq!(client.last_model().as_deref(), Some("gpt-3.5-turbo"));
+        assert_eq!(client.config.model, "gpt-4");
+
+        let mut client = client.with_fallback_models(vec!["gpt-4o-mini"]);
+        let err = client.ask("Hello", false).await.unwrap_err();
+        let err = err.downcast_ref::<FallbackError>().unwrap();
+        assert_eq!(err.models(), ["gpt-4", "gpt-4o-mini"]);
+        assert!(err.attempts[0].1.is_model_not_found());
+        assert!(err.attempts[1].1.is_overloaded());
+        assert!(err.to_string().starts_with("All models failed: gpt-4: "));
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_ask_detailed() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let finish = serde_json::json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion.chunk",
+            "created": 0,
+            "model": "gpt-3.5-turbo",
+            "choices": [{"index": 0, "delta": {}, "finish_reason": "stop"}]
+        });
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({"stream": true})))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                format!(
+                    "{}\n\ndata: {finish}\n\ndata: [DONE]\n\n",
+                    delta_line("Hello")
+                ),
+                "text/event-stream",
+            ))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "chatcmpl-2",
+                "model": "gpt-4-0613",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "Once upon"},
+                    "finish_reason": "length"
+                }],
+                "usage": {"prompt_tokens": 9, "completion_tokens": 2, "total_tokens": 11}
+            })))
+            .mount(&server)
+            .await;
+
+        let mut client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_stream_responses(false);
+        let outcome = client.ask_detailed("Tell me a story", true).await.unwrap();
+        assert_eq!(outcome.text, "Once upon");
+        assert_eq!(outcome.finish_reason, FinishReason::Length);
+        assert_eq!(outcome.usage.unwrap().total_tokens, 11);
+        assert_eq!(outcome.id.as_deref(), Some("chatcmpl-2"));
+        assert_eq!(outcome.model.as_deref(), Some("gpt-4-0613"));
+        assert_eq!(client.config.messages.len(), 2);
+
+        let mut client = client.set_stream_responses(true);
+        let outcome = client
+            ._ask_detailed("Hello", false, Some(&mut |_: &str| {}))
+            .await
+            .unwrap();
+        assert_eq!(outcome.text, "Hello");
+        assert_eq!(outcome.finish_reason, FinishReason::Stop);
+        assert!(outcome.usage.is_some());
+        assert_eq!(outcome.id.as_deref(), Some("chatcmpl-1"));
+        assert_eq!(outcome.model.as_deref(), Some("gpt-3.5-turbo"));
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_ask_stream() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let finish = serde_json::json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion.chunk",
+            "created": 0,
+            "model": "gpt-3.5-turbo",
+            "choices": [{"index": 0, "delta": {}, "finish_reason": "stop"}]
+        });
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({"stream": true})))
+            .and(body_partial_json(
+                serde_json::json!({"messages": [{"content": "Hello"}]}),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                format!(
+                    "{}\n\n{}\n\ndata: {finish}\n\ndata: [DONE]\n\n{}\n\n",
+                    delta_line("Hello"),
+                    delta_line(" there"),
+                    delta_line("ignored")
+                ),
+                "text/event-stream",
+            ))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({"stream": true})))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                format!("{}\n\ndata: {{\"broken\n\n", delta_line("Hi")),
+                "text/event-stream",
+            ))
+            .mount(&server)
+            .await;
+
+        let mut client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_stream_responses(false);
+        let chunks: Vec<_> = client
+            .ask_stream("Hello", true)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        let chunks: Vec<ChatChunk> = chunks.into_iter().map(Result::unwrap).collect();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].content.as_deref(), Some("Hello"));
+        assert_eq!(chunks[1].content.as_deref(), Some(" there"));
+        assert_eq!(chunks[2].content, None);
+        assert_eq!(chunks[2].finish_reason, FinishReason::Stop);
+        assert_eq!(client.config.messages.len(), 2);
+        assert_eq!(
+            client.config.messages[1].content.as_ref().unwrap(),
+            "Hello there"
+        );
+        assert!(client.last_usage().is_some());
+
+        client.config.messages.clear();
+        let results: Vec<_> = client
+            .ask_stream("Broken", true)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap().content.as_deref(), Some("Hi"));
+        assert!(results[1].is_err());
+        assert_eq!(client.config.messages.len(), 1);
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_stream_callback() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                format!(
+                    "{}\n\n{}\n\ndata: [DONE]\n\n",
+                    delta_line("Hello"),
+                    delta_line(" there")
+                ),
+                "text/event-stream",
+            ))
+            .mount(&server)
+            .await;
+
+        let deltas = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&deltas);
+        let mut client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_stream_responses(true)
+            .with_stream_callback(Box::new(move |delta: &str| {
+                recorded.lock().unwrap().push(delta.to_string());
+            }));
+        assert_eq!(client.ask("Hello", false).await.unwrap(), "Hello there");
+        assert_eq!(*deltas.lock().unwrap(), ["Hello", " there"]);
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_set_output() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[derive(Clone, Default)]
+        struct Transcript(Arc<std::sync::Mutex<Vec<u8>>>);
+
+        impl Write for Transcript {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                format!(
+                    "{}\n\n{}\n\ndata: [DONE]\n\n",
+                    delta_line("Hello"),
+                    delta_line(" there")
+                ),
+                "text/event-stream",
+            ))
+            .mount(&server)
+            .await;
+
+        let transcript = Transcript::default();
+        let mut client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_stream_responses(true)
+            .set_output(transcript.clone());
+        assert_eq!(client.ask("Hello", false).await.unwrap(), "Hello there");
+        assert_eq!(*transcript.0.lock().unwrap(), b"AI: Hello there\n");
+
+        let transcript = Transcript::default();
+        let mut client = client.set_output(transcript.clone()).disable_stdout();
+        assert_eq!(client.ask("Hello", false).await.unwrap(), "Hello there");
+        assert!(transcript.0.lock().unwrap().is_empty());
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_on_token() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hi"}}]
+            })))
+            .mount(&server)
+            .await;
+
+        let tokens = Arc::new(std::sync::Mutex::new(String::new()));
+        let recorded = Arc::clone(&tokens);
+        let mut client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_stream_responses(false)
+            .on_token(move |token| recorded.lock().unwrap().push_str(token));
+        assert_eq!(client.ask("Hello", false).await.unwrap(), "Hi");
+        assert_eq!(*tokens.lock().unwrap(), "Hi");
+    }
+
+    #[cfg(feature = "repl")]
+    #[test]
+    fn test_repl_commands() {
+        use crate::openai::repl::Command;
+
+        assert_eq!(Command::parse("Hello /reset"), None);
+        assert_eq!(Command::parse(" /reset "), Some(Ok(Command::Reset)));
+        assert_eq!(
+            Command::parse("/save chat.json"),
+            Some(Ok(Command::Save("chat.json".into())))
+        );
+        assert_eq!(
+            Command::parse("/system  You are a pirate."),
+            Some(Ok(Command::System("You are a pirate.".into())))
+        );
+        assert_eq!(
+            Command::parse("/model gpt-4o"),
+            Some(Ok(Command::Model("gpt-4o".into())))
+        );
+        assert_eq!(Command::parse("/exit"), Some(Ok(Command::Exit)));
+        assert_eq!(
+            Command::parse("/load"),
+            Some(Err("Usage: /load <path>".into()))
+        );
+        assert!(Command::parse("/quit").unwrap().is_err());
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_cancellation() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({
+                        "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hi"}}]
+                    }))
+                    .set_delay(Duration::from_secs(5)),
+            )
+            .mount(&server)
+            .await;
+
+        let token = CancellationToken::new();
+        let mut client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_stream_responses(false)
+            .with_cancellation_token(token.clone());
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            token.cancel();
+        });
+        let start = std::time::Instant::now();
+        let err = client.ask("Hello", true).await.unwrap_err();
+        assert!(err.is::<Cancelled>());
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(client.config.messages.is_empty());
+
+        let err = client.ask_many("Hello", true).await.unwrap_err();
+        assert_eq!(err.to_string(), "The request was cancelled");
+        assert!(client.config.messages.is_empty());
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_ask_cancellable() {
+        use futures_util::stream;
+
+        /// Streams the first chunk of an answer and then stalls.
+        #[derive(Debug)]
+        struct StallingTransport;
+
+        impl Transport for StallingTransport {
+            fn post_json(&self, _request: reqwest::Request) -> transport::TransportFuture<'_> {
+                let first = Ok::<_, io::Error>(format!("{}\n\n", delta_line("Once upon")));
+                let body = stream::iter([first]).chain(stream::pending());
+                Box::pin(async move {
+                    let res = http::Response::builder()
+                        .status(200)
+                        .body(reqwest::Body::wrap_stream(body))?;
+                    Ok(reqwest::Response::from(res))
+                })
+            }
+        }
+
+        let mut client = offline_client::<Chat>()
+            .with_transport(StallingTransport)
+            .set_stream_responses(true);
+        let interrupt = |token: CancellationToken| {
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                token.cancel();
+            });
+        };
+
+        let token = CancellationToken::new();
+        interrupt(token.clone());
+        let answer = client
+            .ask_cancellable("Tell me a story", true, token)
+            .await
+            .unwrap();
+        assert_eq!(answer, "Once upon");
+        assert_eq!(client.messages().len(), 2);
+        assert_eq!(
+            client.messages()[1].content.as_ref().unwrap().to_string(),
+            "Once upon"
+        );
+
+        let token = CancellationToken::new();
+        interrupt(token.clone());
+        let answer = client
+            .ask_cancellable("Another one", false, token)
+            .await
+            .unwrap();
+        assert_eq!(answer, "Once upon");
+        assert_eq!(client.messages().len(), 2);
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let err = client
+            .ask_cancellable("Hello", true, token)
+            .await
+            .unwrap_err();
+        assert!(err.is::<Cancelled>());
+        assert_eq!(client.messages().len(), 2);
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_metrics_sink() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[derive(Debug, Default)]
+        struct Recorded(std::sync::Mutex<Vec<RequestMetrics>>);
+
+        impl MetricsSink for Arc<Recorded> {
+            fn on_request_complete(&self, metrics: &RequestMetrics) {
+                self.0.lock().unwrap().push(metrics.clone());
+            }
+        }
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({"stream": true})))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                format!("{}\n\ndata: [DONE]\n\n", delta_line("Hello")),
+                "text/event-stream",
+            ))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hi"}}],
+                "usage": {"prompt_tokens": 9, "completion_tokens": 1, "total_tokens": 10}
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/models"))
+            .respond_with(ResponseTemplate::new(429))
+            .mount(&server)
+            .await;
+
+        let recorded = Arc::new(Recorded::default());
+        let mut client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .with_metrics_sink(Arc::clone(&recorded))
+            .set_model("gpt-4")
+            .set_stream_responses(false);
+        client.ask("Hello", false).await.unwrap();
+        assert!(client.models().await.is_err());
+        let mut client = client.set_stream_responses(true);
+        client
+            ._ask_with_outcome("Hello", false, Some(&mut |_: &str| {}))
+            .await
+            .unwrap();
+
+        let metrics = recorded.0.lock().unwrap();
+        assert_eq!(metrics.len(), 3);
+        assert_eq!(metrics[0].endpoint, "/chat/completions");
+        assert_eq!(metrics[0].model.as_deref(), Some("gpt-4"));
+        assert_eq!(metrics[0].status, Some(200));
+        assert_eq!(metrics[0].usage.unwrap().total_tokens, 10);
+        assert!(metrics[0].error.is_none());
+        assert!(metrics[0].time_to_first_token.is_none());
+
+        assert_eq!(metrics[1].endpoint, "/models");
+        assert_eq!(metrics[1].status, Some(429));
+        assert_eq!(metrics[1].error, Some(ErrorClass::RateLimit));
+
+        let ttft = metrics[2].time_to_first_token.unwrap();
+        assert!(ttft <= metrics[2].latency);
+        assert!(metrics[2].usage.unwrap().completion_tokens.unwrap() > 0);
+    }
+
+    #[cfg(all(feature = "chat", feature = "embeddings"))]
+    #[tokio::test]
+    async fn test_usage_totals() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hi"}}],
+                "usage": {"prompt_tokens": 9, "completion_tokens": 1, "total_tokens": 10}
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "object": "list",
+                "model": "text-embedding-ada-002",
+                "data": [{"object": "embedding", "embedding": [1.0], "index": 0}],
+                "usage": {"prompt_tokens": 5, "total_tokens": 5}
+            })))
+            .mount(&server)
+            .await;
+
+        let mut chat = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_stream_responses(false);
+        let embedding: OpenAI<Embedding> = chat.clone().into_endpoint();
+        chat.ask("Hello", false).await.unwrap();
+        embedding.embed("Hello").await.unwrap();
+
+        let totals = chat.usage_totals();
+        assert_eq!(totals, embedding.usage_totals());
+        assert_eq!(totals.prompt_tokens, 14);
+        assert_eq!(totals.completion_tokens, 1);
+        assert_eq!(totals.total_tokens, 15);
+        assert_eq!(totals.requests, 2);
+
+        embedding.reset_usage_totals();
+        assert_eq!(chat.usage_totals(), UsageTotals::default());
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_raw_responses() {
+        let server = mock_endpoint(
+            "GET",
+            "/models/gpt-4",
+            wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "gpt-4",
+                "object": "model",
+                "owned_by": "openai",
+                "created": 1_687_882_411
+            })),
+        )
+        .await;
+        let client = offline_client::<Chat>().with_base_url(server.uri());
+        client.check_model("gpt-4").await.unwrap();
+        assert!(client.last_raw_response().is_none());
+
+        let client = client.with_raw_responses(true);
+        let model = client.check_model("gpt-4").await.unwrap();
+        assert_eq!(model.id, "gpt-4");
+        assert_eq!(
+            client.last_raw_response().unwrap()["created"],
+            1_687_882_411
+        );
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_deserialization_error_carries_raw_body() {
+        let server = mock_endpoint(
+            "GET",
+            "/models/gpt-4",
+            wiremock::ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({"id": "gpt-4", "object": "model"})),
+        )
+        .await;
+        let client = offline_client::<Chat>().with_base_url(server.uri());
+        let err = client.check_model("gpt-4").await.unwrap_err();
+        let err = err.downcast_ref::<DeserializationError>().unwrap();
+        assert!(err.message.contains("owned_by"));
+        assert_eq!(err.raw.as_ref().unwrap()["id"], "gpt-4");
+    }
+
+    #[test]
+    fn test_pricing() {
+        let usage = Usage {
+            prompt_tokens: 1000,
+            completion_tokens: Some(500),
+            total_tokens: 1500,
+        };
+        let cost = pricing::estimate_cost(&usage, "gpt-4").unwrap();
+        assert!((cost - 0.06).abs() < 1e-9);
+        let dated = pricing::estimate_cost(&usage, "gpt-4-0613").unwrap();
+        assert!((dated - cost).abs() < 1e-9);
+        let mini = pricing::estimate_cost(&usage, "gpt-4o-mini-2024-07-18").unwrap();
+        assert!((mini - 0.000_45).abs() < 1e-9);
+        assert!(pricing::estimate_cost(&usage, "gpt-4.5-preview").is_none());
+        assert!(pricing::estimate_cost(&usage, "my-model").is_none());
+
+        let pricing = Pricing::default().set_price("my-model", ModelPrice::new(1.0, 2.0));
+        let custom = pricing.estimate_cost(&usage, "my-model").unwrap();
+        assert!((custom - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_cost_for() {
+        let usage = Usage {
+            prompt_tokens: 1000,
+            completion_tokens: Some(500),
+            total_tokens: 1500,
+        };
+        let cost = usage.cost_for("gpt-4").unwrap();
+        assert!((cost.input - 0.03).abs() < 1e-9);
+        assert!((cost.output - 0.03).abs() < 1e-9);
+        assert!((cost.total() - 0.06).abs() < 1e-9);
+
+        let model = "ft:gpt-3.5-turbo:test-cost-for::abc123";
+        assert!(usage.cost_for(model).is_none());
+        let custom = Pricing::default().set_price(model, ModelPrice::new(0.002, 0.004));
+        assert!((custom.cost(&usage, model).unwrap().total() - 0.004).abs() < 1e-9);
+
+        let image = pricing::estimate_image_cost("dall-e-3", "1792x1024", Some("hd"), 2).unwrap();
+        assert!((image - 0.24).abs() < 1e-9);
+        assert!(pricing::estimate_image_cost("dall-e-2", "1792x1024", None, 1).is_none());
+        let transcription = pricing::estimate_transcription_cost("whisper-1", 90.0).unwrap();
+        assert!((transcription - 0.009).abs() < 1e-9);
+        let speech = pricing::estimate_speech_cost("tts-1-hd", 2000).unwrap();
+        assert!((speech - 0.06).abs() < 1e-9);
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_last_cost() {
+        let client = offline_client::<Chat>().set_model("gpt-3.5-turbo");
+        assert!(client.last_cost().is_none());
+        client.last_response.lock().usage = Some(Usage {
+            prompt_tokens: 2000,
+            completion_tokens: Some(1000),
+            total_tokens: 3000,
+        });
+        assert!((client.last_cost().unwrap() - 0.002_5).abs() < 1e-9);
+
+        let client = client
+            .with_pricing(Pricing::empty().set_price("gpt-3.5-turbo", ModelPrice::new(0.01, 0.01)));
+        assert!((client.last_cost().unwrap() - 0.03).abs() < 1e-9);
+        let client = client.set_model("unknown-model");
+        assert!(client.last_cost().is_none());
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_last_usage_streamed_is_estimated() {
+        let body = format!(
+            "{}\n\n{}\n\ndata: [DONE]\n\n",
+            delta_line("Hello"),
+            delta_line(" there")
+        );
+        let server = mock_endpoint(
+            "POST",
+            "/chat/completions",
+            wiremock::ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"),
+        )
+        .await;
+        let mut client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_model("gpt-4");
+        let answer = client.ask("Hi", false).await.unwrap();
+        let usage = client.last_usage().unwrap();
+        let completion_tokens = tokenizer::count_text_tokens("gpt-4", &answer) as u64;
+        assert!(completion_tokens > 0);
+        assert_eq!(usage.completion_tokens, Some(completion_tokens));
+        let prompt_tokens = tokenizer::count_message_tokens("gpt-4", &[Message::from("Hi")]);
+        assert_eq!(usage.prompt_tokens, prompt_tokens as u64);
+        assert_eq!(usage.total_tokens, usage.prompt_tokens + completion_tokens);
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_stream_usage() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let usage = serde_json::json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion.chunk",
+            "created": 0,
+            "model": "gpt-4",
+            "choices": [],
+            "usage": {"prompt_tokens": 8, "completion_tokens": 2, "total_tokens": 10}
+        });
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(
+                serde_json::json!({"stream_options": {"include_usage": true}}),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                format!(
+                    "{}\n\ndata: {usage}\n\ndata: [DONE]\n\n",
+                    delta_line("Hello")
+                ),
+                "text/event-stream",
+            ))
+            .mount(&server)
+            .await;
+
+        let client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_stream_usage(true);
+        assert!(client
+            .clone()
+            .set_stream_responses(false)
+            .request_body()
+            .get("stream_options")
+            .is_none());
+
+        let mut client = client.set_stream_responses(true);
+        assert_eq!(client.ask("Hi", false).await.unwrap(), "Hello");
+        assert_eq!(client.last_usage().unwrap().total_tokens, 10);
+        assert_eq!(client.usage_totals().total_tokens, 10);
+
+        let chunks: Vec<_> = client
+            .ask_stream("Hi", false)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(client.last_usage().unwrap().total_tokens, 10);
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_logit_bias() {
+        let mut client = offline_client::<Chat>()
+            .set_logit_bias(HashMap::from([(50256, 5.0)]))
+            .bias_token(1234, 250.0)
+            .ban_token(42);
+        assert_eq!(client.config.logit_bias.as_ref().unwrap()["1234"], 250.0);
+        assert!(client._logit_bias_sanity_check().is_ok());
+        let logit_bias = client.config.logit_bias.as_ref().unwrap();
+        assert_eq!(logit_bias.len(), 3);
+        assert_eq!(logit_bias["50256"], 5.0);
+        assert_eq!(logit_bias["1234"], 100.0);
+        assert_eq!(logit_bias["42"], -100.0);
+
+        // Strict validation rejects the bias, no matter if it is enabled before or after.
+        for mut strict in [
+            offline_client::<Chat>()
+                .with_strict_validation()
+                .bias_token(1234, 250.0),
+            offline_client::<Chat>()
+                .bias_token(1234, 250.0)
+                .with_strict_validation(),
+        ] {
+            assert!(strict._logit_bias_sanity_check().is_err());
+        }
+
+        let mut client = client.set_logit_bias(HashMap::from([(7, -0.5)]));
+        assert_eq!(
+            client.config.logit_bias,
+            Some(HashMap::from([("7".to_string(), -0.5)]))
+        );
+
+        client.config.logit_bias = Some(HashMap::from([("token".to_string(), 1.0)]));
+        assert!(client._logit_bias_sanity_check().is_err());
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_sampling_parameters() {
+        let client = offline_client::<Chat>()
+            .set_presence_penalty(0.5)
+            .set_frequency_penalty(-1.0)
+            .set_top_p(0.9)
+            .set_stop(vec!["\n", "User:"]);
+        let body = client.request_body();
+        assert_eq!(body["presence_penalty"], 0.5);
+        assert_eq!(body["frequency_penalty"], -1.0);
+        assert_eq!(body["top_p"], 0.9);
+        assert_eq!(body["stop"], serde_json::json!(["\n", "User:"]));
+        assert!(client._sampling_sanity_check().is_ok());
+
+        let config: Chat = serde_json::from_value(
+            serde_json::json!({"model": "gpt-4", "messages": [], "stop": "END"}),
+        )
+        .unwrap();
+        assert_eq!(config.stop, Some(vec!["END".to_string()]));
+
+        let client = client.set_presence_penalty(2.5);
+        let err = client._sampling_sanity_check().unwrap_err();
+        assert!(err.to_string().contains("presence_penalty"));
+        let client = client.set_presence_penalty(2.0).set_frequency_penalty(-2.1);
+        assert!(client._sampling_sanity_check().is_err());
+        let client = client.set_frequency_penalty(0.0).set_top_p(1.5);
+        assert!(client._sampling_sanity_check().is_err());
+        let client = client
+            .set_top_p(1.0)
+            .set_stop(vec!["a", "b", "c", "d", "e"]);
+        let err = client._sampling_sanity_check().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Got 5 stop sequences, but at most 4 are allowed"
+        );
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_set_n() {
+        let client = offline_client::<Chat>().set_n(3);
+        assert_eq!(client.request_body()["n"], 3);
+        assert!(client._sampling_sanity_check().is_ok());
+        let client = client.set_n(0);
+        let err = client._sampling_sanity_check().unwrap_err();
+        assert!(err.to_string().contains("out of range"));
+        assert!(client.set_n(129)._sampling_sanity_check().is_err());
+
+        let client = offline_client::<Chat>();
+        let mut answer = ChatOutcome::default();
+        let second_choice = delta_line("Blue").replace(r#""index":0"#, r#""index":1"#);
+        client
+            ._process_delta(
+                &delta_line("Red"),
+                &mut answer,
+                &mut Some(&mut |_: &str| {}),
+            )
+            .unwrap();
+        client
+            ._process_delta(&second_choice, &mut answer, &mut Some(&mut |_: &str| {}))
+            .unwrap();
+        assert_eq!(answer.text, "Red");
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_message_builder() {
+        let msg = Message::builder()
+            .role(MessageRole::Function)
+            .name("get_weather")
+            .content(r#"{"temperature": 22}"#)
+            .build()
+            .unwrap();
+        assert_eq!(msg.role, "function");
+        assert_eq!(msg.name.as_deref(), Some("get_weather"));
+        assert_eq!(msg.content.unwrap(), r#"{"temperature": 22}"#);
+
+        let msg = Message::builder().content("Hello").build().unwrap();
+        assert_eq!(msg.role, "user");
+        assert!(msg.name.is_none());
+
+        let call = FunctionCall {
+            name: "get_weather".into(),
+            arguments: "{}".into(),
+        };
+        let msg = Message::builder()
+            .role(MessageRole::Assistant)
+            .function_call(call)
+            .build()
+            .unwrap();
+        assert_eq!(msg.function_call.unwrap().name, "get_weather");
+
+        assert!(Message::builder()
+            .role(MessageRole::Function)
+            .content("22")
+            .build()
+            .is_err());
+        assert!(Message::builder().name("get-weather").build().is_err());
+        assert!(Message::builder().name("a".repeat(65)).build().is_err());
+        assert!(Message::builder().name("").build().is_err());
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_vision_content_serialization() {
+        let text = Message::new(&MessageRole::User, "Hello");
+        assert_eq!(
+            serde_json::to_value(&text).unwrap(),
+            serde_json::json!({"role": "user", "content": "Hello"})
+        );
+
+        let mut vision =
+            Message::with_image("What is in this image?", "https://example.com/cat.png");
+        let expected = serde_json::json!({
+            "role": "user",
+            "content": [
+                {"type": "text", "text": "What is in this image?"},
+                {"type": "image_url", "image_url": {"url": "https://example.com/cat.png"}}
+            ]
+        });
+        assert_eq!(serde_json::to_value(&vision).unwrap(), expected);
+        let parsed: Message = serde_json::from_value(expected).unwrap();
+        assert_eq!(parsed.content, vision.content);
+
+        if let Some(Content::Parts(parts)) = &mut vision.content {
+            parts[1] = ContentPart::ImageUrl {
+                url: "data:image/png;base64,iVBORw0KGgo=".into(),
+                detail: Some(chat::ImageDetail::High),
+            };
+        }
+        assert_eq!(
+            serde_json::to_value(&vision).unwrap()["content"][1],
+            serde_json::json!({"type": "image_url", "image_url": {
+                "url": "data:image/png;base64,iVBORw0KGgo=",
+                "detail": "high"
+            }})
+        );
+        assert_eq!(
+            vision.content.as_ref().unwrap().to_string(),
+            "What is in this image?"
+        );
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_finish_reason_parsing() {
+        let choice: chat::Choice = serde_json::from_str(
+            r#"{"index": 0, "finish_reason": "length",
+                "message": {"role": "assistant", "content": "Hello"}}"#,
+        )
+        .unwrap();
+        assert_eq!(choice.finish_reason, FinishReason::Length);
+
+        let chunk: StreamedReponse = serde_json::from_str(
+            r#"{"id": "1", "object": "chat.completion.chunk", "created": 0, "model": "gpt-4",
+                "choices": [{"index": 0, "delta": {}, "finish_reason": null}]}"#,
+        )
+        .unwrap();
+        assert_eq!(chunk.choices[0].finish_reason, FinishReason::Null);
+        assert_eq!(
+            serde_json::to_value(FinishReason::ContentFilter).unwrap(),
+            "content_filter"
+        );
+
+        let reason: FinishReason = serde_json::from_str(r#""max_turns""#).unwrap();
+        assert_eq!(reason, FinishReason::Other("max_turns".to_string()));
+        assert_eq!(serde_json::to_value(&reason).unwrap(), "max_turns");
+    }
+
+    #[cfg(feature = "embeddings")]
+    #[tokio::test]
+    async fn test_embed_chunking() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "object": "list",
+                "model": "text-embedding-ada-002",
+                "data": [
+                    {"object": "embedding", "embedding": [0.0, 1.0], "index": 1},
+                    {"object": "embedding", "embedding": [1.0, 0.0], "index": 0}
+                ],
+                "usage": {"prompt_tokens": 9000, "total_tokens": 9000}
+            })))
+            .mount(&server)
+            .await;
+
+        let long_input = "hello ".repeat(9000);
+        let client = offline_client::<Embedding>().set_chunking(Chunking::Separate);
+        let res = client
+            ._embed(&server.uri(), long_input.as_str().into())
+            .await
+            .unwrap();
+        assert_eq!(res.data.len(), 2);
+        assert_eq!(res.data[0].embedding, vec![1.0, 0.0]);
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        let chunks = body["input"].as_array().unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(
+            chunks[0].as_array().unwrap().len(),
+            Embedding::MAX_INPUT_TOKENS
+        );
+
+        let client = client.set_chunking(Chunking::MeanPool);
+        let res = client
+            ._embed(&server.uri(), long_input.as_str().into())
+            .await
+            .unwrap();
+        assert_eq!(res.data.len(), 1);
+        let pooled = &res.data[0].embedding;
+        assert!(pooled[0] > pooled[1]);
+        assert!((pooled.iter().map(|v| v * v).sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+
+    #[cfg(feature = "embeddings")]
+    #[test]
+    fn test_embedding_similarity() {
+        assert!((embeddings::dot_product(&[1.0, 2.0], &[3.0, 4.0]) - 11.0).abs() < 1e-9);
+        assert!((embeddings::cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-9);
+        assert!(embeddings::cosine_similarity(&[1.0, 0.0], &[0.0, 3.0]).abs() < 1e-9);
+        assert!((embeddings::cosine_similarity(&[1.0, 1.0], &[-1.0, -1.0]) + 1.0).abs() < 1e-9);
+        assert_eq!(embeddings::cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+
+        let corpus = [
+            ("north", vec![0.0, 1.0]),
+            ("east", vec![1.0, 0.0]),
+            ("north-east", vec![1.0, 1.0]),
+            ("south", vec![0.0, -1.0]),
+        ];
+        let nearest = embeddings::top_k(&[0.2, 1.0], &corpus, 3);
+        let labels: Vec<&str> = nearest.iter().map(|(label, _)| *label).collect();
+        assert_eq!(labels, ["north", "north-east", "east"]);
+        assert!(nearest.windows(2).all(|w| w[0].1 >= w[1].1));
+        assert_eq!(embeddings::top_k(&[0.2, 1.0], &corpus, 10).len(), 4);
+
+        let mismatch =
+            std::panic::catch_unwind(|| embeddings::cosine_similarity(&[1.0], &[1.0, 0.0]));
+        assert!(mismatch.is_err());
+    }
+
+    #[cfg(feature = "embeddings")]
+    #[tokio::test]
+    async fn test_embed_current() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        assert_eq!(InputType::from("hello").len(), 1);
+        assert_eq!(InputType::from(vec![1_u64, 2, 3]).len(), 1);
+        assert_eq!(InputType::from(vec!["a".to_string(), "b".into()]).len(), 2);
+        assert!(InputType::from("").is_empty());
+        assert!(InputType::from(Vec::<u64>::new()).is_empty());
+        assert!(InputType::from(Vec::<String>::new()).is_empty());
+        assert!(!InputType::from(vec![String::new()]).is_empty());
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/embeddings"))
+            .and(body_partial_json(serde_json::json!({
+                "model": "text-embedding-3-small",
+                "input": ["first", "second"]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "object": "list",
+                "model": "text-embedding-3-small",
+                "data": [
+                    {"object": "embedding", "embedding": [1.0], "index": 0},
+                    {"object": "embedding", "embedding": [2.0], "index": 1}
+                ],
+                "usage": {"prompt_tokens": 2, "total_tokens": 2}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = offline_client::<Embedding>()
+            .with_base_url(server.uri())
+            .with_config(Embedding::new("text-embedding-3-small"));
+        let err = client.embed_current().await.unwrap_err();
+        assert_eq!(err.to_string(), "The input to embed is empty");
+        let err = client.embed(Vec::<String>::new()).await.unwrap_err();
+        assert_eq!(err.to_string(), "The input to embed is empty");
+
+        let client = client.with_input(vec!["first".to_string(), "second".into()]);
+        let res = client.embed_current().await.unwrap();
+        assert_eq!(res.data.len(), 2);
+        assert_eq!(res.data[1].embedding, vec![2.0]);
+    }
+
+    #[cfg(all(feature = "embeddings", feature = "moderations"))]
+    #[tokio::test]
+    async fn test_shared_client() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "object": "list",
+                "model": "text-embedding-ada-002",
+                "data": [{"object": "embedding", "embedding": [1.0], "index": 0}],
+                "usage": {"prompt_tokens": 1, "total_tokens": 1}
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/moderations"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "modr-1",
+                "model": "text-moderation-007",
+                "results": []
+            })))
+            .mount(&server)
+            .await;
+
+        let embedder = Arc::new(offline_client::<Embedding>().with_base_url(server.uri()));
+        let moderator: Arc<OpenAI<Moderation>> =
+            Arc::new(embedder.as_ref().clone().into_endpoint());
+        let tasks: Vec<_> = (0..4)
+            .map(|i| {
+                let embedder = Arc::clone(&embedder);
+                let moderator = Arc::clone(&moderator);
+                tokio::spawn(async move {
+                    let res = embedder.embed(format!("text {i}")).await.unwrap();
+                    assert_eq!(res.data[0].embedding, vec![1.0]);
+                    moderator.moderate_many(Vec::new()).await.unwrap();
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 8);
+        let mut inputs: Vec<String> = requests
+            .iter()
+            .filter(|request| request.url.path() == "/embeddings")
+            .map(|request| {
+                let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+                body["input"].as_str().unwrap().to_string()
+            })
+            .collect();
+        inputs.sort();
+        assert_eq!(inputs, ["text 0", "text 1", "text 2", "text 3"]);
+    }
+
+    #[cfg(feature = "embeddings")]
+    #[tokio::test]
+    async fn test_embed_batch() {
+        use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+        /// Answers every request with one embedding per input, in reverse order, whose only
+        /// value is the number in the input text.
+        struct Reversed;
+
+        impl Respond for Reversed {
+            fn respond(&self, request: &Request) -> ResponseTemplate {
+                let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+                let inputs = body["input"].as_array().unwrap();
+                let data: Vec<serde_json::Value> = inputs
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .map(|(index, input)| {
+                        let value: f64 = input.as_str().unwrap()[4..].parse().unwrap();
+                        serde_json::json!({"object": "embedding", "embedding": [value], "index": index})
+                    })
+                    .collect();
+                ResponseTemplate::new(200)
+                    .set_delay(std::time::Duration::from_millis(
+                        10 * (3 - inputs.len() as u64),
+                    ))
+                    .set_body_json(serde_json::json!({
+                        "object": "list",
+                        "model": "text-embedding-ada-002",
+                        "data": data,
+                        "usage": {"prompt_tokens": inputs.len(), "total_tokens": inputs.len()}
+                    }))
+            }
+        }
+
+        let server = MockServer::start().await;
+        Mock::given(wiremock::matchers::method("POST"))
+            .respond_with(Reversed)
+            .expect(4)
+            .mount(&server)
+            .await;
+
+        let inputs: Vec<String> = (0..10).map(|i| format!("doc {i}")).collect();
+        let client = offline_client::<Embedding>();
+        let embeddings = client
+            ._embed_batch(&server.uri(), inputs, 3, 2)
+            .await
+            .unwrap();
+        let values: Vec<f64> = embeddings.iter().map(|e| e[0]).collect();
+        assert_eq!(values, (0..10).map(f64::from).collect::<Vec<_>>());
+        assert_eq!(client.last_usage().unwrap().total_tokens, 10);
+    }
+
+    #[cfg(feature = "embeddings")]
+    #[tokio::test]
+    async fn test_embed_dimensions() {
+        use wiremock::matchers::{body_partial_json, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let client = offline_client::<Embedding>().set_dimensions(256);
+        let err = client
+            ._embed("http://127.0.0.1:1", "Hello".into())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Model text-embedding-ada-002 does not support the dimensions parameter"
+        );
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({
+                "model": "text-embedding-3-small",
+                "dimensions": 2,
+                "encoding_format": "base64"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "object": "list",
+                "model": "text-embedding-3-small",
+                "data": [{"object": "embedding", "embedding": "AACAPwAAAL8=", "index": 0}],
+                "usage": {"prompt_tokens": 1, "total_tokens": 1}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let client = client
+            .set_model("text-embedding-3-small")
+            .set_dimensions(2)
+            .set_encoding_format(EncodingFormat::Base64);
+        let res = client._embed(&server.uri(), "Hello".into()).await.unwrap();
+        assert_eq!(res.data[0].embedding, vec![1.0, -0.5]);
+    }
+
+    #[cfg(feature = "embeddings")]
+    #[tokio::test]
+    async fn test_embed_image_input() {
+        let client = offline_client::<Embedding>();
+        let image = ImageInput::url("https://example.com/cat.png");
+        let err = client
+            ._embed("http://127.0.0.1:1", image.clone().into())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Model text-embedding-ada-002 does not support image inputs"
+        );
+
+        let mut client = offline_client::<Embedding>().set_model("jina-clip-v2");
+        client.config.input = vec![
+            ImageInput::url("https://example.com/cat.png"),
+            ImageInput::base64("image/png", "iVBORw0KGgo="),
+        ]
+        .into();
+        assert_eq!(
+            client.request_body()["input"],
+            serde_json::json!([
+                {"image": "https://example.com/cat.png"},
+                {"image": "data:image/png;base64,iVBORw0KGgo="}
+            ])
+        );
+    }
+
+    #[cfg(feature = "audio")]
+    #[tokio::test]
+    async fn test_speak() {
+        use wiremock::matchers::{body_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/audio/speech"))
+            .and(body_json(serde_json::json!({
+                "model": "tts-1",
+                "input": "Hello",
+                "voice": "shimmer",
+                "response_format": "flac",
+                "speed": 1.5
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![0x66, 0x4c, 0x61, 0x43]))
+            .mount(&server)
+            .await;
+
+        let mut client = offline_client::<Speech>()
+            .with_base_url(server.uri())
+            .set_voice(Voice::Shimmer)
+            .set_response_format(SpeechResponseFormat::Flac)
+            .set_speed(1.5);
+        assert_eq!(client.speak("Hello").await.unwrap(), b"fLaC");
+
+        let mut client = client.set_speed(5.0);
+        assert!(client.speak("Hello").await.is_err());
+    }
+
+    #[cfg(feature = "images")]
+    #[tokio::test]
+    async fn test_dall_e_3() {
+        let server = mock_endpoint(
+            "POST",
+            "/images/generations",
+            wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "created": 0,
+                "data": [{
+                    "url": "https://example.com/image.png",
+                    "revised_prompt": "A vivid sunset over a calm sea"
+                }]
+            })),
+        )
+        .await;
+        let mut client = offline_client::<Image>()
+            .with_base_url(server.uri())
+            .set_model("dall-e-3")
+            .set_quality("hd")
+            .set_style("vivid")
+            .set_size(&Size::new(1792, 1024));
+        let res = client.create_response("A sunset").await.unwrap();
+        assert_eq!(
+            res.data[0].revised_prompt.as_deref(),
+            Some("A vivid sunset over a calm sea")
+        );
+        let body: serde_json::Value =
+            serde_json::from_slice(&server.received_requests().await.unwrap()[0].body).unwrap();
+        assert_eq!(body["model"], "dall-e-3");
+        assert_eq!(body["quality"], "hd");
+
+        let mut client = client.set_max_images(2);
+        let err = client.create("A sunset").await.unwrap_err();
+        assert_eq!(err.to_string(), "dall-e-3 only supports n = 1, got n = 2");
+
+        let mut client = client.set_max_images(1).set_size(&Size::new(512, 512));
+        assert!(client.create("A sunset").await.is_err());
+
+        let mut client = offline_client::<Image>().set_quality("hd");
+        assert!(client.create("A sunset").await.is_err());
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_retry_on_rate_limit() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(429))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let client = offline_client::<Chat>().with_retry(RetryConfig {
+            max_retries: 3,
+            base_delay: std::time::Duration::from_millis(1),
+            respect_retry_after: true,
+        });
+        let res = client._make_get_request(server.uri()).await.unwrap();
+        assert_eq!(res.status(), 200);
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[cfg(all(feature = "tracing", feature = "chat"))]
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_retry_attempts_are_traced() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let client = offline_client::<Chat>().with_retry(RetryConfig::default());
+        client._make_get_request(server.uri()).await.unwrap();
+        assert!(logs_contain("Retrying request"));
+        assert!(logs_contain("status=429"));
+        assert!(logs_contain("retry_after_ms=0"));
+        assert!(!logs_contain("sk-test"));
+    }
+
+    #[cfg(all(feature = "tracing", feature = "chat"))]
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_request_logging() {
+        let server = mock_endpoint(
+            "POST",
+            "/chat/completions",
+            wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hi"}}]
+            })),
+        )
+        .await;
+        let mut client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_stream_responses(false);
+        client.ask("Hello", false).await.unwrap();
+        assert!(!logs_contain("Sending request"));
+
+        let mut client = client.with_request_logging(true);
+        client.ask("Is sk-test my key?", false).await.unwrap();
+        assert!(logs_contain("Sending request"));
+        assert!(logs_contain("method=POST"));
+        assert!(logs_contain("/chat/completions"));
+        assert!(logs_contain("Is sk-*** my key?"));
+        assert!(logs_contain("Response received status=200"));
+        assert!(!logs_contain("sk-test"));
+    }
+
+    #[cfg(all(feature = "tracing", feature = "chat"))]
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_requests_are_traced() {
+        let server = mock_endpoint(
+            "POST",
+            "/chat/completions",
+            wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hi"}}],
+                "usage": {"prompt_tokens": 5, "completion_tokens": 1, "total_tokens": 6}
+            })),
+        )
+        .await;
+        let mut client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_model("gpt-4")
+            .set_stream_responses(false)
+            .set_temperature(3.5);
+        client.ask("Hello", false).await.unwrap();
+        assert!(logs_contain("Temperature out of range"));
+        assert!(logs_contain("openai_request"));
+        assert!(logs_contain("model=\"gpt-4\""));
+        assert!(logs_contain("status=200"));
+        assert!(!logs_contain("sk-test"));
+    }
+
+    #[cfg(all(feature = "blocking", feature = "chat"))]
+    #[test]
+    fn test_blocking_ask_streaming() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let body = format!(
+            "{}\n\n{}\n\ndata: [DONE]\n\n",
+            delta_line("Hello"),
+            delta_line("World")
+        );
+        // The mock server runs on its own thread, so it only needs a runtime to be started.
+        let server = tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/chat/completions"))
+                .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"))
+                .mount(&server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/models"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "object": "list",
+                    "data": [{"id": "gpt-4", "object": "model", "created": 0, "owned_by": "openai"}]
+                })))
+                .mount(&server)
+                .await;
+            server
+        });
+        let client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_stream_responses(true);
+        let mut client = crate::blocking::OpenAI::from_async(client).unwrap();
+        assert_eq!(client.models().unwrap(), ["gpt-4"]);
+
+        let mut tokens = Vec::new();
+        let answer = client
+            .ask_streaming("Hi", true, |token| tokens.push(token.to_string()))
+            .unwrap();
+        assert_eq!(tokens, ["Hello", "World"]);
+        assert_eq!(answer, "HelloWorld");
+        assert_eq!(
+            client
+                .get_ref()
+                .get_last_message()
+                .unwrap()
+                .content
+                .as_ref()
+                .unwrap()
+                .to_string(),
+            "HelloWorld"
+        );
+    }
+
+    #[cfg(all(feature = "blocking", feature = "chat"))]
+    #[test]
+    fn test_blocking_ask_streaming_function_call() {
+        let server = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(mock_endpoint(
+                "POST",
+                "/chat/completions",
+                wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "choices": [{
+                        "index": 0,
+                        "message": {
+                            "role": "assistant",
+                            "content": null,
+                            "function_call": {"name": "get_weather", "arguments": "{}"}
+                        },
+                        "finish_reason": "function_call"
+                    }]
+                })),
+            ));
+        let client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_stream_responses(false);
+        let mut client = crate::blocking::OpenAI::from_async(client).unwrap();
+        client
+            .get_mut()
+            .config
+            .messages
+            .push(Message::system("You are a weather bot."));
+
+        let err = client
+            .ask_streaming("How is the weather?", true, |_| {})
+            .unwrap_err();
+        assert!(err.to_string().contains("get_weather"));
+        assert_eq!(client.get_ref().config.messages.len(), 1);
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_last_rate_limit() {
+        let server = mock_endpoint(
+            "POST",
+            "/chat/completions",
+            wiremock::ResponseTemplate::new(200)
+                .insert_header("x-ratelimit-limit-requests", "3500")
+                .insert_header("x-ratelimit-limit-tokens", "90000")
+                .insert_header("x-ratelimit-remaining-requests", "3499")
+                .insert_header("x-ratelimit-remaining-tokens", "89975")
+                .insert_header("x-ratelimit-reset-requests", "17ms")
+                .insert_header("x-ratelimit-reset-tokens", "1m2.5s")
+                .set_body_raw(
+                    format!("{}\n\ndata: [DONE]\n\n", delta_line("Hi")),
+                    "text/event-stream",
+                ),
+        )
+        .await;
+        let mut client = offline_client::<Chat>().with_base_url(server.uri());
+        assert!(client.last_rate_limit().is_none());
+        client.ask("Hello", false).await.unwrap();
+
+        let info = client.last_rate_limit().unwrap();
+        assert_eq!(info.limit_requests, Some(3500));
+        assert_eq!(info.limit_tokens, Some(90000));
+        assert_eq!(info.remaining_requests, Some(3499));
+        assert_eq!(info.remaining_tokens, Some(89975));
+        assert_eq!(
+            info.reset_requests,
+            Some(std::time::Duration::from_millis(17))
+        );
+        assert_eq!(
+            info.reset_tokens,
+            Some(std::time::Duration::from_millis(62_500))
+        );
+    }
+
+    #[cfg(all(feature = "embeddings", feature = "files"))]
+    #[tokio::test]
+    async fn test_request_id() {
+        let server = mock_endpoint(
+            "POST",
+            "/embeddings",
+            wiremock::ResponseTemplate::new(200)
+                .insert_header("x-request-id", "req_123")
+                .set_body_json(serde_json::json!({
+                    "object": "list",
+                    "model": "text-embedding-ada-002",
+                    "data": [{"object": "embedding", "embedding": [0.1], "index": 0}],
+                    "usage": {"prompt_tokens": 1, "total_tokens": 1}
+                })),
+        )
+        .await;
+        let client = offline_client::<Embedding>().with_base_url(server.uri());
+        assert_eq!(client.last_request_id(), None);
+        client.embed("Hello").await.unwrap();
+        assert_eq!(client.last_request_id().as_deref(), Some("req_123"));
+
+        let server = mock_endpoint(
+            "DELETE",
+            "/files/invalid_file_id",
+            api_error("No such File object: invalid_file_id")
+                .insert_header("x-request-id", "req_456"),
+        )
+        .await;
+        let err = offline_client::<Files>()
+            .with_base_url(server.uri())
+            .delete("invalid_file_id")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "No such File object: invalid_file_id (request id: req_456)"
+        );
+    }
 
-        let handled_res = self.handle_api_errors(res).await?;
-        let res: FineTuneEventResponse = handled_res.json().await?;
-        Ok(res)
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_api_error_predicates() {
+        let server = mock_endpoint(
+            "POST",
+            "/chat/completions",
+            wiremock::ResponseTemplate::new(400).set_body_json(serde_json::json!({"error": {
+                "message": "This model's maximum context length is 4097 tokens.",
+                "type": "invalid_request_error",
+                "param": "messages",
+                "code": "context_length_exceeded"
+            }})),
+        )
+        .await;
+        let client = offline_client::<Chat>().with_base_url(server.uri());
+        let res = client
+            ._make_post_request(format!("{}/chat/completions", server.uri()))
+            .await
+            .unwrap();
+        let err = client.handle_api_errors(res).await.unwrap_err();
+        let err = err.downcast_ref::<ApiError>().unwrap();
+        assert_eq!(err.status, 400);
+        assert!(err.endpoint.ends_with("/chat/completions"));
+        assert!(err.is_context_length_exceeded());
+        assert!(!err.is_rate_limited());
+        assert!(!err.is_invalid_api_key());
+        assert!(!err.is_model_not_found());
+        assert_eq!(
+            err.details.as_ref().unwrap().param.as_deref(),
+            Some("messages")
+        );
     }
 
-    /// Delete a fine-tuned model. You must have the Owner role in your organization.
-    ///
-    /// # Arguments
-    ///
-    /// * `model` - The model to delete
-    ///
-    /// # Returns
-    ///
-    /// `Result<DeleteResponse, Box<dyn std::error::Error + Send + Sync>>`:
-    /// A `DeleteResponse` object representing the status of the delete request,
-    /// or an error if the request fails.
-    pub async fn delete_model<S: Into<String> + Send + Sync + std::fmt::Display>(
-        &mut self,
-        model: S,
-    ) -> Result<DeleteResponse, Box<dyn std::error::Error + Send + Sync>> {
-        let url = format!("{}/{}", Self::OPENAI_API_MODELS_URL, model);
-        let res = self._make_delete_request(url).await?;
+    #[cfg(feature = "files")]
+    #[tokio::test]
+    async fn test_non_json_error_body() {
+        let html = format!(
+            "<html><head><title>502 Bad Gateway</title></head><body>{}</body></html>",
+            "x".repeat(500)
+        );
+        let server = mock_endpoint(
+            "GET",
+            "/files",
+            wiremock::ResponseTemplate::new(502).set_body_raw(html, "text/html"),
+        )
+        .await;
+        let client = offline_client::<Files>().with_base_url(server.uri());
+        let msg = client.list().await.unwrap_err().to_string();
+        assert!(msg.starts_with("HTTP 502 Bad Gateway: <html><head><title>502 Bad Gateway"));
+        assert!(msg.ends_with("..."));
+        assert!(msg.len() < 300);
+
+        let server = mock_endpoint(
+            "GET",
+            "/files",
+            wiremock::ResponseTemplate::new(403).set_body_string("Access denied"),
+        )
+        .await;
+        let client = offline_client::<Files>().with_base_url(server.uri());
+        assert_eq!(
+            client.list().await.unwrap_err().to_string(),
+            "HTTP 403 Forbidden: Access denied"
+        );
+    }
 
-        let handled_res = self.handle_api_errors(res).await?;
-        let res: DeleteResponse = handled_res.json().await?;
-        Ok(res)
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_default_client_without_api_key() {
+        let client = OpenAI::<Chat>::default();
+        assert!(client.api_key.is_empty());
+        let err = client.models().await.unwrap_err();
+        let err = err.downcast_ref::<std::io::Error>().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
     }
-}
 
-// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
-// = OpenAI MODERATIONS IMPLEMENTATION
-// =-=-=-=-=--=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
+    #[test]
+    fn test_parse_retry_after() {
+        use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
 
-impl OpenAI<Moderation> {
-    const OPENAI_API_MODERATIONS_URL: &str = "https://api.openai.com/v1/moderations";
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry::parse_retry_after(&headers), None);
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("7"));
+        assert_eq!(
+            retry::parse_retry_after(&headers),
+            Some(std::time::Duration::from_secs(7))
+        );
 
-    /// Create moderation for a classification if text violates `OpenAI`'s Content Policy
-    ///
-    /// # Arguments
-    ///
-    /// * `input` - The text input to classify
-    ///
-    /// # Returns
-    ///
-    /// `Result<, Box<dyn std::error::Error + Send + Sync>>`:
-    /// A `ModerationResponse` object representing the result of the moderation request,
-    /// or an error if the request fails.
-    pub async fn moderate<S: Into<String> + Send + Sync>(
-        &mut self,
-        input: S,
-    ) -> Result<ModerationResponse, Box<dyn std::error::Error + Send + Sync>> {
-        self.config.input = input.into();
-        let res: reqwest::Response = self
-            ._make_post_request(Self::OPENAI_API_MODERATIONS_URL)
-            .await?;
+        let in_a_minute = std::time::SystemTime::now() + std::time::Duration::from_secs(60);
+        let date = httpdate::fmt_http_date(in_a_minute);
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(&date).unwrap());
+        let delay = retry::parse_retry_after(&headers).unwrap();
+        assert!(
+            delay > std::time::Duration::from_secs(55)
+                && delay <= std::time::Duration::from_secs(60)
+        );
 
-        let handled_res = self.handle_api_errors(res).await?;
-        let mod_resp: ModerationResponse = handled_res.json().await?;
-        Ok(mod_resp)
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Sun, 06 Nov 1994 08:49:37 GMT"),
+        );
+        assert_eq!(
+            retry::parse_retry_after(&headers),
+            Some(std::time::Duration::ZERO)
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_rate_limited_error_exposes_retry_after() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", "12")
+                    .set_body_json(serde_json::json!({"error": {
+                        "message": "Rate limit reached",
+                        "type": "requests",
+                        "param": null,
+                        "code": "rate_limit_exceeded"
+                    }})),
+            )
+            .mount(&server)
+            .await;
+
+        let client = offline_client::<Chat>();
+        let res = client._make_get_request(server.uri()).await.unwrap();
+        let err = client.handle_api_errors(res).await.unwrap_err();
+        let rate_limited = err.downcast_ref::<ApiError>().unwrap();
+        assert!(rate_limited.is_rate_limited());
+        assert_eq!(rate_limited.code(), Some("rate_limit_exceeded"));
+        assert_eq!(rate_limited.request_id, None);
+        assert_eq!(rate_limited.message, "Rate limit reached");
+        assert_eq!(
+            rate_limited.retry_after,
+            Some(std::time::Duration::from_secs(12))
+        );
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_no_retry_on_bad_request() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(400))
+            .mount(&server)
+            .await;
+
+        let client = offline_client::<Chat>().with_retry(RetryConfig::default());
+        let res = client._make_get_request(server.uri()).await.unwrap();
+        assert_eq!(res.status(), 400);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
 
+    #[cfg(feature = "chat")]
     #[tokio::test]
     async fn test_get_all_models() {
-        let mut client = OpenAI::<Chat>::new();
+        let server = mock_endpoint(
+            "GET",
+            "/models",
+            wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "object": "list",
+                "data": [{"id": "gpt-3.5-turbo", "object": "model", "owned_by": "openai"}]
+            })),
+        )
+        .await;
+        let client = offline_client::<Chat>().with_base_url(server.uri());
         let models = client.models().await;
         assert!(models.is_ok());
         assert!(models.unwrap().contains(&"gpt-3.5-turbo".to_string()));
     }
 
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_models_detailed() {
+        let server = mock_endpoint(
+            "GET",
+            "/models",
+            wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "object": "list",
+                "data": [
+                    {"id": "gpt-3.5-turbo", "object": "model", "owned_by": "openai", "created": 1677610602},
+                    {
+                        "id": "ft:gpt-3.5-turbo:acme::abc123",
+                        "object": "model",
+                        "owned_by": "org-acme",
+                        "created": 1692901427,
+                        "permission": [{
+                            "id": "modelperm-abc123",
+                            "object": "model_permission",
+                            "created": 1692901427,
+                            "allow_sampling": true,
+                            "allow_view": true,
+                            "organization": "*",
+                            "group": null,
+                            "is_blocking": false
+                        }]
+                    }
+                ]
+            })),
+        )
+        .await;
+        let client = offline_client::<Chat>().with_base_url(server.uri());
+        let models = client.models_detailed().await.unwrap();
+        assert_eq!(models.len(), 2);
+        assert_eq!(models[0].created, Some(1_677_610_602));
+        assert!(models[0].permission.is_none());
+        let permission = &models[1].permission.as_ref().unwrap()[0];
+        assert!(permission.allow_sampling);
+        assert!(!permission.allow_fine_tuning);
+        assert_eq!(permission.organization.as_deref(), Some("*"));
+
+        let owned = client.models_owned_by("openai").await.unwrap();
+        assert_eq!(owned.len(), 1);
+        assert_eq!(owned[0].id, "gpt-3.5-turbo");
+    }
+
+    #[cfg(feature = "chat")]
     #[tokio::test]
     async fn test_check_model() {
-        let mut client = OpenAI::<Chat>::new();
+        let server = mock_endpoint(
+            "GET",
+            "/models/gpt-3.5-turbo",
+            wiremock::ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({"id": "gpt-3.5-turbo", "object": "model", "owned_by": "openai"}),
+            ),
+        )
+        .await;
+        let client = offline_client::<Chat>().with_base_url(server.uri());
         let model = client.check_model("gpt-3.5-turbo").await;
         assert!(model.is_ok());
     }
 
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_concurrent_read_only_requests() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/models"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"object": "list", "data": []})),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/models/gpt-4"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({"id": "gpt-4", "object": "model", "owned_by": "openai"}),
+            ))
+            .mount(&server)
+            .await;
+        let client = offline_client::<Chat>().with_base_url(server.uri());
+        let (models, model) = tokio::join!(client.models(), client.check_model("gpt-4"));
+        assert!(models.is_ok());
+        assert!(model.is_ok());
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_chat_api_errors() {
+        let server = mock_endpoint(
+            "POST",
+            "/chat/completions",
+            api_error("Invalid value for 'temperature'"),
+        )
+        .await;
+        for stream in [false, true] {
+            let mut client = offline_client::<Chat>()
+                .with_base_url(server.uri())
+                .set_stream_responses(stream);
+            let err = client.ask("Hello", false).await.unwrap_err();
+            let err = err.downcast_ref::<ApiError>().unwrap();
+            assert_eq!(err.status, reqwest::StatusCode::NOT_FOUND);
+            assert_eq!(err.message, "Invalid value for 'temperature'");
+            let err = client.ask_many("Hello", false).await.unwrap_err();
+            assert!(err.downcast_ref::<ApiError>().is_some());
+        }
+    }
+
+    #[cfg(feature = "chat")]
     #[tokio::test]
     async fn test_check_model_error() {
-        let mut client = OpenAI::<Chat>::new();
+        let server = mock_endpoint(
+            "GET",
+            "/models/gpt-turbo",
+            api_error("The model 'gpt-turbo' does not exist"),
+        )
+        .await;
+        let client = offline_client::<Chat>().with_base_url(server.uri());
         let model = client.check_model("gpt-turbo").await;
         assert!(model.is_err());
     }
 
+    #[cfg(feature = "chat")]
     #[tokio::test]
     async fn test_single_request() {
-        let mut client = OpenAI::<Chat>::new().set_stream_responses(false);
+        let server = mock_endpoint(
+            "POST",
+            "/chat/completions",
+            wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "gpt-3.5-turbo",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "This is a test!"},
+                    "finish_reason": "stop"
+                }],
+                "usage": {"prompt_tokens": 5, "completion_tokens": 5, "total_tokens": 10}
+            })),
+        )
+        .await;
+        let mut client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .set_stream_responses(false);
         let reply = client.ask("Say this is a test!", false).await;
         assert!(reply.is_ok());
         assert!(reply.unwrap().contains("This is a test"));
     }
 
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let body = format!(
+            "{}\n\n{}\n\ndata: [DONE]\n\n",
+            delta_line("Grüße "),
+            delta_line("from the cassette")
+        );
+        let server = mock_endpoint(
+            "POST",
+            "/chat/completions",
+            wiremock::ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"),
+        )
+        .await;
+        let dir = env::temp_dir().join(format!("aionic_cassettes_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut client = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .with_transport(recorder::Recorder::record(&dir));
+        let recorded = client.ask("Hello", false).await.unwrap();
+        let cassettes: Vec<_> = fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(cassettes.len(), 1);
+        let cassette = fs::read_to_string(cassettes[0].as_ref().unwrap().path()).unwrap();
+        assert!(!cassette.contains("sk-test"));
+        assert!(cassette.contains("[REDACTED]"));
+
+        drop(server);
+        let mut client = offline_client::<Chat>()
+            .with_base_url("http://127.0.0.1:1")
+            .with_transport(recorder::Recorder::replay(&dir));
+        let replayed = client.ask("Hello", false).await.unwrap();
+        assert_eq!(replayed, recorded);
+        let missing = client.ask("Something else", false).await;
+        assert_eq!(
+            missing
+                .unwrap_err()
+                .downcast_ref::<io::Error>()
+                .unwrap()
+                .kind(),
+            io::ErrorKind::NotFound
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "audio")]
+    #[tokio::test]
+    async fn test_record_binary_body_through_proxy() {
+        let audio = vec![0xff, 0xfb, 0x00, 0x80, 0xc3, 0x28];
+        let proxy = mock_endpoint(
+            "POST",
+            "/v1/audio/speech",
+            wiremock::ResponseTemplate::new(200).set_body_raw(audio.clone(), "audio/mpeg"),
+        )
+        .await;
+        let dir = env::temp_dir().join(format!("aionic_binary_cassettes_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut client = offline_client::<Speech>()
+            .with_base_url("http://api.openai.invalid/v1")
+            .with_proxy(proxy.uri())
+            .unwrap()
+            .with_transport(recorder::Recorder::record(&dir));
+        assert_eq!(client.speak("Hello").await.unwrap(), audio);
+
+        drop(proxy);
+        let mut client = offline_client::<Speech>()
+            .with_base_url("http://api.openai.invalid/v1")
+            .with_transport(recorder::Recorder::replay(&dir));
+        assert_eq!(client.speak("Hello").await.unwrap(), audio);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_save_and_load_history() {
+        let mut function_result = Message::new(&MessageRole::Function, "{\"temp\": 22}");
+        function_result.name = Some("get_weather".to_string());
+        let mut function_call = Message::new(&MessageRole::Assistant, "");
+        function_call.function_call = Some(FunctionCall {
+            name: "get_weather".to_string(),
+            arguments: "{\"city\": \"Berlin\"}".to_string(),
+        });
+        let messages = vec![
+            Message::system("You are a weather bot."),
+            Message::new(&MessageRole::User, "How warm is it in Berlin?"),
+            function_call,
+            function_result,
+        ];
+        let client = offline_client::<Chat>().import_history(messages.clone());
+        assert_eq!(client.export_history(), messages);
+
+        let path = env::temp_dir().join(format!("aionic_history_{}.json", std::process::id()));
+        client.save_history(&path).unwrap();
+        let mut resumed = offline_client::<Chat>().set_primer("Another primer");
+        resumed.load_history(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(resumed.export_history(), messages);
+        assert!(resumed.load_history(&path).is_err());
+        assert_eq!(resumed.export_history(), messages);
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_debug_redacts_api_key() {
+        let client = OpenAI::<Chat>::default().with_api_key("sk-secret-key-1234");
+        let debug = format!("{client:?}");
+        assert!(!debug.contains("sk-secret-key-1234"));
+        assert!(debug.contains("sk-****"));
+        let request = client
+            ._build_untimed_request(Method::GET, "https://api.openai.com/v1/models")
+            .build()
+            .unwrap();
+        assert!(!format!("{request:?}").contains("sk-secret-key-1234"));
+    }
+
+    #[cfg(feature = "images")]
+    #[tokio::test]
+    async fn test_create_and_save() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer};
+
+        let png = fs::read("img/logo.png").unwrap();
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/images/generations"))
+            .respond_with(
+                wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "created": 0,
+                    "data": [{"url": format!("{}/files/generated.png", server.uri())}]
+                })),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/files/generated.png"))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_bytes(png.clone()))
+            .mount(&server)
+            .await;
+
+        let dir = env::temp_dir().join(format!("aionic_images_{}", std::process::id()));
+        let paths = offline_client::<Image>()
+            .with_base_url(server.uri())
+            .create_and_save("A logo", dir.join("url"))
+            .await
+            .unwrap();
+        assert_eq!(paths, vec![dir.join("url").join("image_0.png")]);
+        assert_eq!(fs::read(&paths[0]).unwrap(), png);
+
+        let b64_server = mock_endpoint(
+            "POST",
+            "/images/generations",
+            wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "created": 0,
+                "data": [
+                    {"b64_json": base64::engine::general_purpose::STANDARD.encode(&png)},
+                    {"b64_json": base64::engine::general_purpose::STANDARD.encode(b"second")}
+                ]
+            })),
+        )
+        .await;
+        let paths = offline_client::<Image>()
+            .with_base_url(b64_server.uri())
+            .set_response_format(&ResponseDataType::Base64Json)
+            .set_max_images(2)
+            .create_and_save("A logo", dir.join("b64"))
+            .await
+            .unwrap();
+        assert_eq!(paths.len(), 2);
+        assert_eq!(fs::read(&paths[0]).unwrap(), png);
+        assert_eq!(fs::read(&paths[1]).unwrap(), b"second");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_secret_key() {
+        let client = OpenAI::<Chat>::default().with_api_key("sk-secret-key-1234");
+        let clone = client.clone();
+        drop(client);
+        assert!(clone.api_key.expose(|key| key == "sk-secret-key-1234"));
+        assert_eq!(format!("{:?}", clone.api_key), "SecretKey(\"sk-****\")");
+        assert_eq!(format!("{:?}", SecretKey::default()), "SecretKey(\"\")");
+    }
+
+    #[cfg(feature = "images")]
+    #[test]
+    fn test_decode_image_response() {
+        let png = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==";
+        let response: ImageResponse = serde_json::from_value(serde_json::json!({
+            "created": 0,
+            "data": [{"b64_json": png}]
+        }))
+        .unwrap();
+        let images = response.decoded().unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].len(), 70);
+        assert!(Image::is_png(&images[0]));
+
+        let response: ImageResponse = serde_json::from_value(image_response(1)).unwrap();
+        assert!(response.decoded().is_err());
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_proxy() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer};
+
+        let proxy = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .and(header("proxy-authorization", "Basic dXNlcjpwYXNz"))
+            .respond_with(
+                wiremock::ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"object": "list", "data": []})),
+            )
+            .mount(&proxy)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("proxy-authorization", "Basic dXNlcjpwYXNz"))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_raw(
+                format!("{}\n\ndata: [DONE]\n\n", delta_line("Proxied")),
+                "text/event-stream",
+            ))
+            .mount(&proxy)
+            .await;
+
+        let mut client = offline_client::<Chat>()
+            .with_base_url("http://api.openai.invalid/v1")
+            .with_connect_timeout(Duration::from_secs(5))
+            .unwrap()
+            .with_proxy_basic_auth(proxy.uri(), "user", "pass")
+            .unwrap();
+        assert!(client.models().await.is_ok());
+        assert_eq!(client.ask("Hi", false).await.unwrap(), "Proxied");
+        assert!(offline_client::<Chat>().with_proxy("not a url").is_err());
+    }
+
+    #[cfg(feature = "chat")]
+    #[test]
+    fn test_openai_client_alias() {
+        let client: OpenAIClient<Chat> = offline_client();
+        let _: OpenAI<Chat> = client.set_model("gpt-4");
+    }
+
+    #[cfg(all(feature = "chat", feature = "embeddings", feature = "moderations"))]
+    #[tokio::test]
+    async fn test_into_endpoint() {
+        let server = mock_endpoint(
+            "POST",
+            "/embeddings",
+            wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "object": "list",
+                "model": "text-embedding-ada-002",
+                "data": [{"object": "embedding", "embedding": [0.1, 0.2], "index": 0}],
+                "usage": {"prompt_tokens": 8, "total_tokens": 8}
+            })),
+        )
+        .await;
+        let chat = offline_client::<Chat>()
+            .with_base_url(server.uri())
+            .with_organization("org-test")
+            .set_model("gpt-4");
+        let embedding = chat.clone().into_endpoint::<Embedding>();
+        assert_eq!(embedding.base_url, chat.base_url);
+        assert!(embedding.api_key.expose(|key| key == "sk-test"));
+        assert_eq!(embedding.organization.as_deref(), Some("org-test"));
+        assert_eq!(embedding.config.model, Embedding::get_default_model());
+        assert!(embedding.embed("Hello").await.is_ok());
+
+        let moderation: OpenAI<Moderation> = embedding.into_endpoint();
+        assert!(moderation.last_rate_limit().is_none());
+    }
+
+    #[cfg(feature = "chat")]
+    #[tokio::test]
+    async fn test_ask_many() {
+        let choice = |index: u64, content: &str| {
+            serde_json::json!({
+                "index": index,
+                "message": {"role": "assistant", "content": content},
+                "finish_reason": "stop"
+            })
+        };
+        let server = mock_endpoint(
+            "POST",
+            "/chat/completions",
+            wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [choice(2, "Blue"), choice(0, "Red"), choice(1, "Green")],
+                "usage": {"prompt_tokens": 5, "completion_tokens": 3, "total_tokens": 8}
+            })),
+        )
+        .await;
+        let mut client = offline_client::<Chat>().with_base_url(server.uri());
+        client.config.n = Some(3);
+        let answers = client.ask_many("Name a color", true).await.unwrap();
+        assert_eq!(answers, vec!["Red", "Green", "Blue"]);
+        assert_eq!(client.config.stream, Some(true));
+        assert_eq!(client.config.messages.len(), 2);
+        assert_eq!(client.config.messages[1].content.as_ref().unwrap(), "Red");
+
+        let answer = client
+            .set_stream_responses(false)
+            .ask("Name a color", false)
+            .await
+            .unwrap();
+        assert_eq!(answer, "Red");
+    }
+
+    #[cfg(feature = "chat")]
     #[tokio::test]
     async fn test_single_request_streamed() {
-        let mut client = OpenAI::<Chat>::new();
+        let body = format!(
+            "{}\n\n{}\n\ndata: [DONE]\n\n",
+            delta_line("This is "),
+            delta_line("a test!")
+        );
+        let server = mock_endpoint(
+            "POST",
+            "/chat/completions",
+            wiremock::ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"),
+        )
+        .await;
+        let mut client = offline_client::<Chat>().with_base_url(server.uri());
         let reply = client.ask("Say this is a test!", false).await;
         assert!(reply.is_ok());
         assert!(reply.unwrap().contains("This is a test"));
     }
 
+    #[cfg(feature = "images")]
     #[tokio::test]
     async fn test_create_single_image_url() {
-        let mut client = OpenAI::<Image>::new();
+        let server = mock_endpoint(
+            "POST",
+            "/images/generations",
+            wiremock::ResponseTemplate::new(200).set_body_json(image_response(1)),
+        )
+        .await;
+        let mut client = offline_client::<Image>().with_base_url(server.uri());
         let images = client.create("A beautiful sunset over the sea.").await;
         assert!(images.is_ok());
         assert_eq!(images.unwrap().len(), 1);
     }
 
+    #[cfg(feature = "images")]
     #[tokio::test]
     async fn test_create_multiple_image_urls() {
-        let mut client = OpenAI::<Image>::new().set_max_images(2);
+        let server = mock_endpoint(
+            "POST",
+            "/images/generations",
+            wiremock::ResponseTemplate::new(200).set_body_json(image_response(2)),
+        )
+        .await;
+        let mut client = offline_client::<Image>()
+            .with_base_url(server.uri())
+            .set_max_images(2);
         let images = client
             .create("A logo for a library written in Rust that deals with AI")
             .await;
@@ -1726,25 +9906,50 @@ mod tests {
         assert_eq!(images.unwrap().len(), 2);
     }
 
+    #[cfg(feature = "images")]
     #[tokio::test]
     async fn test_create_image_b64_json() {
-        let mut client = OpenAI::<Image>::new().set_response_format(&ResponseDataType::Base64Json);
+        let server = mock_endpoint(
+            "POST",
+            "/images/generations",
+            wiremock::ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({"created": 0, "data": [{"b64_json": "iVBORw0KGgo="}]}),
+            ),
+        )
+        .await;
+        let mut client = offline_client::<Image>()
+            .with_base_url(server.uri())
+            .set_response_format(&ResponseDataType::Base64Json);
         let images = client.create("A beautiful sunset over the sea.").await;
         assert!(images.is_ok());
         assert_eq!(images.unwrap().len(), 1);
     }
 
+    #[cfg(feature = "images")]
     #[tokio::test]
     async fn test_image_variation() {
-        let mut client = OpenAI::<Image>::new();
+        let server = mock_endpoint(
+            "POST",
+            "/images/variations",
+            wiremock::ResponseTemplate::new(200).set_body_json(image_response(1)),
+        )
+        .await;
+        let mut client = offline_client::<Image>().with_base_url(server.uri());
         let images = client.variation("./img/logo.png").await;
         assert!(images.is_ok());
         assert_eq!(images.unwrap().len(), 1);
     }
 
+    #[cfg(feature = "images")]
     #[tokio::test]
     async fn test_image_edit() {
-        let mut client = OpenAI::<Image>::new();
+        let server = mock_endpoint(
+            "POST",
+            "/images/edits",
+            wiremock::ResponseTemplate::new(200).set_body_json(image_response(1)),
+        )
+        .await;
+        let mut client = offline_client::<Image>().with_base_url(server.uri());
         let images = client
             .edit("Make the background transparent", "./img/logo.png", None)
             .await;
@@ -1752,9 +9957,21 @@ mod tests {
         assert_eq!(images.unwrap().len(), 1);
     }
 
+    #[cfg(feature = "embeddings")]
     #[tokio::test]
     async fn test_embedding() {
-        let mut client = OpenAI::<Embedding>::new();
+        let server = mock_endpoint(
+            "POST",
+            "/embeddings",
+            wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "object": "list",
+                "model": "text-embedding-ada-002",
+                "data": [{"object": "embedding", "embedding": [0.1, 0.2], "index": 0}],
+                "usage": {"prompt_tokens": 8, "total_tokens": 8}
+            })),
+        )
+        .await;
+        let client = offline_client::<Embedding>().with_base_url(server.uri());
         let embedding = client
             .embed("The food was delicious and the waiter...")
             .await;
@@ -1762,31 +9979,165 @@ mod tests {
         assert!(!embedding.unwrap().data.is_empty());
     }
 
+    #[cfg(feature = "audio")]
     #[tokio::test]
     async fn test_transcribe() {
-        let mut client = OpenAI::<Audio>::new();
+        let server = mock_endpoint(
+            "POST",
+            "/audio/transcriptions",
+            wiremock::ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({"text": "Hello"})),
+        )
+        .await;
+        let mut client = offline_client::<Audio>().with_base_url(server.uri());
         let transcribe = client.transcribe("examples/samples/sample-1.mp3").await;
         assert!(transcribe.is_ok());
     }
 
+    #[cfg(feature = "audio")]
+    #[tokio::test]
+    async fn test_transcribe_oversized_file() {
+        let path = env::temp_dir().join(format!("aionic_oversized_{}.mp3", std::process::id()));
+        fs::File::create(&path)
+            .unwrap()
+            .set_len(Audio::MAX_FILE_SIZE_BYTES + 1)
+            .unwrap();
+        let server = mock_endpoint(
+            "POST",
+            "/audio/transcriptions",
+            wiremock::ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({"text": "Hello"})),
+        )
+        .await;
+        let mut client = offline_client::<Audio>().with_base_url(server.uri());
+        let err = client.transcribe(&path).await.unwrap_err();
+        fs::remove_file(&path).unwrap();
+        let err = err.downcast_ref::<io::Error>().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("split or compress"));
+    }
+
+    #[cfg(feature = "audio")]
+    #[tokio::test]
+    async fn test_transcribe_verbose() {
+        let server = mock_endpoint(
+            "POST",
+            "/audio/transcriptions",
+            wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "task": "transcribe",
+                "language": "english",
+                "duration": 2.5,
+                "text": "Hello world",
+                "segments": [{
+                    "id": 0,
+                    "seek": 0,
+                    "start": 0.0,
+                    "end": 2.5,
+                    "text": "Hello world",
+                    "tokens": [50364, 2425, 1002],
+                    "temperature": 0.0,
+                    "avg_logprob": -0.3,
+                    "compression_ratio": 0.8,
+                    "no_speech_prob": 0.01
+                }]
+            })),
+        )
+        .await;
+        let mut client = offline_client::<Audio>().with_base_url(server.uri());
+        let transcription = client
+            .transcribe_verbose("examples/samples/sample-1.mp3")
+            .await
+            .unwrap();
+        assert_eq!(transcription.language, "english");
+        assert_eq!(transcription.segments.len(), 1);
+        assert_eq!(transcription.segments[0].end, 2.5);
+        assert!(matches!(
+            client.config.response_format,
+            Some(AudioResponseFormat::Json)
+        ));
+    }
+
+    #[cfg(feature = "audio")]
+    #[tokio::test]
+    async fn test_timestamp_granularities() {
+        let server = mock_endpoint(
+            "POST",
+            "/audio/transcriptions",
+            wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "language": "english",
+                "duration": 1.0,
+                "text": "Hello world",
+                "words": [
+                    {"word": "Hello", "start": 0.0, "end": 0.4},
+                    {"word": "world", "start": 0.5, "end": 1.0}
+                ]
+            })),
+        )
+        .await;
+        let mut client = offline_client::<Audio>()
+            .with_base_url(server.uri())
+            .set_timestamp_granularities(vec![Granularity::Word, Granularity::Segment]);
+        let err = client
+            .transcribe("examples/samples/sample-1.mp3")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("verbose_json"));
+        let transcription = client
+            .transcribe_verbose("examples/samples/sample-1.mp3")
+            .await
+            .unwrap();
+        assert_eq!(transcription.words.len(), 2);
+        assert_eq!(transcription.words[1].word, "world");
+        assert!(transcription.segments.is_empty());
+    }
+
+    #[cfg(feature = "audio")]
     #[tokio::test]
     async fn test_translate() {
-        let mut client = OpenAI::<Audio>::new();
+        let server = mock_endpoint(
+            "POST",
+            "/audio/translations",
+            wiremock::ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({"text": "Red, green, blue"})),
+        )
+        .await;
+        let mut client = offline_client::<Audio>().with_base_url(server.uri());
         let translate = client
             .translate("examples/samples/colours-german.mp3")
             .await;
         assert!(translate.is_ok());
     }
 
+    #[cfg(feature = "files")]
     #[tokio::test]
     async fn test_list_files() {
-        let files = OpenAI::<Files>::new().list().await;
+        let server = mock_endpoint(
+            "GET",
+            "/files",
+            wiremock::ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({"object": "list", "data": []})),
+        )
+        .await;
+        let files = offline_client::<Files>()
+            .with_base_url(server.uri())
+            .list()
+            .await;
         assert!(files.is_ok());
     }
 
+    #[cfg(feature = "files")]
     #[tokio::test]
     async fn test_delete_non_existing_file() {
-        let files = OpenAI::<Files>::new().delete("invalid_file_id").await;
+        let server = mock_endpoint(
+            "DELETE",
+            "/files/invalid_file_id",
+            api_error("No such File object: invalid_file_id"),
+        )
+        .await;
+        let files = offline_client::<Files>()
+            .with_base_url(server.uri())
+            .delete("invalid_file_id")
+            .await;
         assert!(files.is_err());
         assert_eq!(
             files.unwrap_err().to_string(),
@@ -1794,9 +10145,50 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "files")]
+    #[tokio::test]
+    async fn test_upload_with_purpose() {
+        use wiremock::matchers::{body_string_contains, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/files"))
+            .and(body_string_contains("assistants"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(file_data("file-abc123")))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let mut client = offline_client::<Files>().with_base_url(server.uri());
+        let notes = env::temp_dir().join(format!(
+            "aionic_upload_with_purpose_{}.txt",
+            std::process::id()
+        ));
+        fs::write(&notes, "Some notes for an assistant.").unwrap();
+
+        let file = client
+            .upload_with_purpose(&notes, FilePurpose::Assistants)
+            .await
+            .unwrap();
+        assert_eq!(file.id, "file-abc123");
+        assert_eq!(client.config.purpose.as_deref(), Some("assistants"));
+
+        let err = client
+            .upload_with_purpose(&notes, FilePurpose::FineTune)
+            .await
+            .unwrap_err();
+        fs::remove_file(&notes).unwrap();
+        assert!(err.to_string().contains("must be a .jsonl file"));
+        assert_eq!(
+            serde_json::to_value(FilePurpose::FineTune).unwrap(),
+            "fine-tune"
+        );
+    }
+
+    #[cfg(feature = "files")]
     #[tokio::test]
     async fn test_upload_non_existing_file() {
-        let files = OpenAI::<Files>::new().upload("invalid_file").await;
+        let files = offline_client::<Files>().upload("invalid_file").await;
         assert!(files.is_err());
         assert_eq!(
             files.unwrap_err().to_string(),
@@ -1804,10 +10196,55 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "files")]
     #[tokio::test]
     async fn test_file_ops() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
         let test_file = "examples/samples/test.jsonl";
-        let mut client = OpenAI::<Files>::new();
+        let file_id = "file-abc123";
+        let contents = fs::read_to_string(test_file).unwrap();
+        let server = MockServer::start().await;
+        let routes = [
+            (
+                "POST",
+                "/files",
+                ResponseTemplate::new(200).set_body_json(file_data(file_id)),
+            ),
+            (
+                "GET",
+                "/files",
+                ResponseTemplate::new(200).set_body_json(
+                    serde_json::json!({"object": "list", "data": [file_data(file_id)]}),
+                ),
+            ),
+            (
+                "GET",
+                "/files/file-abc123",
+                ResponseTemplate::new(200).set_body_json(file_data(file_id)),
+            ),
+            (
+                "GET",
+                "/files/file-abc123/content",
+                ResponseTemplate::new(200).set_body_string(contents),
+            ),
+            (
+                "DELETE",
+                "/files/file-abc123",
+                ResponseTemplate::new(200).set_body_json(
+                    serde_json::json!({"object": "file", "id": file_id, "deleted": true}),
+                ),
+            ),
+        ];
+        for (http_method, endpoint, response) in routes {
+            Mock::given(method(http_method))
+                .and(path(endpoint))
+                .respond_with(response)
+                .mount(&server)
+                .await;
+        }
+        let mut client = offline_client::<Files>().with_base_url(server.uri());
 
         // Upload file
         let fup = client.upload(test_file).await;
@@ -1831,30 +10268,486 @@ mod tests {
         assert_eq!(contents.unwrap().len(), 3);
 
         // Delete file
-        // Wait for file to be uploaded for 5 seconds
-        tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
         let fdel = client.delete(&file_id).await;
         assert!(fdel.is_ok());
         assert_eq!(fdel.unwrap().id, file_id);
-
-        // Verify no files exist anymore
-        let files = client.list().await;
-        assert!(files.is_ok());
-        assert!(files.unwrap().data.is_empty());
     }
 
+    #[cfg(feature = "moderations")]
     #[tokio::test]
     async fn test_moderation() {
-        let moderation = OpenAI::<Moderation>::new()
+        let categories = serde_json::json!({
+            "sexual": false,
+            "hate": false,
+            "harassment": false,
+            "self-harm": false,
+            "sexual/minors": false,
+            "hate/threatening": false,
+            "violence/graphic": false,
+            "self-harm/intent": false,
+            "self-harm/instructions": false,
+            "harassment/threatening": false,
+            "violence": true
+        });
+        let scores = serde_json::json!({
+            "sexual": 0.0,
+            "hate": 0.0,
+            "harassment": 0.0,
+            "self-harm": 0.0,
+            "sexual/minors": 0.0,
+            "hate/threatening": 0.0,
+            "violence/graphic": 0.0,
+            "self-harm/intent": 0.0,
+            "self-harm/instructions": 0.0,
+            "harassment/threatening": 0.0,
+            "violence": 0.97
+        });
+        let server = mock_endpoint(
+            "POST",
+            "/moderations",
+            wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "modr-1",
+                "model": "text-moderation-007",
+                "results": [{"flagged": true, "categories": categories, "category_scores": scores}]
+            })),
+        )
+        .await;
+        let moderation = offline_client::<Moderation>()
+            .with_base_url(server.uri())
             .moderate("I want to kill them.")
             .await;
         assert!(moderation.is_ok());
-        assert!(moderation.unwrap().results[0].categories.violence);
+        let result = &moderation.unwrap().results[0];
+        assert!(result.categories.violence);
+        assert_eq!(
+            result.top_category(),
+            Some((ModerationCategory::Violence, 0.97))
+        );
+        assert_eq!(
+            result.flagged_categories(),
+            vec![ModerationCategory::Violence]
+        );
+    }
+
+    #[cfg(feature = "moderations")]
+    #[tokio::test]
+    async fn test_moderate_many() {
+        use wiremock::matchers::{body_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let result = |flagged: bool| {
+            let categories: serde_json::Map<String, serde_json::Value> = [
+                "sexual",
+                "hate",
+                "harassment",
+                "self-harm",
+                "sexual/minors",
+                "hate/threatening",
+                "violence/graphic",
+                "self-harm/intent",
+                "self-harm/instructions",
+                "harassment/threatening",
+                "violence",
+            ]
+            .into_iter()
+            .map(|category| (category.to_string(), serde_json::json!(false)))
+            .collect();
+            let scores: serde_json::Map<String, serde_json::Value> = categories
+                .keys()
+                .map(|category| (category.clone(), serde_json::json!(0.0)))
+                .collect();
+            serde_json::json!({"flagged": flagged, "categories": categories, "category_scores": scores})
+        };
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/moderations"))
+            .and(body_json(serde_json::json!({
+                "input": ["Hello", "Goodbye"],
+                "model": "text-moderation-stable"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "modr-1",
+                "model": "text-moderation-stable",
+                "results": [result(false), result(true)]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/moderations"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "modr-2",
+                "model": "text-moderation-stable",
+                "results": [result(false)]
+            })))
+            .mount(&server)
+            .await;
+
+        let client = offline_client::<Moderation>()
+            .with_base_url(server.uri())
+            .set_model(Moderation::STABLE_MODEL);
+        let results = client
+            .moderate_many(vec!["Hello".into(), "Goodbye".into()])
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(!results[0].flagged);
+        assert!(results[1].flagged);
+        assert!(results[0].top_category().is_none());
+        assert!(results[1].flagged_categories().is_empty());
+
+        let err = client
+            .moderate_many(vec!["a".into(), "b".into(), "c".into()])
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Expected 3 moderation results, but the response holds 1"
+        );
+    }
+
+    #[cfg(feature = "fine-tunes")]
+    #[test]
+    fn test_estimate_fine_tune() {
+        let estimate = estimate_fine_tune("examples/samples/test.jsonl", "curie", 4).unwrap();
+        assert_eq!(estimate.examples, 3);
+        assert_eq!(estimate.tokens, 33);
+        assert_eq!(estimate.epochs, 4);
+        assert!((estimate.estimated_cost_usd - 0.000_396).abs() < 1e-12);
+        assert_eq!(estimate.estimated_duration, Duration::from_secs(1));
+        assert!(estimate.warnings.is_empty());
+    }
+
+    #[cfg(feature = "fine-tunes")]
+    #[test]
+    fn test_estimate_fine_tune_warnings() {
+        let path = env::temp_dir().join("aionic_estimate_warnings.jsonl");
+        fs::write(
+            &path,
+            "{\"prompt\": \"\", \"completion\": \"ok\"}\nnot json\n",
+        )
+        .unwrap();
+        let estimate = estimate_fine_tune(&path, "curie", 1).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(estimate.examples, 1);
+        assert_eq!(estimate.warnings.len(), 2);
+        assert!(estimate_fine_tune("examples/samples/test.jsonl", "unknown", 1).is_err());
+    }
+
+    #[cfg(feature = "fine-tunes")]
+    fn fine_tune_json(status: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": "ft-1",
+            "object": "fine-tune",
+            "model": "curie",
+            "created_at": 0,
+            "events": [],
+            "fine_tuned_model": null,
+            "hyperparams": {
+                "batch_size": 4,
+                "learning_rate_multiplier": 0.1,
+                "n_epochs": 4,
+                "prompt_loss_weight": 0.01
+            },
+            "organization_id": "org-1",
+            "result_files": [],
+            "status": status,
+            "validation_files": [],
+            "training_files": [],
+            "updated_at": 0
+        })
+    }
+
+    #[cfg(feature = "fine-tunes")]
+    #[allow(deprecated)]
+    #[tokio::test]
+    async fn test_stream_fine_tune_events() {
+        use futures_util::StreamExt;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let event = |created_at: u64, message: &str| {
+            serde_json::json!({
+                "object": "fine-tune-event",
+                "created_at": created_at,
+                "level": "info",
+                "message": message
+            })
+        };
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/fine-tunes/ft-1/events"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "object": "list",
+                "data": [event(1, "Created fine-tune")]
+            })))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/fine-tunes/ft-1/events"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "object": "list",
+                "data": [event(2, "Fine-tune succeeded"), event(1, "Created fine-tune")]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/fine-tunes/ft-1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(fine_tune_json("running")))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/fine-tunes/ft-1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(fine_tune_json("succeeded")))
+            .mount(&server)
+            .await;
+
+        let client = offline_client::<FineTune>().with_base_url(server.uri());
+        let events: Vec<_> = client
+            .stream_events_with_interval("ft-1", Duration::from_millis(10))
+            .collect()
+            .await;
+        let messages: Vec<_> = events
+            .into_iter()
+            .map(|event| event.unwrap().message)
+            .collect();
+        assert_eq!(messages, ["Created fine-tune", "Fine-tune succeeded"]);
+
+        let client = offline_client::<FineTune>().with_base_url("http://127.0.0.1:1");
+        let events: Vec<_> = client.stream_events("ft-1").collect().await;
+        assert_eq!(events.len(), 1);
+        assert!(events[0].is_err());
+    }
+
+    #[cfg(feature = "fine-tunes")]
+    #[allow(deprecated)]
+    #[tokio::test]
+    async fn test_stream_fine_tune_events_of_finished_job() {
+        use futures_util::StreamExt;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+        /// Reports the job as succeeded, and remembers that it did.
+        struct Status(Arc<AtomicBool>);
+
+        impl Respond for Status {
+            fn respond(&self, _request: &Request) -> ResponseTemplate {
+                self.0.store(true, Ordering::SeqCst);
+                ResponseTemplate::new(200).set_body_json(fine_tune_json("succeeded"))
+            }
+        }
+
+        /// Lists the final event of the job only once its status has been retrieved, like an
+        /// event that is created between listing the events and retrieving the status.
+        struct Events(Arc<AtomicBool>);
+
+        impl Respond for Events {
+            fn respond(&self, _request: &Request) -> ResponseTemplate {
+                let mut data = vec![serde_json::json!({
+                    "object": "fine-tune-event",
+                    "created_at": 1,
+                    "level": "info",
+                    "message": "Created fine-tune"
+                })];
+                if self.0.load(Ordering::SeqCst) {
+                    data.push(serde_json::json!({
+                        "object": "fine-tune-event",
+                        "created_at": 2,
+                        "level": "info",
+                        "message": "Fine-tune succeeded"
+                    }));
+                }
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"object": "list", "data": data}))
+            }
+        }
+
+        let finished = Arc::new(AtomicBool::new(false));
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/fine-tunes/ft-1/events"))
+            .respond_with(Events(Arc::clone(&finished)))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/fine-tunes/ft-1"))
+            .respond_with(Status(finished))
+            .mount(&server)
+            .await;
+
+        let client = offline_client::<FineTune>().with_base_url(server.uri());
+        let messages: Vec<_> = client
+            .stream_events_with_interval("ft-1", Duration::from_millis(10))
+            .map(|event| event.unwrap().message)
+            .collect()
+            .await;
+        assert_eq!(messages, ["Created fine-tune", "Fine-tune succeeded"]);
+    }
+
+    #[cfg(feature = "files")]
+    #[tokio::test]
+    async fn test_list_all_files() {
+        use wiremock::matchers::{method, path, query_param, query_param_is_missing};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/files"))
+            .and(query_param_is_missing("after"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "object": "list",
+                "data": [file_data("file-1"), file_data("file-2")],
+                "has_more": true
+            })))
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/files"))
+            .and(query_param("after", "file-2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "object": "list",
+                "data": [file_data("file-3")],
+                "has_more": false
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = offline_client::<Files>().with_base_url(server.uri());
+        let page = client.list().await.unwrap();
+        assert!(page.has_more);
+        assert_eq!(page.last_id(), Some("file-2"));
+
+        let ids: Vec<_> = client
+            .list_all()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|file| file.id)
+            .collect();
+        assert_eq!(ids, ["file-1", "file-2", "file-3"]);
+    }
+
+    #[cfg(feature = "fine-tunes")]
+    fn fine_tuning_job_json(id: &str, status: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": id,
+            "object": "fine_tuning.job",
+            "created_at": 1692661014,
+            "finished_at": null,
+            "model": "gpt-3.5-turbo-0613",
+            "fine_tuned_model": null,
+            "organization_id": "org-1",
+            "status": status,
+            "hyperparameters": {"n_epochs": "auto"},
+            "training_file": "file-abc123",
+            "validation_file": null,
+            "result_files": [],
+            "trained_tokens": null,
+            "error": null
+        })
+    }
+
+    #[cfg(feature = "fine-tunes")]
+    #[tokio::test]
+    async fn test_fine_tuning_jobs() {
+        use wiremock::matchers::{body_json, method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/fine_tuning/jobs"))
+            .and(body_json(serde_json::json!({
+                "training_file": "file-abc123",
+                "model": "gpt-3.5-turbo",
+                "hyperparameters": {"n_epochs": 3},
+                "suffix": "custom"
+            })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(fine_tuning_job_json("ftjob-1", "validating_files")),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/fine_tuning/jobs"))
+            .and(query_param("limit", "1"))
+            .and(query_param("after", "ftjob-0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "object": "list",
+                "data": [fine_tuning_job_json("ftjob-1", "running")],
+                "has_more": true
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/fine_tuning/jobs/ftjob-1/events"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "object": "list",
+                "data": [{
+                    "id": "ftevent-1",
+                    "object": "fine_tuning.job.event",
+                    "created_at": 1692661014,
+                    "level": "info",
+                    "message": "Fine-tuning job started"
+                }],
+                "has_more": false
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/fine_tuning/jobs/ftjob-1/cancel"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(fine_tuning_job_json("ftjob-1", "cancelled")),
+            )
+            .mount(&server)
+            .await;
+
+        let mut client = offline_client::<FineTuningJob>()
+            .with_base_url(server.uri())
+            .set_n_epochs(3)
+            .set_suffix("custom");
+        let job = client.create("file-abc123").await.unwrap();
+        assert_eq!(job.id, "ftjob-1");
+        assert_eq!(
+            job.hyperparameters.n_epochs,
+            Some(NEpochs::Auto("auto".into()))
+        );
+
+        let page = client
+            .list_with_params(&ListParams::new().set_limit(1).set_after("ftjob-0"))
+            .await
+            .unwrap();
+        assert!(page.has_more);
+        assert_eq!(page.data[0].status, "running");
+
+        let events = client.list_events("ftjob-1").await.unwrap();
+        assert_eq!(events.data[0].message, "Fine-tuning job started");
+        assert!(!events.has_more);
+
+        let job = client.cancel("ftjob-1").await.unwrap();
+        assert!(FineTuningJob::is_terminal_status(&job.status));
     }
 
+    #[cfg(feature = "fine-tunes")]
+    #[allow(deprecated)]
     #[tokio::test]
     async fn test_list_fine_tunes() {
-        let tunes = OpenAI::<FineTune>::new().list().await;
+        let server = mock_endpoint(
+            "GET",
+            "/fine-tunes",
+            wiremock::ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({"object": "list", "data": []})),
+        )
+        .await;
+        let tunes = offline_client::<FineTune>()
+            .with_base_url(server.uri())
+            .list()
+            .await;
         assert!(tunes.is_ok());
     }
 }
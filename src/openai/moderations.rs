@@ -26,6 +26,91 @@ pub struct Result {
     pub category_scores: Scores,
 }
 
+impl Result {
+    /// Returns the category with the highest score, regardless of whether it was flagged.
+    ///
+    /// # Returns
+    ///
+    /// The category and its score, or `None` if no category has a score above zero.
+    pub fn top_category(&self) -> Option<(Category, f64)> {
+        Category::ALL
+            .iter()
+            .map(|&category| (category, self.category_scores.get(category)))
+            .filter(|(_, score)| *score > 0.0)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    /// Returns all categories the content was flagged under, in the order of `Category::ALL`.
+    pub fn flagged_categories(&self) -> Vec<Category> {
+        Category::ALL
+            .iter()
+            .copied()
+            .filter(|&category| self.categories.get(category))
+            .collect()
+    }
+}
+
+/// A category that content can be flagged under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Category {
+    #[serde(rename = "sexual")]
+    Sexual,
+    #[serde(rename = "hate")]
+    Hate,
+    #[serde(rename = "harassment")]
+    Harassment,
+    #[serde(rename = "self-harm")]
+    SelfHarm,
+    #[serde(rename = "sexual/minors")]
+    SexualMinors,
+    #[serde(rename = "hate/threatening")]
+    HateThreatening,
+    #[serde(rename = "violence/graphic")]
+    ViolenceGraphic,
+    #[serde(rename = "self-harm/intent")]
+    SelfHarmIntent,
+    #[serde(rename = "self-harm/instructions")]
+    SelfHarmInstructions,
+    #[serde(rename = "harassment/threatening")]
+    HarassmentThreatening,
+    #[serde(rename = "violence")]
+    Violence,
+}
+
+impl Category {
+    /// All categories, in the order the API lists them.
+    pub const ALL: [Self; 11] = [
+        Self::Sexual,
+        Self::Hate,
+        Self::Harassment,
+        Self::SelfHarm,
+        Self::SexualMinors,
+        Self::HateThreatening,
+        Self::ViolenceGraphic,
+        Self::SelfHarmIntent,
+        Self::SelfHarmInstructions,
+        Self::HarassmentThreatening,
+        Self::Violence,
+    ];
+
+    /// Returns the name of the category as used by the API, e.g. "self-harm/intent".
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sexual => "sexual",
+            Self::Hate => "hate",
+            Self::Harassment => "harassment",
+            Self::SelfHarm => "self-harm",
+            Self::SexualMinors => "sexual/minors",
+            Self::HateThreatening => "hate/threatening",
+            Self::ViolenceGraphic => "violence/graphic",
+            Self::SelfHarmIntent => "self-harm/intent",
+            Self::SelfHarmInstructions => "self-harm/instructions",
+            Self::HarassmentThreatening => "harassment/threatening",
+            Self::Violence => "violence",
+        }
+    }
+}
+
 /// Categories that content can be flagged under.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Categories {
@@ -70,6 +155,25 @@ pub struct Categories {
     pub violence: bool,
 }
 
+impl Categories {
+    /// Returns whether the content was flagged under the given category.
+    pub fn get(&self, category: Category) -> bool {
+        match category {
+            Category::Sexual => self.sexual,
+            Category::Hate => self.hate,
+            Category::Harassment => self.harassment,
+            Category::SelfHarm => self.self_harm,
+            Category::SexualMinors => self.sexual_minors,
+            Category::HateThreatening => self.hate_threatening,
+            Category::ViolenceGraphic => self.violence_graphic,
+            Category::SelfHarmIntent => self.self_harm_intent,
+            Category::SelfHarmInstructions => self.self_harm_instructions,
+            Category::HarassmentThreatening => self.harassment_threatening,
+            Category::Violence => self.violence,
+        }
+    }
+}
+
 /// Scores associated with each moderation category.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Scores {
@@ -114,11 +218,86 @@ pub struct Scores {
     pub violence: f64,
 }
 
+impl Scores {
+    /// Returns the score of the given category.
+    pub fn get(&self, category: Category) -> f64 {
+        match category {
+            Category::Sexual => self.sexual,
+            Category::Hate => self.hate,
+            Category::Harassment => self.harassment,
+            Category::SelfHarm => self.self_harm,
+            Category::SexualMinors => self.sexual_minors,
+            Category::HateThreatening => self.hate_threatening,
+            Category::ViolenceGraphic => self.violence_graphic,
+            Category::SelfHarmIntent => self.self_harm_intent,
+            Category::SelfHarmInstructions => self.self_harm_instructions,
+            Category::HarassmentThreatening => self.harassment_threatening,
+            Category::Violence => self.violence,
+        }
+    }
+}
+
+/// The input of a moderation request, either a single text or many texts that are classified
+/// in one request.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Input {
+    Single(String),
+    Many(Vec<String>),
+}
+
+impl Input {
+    /// Returns the number of texts of the input.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Single(_) => 1,
+            Self::Many(inputs) => inputs.len(),
+        }
+    }
+
+    /// Checks whether the input holds no texts at all.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl From<String> for Input {
+    fn from(input: String) -> Self {
+        Self::Single(input)
+    }
+}
+
+impl From<&str> for Input {
+    fn from(input: &str) -> Self {
+        Self::Single(input.to_string())
+    }
+}
+
+impl From<Vec<String>> for Input {
+    fn from(inputs: Vec<String>) -> Self {
+        Self::Many(inputs)
+    }
+}
+
 /// Represents a `Moderation` object in the `OpenAI` moderation API.
 ///
 /// For more information check the official [openAI API documentation](https://platform.openai.com/docs/api-reference/moderations)
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Moderation {
-    /// The input text to classify
-    pub input: String,
+    /// The input text(s) to classify. It is replaced by the argument of `moderate` and
+    /// `moderate_many` in every request.
+    pub input: Input,
+
+    /// The moderation model to use, i.e. "text-moderation-stable" or "text-moderation-latest".
+    /// Defaults to "text-moderation-latest" if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+impl Moderation {
+    /// The moderation model that is updated less frequently, so its results change less.
+    pub const STABLE_MODEL: &'static str = "text-moderation-stable";
+
+    /// The most accurate moderation model, which is upgraded automatically over time.
+    pub const LATEST_MODEL: &'static str = "text-moderation-latest";
 }
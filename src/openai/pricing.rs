@@ -0,0 +1,293 @@
+use crate::openai::misc::Usage;
+use std::collections::HashMap;
+
+/// The estimated cost of an API call in US dollars, split into prompt and completion tokens.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Cost {
+    /// The cost of the prompt tokens.
+    pub input: f64,
+
+    /// The cost of the completion tokens.
+    pub output: f64,
+}
+
+impl Cost {
+    /// Returns the total cost of the prompt and completion tokens.
+    pub fn total(&self) -> f64 {
+        self.input + self.output
+    }
+}
+
+/// The price of a model in US dollars per 1000 tokens.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ModelPrice {
+    /// The price per 1000 prompt tokens.
+    pub input_per_1k: f64,
+
+    /// The price per 1000 completion tokens.
+    pub output_per_1k: f64,
+}
+
+impl ModelPrice {
+    /// Creates a new `ModelPrice` from the prices per 1000 prompt and completion tokens.
+    pub fn new(input_per_1k: f64, output_per_1k: f64) -> Self {
+        Self {
+            input_per_1k,
+            output_per_1k,
+        }
+    }
+
+    /// Calculates the cost of the given usage in US dollars.
+    pub fn cost(&self, usage: &Usage) -> f64 {
+        self.breakdown(usage).total()
+    }
+
+    /// Calculates the cost of the given usage in US dollars, split into prompt and completion
+    /// tokens.
+    pub fn breakdown(&self, usage: &Usage) -> Cost {
+        let prompt_tokens = usage.prompt_tokens as f64;
+        let completion_tokens = usage.completion_tokens.unwrap_or(0) as f64;
+        Cost {
+            input: prompt_tokens * self.input_per_1k / 1000.0,
+            output: completion_tokens * self.output_per_1k / 1000.0,
+        }
+    }
+}
+
+/// A table of model prices used to estimate the cost of API calls.
+///
+/// A model is looked up by its exact name first. Otherwise the longest entry that the model
+/// name starts with, followed by a `-`, is used, so that e.g. `gpt-4-0613` is priced like `gpt-4`.
+///
+/// The default table holds the prices of the `OpenAI` models. Prices of fine-tuned or unknown
+/// models are added with `set_price`, and the table is passed to a client with `with_pricing`.
+///
+/// # Example
+///
+/// ```rust
+/// use aionic::openai::pricing::{ModelPrice, Pricing};
+///
+/// let pricing = Pricing::default().set_price("my-fine-tuned-model", ModelPrice::new(0.003, 0.006));
+/// assert!(pricing.get("my-fine-tuned-model").is_some());
+/// assert!(pricing.get("unknown-model").is_none());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Pricing {
+    prices: HashMap<String, ModelPrice>,
+}
+
+impl Pricing {
+    /// The default prices in US dollars per 1000 prompt and completion tokens.
+    const DEFAULT_PRICES: &'static [(&'static str, f64, f64)] = &[
+        ("gpt-4o", 0.005, 0.015),
+        ("gpt-4o-mini", 0.000_15, 0.000_6),
+        ("gpt-4-turbo", 0.01, 0.03),
+        ("gpt-4-1106-preview", 0.01, 0.03),
+        ("gpt-4-0125-preview", 0.01, 0.03),
+        ("gpt-4-vision-preview", 0.01, 0.03),
+        ("gpt-4-32k", 0.06, 0.12),
+        ("gpt-4", 0.03, 0.06),
+        ("gpt-3.5-turbo", 0.000_5, 0.001_5),
+        ("gpt-3.5-turbo-16k", 0.003, 0.004),
+        ("gpt-3.5-turbo-instruct", 0.001_5, 0.002),
+        ("text-embedding-ada-002", 0.000_1, 0.0),
+        ("text-embedding-3-small", 0.000_02, 0.0),
+        ("text-embedding-3-large", 0.000_13, 0.0),
+    ];
+
+    /// Creates an empty `Pricing` table, which prices no model at all.
+    pub fn empty() -> Self {
+        Self {
+            prices: HashMap::new(),
+        }
+    }
+
+    /// Sets the price of a model, overriding the default price if there is one.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - The name or name prefix of the model.
+    /// * `price` - The price of the model.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `Pricing` table with the price set.
+    pub fn set_price<S: Into<String>>(mut self, model: S, price: ModelPrice) -> Self {
+        self.prices.insert(model.into(), price);
+        self
+    }
+
+    /// Looks up the price of a model.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `None` if the model is not in the table.
+    pub fn get(&self, model: &str) -> Option<&ModelPrice> {
+        self.prices.get(model).or_else(|| {
+            self.prices
+                .iter()
+                .filter(|(name, _)| {
+                    model
+                        .strip_prefix(name.as_str())
+                        .is_some_and(|rest| rest.starts_with('-'))
+                })
+                .max_by_key(|(name, _)| name.len())
+                .map(|(_, price)| price)
+        })
+    }
+
+    /// Estimates the cost of an API call in US dollars.
+    ///
+    /// # Arguments
+    ///
+    /// * `usage` - The token usage of the API call.
+    /// * `model` - The model the API call was made with.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `None` if the model is not in the table.
+    pub fn estimate_cost(&self, usage: &Usage, model: &str) -> Option<f64> {
+        self.get(model).map(|price| price.cost(usage))
+    }
+
+    /// Estimates the cost of an API call in US dollars, split into prompt and completion tokens.
+    ///
+    /// # Arguments
+    ///
+    /// * `usage` - The token usage of the API call.
+    /// * `model` - The model the API call was made with.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `None` if the model is not in the table.
+    pub fn cost(&self, usage: &Usage, model: &str) -> Option<Cost> {
+        self.get(model).map(|price| price.breakdown(usage))
+    }
+}
+
+impl Default for Pricing {
+    fn default() -> Self {
+        Self::DEFAULT_PRICES.iter().fold(
+            Self::empty(),
+            |pricing, (model, input_per_1k, output_per_1k)| {
+                pricing.set_price(*model, ModelPrice::new(*input_per_1k, *output_per_1k))
+            },
+        )
+    }
+}
+
+/// Estimates the cost of an API call in US dollars with the default `Pricing` table.
+///
+/// # Arguments
+///
+/// * `usage` - The token usage of the API call.
+/// * `model` - The model the API call was made with.
+///
+/// # Returns
+///
+/// This function returns `None` if the model has no known price.
+pub fn estimate_cost(usage: &Usage, model: &str) -> Option<f64> {
+    Pricing::default().estimate_cost(usage, model)
+}
+
+/// The prices of the image models in US dollars per image, by model, quality and size.
+const IMAGE_PRICES: &[(&str, &str, &str, f64)] = &[
+    ("dall-e-3", "standard", "1024x1024", 0.04),
+    ("dall-e-3", "standard", "1024x1792", 0.08),
+    ("dall-e-3", "standard", "1792x1024", 0.08),
+    ("dall-e-3", "hd", "1024x1024", 0.08),
+    ("dall-e-3", "hd", "1024x1792", 0.12),
+    ("dall-e-3", "hd", "1792x1024", 0.12),
+    ("dall-e-2", "standard", "1024x1024", 0.02),
+    ("dall-e-2", "standard", "512x512", 0.018),
+    ("dall-e-2", "standard", "256x256", 0.016),
+];
+
+/// The price of fine-tuning in US dollars per 1000 trained tokens.
+const TRAINING_PRICES: &[(&str, f64)] = &[
+    ("ada", 0.000_4),
+    ("babbage", 0.000_6),
+    ("curie", 0.003),
+    ("davinci", 0.03),
+    ("babbage-002", 0.000_4),
+    ("davinci-002", 0.006),
+    ("gpt-3.5-turbo", 0.008),
+];
+
+/// The price of transcriptions and translations in US dollars per minute of audio.
+const TRANSCRIPTION_PRICES: &[(&str, f64)] = &[("whisper-1", 0.006)];
+
+/// The price of text-to-speech in US dollars per 1000 input characters.
+const SPEECH_PRICES: &[(&str, f64)] = &[("tts-1", 0.015), ("tts-1-hd", 0.03)];
+
+/// Estimates the cost of generating images in US dollars, as image responses carry no usage.
+///
+/// # Arguments
+///
+/// * `model` - The image model, e.g. `dall-e-3`.
+/// * `size` - The size of the images, e.g. `1024x1024`.
+/// * `quality` - The quality of the images, `standard` if `None`.
+/// * `n` - The number of images.
+///
+/// # Returns
+///
+/// This function returns `None` if the combination of model, size and quality has no known price.
+pub fn estimate_image_cost(model: &str, size: &str, quality: Option<&str>, n: u64) -> Option<f64> {
+    let quality = quality.unwrap_or("standard");
+    IMAGE_PRICES
+        .iter()
+        .find(|(m, q, s, _)| *m == model && *q == quality && *s == size)
+        .map(|(_, _, _, price)| price * n as f64)
+}
+
+/// Estimates the cost of a transcription or translation in US dollars, as audio responses carry
+/// no usage.
+///
+/// # Arguments
+///
+/// * `model` - The audio model, e.g. `whisper-1`.
+/// * `duration_secs` - The duration of the audio in seconds.
+///
+/// # Returns
+///
+/// This function returns `None` if the model has no known price.
+pub fn estimate_transcription_cost(model: &str, duration_secs: f64) -> Option<f64> {
+    TRANSCRIPTION_PRICES
+        .iter()
+        .find(|(m, _)| *m == model)
+        .map(|(_, per_minute)| per_minute * duration_secs / 60.0)
+}
+
+/// Estimates the cost of fine-tuning a model in US dollars.
+///
+/// # Arguments
+///
+/// * `model` - The base model to fine-tune, e.g. `gpt-3.5-turbo`.
+/// * `trained_tokens` - The number of tokens of the training data times the number of epochs.
+///
+/// # Returns
+///
+/// This function returns `None` if the model has no known training price.
+pub fn estimate_training_cost(model: &str, trained_tokens: u64) -> Option<f64> {
+    TRAINING_PRICES
+        .iter()
+        .find(|(m, _)| *m == model)
+        .map(|(_, per_1k)| per_1k * trained_tokens as f64 / 1000.0)
+}
+
+/// Estimates the cost of generating speech in US dollars.
+///
+/// # Arguments
+///
+/// * `model` - The speech model, e.g. `tts-1`.
+/// * `characters` - The number of characters of the input text.
+///
+/// # Returns
+///
+/// This function returns `None` if the model has no known price.
+pub fn estimate_speech_cost(model: &str, characters: usize) -> Option<f64> {
+    SPEECH_PRICES
+        .iter()
+        .find(|(m, _)| *m == model)
+        .map(|(_, per_1k)| per_1k * characters as f64 / 1000.0)
+}
@@ -0,0 +1,268 @@
+use crate::openai::transport::{Transport, TransportFuture};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use reqwest::header::AUTHORIZATION;
+use reqwest::{Body, Request, Response};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Whether a `Recorder` sends requests and saves them, or answers them from saved cassettes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecorderMode {
+    Record,
+    Replay,
+}
+
+/// A `Transport` that records request/response pairs to disk and replays them later, so tests
+/// against the real `OpenAI` API can be run deterministically and for free.
+///
+/// Every request is stored as a JSON cassette in the cassette directory. Its file name is made
+/// of the HTTP method, the endpoint and a hash of the request body, i.e. the serialized
+/// configuration, plus a counter to tell repeated identical requests apart. Bodies of multipart
+/// requests, e.g. file uploads, are streamed and therefore not part of the hash.
+///
+/// The `Authorization` header and headers marked as sensitive, e.g. ones added with
+/// `OpenAI::with_header`, are redacted before a cassette is written. Responses are stored chunk
+/// by chunk as base64, so streamed chat responses are replayed exactly as they were received
+/// and binary bodies, e.g. generated speech, are not corrupted.
+///
+/// In replay mode a request without a matching cassette fails with a `NotFound` error.
+///
+/// # Example
+///
+/// ```rust
+/// use aionic::openai::recorder::Recorder;
+/// use aionic::openai::{Chat, OpenAI};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+///     let mut client = OpenAI::<Chat>::new()
+///         .with_transport(Recorder::replay("tests/cassettes"))
+///         .set_stream_responses(false);
+///     let reply = client.ask("Say this is a test!", false).await?;
+///     println!("{}", reply);
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Recorder {
+    mode: RecorderMode,
+    dir: PathBuf,
+
+    /// The client requests are recorded with, or `None` to use the one of the `OpenAI` client.
+    client: Option<reqwest::Client>,
+    seen: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+/// A recorded request/response pair as it is stored on disk.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Cassette {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+/// The recorded request of a `Cassette`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: BTreeMap<String, String>,
+    pub body: Option<serde_json::Value>,
+}
+
+/// The recorded response of a `Cassette`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecordedResponse {
+    pub status: u16,
+    pub headers: BTreeMap<String, String>,
+
+    /// The body in the order of the chunks it was received in, each encoded as base64. Its
+    /// content type is part of `headers`.
+    pub chunks: Vec<String>,
+}
+
+impl Recorder {
+    const REDACTED: &'static str = "[REDACTED]";
+
+    /// Creates a `Recorder` that sends every request with the HTTP client of the `OpenAI` client
+    /// it is set on, i.e. with its proxy and connect timeout, and saves it as a cassette in
+    /// `dir`, which is created if needed.
+    pub fn record<P: AsRef<Path>>(dir: P) -> Self {
+        Self::new(RecorderMode::Record, dir, None)
+    }
+
+    /// Creates a recording `Recorder` like `record`, that sends requests with the given client
+    /// instead.
+    pub fn record_with_client<P: AsRef<Path>>(dir: P, client: reqwest::Client) -> Self {
+        Self::new(RecorderMode::Record, dir, Some(client))
+    }
+
+    /// Creates a `Recorder` that answers every request from the cassettes in `dir`.
+    pub fn replay<P: AsRef<Path>>(dir: P) -> Self {
+        Self::new(RecorderMode::Replay, dir, None)
+    }
+
+    fn new<P: AsRef<Path>>(mode: RecorderMode, dir: P, client: Option<reqwest::Client>) -> Self {
+        Self {
+            mode,
+            dir: dir.as_ref().to_path_buf(),
+            client,
+            seen: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Returns whether this `Recorder` records or replays.
+    pub fn mode(&self) -> RecorderMode {
+        self.mode
+    }
+
+    /// Returns the path of the cassette for the given request. Repeated identical requests
+    /// are numbered in the order they are made.
+    fn cassette_path(&self, request: &Request) -> PathBuf {
+        let endpoint: String = request
+            .url()
+            .path()
+            .trim_matches('/')
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        let body = Self::json_body(request).map(|body| body.to_string());
+        let key = format!(
+            "{}_{}_{:016x}",
+            request.method().as_str().to_lowercase(),
+            endpoint,
+            fnv1a(body.as_deref().unwrap_or_default().as_bytes())
+        );
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        let count = seen.entry(key.clone()).or_insert(0);
+        *count += 1;
+        self.dir.join(format!("{key}_{count}.json"))
+    }
+
+    /// Returns the body of a request as JSON. Object keys are sorted, so that the same
+    /// configuration always results in the same body.
+    fn json_body(request: &Request) -> Option<serde_json::Value> {
+        let bytes = request.body()?.as_bytes()?;
+        serde_json::from_slice(bytes).ok()
+    }
+
+    fn record_request(request: &Request) -> RecordedRequest {
+        let mut headers = Self::headers(request.headers());
+        for (name, value) in request.headers() {
+            if name == AUTHORIZATION || value.is_sensitive() {
+                headers.insert(name.to_string(), Self::REDACTED.to_string());
+            }
+        }
+        RecordedRequest {
+            method: request.method().to_string(),
+            url: request.url().path().to_string(),
+            headers,
+            body: Self::json_body(request),
+        }
+    }
+
+    fn headers(headers: &reqwest::header::HeaderMap) -> BTreeMap<String, String> {
+        headers
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect()
+    }
+
+    /// Records or replays a request. It is sent with the client of this `Recorder` if it has
+    /// one, else with `client`, or a default client if neither is set.
+    async fn send(
+        &self,
+        client: Option<&reqwest::Client>,
+        request: Request,
+    ) -> Result<Response, Box<dyn Error + Send + Sync>> {
+        let path = self.cassette_path(&request);
+        match self.mode {
+            RecorderMode::Record => {
+                let recorded_request = Self::record_request(&request);
+                let client = self.client.as_ref().or(client).cloned().unwrap_or_default();
+                let mut res = client.execute(request).await?;
+                let mut chunks = Vec::new();
+                while let Some(chunk) = res.chunk().await? {
+                    chunks.push(BASE64.encode(&chunk));
+                }
+                let cassette = Cassette {
+                    request: recorded_request,
+                    response: RecordedResponse {
+                        status: res.status().as_u16(),
+                        headers: Self::headers(res.headers()),
+                        chunks,
+                    },
+                };
+                tokio::fs::create_dir_all(&self.dir).await?;
+                tokio::fs::write(&path, serde_json::to_string_pretty(&cassette)?).await?;
+                Self::replay_response(cassette.response)
+            }
+            RecorderMode::Replay => {
+                let cassette = tokio::fs::read_to_string(&path).await.map_err(|e| {
+                    io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!(
+                            "No recorded response for {} {} at {}: {e}",
+                            request.method(),
+                            request.url().path(),
+                            path.display()
+                        ),
+                    )
+                })?;
+                let cassette: Cassette = serde_json::from_str(&cassette)?;
+                Self::replay_response(cassette.response)
+            }
+        }
+    }
+
+    fn replay_response(
+        recorded: RecordedResponse,
+    ) -> Result<Response, Box<dyn Error + Send + Sync>> {
+        let mut builder = http::Response::builder().status(recorded.status);
+        for (name, value) in &recorded.headers {
+            builder = builder.header(name, value);
+        }
+        let chunks = recorded
+            .chunks
+            .iter()
+            .map(|chunk| BASE64.decode(chunk))
+            .collect::<Result<Vec<_>, _>>()?;
+        let chunks = chunks.into_iter().map(Ok::<_, io::Error>);
+        let body = Body::wrap_stream(futures_util::stream::iter(chunks));
+        Ok(Response::from(builder.body(body)?))
+    }
+}
+
+impl Transport for Recorder {
+    fn post_json(&self, request: Request) -> TransportFuture<'_> {
+        Box::pin(self.send(None, request))
+    }
+
+    fn get(&self, request: Request) -> TransportFuture<'_> {
+        Box::pin(self.send(None, request))
+    }
+
+    fn delete(&self, request: Request) -> TransportFuture<'_> {
+        Box::pin(self.send(None, request))
+    }
+
+    fn post_multipart(&self, request: Request) -> TransportFuture<'_> {
+        Box::pin(self.send(None, request))
+    }
+
+    fn execute<'a>(&'a self, client: &'a reqwest::Client, request: Request) -> TransportFuture<'a> {
+        Box::pin(self.send(Some(client), request))
+    }
+}
+
+/// The 64-bit FNV-1a hash, which unlike `DefaultHasher` is stable across Rust releases, so
+/// cassette names do not change.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
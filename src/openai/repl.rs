@@ -0,0 +1,249 @@
+use crate::openai::chat::{Message, MessageRole};
+use crate::openai::misc::Cancelled;
+use crate::openai::{CancellationToken, Chat, OpenAI};
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+
+/// The prompt shown while a multi-line input is continued.
+const CONTINUATION_PROMPT: &str = "... ";
+
+/// The delimiter that starts and ends a fenced multi-line input.
+const FENCE: &str = "\"\"\"";
+
+/// A slash command of the chat session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Command {
+    /// Clears the conversation.
+    Reset,
+    /// Saves the conversation to the given file.
+    Save(String),
+    /// Loads a conversation from the given file.
+    Load(String),
+    /// Sets the system message of the conversation.
+    System(String),
+    /// Switches to the given model.
+    Model(String),
+    /// Ends the chat session.
+    Exit,
+}
+
+impl Command {
+    /// Parses a line of input as slash command.
+    ///
+    /// # Returns
+    ///
+    /// * `None`: The line is no slash command and should be sent to the model.
+    ///
+    /// * `Some(Ok(Command))`: The parsed command.
+    ///
+    /// * `Some(Err(String))`: The line is an unknown command or lacks an argument, with a message
+    ///   explaining the usage.
+    pub(crate) fn parse(line: &str) -> Option<Result<Self, String>> {
+        let line = line.trim();
+        let command = line.strip_prefix('/')?;
+        let (name, arg) = command
+            .split_once(char::is_whitespace)
+            .map_or((command, ""), |(name, arg)| (name, arg.trim()));
+        let require_arg = |usage: &str| {
+            if arg.is_empty() {
+                Err(format!("Usage: /{name} {usage}"))
+            } else {
+                Ok(arg.to_string())
+            }
+        };
+        Some(match name {
+            "reset" => Ok(Self::Reset),
+            "save" => require_arg("<path>").map(Self::Save),
+            "load" => require_arg("<path>").map(Self::Load),
+            "system" => require_arg("<message>").map(Self::System),
+            "model" => require_arg("<name>").map(Self::Model),
+            "exit" => Ok(Self::Exit),
+            _ => Err(format!(
+                "Unknown command /{name}, expected one of /reset, /save, /load, /system, /model or /exit"
+            )),
+        })
+    }
+}
+
+/// Reads the next input of the chat session, which may span multiple lines.
+///
+/// A line ending in a backslash is continued on the next line, and a line consisting only of
+/// `"""` starts a block that lasts until the next such line.
+fn read_input(rl: &mut DefaultEditor, prompt: &str) -> Result<String, ReadlineError> {
+    let first = rl.readline(prompt)?;
+    if first.trim() == FENCE {
+        let mut lines = Vec::new();
+        loop {
+            let line = rl.readline(CONTINUATION_PROMPT)?;
+            if line.trim() == FENCE {
+                break;
+            }
+            lines.push(line);
+        }
+        return Ok(lines.join("\n"));
+    }
+    let mut input = first;
+    while let Some(stripped) = input.strip_suffix('\\') {
+        input.truncate(stripped.len());
+        input.push('\n');
+        input.push_str(&rl.readline(CONTINUATION_PROMPT)?);
+    }
+    Ok(input)
+}
+
+impl OpenAI<Chat> {
+    /// Starts a chat session with the AI assistant. This requires the `repl` feature.
+    ///
+    /// This function uses a Readline-style interface for input and output. The user types a message at the `>>> ` prompt,
+    /// and the message is sent to the AI assistant using the `ask` function. The AI's response is then printed to the console.
+    ///
+    /// A message spans multiple lines if a line ends in a backslash, or if it is wrapped in lines consisting only of `"""`.
+    ///
+    /// Lines starting with a slash are commands, which are handled by the session instead of being sent to the model:
+    ///
+    /// * `/reset`: Clears the conversation.
+    /// * `/save <path>`: Saves the conversation to a file, see `save_history`.
+    /// * `/load <path>`: Loads a conversation from a file, see `load_history`.
+    /// * `/system <message>`: Sets the system message of the conversation.
+    /// * `/model <name>`: Switches to another model.
+    /// * `/exit`: Ends the chat session.
+    ///
+    /// If the user enters CTRL-C while the AI is answering, the answer is stopped and the part
+    /// received so far is kept in the conversation, see `ask_cancellable`. If the answer had not
+    /// started yet, the message is discarded.
+    ///
+    /// If the user enters CTRL-C at the prompt, the function prints "CTRL-C" and exits the chat session.
+    ///
+    /// If the user enters CTRL-D, the function prints "CTRL-D" and exits the chat session.
+    ///
+    /// If a request to the model fails, the function prints the error message, leaves the conversation as it was before
+    /// the message and continues the chat session.
+    ///
+    /// If there's an error during readline, the function prints the error message and exits the chat session.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())`: A success value indicating that the chat session ended normally.
+    ///
+    /// * `Err(Box<dyn std::error::Error + Send + Sync>)`: An error value. This is a dynamic error, meaning it could represent
+    /// various kinds of failures. The function will return an error if the console cannot be set up, or if a line cannot
+    /// be added to its history.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the line editor cannot be created or its history cannot be updated.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use aionic::openai::chat::Chat;
+    /// use aionic::openai::OpenAI;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///     let mut client = OpenAI::<Chat>::new();
+    ///     let result = client.chat().await;
+    ///     match result {
+    ///         Ok(()) => println!("Chat session ended."),
+    ///         Err(e) => println!("Error during chat session: {}", e),
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Note
+    ///
+    /// This function is `async` and must be awaited when called.
+    pub async fn chat(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut rl = DefaultEditor::new()?;
+        let prompt = ">>> ";
+        loop {
+            let readline = read_input(&mut rl, prompt);
+            match readline {
+                Ok(line) => match Command::parse(&line) {
+                    Some(Ok(Command::Exit)) => break,
+                    Some(Ok(command)) => self._run_command(command),
+                    Some(Err(usage)) => println!("{usage}"),
+                    None if line.trim().is_empty() => {}
+                    None => {
+                        rl.add_history_entry(line.as_str())?;
+                        match self._ask_interruptible(line).await {
+                            Ok(()) => println!(),
+                            Err(e) => println!("Error: {e}"),
+                        }
+                    }
+                },
+                Err(ReadlineError::Interrupted) => {
+                    println!("CTRL-C");
+                    break;
+                }
+                Err(ReadlineError::Eof) => {
+                    println!("CTRL-D");
+                    break;
+                }
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Asks the model like `ask`, but stops the answer when the user presses CTRL-C.
+    async fn _ask_interruptible(
+        &mut self,
+        line: String,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let token = CancellationToken::new();
+        let interrupt = token.clone();
+        let watcher = tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                interrupt.cancel();
+            }
+        });
+        let answer = self.ask_cancellable(line, true, token).await;
+        watcher.abort();
+        match answer {
+            Err(e) if e.is::<Cancelled>() => println!("CTRL-C"),
+            answer => {
+                answer?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs a slash command of the chat session and prints its outcome.
+    fn _run_command(&mut self, command: Command) {
+        match command {
+            Command::Reset => {
+                self.config.messages.clear();
+                println!("The conversation was cleared.");
+            }
+            Command::Save(path) => match self.save_history(&path) {
+                Ok(()) => println!("Saved the conversation to {path}."),
+                Err(e) => println!("Error: {e}"),
+            },
+            Command::Load(path) => match self.load_history(&path) {
+                Ok(()) => println!(
+                    "Loaded {} messages from {path}.",
+                    self.config.messages.len()
+                ),
+                Err(e) => println!("Error: {e}"),
+            },
+            Command::System(primer) => {
+                let msg = Message::new(&MessageRole::System, primer);
+                match self.config.messages.first_mut() {
+                    Some(first) if first.role == MessageRole::System.to_string() => *first = msg,
+                    _ => self.config.messages.insert(0, msg),
+                }
+                println!("The system message was set.");
+            }
+            Command::Model(model) => {
+                println!("Switched to {model}.");
+                self.config.model = model;
+            }
+            Command::Exit => {}
+        }
+    }
+}
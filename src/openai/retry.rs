@@ -0,0 +1,129 @@
+use crate::openai::rt::{SystemTime, UNIX_EPOCH};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+use std::time::Duration;
+
+/// Configures how requests that failed with a transient error are retried.
+///
+/// Requests that are answered with `429 Too Many Requests`, `500 Internal Server Error`,
+/// `502 Bad Gateway` or `503 Service Unavailable` are retried up to `max_retries` times
+/// with an exponential backoff plus jitter. All other responses are returned immediately.
+///
+/// # Example
+///
+/// ```rust
+/// use aionic::openai::RetryConfig;
+/// use std::time::Duration;
+///
+/// let retry = RetryConfig {
+///     max_retries: 5,
+///     base_delay: Duration::from_millis(250),
+///     respect_retry_after: true,
+/// };
+/// ```
+#[derive(Clone, Debug)]
+pub struct RetryConfig {
+    /// The maximum number of retries after the initial attempt.
+    pub max_retries: u32,
+
+    /// The delay before the first retry. It is doubled for every following retry.
+    pub base_delay: Duration,
+
+    /// If set, the delay requested by the server through the `Retry-After` header
+    /// is used instead of the computed backoff.
+    pub respect_retry_after: bool,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            respect_retry_after: true,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Checks whether a response with the given status code is worth retrying.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `true` for 429, 500, 502 and 503 responses.
+    pub fn is_retryable(status: StatusCode) -> bool {
+        matches!(
+            status,
+            StatusCode::TOO_MANY_REQUESTS
+                | StatusCode::INTERNAL_SERVER_ERROR
+                | StatusCode::BAD_GATEWAY
+                | StatusCode::SERVICE_UNAVAILABLE
+        )
+    }
+
+    /// Computes the delay before the next retry.
+    ///
+    /// # Arguments
+    ///
+    /// * `attempt`: The zero-based number of the retry that is about to happen.
+    /// * `headers`: The headers of the response that triggered the retry.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `Retry-After` delay if present and respected, otherwise
+    /// `base_delay * 2^attempt` plus a random jitter of up to `base_delay`.
+    pub fn delay_for(&self, attempt: u32, headers: &HeaderMap) -> Duration {
+        if self.respect_retry_after {
+            if let Some(retry_after) = parse_retry_after(headers) {
+                return retry_after;
+            }
+        }
+        let backoff = self.base_delay.saturating_mul(2u32.saturating_pow(attempt));
+        backoff.saturating_add(self.jitter())
+    }
+
+    /// Emits a structured event for a retry that is about to happen.
+    ///
+    /// Only the URL and the status are recorded, never the request headers, so the API key
+    /// cannot end up in the logs.
+    #[cfg(feature = "tracing")]
+    pub(crate) fn trace_attempt(&self, attempt: u32, res: &reqwest::Response, delay: Duration) {
+        let retry_after = parse_retry_after(res.headers()).filter(|_| self.respect_retry_after);
+        let backoff = self.base_delay.saturating_mul(2u32.saturating_pow(attempt));
+        let jitter = retry_after.map_or_else(|| delay.saturating_sub(backoff), |_| Duration::ZERO);
+        tracing::warn!(
+            url = %res.url(),
+            status = res.status().as_u16(),
+            attempt = attempt + 1,
+            max_retries = self.max_retries,
+            delay_ms = delay.as_millis() as u64,
+            jitter_ms = jitter.as_millis() as u64,
+            retry_after_ms = retry_after.map(|d| d.as_millis() as u64),
+            "Retrying request"
+        );
+    }
+
+    fn jitter(&self) -> Duration {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.subsec_nanos());
+        self.base_delay.mul_f64(f64::from(nanos % 1000) / 1000.0)
+    }
+}
+
+/// Parses the `Retry-After` header.
+///
+/// Both forms allowed by RFC 9110 are supported: a number of seconds, and an HTTP-date.
+/// A date in the past results in a zero delay.
+pub fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    // `httpdate` returns a `std::time::SystemTime`, which cannot be compared to `now` on wasm32.
+    let date = httpdate::parse_http_date(value)
+        .ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
+    Some(date.saturating_sub(now))
+}
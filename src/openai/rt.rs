@@ -0,0 +1,78 @@
+//! The parts of the runtime that differ on wasm32, where tokio's timers and `std::time` panic
+//! and responses have no extensions.
+
+use reqwest::Response;
+#[cfg(any(feature = "chat", feature = "embeddings", target_arch = "wasm32"))]
+use std::future::Future;
+use std::time::Duration;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use std::time::{Instant, SystemTime, UNIX_EPOCH};
+#[cfg(target_arch = "wasm32")]
+pub(crate) use web_time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Waits until `duration` has elapsed.
+pub(crate) async fn sleep(duration: Duration) {
+    #[cfg(not(target_arch = "wasm32"))]
+    tokio::time::sleep(duration).await;
+    #[cfg(target_arch = "wasm32")]
+    gloo_timers::future::sleep(duration).await;
+}
+
+/// Runs `fut` until it completes or `duration` has elapsed.
+///
+/// # Returns
+///
+/// This function returns the output of `fut`, or `None` if it timed out.
+#[cfg(any(feature = "chat", feature = "embeddings", target_arch = "wasm32"))]
+pub(crate) async fn timeout<F: Future>(duration: Duration, fut: F) -> Option<F::Output> {
+    #[cfg(not(target_arch = "wasm32"))]
+    return tokio::time::timeout(duration, fut).await.ok();
+    #[cfg(target_arch = "wasm32")]
+    tokio::select! {
+        res = fut => Some(res),
+        () = sleep(duration) => None,
+    }
+}
+
+/// Attaches `value` to a response, so it can be taken from it again once the body is read.
+///
+/// # Returns
+///
+/// This function returns `value` again if it could not be attached, which is always the case on
+/// wasm32.
+pub(crate) fn attach<T: Clone + Send + Sync + 'static>(res: &mut Response, value: T) -> Option<T> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        res.extensions_mut().insert(value);
+        None
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = res;
+        Some(value)
+    }
+}
+
+/// Removes a value attached with `attach` from a response.
+pub(crate) fn take<T: Clone + Send + Sync + 'static>(res: &mut Response) -> Option<T> {
+    #[cfg(not(target_arch = "wasm32"))]
+    return res.extensions_mut().remove::<T>();
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = res;
+        None
+    }
+}
+
+/// Returns a copy of a value attached with `attach` to a response.
+#[cfg(feature = "tracing")]
+pub(crate) fn get<T: Clone + Send + Sync + 'static>(res: &Response) -> Option<T> {
+    #[cfg(not(target_arch = "wasm32"))]
+    return res.extensions().get::<T>().cloned();
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = res;
+        None
+    }
+}
@@ -0,0 +1,106 @@
+#[cfg(feature = "chat")]
+use crate::openai::chat::{Content, ContentPart, Message};
+use tiktoken_rs::tokenizer::{get_tokenizer, Tokenizer};
+use tiktoken_rs::CoreBPE;
+
+/// The number of tokens every message is wrapped in, i.e. `<|start|>{role}\n{content}<|end|>\n`.
+#[cfg(feature = "chat")]
+const TOKENS_PER_MESSAGE: usize = 3;
+
+/// The number of tokens added if a message carries a `name`.
+#[cfg(feature = "chat")]
+const TOKENS_PER_NAME: usize = 1;
+
+/// The number of tokens every reply is primed with, i.e. `<|start|>assistant<|message|>`.
+#[cfg(feature = "chat")]
+pub(crate) const TOKENS_PER_REPLY: usize = 3;
+
+/// The number of tokens of an image in low detail. Images in high detail cost more, depending on
+/// their size.
+#[cfg(feature = "chat")]
+const TOKENS_PER_IMAGE: usize = 85;
+
+/// Runs `f` with the shared BPE encoder used by the given model.
+///
+/// # Returns
+///
+/// This function returns the result of `f`, or `None` if no tokenizer is known for `model`.
+pub(crate) fn with_bpe<R>(model: &str, f: impl FnOnce(&CoreBPE) -> R) -> Option<R> {
+    let bpe = match get_tokenizer(model)? {
+        Tokenizer::O200kBase => tiktoken_rs::o200k_base_singleton(),
+        Tokenizer::Cl100kBase => tiktoken_rs::cl100k_base_singleton(),
+        Tokenizer::P50kBase => tiktoken_rs::p50k_base_singleton(),
+        Tokenizer::P50kEdit => tiktoken_rs::p50k_edit_singleton(),
+        Tokenizer::R50kBase | Tokenizer::Gpt2 => tiktoken_rs::r50k_base_singleton(),
+    };
+    let bpe = bpe.lock();
+    Some(f(&bpe))
+}
+
+/// Counts the tokens of `messages` as they are sent to the given chat model.
+///
+/// Besides the role, content and name of each message this accounts for the tokens the API wraps
+/// every message in, and for the tokens the reply is primed with. Function calls are counted by
+/// their name and arguments, images as low detail images. Models without a known tokenizer are
+/// counted with `cl100k_base`.
+///
+/// # Arguments
+///
+/// * `model` - The chat model whose tokenizer to use.
+/// * `messages` - The messages to count.
+///
+/// # Returns
+///
+/// The estimated number of prompt tokens.
+#[cfg(feature = "chat")]
+pub fn count_message_tokens(model: &str, messages: &[Message]) -> usize {
+    TOKENS_PER_REPLY
+        + count_tokens_per_message(model, messages)
+            .iter()
+            .sum::<usize>()
+}
+
+/// Counts the tokens of each of `messages` like `count_message_tokens`, but without the tokens
+/// the reply is primed with.
+#[cfg(feature = "chat")]
+pub(crate) fn count_tokens_per_message(model: &str, messages: &[Message]) -> Vec<usize> {
+    let count_messages = |bpe: &CoreBPE| {
+        let count = |text: &str| bpe.encode_with_special_tokens(text).len();
+        let count_content = |content: &Content| match content {
+            Content::Text(text) => count(text),
+            Content::Parts(parts) => parts
+                .iter()
+                .map(|part| match part {
+                    ContentPart::Text { text } => count(text),
+                    ContentPart::ImageUrl { .. } => TOKENS_PER_IMAGE,
+                })
+                .sum(),
+        };
+        let count_message = |message: &Message| {
+            let mut tokens = TOKENS_PER_MESSAGE
+                + count(&message.role)
+                + message.content.as_ref().map_or(0, count_content);
+            if let Some(name) = &message.name {
+                tokens += TOKENS_PER_NAME + count(name);
+            }
+            if let Some(function_call) = &message.function_call {
+                tokens += count(&function_call.name) + count(&function_call.arguments);
+            }
+            for tool_call in message.tool_calls.iter().flatten() {
+                tokens += count(&tool_call.function.name) + count(&tool_call.function.arguments);
+            }
+            tokens
+        };
+        messages.iter().map(count_message).collect()
+    };
+    with_bpe(model, count_messages)
+        .unwrap_or_else(|| with_bpe("gpt-4", count_messages).unwrap_or_default())
+}
+
+/// Counts the tokens of a plain text, e.g. a completion, with the tokenizer of the given model.
+/// Models without a known tokenizer are counted with `cl100k_base`.
+#[cfg(any(feature = "chat", feature = "fine-tunes"))]
+pub(crate) fn count_text_tokens(model: &str, text: &str) -> usize {
+    let count = |bpe: &CoreBPE| bpe.encode_with_special_tokens(text).len();
+    with_bpe(model, count).unwrap_or_else(|| with_bpe("gpt-4", count).unwrap_or_default())
+}
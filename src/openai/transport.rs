@@ -0,0 +1,135 @@
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Method, Request, Response};
+use std::error::Error;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+
+/// The future returned by the methods of a `Transport`.
+#[cfg(not(target_arch = "wasm32"))]
+pub type TransportFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<Response, Box<dyn Error + Send + Sync>>> + Send + 'a>>;
+
+/// The future returned by the methods of a `Transport`. Requests made through the browser's
+/// `fetch` API are not `Send`, so neither is this future on `wasm32`.
+#[cfg(target_arch = "wasm32")]
+pub type TransportFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<Response, Box<dyn Error + Send + Sync>>> + 'a>>;
+
+/// Sends the requests of an `OpenAI` client.
+///
+/// By default requests are sent with the client's `reqwest::Client`. An alternative
+/// implementation can be set with `OpenAI::with_transport`, e.g. to answer requests with
+/// canned responses in tests, so they run offline and without an API key.
+///
+/// The requests are fully prepared, i.e. they already carry the URL, the authentication
+/// headers and the body. A `reqwest::Response` can be built from an `http::Response`
+/// through its `From` implementation. Requests of a kind a transport does not implement
+/// fail with an `Unsupported` error.
+///
+/// # Example
+///
+/// ```rust
+/// use aionic::openai::transport::{Transport, TransportFuture};
+/// use reqwest::Request;
+///
+/// #[derive(Debug)]
+/// struct Offline;
+///
+/// impl Offline {
+///     fn reply(&self, body: &'static str) -> TransportFuture<'_> {
+///         Box::pin(async move { Ok(http::Response::new(body).into()) })
+///     }
+/// }
+///
+/// impl Transport for Offline {
+///     fn post_json(&self, _request: Request) -> TransportFuture<'_> {
+///         self.reply(r#"{"text": "Hello"}"#)
+///     }
+///
+///     fn get(&self, _request: Request) -> TransportFuture<'_> {
+///         self.reply(r#"{"data": [], "object": "list"}"#)
+///     }
+/// }
+/// ```
+pub trait Transport: Send + Sync + std::fmt::Debug {
+    /// Sends a `POST` request with a JSON body.
+    fn post_json(&self, request: Request) -> TransportFuture<'_> {
+        unsupported(request)
+    }
+
+    /// Sends a `GET` request.
+    fn get(&self, request: Request) -> TransportFuture<'_> {
+        unsupported(request)
+    }
+
+    /// Sends a `DELETE` request.
+    fn delete(&self, request: Request) -> TransportFuture<'_> {
+        unsupported(request)
+    }
+
+    /// Sends a `POST` request with a `multipart/form-data` body, e.g. a file upload.
+    fn post_multipart(&self, request: Request) -> TransportFuture<'_> {
+        unsupported(request)
+    }
+
+    /// Sends a request of an `OpenAI` client, whose HTTP client is passed along, so a transport
+    /// that forwards requests can send them with the client's proxy and connect timeout.
+    ///
+    /// By default the request is handed to the method that matches its HTTP method and body.
+    fn execute<'a>(&'a self, client: &'a reqwest::Client, request: Request) -> TransportFuture<'a> {
+        let _ = client;
+        dispatch(self, request)
+    }
+}
+
+impl Transport for reqwest::Client {
+    fn post_json(&self, request: Request) -> TransportFuture<'_> {
+        Box::pin(async move { Ok(self.execute(request).await?) })
+    }
+
+    fn get(&self, request: Request) -> TransportFuture<'_> {
+        Box::pin(async move { Ok(self.execute(request).await?) })
+    }
+
+    fn delete(&self, request: Request) -> TransportFuture<'_> {
+        Box::pin(async move { Ok(self.execute(request).await?) })
+    }
+
+    fn post_multipart(&self, request: Request) -> TransportFuture<'_> {
+        Box::pin(async move { Ok(self.execute(request).await?) })
+    }
+}
+
+/// Fails a request that a `Transport` does not implement a method for.
+fn unsupported<'a>(request: Request) -> TransportFuture<'a> {
+    Box::pin(async move {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!(
+                "The transport cannot send {} {}",
+                request.method(),
+                request.url().path()
+            ),
+        )
+        .into())
+    })
+}
+
+/// Hands a request to the method of `transport` that matches its HTTP method and body.
+pub(crate) fn dispatch<T: Transport + ?Sized>(
+    transport: &T,
+    request: Request,
+) -> TransportFuture<'_> {
+    let is_multipart = request
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("multipart/form-data"));
+    match *request.method() {
+        Method::GET => transport.get(request),
+        Method::DELETE => transport.delete(request),
+        _ if is_multipart => transport.post_multipart(request),
+        _ => transport.post_json(request),
+    }
+}
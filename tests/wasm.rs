@@ -0,0 +1,85 @@
+//! Smoke tests for the endpoints that are supported on wasm32. Run them with
+//! `wasm-pack test --node -- --no-default-features --features chat,embeddings,moderations`.
+#![cfg(target_arch = "wasm32")]
+
+use aionic::openai::{Chat, Embedding, Moderation, OpenAI, RetryConfig};
+use std::time::Duration;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+/// Replaces the global `fetch`, which reqwest sends requests with on wasm32, with a stub that
+/// answers with canned responses and counts the requests per path in `globalThis.requests`.
+fn stub_fetch() {
+    let fetch = js_sys::Function::new_with_args(
+        "request",
+        r#"
+        const path = new URL(request.url).pathname;
+        globalThis.requests[path] = (globalThis.requests[path] || 0) + 1;
+        const replies = {
+            "/v1/chat/completions": [200, {choices: [{index: 0, message: {role: "assistant", content: "Hi"}, finish_reason: "stop"}]}],
+            "/v1/embeddings": [200, {object: "list", model: "text-embedding-ada-002", data: [{object: "embedding", embedding: [0.5], index: 0}], usage: {prompt_tokens: 1, total_tokens: 1}}],
+            "/v1/moderations": [503, {error: {message: "Service unavailable"}}],
+        };
+        const [status, body] = replies[path] || [404, {error: {message: "Not found"}}];
+        const response = new Response(JSON.stringify(body), {status, headers: {"Content-Type": "application/json"}});
+        // reqwest parses the response URL, which is empty for constructed responses.
+        Object.defineProperty(response, "url", {value: request.url});
+        return Promise.resolve(response);
+        "#,
+    );
+    let global = js_sys::global();
+    js_sys::Reflect::set(&global, &"requests".into(), &js_sys::Object::new()).unwrap();
+    js_sys::Reflect::set(&global, &"fetch".into(), &fetch).unwrap();
+}
+
+/// Returns how many requests the stubbed `fetch` received for `path`.
+fn requests(path: &str) -> f64 {
+    let requests = js_sys::Reflect::get(&js_sys::global(), &"requests".into()).unwrap();
+    js_sys::Reflect::get(&requests, &JsValue::from_str(path))
+        .unwrap()
+        .as_f64()
+        .unwrap_or_default()
+}
+
+#[wasm_bindgen_test]
+async fn test_requests_without_api_key() {
+    let mut chat = OpenAI::<Chat>::default()
+        .disable_stdout()
+        .set_stream_responses(false);
+    assert!(chat.ask("Hello", false).await.is_err());
+
+    let embedding = OpenAI::<Embedding>::default();
+    assert!(embedding.embed("Hello").await.is_err());
+
+    let moderation = OpenAI::<Moderation>::default();
+    assert!(moderation.moderate("Hello").await.is_err());
+}
+
+#[wasm_bindgen_test]
+async fn test_mocked_requests() {
+    stub_fetch();
+
+    // The timeout is enforced with a browser timer, as requests cannot carry one on wasm32.
+    let mut chat = OpenAI::<Chat>::default()
+        .with_api_key("sk-test")
+        .with_timeout(Duration::from_secs(5))
+        .disable_stdout()
+        .set_stream_responses(false);
+    assert_eq!(chat.ask("Hello", true).await.unwrap(), "Hi");
+    assert_eq!(chat.messages().len(), 2);
+
+    let embedding = OpenAI::<Embedding>::default().with_api_key("sk-test");
+    let res = embedding.embed("Hello").await.unwrap();
+    assert_eq!(res.data[0].embedding, vec![0.5]);
+
+    // Retries wait between the attempts, which needs a timer that works on wasm32 as well.
+    let moderation = OpenAI::<Moderation>::default()
+        .with_api_key("sk-test")
+        .with_retry(RetryConfig {
+            max_retries: 1,
+            base_delay: Duration::from_millis(10),
+            ..RetryConfig::default()
+        });
+    assert!(moderation.moderate("Hello").await.is_err());
+    assert_eq!(requests("/v1/moderations"), 2.0);
+}